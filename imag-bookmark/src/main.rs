@@ -34,7 +34,9 @@
 
 extern crate clap;
 #[macro_use] extern crate log;
+extern crate serde_json;
 #[macro_use] extern crate version;
+#[cfg(feature = "open-crate")] extern crate open;
 
 extern crate libimagbookmark;
 extern crate libimagentrylink;
@@ -43,15 +45,17 @@ extern crate libimagrt;
 extern crate libimagerror;
 extern crate libimagutil;
 
+use std::collections::BTreeMap;
+use std::io::Result as IoResult;
 use std::process::exit;
 
 use libimagrt::runtime::Runtime;
 use libimagrt::setup::generate_runtime_setup;
 use libimagbookmark::collection::BookmarkCollection;
 use libimagbookmark::link::Link as BookmarkLink;
-use libimagerror::trace::{MapErrTrace, trace_error, trace_error_exit};
-use libimagutil::info_result::*;
-use libimagutil::iter::*;
+use libimagerror::trace::{trace_error, trace_error_exit};
+
+use serde_json::Value as JsonValue;
 
 mod ui;
 
@@ -71,6 +75,7 @@ fn main() {
                 "add"        => add(&rt),
                 "collection" => collection(&rt),
                 "list"       => list(&rt),
+                "open"       => open(&rt),
                 "remove"     => remove(&rt),
                 _            => {
                     debug!("Unknown command"); // More error handling
@@ -80,18 +85,35 @@ fn main() {
 }
 
 fn add(rt: &Runtime) {
-    let scmd = rt.cli().subcommand_matches("add").unwrap();
-    let coll = scmd.value_of("collection").unwrap(); // enforced by clap
+    let scmd      = rt.cli().subcommand_matches("add").unwrap();
+    let coll      = scmd.value_of("collection").unwrap(); // enforced by clap
+    let unchecked = scmd.is_present("unchecked");
 
-    BookmarkCollection::get(rt.store(), coll)
-        .and_then(|mut collection| {
-            scmd.values_of("urls")
-                .unwrap() // enforced by clap
-                .fold_result(|url| collection.add_link(BookmarkLink::from(url)))
-        })
-        .map_err_trace()
-        .map_info_str("Ready")
-        .ok();
+    let mut collection = match BookmarkCollection::get(rt.store(), coll) {
+        Ok(collection) => collection,
+        Err(e)         => trace_error_exit(&e, 1),
+    };
+
+    let mut failed = false;
+    for url in scmd.values_of("urls").unwrap() { // enforced by clap
+        let link = BookmarkLink::from(url);
+        let res  = if unchecked {
+            collection.add_link_unchecked(link)
+        } else {
+            collection.add_link(link)
+        };
+
+        if let Err(e) = res {
+            error!("Could not add '{}'", url);
+            trace_error(&e);
+            failed = true;
+        }
+    }
+
+    if failed {
+        exit(1);
+    }
+    info!("Ready");
 }
 
 fn collection(rt: &Runtime) {
@@ -116,23 +138,49 @@ fn collection(rt: &Runtime) {
             exit(1);
         }
     }
+
+    if scmd.is_present("stats") { // print statistics about a collection
+        let name = scmd.value_of("stats").unwrap();
+        match BookmarkCollection::get(rt.store(), name).and_then(|c| c.stats()) {
+            Ok(stats) => {
+                println!("Links:        {}", stats.links);
+                println!("Unique hosts: {}", stats.unique_hosts);
+            },
+            Err(e) => trace_error_exit(&e, 1),
+        }
+    }
+
+    if scmd.is_present("list") { // list all collections
+        match BookmarkCollection::list(rt.store()) {
+            Ok(collections) => for (name, count) in collections {
+                println!("{} ({} links)", name, count);
+            },
+            Err(e) => trace_error_exit(&e, 1),
+        }
+    }
 }
 
 fn list(rt: &Runtime) {
-    let scmd = rt.cli().subcommand_matches("list").unwrap();
-    let coll = scmd.value_of("collection").unwrap(); // enforced by clap
+    let scmd   = rt.cli().subcommand_matches("list").unwrap();
+    let coll   = scmd.value_of("collection").unwrap(); // enforced by clap
+    let format = ListFormat::from_str(scmd.value_of("format").unwrap()); // enforced by clap
 
     BookmarkCollection::get(rt.store(), coll)
         .map(|collection| {
             match collection.links() {
                 Ok(links) => {
                     debug!("Listing...");
-                    for (i, link) in links.enumerate() {
-                        match link {
-                            Ok(link) => println!("{: >3}: {}", i, link),
-                            Err(e)   => trace_error(&e)
-                        }
-                    };
+                    let links : Vec<(usize, String)> = links
+                        .enumerate()
+                        .filter_map(|(i, link)| match link {
+                            Ok(link) => Some((i, format!("{}", link))),
+                            Err(e)   => {
+                                trace_error(&e);
+                                None
+                            },
+                        })
+                        .collect();
+                    print!("{}", format_links(&links, format));
                     debug!("... ready with listing");
                 },
                 Err(e) => trace_error_exit(&e, 1),
@@ -142,17 +190,270 @@ fn list(rt: &Runtime) {
     info!("Ready");
 }
 
+/// The output formats of the `list` subcommand
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ListFormat {
+    Plain,
+    Json,
+    UrlsOnly,
+}
+
+impl ListFormat {
+
+    /// Get the format for the value of the `--format` argument, defaulting to `ListFormat::Plain`
+    fn from_str(s: &str) -> ListFormat {
+        match s {
+            "json"      => ListFormat::Json,
+            "urls-only" => ListFormat::UrlsOnly,
+            _           => ListFormat::Plain,
+        }
+    }
+
+}
+
+/// Format the `(index, url)` pairs of a collection in `format`, one line per link
+///
+/// The `ListFormat::Json` format prints a single line with an array of `{index, url}` objects,
+/// which is `[]` for an empty collection.
+fn format_links(links: &[(usize, String)], format: ListFormat) -> String {
+    match format {
+        ListFormat::Plain => links
+            .iter()
+            .map(|&(i, ref url)| format!("{: >3}: {}\n", i, url))
+            .collect(),
+
+        ListFormat::UrlsOnly => links
+            .iter()
+            .map(|&(_, ref url)| format!("{}\n", url))
+            .collect(),
+
+        ListFormat::Json => {
+            let arr = links
+                .iter()
+                .map(|&(i, ref url)| {
+                    let mut obj = BTreeMap::new();
+                    obj.insert(String::from("index"), JsonValue::U64(i as u64));
+                    obj.insert(String::from("url"), JsonValue::String(url.clone()));
+                    JsonValue::Object(obj)
+                })
+                .collect();
+
+            format!("{}\n", JsonValue::Array(arr))
+        },
+    }
+}
+
+/// The number of links `open` opens at once without `--force`
+const OPEN_LIMIT : usize = 10;
+
+fn open(rt: &Runtime) {
+    let scmd    = rt.cli().subcommand_matches("open").unwrap();
+    let coll    = scmd.value_of("collection").unwrap(); // enforced by clap
+    let indices = scmd.values_of("indices")
+        .map(|v| v.filter_map(|i| i.parse::<usize>().ok()).collect::<Vec<_>>())
+        .unwrap_or_else(|| vec![]); // validated by clap
+
+    let links : Vec<(usize, String)> = match BookmarkCollection::get(rt.store(), coll)
+        .and_then(|c| c.links().map(|links| links.collect::<Vec<_>>()))
+    {
+        Ok(links) => links
+            .into_iter()
+            .enumerate()
+            .filter_map(|(i, link)| match link {
+                Ok(link) => Some((i, format!("{}", link))),
+                Err(e)   => {
+                    trace_error(&e);
+                    None
+                },
+            })
+            .collect(),
+        Err(e) => trace_error_exit(&e, 1),
+    };
+
+    let urls = match select_links(&links, &indices) {
+        Ok(urls) => urls,
+        Err(i)   => {
+            error!("No link with index {} in collection '{}'", i, coll);
+            exit(1);
+        },
+    };
+
+    if urls.len() > OPEN_LIMIT && !scmd.is_present("force") {
+        error!("Refusing to open {} links at once, use --force to open them anyways", urls.len());
+        exit(1);
+    }
+
+    let failed = launch_all(&urls, launch_url);
+    for &(ref url, ref e) in failed.iter() {
+        error!("Could not open '{}'", url);
+        trace_error(e);
+    }
+
+    if !failed.is_empty() {
+        exit(1);
+    }
+    info!("Ready");
+}
+
+/// Get the urls of the links with the `indices`, or of all links if no indices are given
+///
+/// Returns the first index for which there is no link as error.
+fn select_links(links: &[(usize, String)], indices: &[usize]) -> Result<Vec<String>, usize> {
+    if indices.is_empty() {
+        return Ok(links.iter().map(|&(_, ref url)| url.clone()).collect());
+    }
+
+    indices
+        .iter()
+        .map(|i| {
+            links.iter()
+                .find(|&&(idx, _)| idx == *i)
+                .map(|&(_, ref url)| url.clone())
+                .ok_or(*i)
+        })
+        .collect()
+}
+
+/// Open all `urls` with `launch`, returning the urls which could not be opened with the error
+fn launch_all<F>(urls: &[String], mut launch: F) -> Vec<(String, ::std::io::Error)>
+    where F: FnMut(&str) -> IoResult<()>
+{
+    urls.iter()
+        .filter_map(|url| launch(url).err().map(|e| (url.clone(), e)))
+        .collect()
+}
+
+#[cfg(feature = "open-crate")]
+fn launch_url(url: &str) -> IoResult<()> {
+    ::open::that(url).map(|_| ())
+}
+
+#[cfg(not(feature = "open-crate"))]
+fn launch_url(url: &str) -> IoResult<()> {
+    use std::io::Error as IoError;
+    use std::io::ErrorKind;
+    use std::process::Command;
+
+    Command::new("xdg-open")
+        .arg(url)
+        .status()
+        .and_then(|status| if status.success() {
+            Ok(())
+        } else {
+            Err(IoError::new(ErrorKind::Other, format!("xdg-open exited with {}", status)))
+        })
+}
+
 fn remove(rt: &Runtime) {
     let scmd = rt.cli().subcommand_matches("remove").unwrap();
     let coll = scmd.value_of("collection").unwrap(); // enforced by clap
 
-    BookmarkCollection::get(rt.store(), coll)
-        .map(|mut collection| {
-            for url in scmd.values_of("urls").unwrap() { // enforced by clap
-                collection.remove_link(BookmarkLink::from(url)).map_err(|e| trace_error(&e)).ok();
-            }
-        })
-        .ok();
+    let mut collection = match BookmarkCollection::get(rt.store(), coll) {
+        Ok(collection) => collection,
+        Err(e)         => trace_error_exit(&e, 1),
+    };
+
+    let mut failed = false;
+    for url in scmd.values_of("urls").unwrap() { // enforced by clap
+        match collection.remove_link(BookmarkLink::from(url)) {
+            Ok(true)  => println!("removed:   {}", url),
+            Ok(false) => println!("not found: {}", url),
+            Err(e)    => {
+                println!("error:     {}", url);
+                trace_error(&e);
+                failed = true;
+            },
+        }
+    }
+
+    if failed {
+        exit(1);
+    }
     info!("Ready");
 }
 
+#[cfg(test)]
+mod test {
+    use serde_json::Value as JsonValue;
+    use serde_json::de::from_str as json_from_str;
+
+    use super::ListFormat;
+    use super::format_links;
+    use super::launch_all;
+    use super::select_links;
+
+    fn links() -> Vec<(usize, String)> {
+        vec![
+            (0, String::from("https://imag-pim.org/")),
+            (1, String::from("https://github.com/matthiasbeyer/imag")),
+        ]
+    }
+
+    #[test]
+    fn test_format_plain() {
+        assert_eq!(format_links(&links(), ListFormat::Plain),
+                   "  0: https://imag-pim.org/\n  1: https://github.com/matthiasbeyer/imag\n");
+        assert_eq!(format_links(&[], ListFormat::Plain), "");
+    }
+
+    #[test]
+    fn test_format_urls_only() {
+        assert_eq!(format_links(&links(), ListFormat::UrlsOnly),
+                   "https://imag-pim.org/\nhttps://github.com/matthiasbeyer/imag\n");
+        assert_eq!(format_links(&[], ListFormat::UrlsOnly), "");
+    }
+
+    #[test]
+    fn test_format_json() {
+        let json : JsonValue = json_from_str(&format_links(&links(), ListFormat::Json)).unwrap();
+        let arr = json.as_array().unwrap();
+
+        assert_eq!(arr.len(), 2);
+        assert_eq!(arr[0].find("index").and_then(|v| v.as_u64()), Some(0));
+        assert_eq!(arr[0].find("url").and_then(|v| v.as_str()), Some("https://imag-pim.org/"));
+        assert_eq!(arr[1].find("index").and_then(|v| v.as_u64()), Some(1));
+        assert_eq!(arr[1].find("url").and_then(|v| v.as_str()),
+                   Some("https://github.com/matthiasbeyer/imag"));
+    }
+
+    #[test]
+    fn test_format_json_empty() {
+        assert_eq!(format_links(&[], ListFormat::Json), "[]\n");
+    }
+
+    #[test]
+    fn test_open_dispatches_selected_links() {
+        let mut dispatched = vec![];
+        let urls   = select_links(&links(), &[1, 0]).unwrap();
+        let failed = launch_all(&urls, |url| {
+            dispatched.push(String::from(url));
+            Ok(())
+        });
+
+        assert!(failed.is_empty());
+        assert_eq!(dispatched, vec!["https://github.com/matthiasbeyer/imag", "https://imag-pim.org/"]);
+    }
+
+    #[test]
+    fn test_open_selects_all_links_without_indices() {
+        assert_eq!(select_links(&links(), &[]).unwrap().len(), 2);
+        assert_eq!(select_links(&links(), &[0, 5]), Err(5));
+    }
+
+    #[test]
+    fn test_open_reports_failing_links() {
+        use std::io::Error as IoError;
+        use std::io::ErrorKind;
+
+        let urls   = select_links(&links(), &[]).unwrap();
+        let failed = launch_all(&urls, |url| if url.contains("github") {
+            Err(IoError::new(ErrorKind::Other, "no browser"))
+        } else {
+            Ok(())
+        });
+
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].0, "https://github.com/matthiasbeyer/imag");
+    }
+
+}
@@ -42,8 +42,12 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
                         .required(true)
                         .multiple(true)
                         .value_name("URL")
-                        .validator(is_url)
                         .help("Add this URL, multiple possible"))
+                   .arg(Arg::with_name("unchecked")
+                        .long("unchecked")
+                        .takes_value(false)
+                        .required(false)
+                        .help("Do not validate the URLs (but they still have to be URLs), for unusual schemes"))
                    .arg(tag_add_arg())
                    )
 
@@ -69,18 +73,30 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
                         .help("Remove these urls, regex supported"))
                    )
 
-        // .subcommand(SubCommand::with_name("open")
-        //            .about("Open bookmarks (via xdg-open)")
-        //            .version("0.1")
-        //            .arg(Arg::with_name("collection")
-        //                 .long("collection")
-        //                 .short("c")
-        //                 .takes_value(true)
-        //                 .required(true)
-        //                 .multiple(false)
-        //                 .value_name("COLLECTION")
-        //                 .help("Select from this collection"))
-        //            )
+        .subcommand(SubCommand::with_name("open")
+                   .about("Open bookmarks (via xdg-open)")
+                   .version("0.1")
+                   .arg(Arg::with_name("collection")
+                        .long("collection")
+                        .short("c")
+                        .takes_value(true)
+                        .required(true)
+                        .multiple(false)
+                        .value_name("COLLECTION")
+                        .help("Select from this collection"))
+                   .arg(Arg::with_name("indices")
+                        .index(1)
+                        .takes_value(true)
+                        .required(false)
+                        .multiple(true)
+                        .value_name("INDEX")
+                        .validator(|s| s.parse::<usize>().map(|_| ()).map_err(|e| format!("{}", e)))
+                        .help("Open the links with these indices (as printed by 'list'). Opens all links if none are given"))
+                   .arg(Arg::with_name("force")
+                        .long("force")
+                        .short("f")
+                        .help("Open the links even if there are many of them"))
+                   )
 
         .subcommand(SubCommand::with_name("list")
                    .about("List bookmarks")
@@ -101,6 +117,16 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
                         .multiple(true)
                         .value_name("TAGS")
                         .help("Filter links to contain these tags. When multiple tags are specified, all of them must be set for the link to match."))
+                   .arg(Arg::with_name("format")
+                        .long("format")
+                        .short("f")
+                        .takes_value(true)
+                        .required(false)
+                        .multiple(false)
+                        .value_name("FORMAT")
+                        .possible_values(&["plain", "json", "urls-only"])
+                        .default_value("plain")
+                        .help("Output format. 'json' prints an array of {index, url} objects, 'urls-only' prints one URL per line"))
                    )
 
         .subcommand(SubCommand::with_name("collection")
@@ -118,5 +144,16 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
                         .takes_value(true)
                         .value_name("NAME")
                         .help("Remove a collection with this name (and all links)"))
+                   .arg(Arg::with_name("stats")
+                        .long("stats")
+                        .short("s")
+                        .takes_value(true)
+                        .value_name("NAME")
+                        .help("Print statistics about the collection with this name"))
+                   .arg(Arg::with_name("list")
+                        .long("list")
+                        .short("l")
+                        .takes_value(false)
+                        .help("List all collections with the number of links in them"))
                    )
 }
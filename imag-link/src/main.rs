@@ -71,6 +71,14 @@ fn main() {
                                     "Link entries",
                                     build_ui);
 
+    if rt.cli().is_present("check") {
+        return check_links(&rt);
+    }
+
+    if rt.cli().is_present("graph") {
+        return print_graph(&rt);
+    }
+
     rt.cli()
         .subcommand_name()
         .map(|name| {
@@ -82,6 +90,44 @@ fn main() {
         });
 }
 
+fn check_links(rt: &Runtime) {
+    use libimagentrylink::consistency::check_link_consistency;
+    use libimagentrylink::consistency::repair_link_inconsistencies;
+    use libimagentrylink::consistency::LinkInconsistency as LI;
+
+    let inconsistencies = match check_link_consistency(rt.store()) {
+        Ok(incs) => incs,
+        Err(e)   => trace_error_exit(&e, 1),
+    };
+
+    for inc in inconsistencies.iter() {
+        match *inc {
+            LI::TargetMissing { ref from, ref to } => {
+                println!("{} -> {}: link target does not exist", from, to)
+            },
+            LI::NoBackLink { ref from, ref to } => {
+                println!("{} -> {}: no link back", from, to)
+            },
+        }
+    }
+
+    if rt.cli().is_present("repair") {
+        let _ = repair_link_inconsistencies(rt.store(), &inconsistencies).map_err_trace_exit(1);
+        info!("Removed {} inconsistent links", inconsistencies.len());
+    } else if !inconsistencies.is_empty() {
+        ::std::process::exit(1);
+    }
+}
+
+fn print_graph(rt: &Runtime) {
+    use std::io::stdout;
+    use libimagentrylink::graph::export_link_graph_dot_for_module;
+
+    let module = rt.cli().value_of("module");
+    let stdout = stdout();
+    let _ = export_link_graph_dot_for_module(rt.store(), stdout.lock(), module).map_err_trace_exit(1);
+}
+
 fn handle_internal_linking(rt: &Runtime) {
     use libimagentrylink::internal::InternalLinker;
     use libimagentrylink::external::is_external_link_storeid;
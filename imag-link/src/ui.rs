@@ -21,6 +21,34 @@ use clap::{Arg, ArgGroup, App, SubCommand};
 
 pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
     app
+        .arg(Arg::with_name("check")
+             .long("check")
+             .takes_value(false)
+             .required(false)
+             .help("Check all internal links in the store for links to non-existing entries and links which are not linked back"))
+
+        .arg(Arg::with_name("repair")
+             .long("repair")
+             .takes_value(false)
+             .required(false)
+             .requires("check")
+             .help("With --check, remove the inconsistent links found"))
+
+        .arg(Arg::with_name("graph")
+             .long("graph")
+             .takes_value(false)
+             .required(false)
+             .conflicts_with("check")
+             .help("Print the internal links of all entries as Graphviz DOT graph"))
+
+        .arg(Arg::with_name("module")
+             .long("module")
+             .takes_value(true)
+             .required(false)
+             .requires("graph")
+             .help("With --graph, only print the entries of this module")
+             .value_name("MODULE"))
+
         .subcommand(SubCommand::with_name("internal")
                     .about("Add, remove and list internal links")
                     .version("0.1")
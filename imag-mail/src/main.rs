@@ -29,12 +29,24 @@ extern crate libimagmail;
 extern crate libimagerror;
 extern crate libimagutil;
 extern crate libimagref;
+extern crate libimagentrylist;
+extern crate libimagstore;
 
+#[cfg(test)] extern crate tempdir;
+
+use std::collections::HashMap;
+
+use libimagentrylist::lister::Lister;
+use libimagentrylist::listers::table::TableLister;
 use libimagerror::trace::{MapErrTrace, trace_error, trace_error_exit};
+use libimagmail::import::{ImportReport, MailImporter};
 use libimagmail::mail::Mail;
+use libimagmail::stats::MailStats;
+use libimagmail::thread::{ThreadBuilder, ThreadNode};
 use libimagref::reference::Ref;
 use libimagrt::runtime::Runtime;
 use libimagrt::setup::generate_runtime_setup;
+use libimagstore::store::FileLockEntry;
 use libimagutil::debug_result::*;
 use libimagutil::info_result::*;
 
@@ -54,7 +66,14 @@ fn main() {
             debug!("Call {}", name);
             match name {
                 "import-mail" => import_mail(&rt),
+                "import"      => import(&rt),
                 "list"        => list(&rt),
+                "search"      => search(&rt),
+                "attachments" => attachments(&rt),
+                "export"      => export(&rt),
+                "reply"       => reply(&rt),
+                "thread"      => thread(&rt),
+                "stats"       => stats(&rt),
                 "mail-store"  => mail_store(&rt),
                 _             => debug!("Unknown command") // More error handling
             }
@@ -62,12 +81,58 @@ fn main() {
 }
 
 fn import_mail(rt: &Runtime) {
+    use std::path::Path;
+
     let scmd = rt.cli().subcommand_matches("import-mail").unwrap();
     let path = scmd.value_of("path").unwrap(); // enforced by clap
 
-    Mail::import_from_path(rt.store(), path)
-        .map_err_trace()
-        .map_info_str("Ok");
+    if Path::new(path).is_dir() {
+        let importer = MailImporter::new().dedup(scmd.is_present("dedup"));
+        import_dir(rt, path, importer);
+    } else if scmd.is_present("dedup") {
+        match Mail::import_from_path_dedup(rt.store(), path) {
+            Ok(Some(_)) => info!("Imported: 1, skipped: 0"),
+            Ok(None)    => info!("Imported: 0, skipped: 1"),
+            Err(e)      => trace_error_exit(&e, 1),
+        }
+    } else {
+        Mail::import_from_path(rt.store(), path)
+            .map_err_trace()
+            .map_info_str("Ok");
+    }
+}
+
+fn import(rt: &Runtime) {
+    let scmd     = rt.cli().subcommand_matches("import").unwrap();
+    let dir      = scmd.value_of("dir").unwrap(); // enforced by clap
+    let importer = MailImporter::new()
+        .dedup(scmd.is_present("dedup"))
+        .follow_links(scmd.is_present("follow-links"));
+
+    import_dir(rt, dir, importer);
+}
+
+/// Import the directory `dir` with `importer`, print the report and exit with 1 if any mail failed
+fn import_dir(rt: &Runtime, dir: &str, importer: MailImporter) {
+    use std::io::Write;
+    use std::io::stderr;
+    use std::process::exit;
+
+    let report = match importer.import(rt.store(), dir) {
+        Ok(results) => ImportReport::from_results(results.into_iter()),
+        Err(e)      => trace_error_exit(&e, 1),
+    };
+
+    for &(ref path, ref e) in report.failed() {
+        let _ = writeln!(stderr(), "Failed: {}", path.display());
+        trace_error(e);
+    }
+
+    println!("{}", report);
+
+    if !report.failed().is_empty() {
+        exit(1);
+    }
 }
 
 fn list(rt: &Runtime) {
@@ -79,73 +144,360 @@ fn list(rt: &Runtime) {
     let do_check_changed         = scmd.is_present("check-changed");
     let do_check_changed_content = scmd.is_present("check-changed-content");
     let do_check_changed_permiss = scmd.is_present("check-changed-permissions");
+    let sort_by_date             = scmd.value_of("sort") == Some("date");
     let store = rt.store();
 
-    let iter = match store.retrieve_for_module("ref") {
-        Ok(iter) => iter.filter_map(|id| {
-            Ref::get(store, id)
-                .map_err_into(MEK::RefHandlingError)
-                .and_then(|rf| Mail::from_ref(rf))
-                .map_err_trace()
-                .ok()
-        }),
+    // The TableLister works on the entries, so the rows are built from the mails beforehand.
+    // Mails which cannot be parsed are listed, too, so they do not silently vanish from the list.
+    let mut mails = match store.retrieve_for_module("ref") {
+        Ok(iter) => iter
+            .filter_map(|id| {
+                let mail = Ref::get(store, id.clone())
+                    .map_err_into(MEK::RefHandlingError)
+                    .and_then(|rf| Mail::from_ref_headers_only(rf));
+
+                match mail {
+                    Ok(mail) => {
+                        let date = mail.get_date().ok().and_then(|d| d);
+                        let row  = mail_row(&mail);
+                        let rf : Ref = mail.into();
+                        let fle : FileLockEntry = rf.into();
+                        Some((date, row, fle))
+                    },
+                    Err(e) => {
+                        trace_error(&e);
+                        store.get(id)
+                            .map_err_trace()
+                            .ok()
+                            .and_then(|o| o)
+                            .map(|fle| (None, unparseable_row(), fle))
+                    },
+                }
+            })
+            .collect::<Vec<_>>(),
         Err(e)   => trace_error_exit(&e, 1),
     };
 
-    fn list_mail(m: Mail) {
-        let id = match m.get_message_id() {
-            Ok(Some(f)) => f,
-            Ok(None) => "<no id>".to_owned(),
-            Err(e) => {
-                trace_error(&e);
-                "<error>".to_owned()
-            },
-        };
-
-        let from = match m.get_from() {
-            Ok(Some(f)) => f,
-            Ok(None) => "<no from>".to_owned(),
-            Err(e) => {
-                trace_error(&e);
-                "<error>".to_owned()
-            },
-        };
-
-        let to = match m.get_to() {
-            Ok(Some(f)) => f,
-            Ok(None) => "<no to>".to_owned(),
-            Err(e) => {
-                trace_error(&e);
-                "<error>".to_owned()
-            },
-        };
-
-        let subject = match m.get_subject() {
-            Ok(Some(f)) => f,
-            Ok(None) => "<no subject>".to_owned(),
-            Err(e) => {
-                trace_error(&e);
-                "<error>".to_owned()
-            },
-        };
-
-        println!("Mail: {id}\n\tFrom: {from}\n\tTo: {to}\n\t{subj}\n",
-                 from = from,
-                 id   = id,
-                 subj = subject,
-                 to   = to
-        );
-    }
-
-    // TODO: Implement lister type in libimagmail for this
-    for mail in iter {
-        list_mail(mail)
+    if sort_by_date {
+        // `None` sorts before `Some(_)`, so compare the negation to put mails without date last
+        mails.sort_by(|a, b| (a.0.is_none(), a.0).cmp(&(b.0.is_none(), b.0)));
+    }
+
+    let mut rows = HashMap::new();
+    let mut entries = vec![];
+    for (_, row, fle) in mails {
+        rows.insert(fle.get_location().clone(), row);
+        entries.push(fle);
+    }
+
+    let header = vec!["Date", "From", "Subject"].into_iter().map(String::from).collect();
+    let _ = TableLister::new(|fle: &FileLockEntry| {
+            rows.get(fle.get_location()).cloned().unwrap_or_else(|| vec![])
+        })
+        .with_header(header)
+        .list(entries.into_iter())
+        .map_err_trace_exit(1);
+}
+
+/// Build the table row (Date, From, Subject) for a mail for `imag-mail list`
+///
+/// If the headers of the mail cannot be parsed, the row reads `<unparseable>`, so a single broken
+/// mail does not abort the listing.
+fn mail_row(m: &Mail) -> Vec<String> {
+    match (m.get_date(), m.get_from(), m.get_subject()) {
+        (Ok(date), Ok(from), Ok(subject)) => vec![
+            date.map(|d| d.format("%Y-%m-%d %H:%M").to_string())
+                .unwrap_or_else(|| "<no date>".to_owned()),
+            from.unwrap_or_else(|| "<no from>".to_owned()),
+            subject.unwrap_or_else(|| "<no subject>".to_owned()),
+        ],
+        (Err(e), _, _) | (_, Err(e), _) | (_, _, Err(e)) => {
+            trace_error(&e);
+            unparseable_row()
+        },
+    }
+}
+
+fn unparseable_row() -> Vec<String> {
+    vec!["<unparseable>".to_owned(), String::new(), String::new()]
+}
+
+fn search(rt: &Runtime) {
+    let scmd = rt.cli().subcommand_matches("search").unwrap();
+
+    let (field, needle) = vec![("From", "from"), ("To", "to"), ("Subject", "subject")]
+        .into_iter()
+        .filter_map(|(field, arg)| scmd.value_of(arg).map(|needle| (field, needle)))
+        .next()
+        .unwrap(); // enforced by clap
+
+    match Mail::search_store(rt.store(), field, needle) {
+        Ok(mails) => for mail in mails {
+            list_mail(mail)
+        },
+        Err(e) => trace_error_exit(&e, 1),
+    }
+}
+
+fn attachments(rt: &Runtime) {
+    use std::fs::File;
+    use std::path::PathBuf;
+    use std::process::exit;
+
+    let scmd        = rt.cli().subcommand_matches("attachments").unwrap();
+    let hash        = scmd.value_of("hash").unwrap(); // enforced by clap
+    let extract_dir = scmd.value_of("extract").map(PathBuf::from);
+
+    let mail = match Mail::open(rt.store(), hash) {
+        Ok(Some(mail)) => mail,
+        Ok(None) => {
+            error!("No mail with hash '{}' found", hash);
+            exit(1);
+        },
+        Err(e) => trace_error_exit(&e, 1),
+    };
+
+    let attachments = match mail.attachments() {
+        Ok(attachments) => attachments,
+        Err(e)          => trace_error_exit(&e, 1),
+    };
+
+    for (i, attachment) in attachments.iter().enumerate() {
+        let name = attachment_file_name(attachment.filename(), i);
+
+        println!("{: >3}: {} ({})", i, name, attachment.content_type());
+
+        if let Some(ref dir) = extract_dir {
+            let mut path = dir.clone();
+            path.push(&name);
+
+            match File::create(&path) {
+                Ok(mut file) => {
+                    let _ = attachment.write_to(&mut file).map_err_trace();
+                },
+                Err(e) => trace_error(&e),
+            }
+        }
+    }
+}
+
+/// Get a file name for the `i`th attachment which is safe to create inside the extract directory
+///
+/// Only the last component of the name the mail suggests is used, so an attachment cannot be
+/// written outside of the extract directory. Falls back to "attachment-<i>".
+fn attachment_file_name(suggested: Option<&String>, i: usize) -> String {
+    use std::path::Path;
+
+    suggested
+        .and_then(|name| Path::new(name).file_name())
+        .and_then(|name| name.to_str())
+        .and_then(|name| if name.is_empty() || name == ".." { None } else { Some(String::from(name)) })
+        .unwrap_or_else(|| format!("attachment-{}", i))
+}
+
+fn export(rt: &Runtime) {
+    use std::process::exit;
+
+    let scmd = rt.cli().subcommand_matches("export").unwrap();
+    let hash = scmd.value_of("hash").unwrap(); // enforced by clap
+    let file = scmd.value_of("file").unwrap(); // enforced by clap
+
+    match Mail::open(rt.store(), hash) {
+        Ok(Some(mail)) => {
+            let _ = mail.export_to_path(file).map_err_trace_exit(1);
+        },
+        Ok(None) => {
+            error!("No mail with hash '{}' found", hash);
+            exit(1);
+        },
+        Err(e) => trace_error_exit(&e, 1),
+    }
+}
+
+fn reply(rt: &Runtime) {
+    use std::process::exit;
+
+    let scmd = rt.cli().subcommand_matches("reply").unwrap();
+    let hash = scmd.value_of("hash").unwrap(); // enforced by clap
+
+    match Mail::open(rt.store(), hash) {
+        Ok(Some(mail)) => {
+            let reply = mail.build_reply().map_err_trace_exit(1).unwrap();
+            println!("{}", reply);
+        },
+        Ok(None) => {
+            error!("No mail with hash '{}' found", hash);
+            exit(1);
+        },
+        Err(e) => trace_error_exit(&e, 1),
+    }
+}
+
+fn thread(rt: &Runtime) {
+    use std::process::exit;
+    use libimagmail::error::MailErrorKind as MEK;
+    use libimagmail::error::MapErrInto;
+
+    let scmd  = rt.cli().subcommand_matches("thread").unwrap();
+    let msgid = scmd.value_of("message-id").unwrap(); // enforced by clap
+    let store = rt.store();
+
+    let mut builder = ThreadBuilder::new()
+        .ignore_nomsgid(scmd.is_present("ignore-no-msgid"))
+        .link_by_subject(scmd.is_present("link-by-subject"));
+    match store.retrieve_for_module("ref") {
+        Ok(iter) => for id in iter {
+            let mail = Ref::get(store, id)
+                .map_err_into(MEK::RefHandlingError)
+                .and_then(|rf| Mail::from_ref_headers_only(rf))
+                .map_err_trace(); // a broken mail should not prevent showing the thread
+
+            if let Ok(mail) = mail {
+                // fails for mails without Message-ID, unless --ignore-no-msgid was passed
+                if let Err(e) = builder.add_mail(&mail) {
+                    trace_error_exit(&e, 1);
+                }
+            }
+        },
+        Err(e) => trace_error_exit(&e, 1),
+    }
+
+    fn print_node(node: &ThreadNode, depth: usize) {
+        let indent : String = ::std::iter::repeat("  ").take(depth).collect();
+        println!("{}{}", indent, node.message_id());
+        for reply in node.replies() {
+            print_node(reply, depth + 1);
+        }
+    }
+
+    let threads = builder.threads();
+    match threads.iter().filter(|t| t.find(msgid).is_some()).next() {
+        Some(thread) => print_node(thread, 0),
+        None => {
+            error!("No mail with Message-ID '{}' found", msgid);
+            exit(1);
+        },
     }
 }
 
+fn stats(rt: &Runtime) {
+    let scmd = rt.cli().subcommand_matches("stats").unwrap();
+    let top  = scmd.value_of("top").and_then(|s| s.parse::<usize>().ok()).unwrap_or(10);
+
+    let stats = MailStats::collect(rt.store()).map_err_trace_exit(1).unwrap();
+
+    println!("Mails:              {}", stats.total());
+    println!("Without Message-ID: {}", stats.without_message_id());
+    println!("Parse failures:     {}", stats.parse_failures());
+
+    println!();
+    println!("Top senders:");
+    for (sender, count) in stats.top_senders(top) {
+        println!("{:>6} {}", count, sender);
+    }
+
+    println!();
+    println!("Per year:");
+    for (year, count) in stats.years() {
+        println!("{:>6} {}", count, year);
+    }
+}
+
+fn list_mail(m: Mail) {
+    let id = match m.get_message_id() {
+        Ok(Some(f)) => f,
+        Ok(None) => "<no id>".to_owned(),
+        Err(e) => {
+            trace_error(&e);
+            "<error>".to_owned()
+        },
+    };
+
+    let from = match m.get_from() {
+        Ok(Some(f)) => f,
+        Ok(None) => "<no from>".to_owned(),
+        Err(e) => {
+            trace_error(&e);
+            "<error>".to_owned()
+        },
+    };
+
+    let to = match m.get_to() {
+        Ok(Some(f)) => f,
+        Ok(None) => "<no to>".to_owned(),
+        Err(e) => {
+            trace_error(&e);
+            "<error>".to_owned()
+        },
+    };
+
+    let subject = match m.get_subject() {
+        Ok(Some(f)) => f,
+        Ok(None) => "<no subject>".to_owned(),
+        Err(e) => {
+            trace_error(&e);
+            "<error>".to_owned()
+        },
+    };
+
+    println!("Mail: {id}\n\tFrom: {from}\n\tTo: {to}\n\t{subj}\n",
+             from = from,
+             id   = id,
+             subj = subject,
+             to   = to
+    );
+}
+
 fn mail_store(rt: &Runtime) {
     let scmd = rt.cli().subcommand_matches("mail-store").unwrap();
     error!("This feature is currently not implemented.");
     unimplemented!()
 }
 
+#[cfg(test)]
+mod test {
+    use std::fs::File;
+    use std::io::Write;
+    use std::path::PathBuf;
+
+    use tempdir::TempDir;
+
+    use libimagmail::mail::Mail;
+    use libimagstore::store::Store;
+
+    use super::attachment_file_name;
+    use super::mail_row;
+
+    #[test]
+    fn test_mail_row() {
+        let tmp  = TempDir::new("imag-mail-test").unwrap();
+        let path = tmp.path().join("mail.eml");
+        {
+            let mut file = File::create(&path).unwrap();
+            write!(file, "From: alice@example.com\r\n\
+                          To: bob@example.com\r\n\
+                          Subject: Hello\r\n\
+                          Date: Tue, 1 Jul 2003 10:52:37 +0200\r\n\
+                          \r\n\
+                          Hello\r\n").unwrap();
+        }
+
+        let store = Store::new_in_memory(PathBuf::from("/"), None).unwrap();
+        let mail  = Mail::import_from_path(&store, &path).unwrap();
+
+        assert_eq!(mail_row(&mail), vec!["2003-07-01 10:52", "alice@example.com", "Hello"]);
+    }
+
+    #[test]
+    fn test_attachment_file_name() {
+        let name = |s: &str| attachment_file_name(Some(&String::from(s)), 3);
+
+        assert_eq!(name("report.pdf"), "report.pdf");
+        assert_eq!(name("../../.bashrc"), ".bashrc");
+        assert_eq!(name("/etc/passwd"), "passwd");
+        assert_eq!(name(".."), "attachment-3");
+        assert_eq!(name(""), "attachment-3");
+        assert_eq!(attachment_file_name(None, 3), "attachment-3");
+    }
+
+}
@@ -31,6 +31,29 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
                          .required(true)
                          .help("Path to the mail file or a directory which is then searched recursively")
                          .value_name("PATH"))
+                    .arg(Arg::with_name("dedup")
+                         .long("dedup")
+                         .short("d")
+                         .help("Skip mails whose Message-ID was already imported"))
+                    )
+
+        .subcommand(SubCommand::with_name("import")
+                    .about("Import all mails from a directory (recursively) and report the results")
+                    .version("0.1")
+                    .arg(Arg::with_name("dir")
+                         .index(1)
+                         .takes_value(true)
+                         .required(true)
+                         .help("Directory to import the mails from, for example a Maildir")
+                         .value_name("DIR"))
+                    .arg(Arg::with_name("dedup")
+                         .long("dedup")
+                         .short("d")
+                         .help("Skip mails whose Message-ID was already imported"))
+                    .arg(Arg::with_name("follow-links")
+                         .long("follow-links")
+                         .short("L")
+                         .help("Follow symlinks to directories"))
                     )
 
         .subcommand(SubCommand::with_name("list")
@@ -60,6 +83,123 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
                          .short("P")
                          .help("Check whether the permissions of the referenced file changed"))
 
+                    .arg(Arg::with_name("sort")
+                         .long("sort")
+                         .short("s")
+                         .takes_value(true)
+                         .required(false)
+                         .possible_values(&["date"])
+                         .help("Sort the mails, mails without a (valid) Date header come last")
+                         .value_name("KEY"))
+
+                    )
+
+        .subcommand(SubCommand::with_name("search")
+                    .about("Search mails by header field (case-insensitive)")
+                    .version("0.1")
+                    .arg(Arg::with_name("from")
+                         .long("from")
+                         .short("f")
+                         .takes_value(true)
+                         .required(false)
+                         .help("Search mails where the From header contains this")
+                         .value_name("FROM"))
+                    .arg(Arg::with_name("to")
+                         .long("to")
+                         .short("t")
+                         .takes_value(true)
+                         .required(false)
+                         .help("Search mails where the To header contains this")
+                         .value_name("TO"))
+                    .arg(Arg::with_name("subject")
+                         .long("subject")
+                         .short("s")
+                         .takes_value(true)
+                         .required(false)
+                         .help("Search mails where the Subject header contains this")
+                         .value_name("SUBJECT"))
+                    .group(ArgGroup::with_name("field")
+                           .args(&["from", "to", "subject"])
+                           .required(true))
+                    )
+
+        .subcommand(SubCommand::with_name("attachments")
+                    .about("List (and extract) the attachments of a mail")
+                    .version("0.1")
+                    .arg(Arg::with_name("hash")
+                         .index(1)
+                         .takes_value(true)
+                         .required(true)
+                         .help("Hash of the mail")
+                         .value_name("HASH"))
+                    .arg(Arg::with_name("extract")
+                         .long("extract")
+                         .short("e")
+                         .takes_value(true)
+                         .required(false)
+                         .help("Extract the attachments into this directory")
+                         .value_name("DIR"))
+                    )
+
+        .subcommand(SubCommand::with_name("export")
+                    .about("Export a mail to a file")
+                    .version("0.1")
+                    .arg(Arg::with_name("hash")
+                         .index(1)
+                         .takes_value(true)
+                         .required(true)
+                         .help("Hash of the mail")
+                         .value_name("HASH"))
+                    .arg(Arg::with_name("file")
+                         .index(2)
+                         .takes_value(true)
+                         .required(true)
+                         .help("File to write the mail to")
+                         .value_name("FILE"))
+                    )
+
+        .subcommand(SubCommand::with_name("reply")
+                    .about("Print a reply skeleton for a mail")
+                    .version("0.1")
+                    .arg(Arg::with_name("hash")
+                         .index(1)
+                         .takes_value(true)
+                         .required(true)
+                         .help("Hash of the mail to reply to")
+                         .value_name("HASH"))
+                    )
+
+        .subcommand(SubCommand::with_name("thread")
+                    .about("Show the thread a mail is part of")
+                    .version("0.1")
+                    .arg(Arg::with_name("message-id")
+                         .index(1)
+                         .takes_value(true)
+                         .required(true)
+                         .help("Message-ID of a mail in the thread")
+                         .value_name("MSGID"))
+                    .arg(Arg::with_name("ignore-no-msgid")
+                         .long("ignore-no-msgid")
+                         .short("I")
+                         .help("Skip mails without Message-ID instead of failing"))
+                    .arg(Arg::with_name("link-by-subject")
+                         .long("link-by-subject")
+                         .short("S")
+                         .help("Link mails without Message-ID to mails with the same subject"))
+                    )
+
+        .subcommand(SubCommand::with_name("stats")
+                    .about("Show statistics about all mails")
+                    .version("0.1")
+                    .arg(Arg::with_name("top")
+                         .long("top")
+                         .short("n")
+                         .takes_value(true)
+                         .required(false)
+                         .default_value("10")
+                         .validator(|s| s.parse::<usize>().map(|_| ()).map_err(|e| format!("{}", e)))
+                         .help("Number of senders to show")
+                         .value_name("N"))
                     )
 
         .subcommand(SubCommand::with_name("mail-store")
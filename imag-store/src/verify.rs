@@ -18,9 +18,12 @@
 //
 
 use libimagrt::runtime::Runtime;
+use libimagerror::trace::trace_error;
 use libimagutil::warn_exit::warn_exit;
 
 pub fn verify(rt: &Runtime) {
+    report_id_collisions(rt);
+
     if rt.store().verify() {
         info!("Store seems to be fine");
     } else {
@@ -28,3 +31,18 @@ pub fn verify(rt: &Runtime) {
     }
 }
 
+/// Warn about entries of different modules which share the same module-relative id
+///
+/// Collisions do not make the store broken, so they are only reported.
+fn report_id_collisions(rt: &Runtime) {
+    match rt.store().find_id_collisions() {
+        Ok(collisions) => for (id, paths) in collisions {
+            warn!("Id collision for '{}' in:", id);
+            for path in paths {
+                warn!("    {}", path.display());
+            }
+        },
+        Err(e) => trace_error(&e),
+    }
+}
+
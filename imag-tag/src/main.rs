@@ -33,6 +33,7 @@ use std::path::PathBuf;
 
 use libimagrt::runtime::Runtime;
 use libimagrt::setup::generate_runtime_setup;
+use libimagentrytag::ops::collect_tags;
 use libimagentrytag::tagable::Tagable;
 use libimagentrytag::tag::Tag;
 use libimagerror::trace::{trace_error, trace_error_exit};
@@ -50,6 +51,10 @@ fn main() {
                                     "Direct interface to the store. Use with great care!",
                                     build_ui);
 
+    if rt.cli().is_present("list-all-tags") {
+        return list_all_tags(&rt);
+    }
+
     let id = rt.cli().value_of("id").unwrap(); // enforced by clap
     rt.cli()
         .subcommand_name()
@@ -169,3 +174,11 @@ fn list(id: PathBuf, rt: &Runtime) {
     }
 }
 
+fn list_all_tags(rt: &Runtime) {
+    match collect_tags(rt.store()) {
+        Ok(tags) => for (tag, count) in tags {
+            println!("{}: {}", tag, count);
+        },
+        Err(e) => trace_error_exit(&e, 1),
+    }
+}
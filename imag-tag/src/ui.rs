@@ -26,10 +26,17 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
                 .long("id")
                 .short("i")
                 .takes_value(true)
-                .required(true)
+                .required_unless("list-all-tags")
                 .help("Use this entry")
                 .value_name("ID"))
 
+        .arg(Arg::with_name("list-all-tags")
+                .long("list")
+                .short("L")
+                .takes_value(false)
+                .required(false)
+                .help("List all tags used in the store, with the number of entries carrying them"))
+
         .arg(tag_add_arg())
         .arg(tag_remove_arg())
 
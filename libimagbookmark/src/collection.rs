@@ -23,6 +23,7 @@
 //! from the libimagentrylink::external::ExternalLinker trait on this to generate external links.
 //!
 //! The BookmarkCollection type offers helper functions to get all links or such things.
+use std::collections::BTreeSet;
 use std::ops::Deref;
 use std::ops::DerefMut;
 
@@ -45,12 +46,24 @@ use libimagerror::into::IntoError;
 use link::Link;
 
 use self::iter::LinksMatchingRegexIter;
+use self::iter::LazyLinkIter;
 
 pub struct BookmarkCollection<'a> {
     fle: FileLockEntry<'a>,
     store: &'a Store,
 }
 
+/// Statistics about a BookmarkCollection, see `BookmarkCollection::stats()`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CollectionStats {
+    /// Number of links in the collection
+    pub links: usize,
+
+    /// Number of distinct hosts of the links. Links without a host (like `mailto:` links) are not
+    /// counted.
+    pub unique_hosts: usize,
+}
+
 /// {Internal, External}Linker is implemented as Deref is implemented
 impl<'a> Deref for BookmarkCollection<'a> {
     type Target = FileLockEntry<'a>;
@@ -107,10 +120,73 @@ impl<'a> BookmarkCollection<'a> {
             .map_err_into(BEK::StoreReadError)
     }
 
+    /// Get the names of all collections with the number of links in them, sorted by name
+    pub fn list(store: &Store) -> Result<Vec<(String, usize)>> {
+        let ids = try!(store.retrieve_for_module("bookmark").map_err_into(BEK::StoreReadError));
+
+        let mut collections = vec![];
+        for id in ids {
+            // The directories of nested collection names are listed as well
+            let path = try!(id.clone().into_pathbuf().map_err_into(BEK::StoreReadError));
+            if path.is_dir() {
+                continue;
+            }
+
+            let name = match id.local().strip_prefix("bookmark").ok().and_then(|p| p.to_str()) {
+                Some(name) => String::from(name),
+                None       => {
+                    warn!("Cannot get collection name from {}, skipping", id);
+                    continue;
+                },
+            };
+
+            let count = try!(BookmarkCollection::get(store, &name).and_then(|c| c.count()));
+            collections.push((name, count));
+        }
+
+        collections.sort();
+        Ok(collections)
+    }
+
     pub fn links(&self) -> Result<UrlIter> {
         self.fle.get_external_links(&self.store).map_err_into(BEK::LinkError)
     }
 
+    /// Get an iterator over the links of the collection which reads one link at a time
+    ///
+    /// Only the ids of the link entries are read upfront, each step of the iterator reads one link
+    /// entry from the store. As the number of links is known from the ids, the iterator is an
+    /// `ExactSizeIterator`.
+    pub fn link_iter(&self) -> Result<LazyLinkIter<'a>> {
+        self.link_entries().map(|ids| LazyLinkIter::new(ids, self.store))
+    }
+
+    /// Get the number of links in the collection
+    ///
+    /// This only counts the link entries and does not read the URLs from the store.
+    pub fn count(&self) -> Result<usize> {
+        self.link_entries().map(|v| v.len())
+    }
+
+    /// Get statistics about the links in the collection
+    pub fn stats(&self) -> Result<CollectionStats> {
+        let mut links = 0;
+        let mut hosts = BTreeSet::new();
+
+        for url in try!(self.links()) {
+            let url = try!(url.map_err_into(BEK::LinkError));
+            links += 1;
+            if let Some(host) = url.host_str() {
+                hosts.insert(String::from(host));
+            }
+        }
+
+        Ok(CollectionStats {
+            links: links,
+            unique_hosts: hosts.len(),
+        })
+    }
+
     pub fn link_entries(&self) -> Result<Vec<StoreLink>> {
         use libimagentrylink::external::is_external_link_storeid;
 
@@ -120,11 +196,41 @@ impl<'a> BookmarkCollection<'a> {
             .map_err_into(BEK::StoreReadError)
     }
 
+    /// Add a link to the collection
+    ///
+    /// The link is validated with `Link::validate()` before it is added, see
+    /// `BookmarkCollection::add_link_unchecked()` for adding links with unusual schemes.
+    ///
+    /// If a link with the same canonical form (see `Link::canonicalize()`) is already in the
+    /// collection, the link is not added again. Otherwise the link is added as parsed URL, so
+    /// "http://x.com" is stored as "http://x.com/".
     pub fn add_link(&mut self, l: Link) -> Result<()> {
+        let url = try!(l.validate().map_err_into(BEK::LinkError));
+
+        let canonical = l.canonicalize();
+        for link in try!(self.links()) {
+            let link = try!(link.map_err_into(BEK::LinkError));
+            if Link::from(link.as_str()).canonicalize()[..] == canonical[..] {
+                debug!("Link '{}' already in collection as '{}'", l.deref(), link);
+                return Ok(());
+            }
+        }
+
+        let store = self.store;
+        self.add_external_link(store, url)
+            .map_err_into(BEK::LinkingError)
+            .map_err_into(BEK::LinkError)
+    }
+
+    /// Add a link to the collection without validating it
+    ///
+    /// The link still has to be a URL.
+    pub fn add_link_unchecked(&mut self, l: Link) -> Result<()> {
         use link::IntoUrl;
 
+        let store = self.store;
         l.into_url()
-            .and_then(|url| self.add_external_link(self.store, url).map_err_into(BEK::LinkingError))
+            .and_then(|url| self.add_external_link(store, url).map_err_into(BEK::LinkingError))
             .map_err_into(BEK::LinkError)
     }
 
@@ -136,14 +242,32 @@ impl<'a> BookmarkCollection<'a> {
             .map(|iter| iter.matching_regex(r))
     }
 
-    pub fn remove_link(&mut self, l: Link) -> Result<()> {
+    /// Remove a link from the collection
+    ///
+    /// Returns `Ok(false)` if the link is not in the collection.
+    pub fn remove_link(&mut self, l: Link) -> Result<bool> {
         use link::IntoUrl;
 
-        l.into_url()
-            .and_then(|url| {
-                self.remove_external_link(self.store, url).map_err_into(BEK::LinkingError)
-            })
+        let url = try!(l.into_url().map_err_into(BEK::LinkError));
+
+        let mut present = false;
+        for link in try!(self.links()) {
+            if try!(link.map_err_into(BEK::LinkError)) == url {
+                present = true;
+                break;
+            }
+        }
+
+        if !present {
+            debug!("Link '{}' not in collection, nothing to remove", url);
+            return Ok(false);
+        }
+
+        let store = self.store;
+        self.remove_external_link(store, url)
+            .map_err_into(BEK::LinkingError)
             .map_err_into(BEK::LinkError)
+            .map(|_| true)
     }
 
 }
@@ -176,6 +300,52 @@ pub mod iter {
         }
     }
 
+    use std::vec::IntoIter;
+
+    use libimagstore::store::Store;
+    use libimagentrylink::external::Link as ExternalLink;
+    use libimagentrylink::internal::Link as StoreLink;
+    use libimagerror::into::IntoError;
+
+    /// Iterator over the links of a collection, see `BookmarkCollection::link_iter()`
+    pub struct LazyLinkIter<'a> {
+        ids: IntoIter<StoreLink>,
+        store: &'a Store,
+    }
+
+    impl<'a> LazyLinkIter<'a> {
+        pub fn new(ids: Vec<StoreLink>, store: &'a Store) -> LazyLinkIter<'a> {
+            LazyLinkIter {
+                ids: ids.into_iter(),
+                store: store,
+            }
+        }
+    }
+
+    impl<'a> Iterator for LazyLinkIter<'a> {
+        type Item = Result<Link>;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            self.ids.next().map(|id| {
+                let id = id.get_store_id().clone();
+                debug!("Reading link from {:?}", id);
+
+                let entry = try!(self.store.get(id).map_err_into(BEK::StoreReadError));
+                let entry = try!(entry.ok_or(BEK::LinkError.into_error()));
+                match try!(ExternalLink::new(entry).get_url().map_err_into(BEK::LinkError)) {
+                    Some(url) => Ok(Link::from(url.into_string())),
+                    None      => Err(BEK::LinkError.into_error()),
+                }
+            })
+        }
+
+        fn size_hint(&self) -> (usize, Option<usize>) {
+            self.ids.size_hint()
+        }
+    }
+
+    impl<'a> ExactSizeIterator for LazyLinkIter<'a> { }
+
     use libimagentrylink::external::iter::UrlIter;
     use regex::Regex;
 
@@ -220,3 +390,169 @@ pub mod iter {
 
 }
 
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use libimagstore::store::Store;
+
+    use link::Link;
+    use super::BookmarkCollection;
+    use super::CollectionStats;
+
+    fn get_store() -> Store {
+        Store::new_in_memory(PathBuf::from("/"), None).unwrap()
+    }
+
+    #[test]
+    fn test_count_and_stats() {
+        let store = get_store();
+        let mut c = BookmarkCollection::new(&store, "test").unwrap();
+
+        for url in &["https://imag-pim.org/",
+                     "https://imag-pim.org/faq.html",
+                     "https://github.com/matthiasbeyer/imag",
+                     "mailto:someone@example.com"] {
+            assert!(c.add_link(Link::from(*url)).is_ok());
+        }
+
+        assert_eq!(c.count().unwrap(), 4);
+        assert_eq!(c.stats().unwrap(), CollectionStats { links: 4, unique_hosts: 2 });
+    }
+
+    #[test]
+    fn test_list_collections() {
+        let store = get_store();
+
+        for &(name, urls) in &[("work", &["https://imag-pim.org/", "https://example.com/"][..]),
+                               ("empty", &[][..]),
+                               ("news", &["https://lwn.net/"][..])] {
+            let mut c = BookmarkCollection::new(&store, name).unwrap();
+            assert_eq!(add_all(&mut c, urls), urls.len());
+        }
+
+        let list = BookmarkCollection::list(&store).unwrap();
+        assert_eq!(list, vec![
+            (String::from("empty"), 0),
+            (String::from("news"), 1),
+            (String::from("work"), 2),
+        ]);
+    }
+
+    fn add_all(c: &mut BookmarkCollection, urls: &[&str]) -> usize {
+        urls.iter().filter(|u| c.add_link(Link::from(**u)).is_ok()).count()
+    }
+
+    #[test]
+    fn test_add_valid_links() {
+        let store = get_store();
+        let mut c = BookmarkCollection::new(&store, "test").unwrap();
+
+        assert_eq!(add_all(&mut c, &["https://imag-pim.org/", "http://example.com/"]), 2);
+        assert_eq!(c.count().unwrap(), 2);
+    }
+
+    #[test]
+    fn test_add_invalid_links() {
+        let store = get_store();
+        let mut c = BookmarkCollection::new(&store, "test").unwrap();
+
+        assert_eq!(add_all(&mut c, &["", "htp://imag-pim.org", "not a url"]), 0);
+        assert_eq!(c.count().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_add_mixed_links() {
+        let store = get_store();
+        let mut c = BookmarkCollection::new(&store, "test").unwrap();
+
+        assert_eq!(add_all(&mut c, &["https://imag-pim.org/", "htp://imag-pim.org", "http://example.com/"]), 2);
+        assert_eq!(c.count().unwrap(), 2);
+    }
+
+    #[test]
+    fn test_add_link_deduplicates_canonical_links() {
+        let store = get_store();
+        let mut c = BookmarkCollection::new(&store, "test").unwrap();
+
+        assert_eq!(add_all(&mut c, &["http://x.com",
+                                     "http://x.com/",
+                                     "http://x.com/?utm_source=feed",
+                                     "http://y.com/"]), 4);
+        assert_eq!(c.count().unwrap(), 2);
+
+        // the first link is kept, as parsed URL
+        let links : Vec<String> = c.links().unwrap().map(|l| l.unwrap().into_string()).collect();
+        assert!(links.contains(&String::from("http://x.com/")));
+        assert!(links.contains(&String::from("http://y.com/")));
+    }
+
+    #[test]
+    fn test_link_iter() {
+        let store = get_store();
+        let mut c = BookmarkCollection::new(&store, "test").unwrap();
+
+        assert_eq!(add_all(&mut c, &["https://imag-pim.org/",
+                                     "https://example.com/",
+                                     "https://lwn.net/"]), 3);
+
+        let mut iter = c.link_iter().unwrap();
+        assert_eq!(iter.len(), 3);
+        assert!(iter.next().unwrap().is_ok());
+        assert_eq!(iter.len(), 2);
+
+        let lazy : Vec<String> = c.link_iter().unwrap().map(|l| l.unwrap().to_string()).collect();
+        let eager : Vec<String> = c.links().unwrap().map(|l| l.unwrap().into_string()).collect();
+        assert_eq!(lazy, eager);
+
+        let mut sorted = lazy.clone();
+        sorted.sort();
+        sorted.dedup();
+        assert_eq!(sorted, vec!["https://example.com/", "https://imag-pim.org/", "https://lwn.net/"]);
+    }
+
+    #[test]
+    fn test_add_link_unchecked() {
+        let store = get_store();
+        let mut c = BookmarkCollection::new(&store, "test").unwrap();
+
+        assert!(c.add_link(Link::from("gopher://example.com/")).is_err());
+        assert!(c.add_link_unchecked(Link::from("gopher://example.com/")).is_ok());
+        assert!(c.add_link_unchecked(Link::from("not a url")).is_err());
+        assert_eq!(c.count().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_remove_link() {
+        let store = get_store();
+        let mut c = BookmarkCollection::new(&store, "test").unwrap();
+
+        assert!(c.add_link(Link::from("https://imag-pim.org/")).is_ok());
+
+        assert_eq!(c.remove_link(Link::from("https://example.com/")).unwrap(), false);
+        assert_eq!(c.count().unwrap(), 1);
+
+        assert_eq!(c.remove_link(Link::from("https://imag-pim.org/")).unwrap(), true);
+        assert_eq!(c.count().unwrap(), 0);
+
+        assert_eq!(c.remove_link(Link::from("https://imag-pim.org/")).unwrap(), false);
+    }
+
+    #[test]
+    fn test_remove_invalid_link_errors() {
+        let store = get_store();
+        let mut c = BookmarkCollection::new(&store, "test").unwrap();
+
+        assert!(c.remove_link(Link::from("not a url")).is_err());
+    }
+
+    #[test]
+    fn test_count_empty_collection() {
+        let store = get_store();
+        let c     = BookmarkCollection::new(&store, "empty").unwrap();
+
+        assert_eq!(c.count().unwrap(), 0);
+        assert_eq!(c.stats().unwrap(), CollectionStats { links: 0, unique_hosts: 0 });
+    }
+
+}
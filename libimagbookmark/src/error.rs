@@ -22,6 +22,7 @@ generate_error_module!(
         StoreReadError     => "Store read error",
         LinkError          => "Link error",
         LinkParsingError   => "Link parsing error",
+        LinkValidationError => "Link is not a valid bookmark URL",
         LinkingError       => "Error while linking",
         CollectionNotFound => "Link-Collection not found"
     );
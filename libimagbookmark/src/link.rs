@@ -74,3 +74,149 @@ impl IntoUrl for Link {
 
 }
 
+/// The URL schemes which are accepted by `Link::validate()`
+pub const VALID_SCHEMES : &'static [&'static str] = &["http", "https", "ftp", "ftps", "file", "mailto"];
+
+/// The query parameters which are removed by `Link::canonicalize()`
+pub const TRACKING_PARAMS : &'static [&'static str] = &[
+    "utm_source",
+    "utm_medium",
+    "utm_campaign",
+    "utm_term",
+    "utm_content",
+    "fbclid",
+    "gclid",
+];
+
+impl Link {
+
+    /// Parse the link as URL and check whether it is a sensible bookmark
+    ///
+    /// Fails with `BookmarkErrorKind::LinkParsingError` if the link is not a URL at all and with
+    /// `BookmarkErrorKind::LinkValidationError` if the scheme is not one of `VALID_SCHEMES` or a
+    /// http(s)/ftp(s) URL has no host. This catches typos like `htp://`.
+    pub fn validate(&self) -> Result<Url> {
+        use error::BookmarkErrorKind as BEK;
+        use libimagerror::into::IntoError;
+
+        let url = try!(self.clone().into_url());
+
+        if !VALID_SCHEMES.contains(&url.scheme()) {
+            debug!("Invalid scheme '{}' in link '{}'", url.scheme(), url);
+            return Err(BEK::LinkValidationError.into_error());
+        }
+
+        let needs_host = url.scheme() != "file" && url.scheme() != "mailto";
+        if needs_host && url.host_str().map(|h| h.is_empty()).unwrap_or(true) {
+            debug!("No host in link '{}'", url);
+            return Err(BEK::LinkValidationError.into_error());
+        }
+
+        Ok(url)
+    }
+
+    /// Get the canonical form of the link, used to find links which point to the same resource
+    ///
+    /// Same as `Link::canonicalize_stripping()` with the `TRACKING_PARAMS`.
+    pub fn canonicalize(&self) -> Link {
+        self.canonicalize_stripping(TRACKING_PARAMS)
+    }
+
+    /// Get the canonical form of the link, removing the query parameters in `params`
+    ///
+    /// Scheme and host are lowercased, default ports are dropped, a trailing slash is removed from
+    /// the path (the root path is always `/`) and the query parameters with one of the names in
+    /// `params` are removed. Links which are not URLs are returned unchanged.
+    ///
+    /// The link itself is not altered, so the raw form stays available.
+    pub fn canonicalize_stripping(&self, params: &[&str]) -> Link {
+        let mut url = match self.clone().into_url() {
+            Ok(url) => url,
+            Err(_)  => return self.clone(),
+        };
+
+        if url.path().len() > 1 && url.path().ends_with('/') {
+            let path = String::from(url.path().trim_right_matches('/'));
+            url.set_path(&path);
+        }
+
+        if url.query().is_some() {
+            let pairs : Vec<(String, String)> = url.query_pairs()
+                .filter(|&(ref k, _)| !params.contains(&&k[..]))
+                .map(|(k, v)| (k.into_owned(), v.into_owned()))
+                .collect();
+
+            if pairs.is_empty() {
+                url.set_query(None);
+            } else {
+                url.query_pairs_mut().clear().extend_pairs(pairs);
+            }
+        }
+
+        Link::from(url.into_string())
+    }
+
+}
+
+#[cfg(test)]
+mod test {
+    use super::Link;
+    use error::BookmarkErrorKind as BEK;
+
+    fn canonical(s: &str) -> String {
+        Link::from(s).canonicalize().to_string()
+    }
+
+    #[test]
+    fn test_canonicalize_equivalent_links() {
+        for l in &["http://x.com",
+                   "http://x.com/",
+                   "HTTP://X.com:80/",
+                   "http://x.com/?utm_source=feed",
+                   "http://x.com/?utm_source=feed&utm_medium=rss"] {
+            assert_eq!(canonical(l), "http://x.com/", "Expected '{}' to be canonical", l);
+        }
+
+        assert_eq!(canonical("https://x.com/a/b/?id=5&utm_campaign=c"), "https://x.com/a/b?id=5");
+        assert_eq!(canonical("https://x.com:443/a/b?id=5"), "https://x.com/a/b?id=5");
+    }
+
+    #[test]
+    fn test_canonicalize_unrelated_links_differ() {
+        let links = ["http://x.com/", "https://x.com/", "http://x.com:8080/", "http://y.com/",
+                     "http://x.com/a", "http://x.com/?id=1", "http://x.com/?id=2"];
+
+        for (i, a) in links.iter().enumerate() {
+            for b in links.iter().skip(i + 1) {
+                assert!(canonical(a) != canonical(b), "'{}' and '{}' collide", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn test_canonicalize_keeps_raw_form() {
+        let l = Link::from("HTTP://X.com/?utm_source=feed");
+        assert_eq!(&l.canonicalize()[..], "http://x.com/");
+        assert_eq!(&l[..], "HTTP://X.com/?utm_source=feed");
+
+        assert_eq!(&Link::from("not a url").canonicalize()[..], "not a url");
+        assert_eq!(&l.canonicalize_stripping(&[])[..], "http://x.com/?utm_source=feed");
+    }
+
+    #[test]
+    fn test_validate_valid_links() {
+        for l in &["https://imag-pim.org/", "http://example.com/a?b=c", "mailto:someone@example.com"] {
+            assert!(Link::from(*l).validate().is_ok(), "Expected '{}' to be valid", l);
+        }
+    }
+
+    #[test]
+    fn test_validate_invalid_links() {
+        assert_eq!(Link::from("").validate().unwrap_err().err_type(), BEK::LinkParsingError);
+        assert_eq!(Link::from("imag-pim.org").validate().unwrap_err().err_type(), BEK::LinkParsingError);
+        assert_eq!(Link::from("htp://imag-pim.org").validate().unwrap_err().err_type(),
+                   BEK::LinkValidationError);
+        assert_eq!(Link::from("https://").validate().unwrap_err().err_type(), BEK::LinkParsingError);
+    }
+
+}
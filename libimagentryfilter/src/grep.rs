@@ -0,0 +1,166 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015, 2016 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+
+//! Searching the content of all entries of a module
+//!
+//! This is the backend for a `grep`-like command: For each entry whose content matches, the
+//! matching lines are returned with their line numbers.
+
+use regex::Regex;
+
+use libimagstore::store::Store;
+use libimagstore::storeid::StoreId;
+use libimagerror::trace::MapErrTrace;
+
+use error::EntryFilterErrorKind as EFEK;
+use error::MapErrInto;
+use result::Result;
+
+/// A matching line: the (1-based) line number and the line itself
+pub type GrepMatch = (usize, String);
+
+enum Matcher {
+    Substring(String),
+    Regex(Regex),
+}
+
+impl Matcher {
+
+    fn is_match(&self, line: &str) -> bool {
+        match *self {
+            Matcher::Substring(ref s) => line.contains(&s[..]),
+            Matcher::Regex(ref r)     => r.is_match(line),
+        }
+    }
+
+}
+
+/// Search the content of all entries of the module `module` for `pattern`
+///
+/// If `regex` is set, `pattern` is a regular expression, otherwise it is matched as substring.
+/// Entries are searched one after another via `Store::retrieve_copy()`, so only one entry is held
+/// in memory at a time. Entries which cannot be read (for example because they are borrowed) are
+/// skipped, the error is traced.
+///
+/// Returns the ids of all matching entries together with their matching lines. Entries without a
+/// matching line are not returned.
+pub fn grep_module(store: &Store, module: &str, pattern: &str, regex: bool)
+    -> Result<Vec<(StoreId, Vec<GrepMatch>)>>
+{
+    let matcher = if regex {
+        Matcher::Regex(try!(Regex::new(pattern).map_err_into(EFEK::InvalidRegex)))
+    } else {
+        Matcher::Substring(String::from(pattern))
+    };
+
+    let ids = try!(store.retrieve_for_module(module).map_err_into(EFEK::StoreReadError));
+
+    Ok(ids
+       .filter_map(|id| {
+           store.retrieve_copy(id.clone())
+               .map_err_into(EFEK::StoreReadError)
+               .map_err_trace()
+               .ok()
+               .map(|entry| (id, grep_lines(&matcher, entry.get_content())))
+       })
+       .filter(|&(_, ref matches)| !matches.is_empty())
+       .collect())
+}
+
+fn grep_lines(matcher: &Matcher, content: &str) -> Vec<GrepMatch> {
+    content.lines()
+        .enumerate()
+        .filter(|&(_, line)| matcher.is_match(line))
+        .map(|(i, line)| (i + 1, String::from(line)))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use libimagstore::store::Store;
+    use libimagstore::storeid::StoreId;
+
+    use error::EntryFilterErrorKind as EFEK;
+    use super::grep_module;
+
+    fn create(store: &Store, name: &str, content: &str) {
+        let id        = StoreId::new_baseless(PathBuf::from(name)).unwrap();
+        let mut entry = store.create(id).unwrap();
+        *entry.get_content_mut() = String::from(content);
+    }
+
+    fn setup() -> Store {
+        let store = Store::new_in_memory(PathBuf::from("/"), None).unwrap();
+        create(&store, "notes/with", "some text\nwith the needle\nmore text");
+        create(&store, "notes/without", "only hay\nand more hay");
+        create(&store, "notes/multi", "needle one\nhay\nneedle two\nneedle three");
+        create(&store, "other/needle", "needle in another module");
+        store
+    }
+
+    fn grep(store: &Store, pattern: &str, regex: bool) -> Vec<(String, Vec<(usize, String)>)> {
+        let mut res : Vec<_> = grep_module(store, "notes", pattern, regex)
+            .unwrap()
+            .into_iter()
+            .map(|(id, matches)| (id.local().to_str().unwrap().to_owned(), matches))
+            .collect();
+        res.sort();
+        res
+    }
+
+    #[test]
+    fn test_grep_substring() {
+        let store = setup();
+
+        let res = grep(&store, "needle", false);
+        assert_eq!(res.len(), 2);
+
+        assert_eq!(res[0].0, "notes/multi");
+        assert_eq!(res[0].1, vec![(1, String::from("needle one")),
+                                  (3, String::from("needle two")),
+                                  (4, String::from("needle three"))]);
+
+        assert_eq!(res[1].0, "notes/with");
+        assert_eq!(res[1].1, vec![(2, String::from("with the needle"))]);
+    }
+
+    #[test]
+    fn test_grep_regex() {
+        let store = setup();
+
+        let res = grep(&store, "^needle t", true);
+        assert_eq!(res.len(), 1);
+        assert_eq!(res[0].1, vec![(3, String::from("needle two")), (4, String::from("needle three"))]);
+
+        assert!(grep(&store, "^needle t", false).is_empty());
+    }
+
+    #[test]
+    fn test_grep_invalid_regex() {
+        let store = setup();
+
+        let res = grep_module(&store, "notes", "(unclosed", true);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().err_type(), EFEK::InvalidRegex);
+    }
+
+}
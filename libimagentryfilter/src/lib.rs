@@ -40,15 +40,21 @@ extern crate toml;
 
 extern crate libimagstore;
 extern crate libimagentrytag;
+extern crate libimagentrylink;
+#[macro_use] extern crate libimagerror;
 
 // core functionality modules of the crate,
 // these depend only on libimagstore
 
 pub mod cli;
 pub mod builtin;
+pub mod error;
+pub mod grep;
+pub mod result;
 
 // extended functionality of the crate
 // these depend on other internal libraries than libimagstore and use the upper core modules for
 // their functionality
 
+pub mod query;
 pub mod tags;
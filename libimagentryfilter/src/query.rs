@@ -0,0 +1,229 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015, 2016 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+
+//! Querying the entries of a module with several predicates at once
+//!
+//! Composing the tag, link and header filters by hand means one pass over the store per filter.
+//! An `EntryQuery` collects the predicates and applies all of them in a single pass, which loads
+//! each entry once:
+//!
+//! ```ignore
+//! let entries = query(store, "notes")
+//!     .tagged(String::from("work"))
+//!     .linked_to(id)
+//!     .run();
+//! ```
+
+use libimagstore::store::FileLockEntry;
+use libimagstore::store::Store;
+use libimagstore::storeid::StoreId;
+use libimagentrytag::tag::Tag;
+use libimagentrylink::internal::InternalLinker;
+use libimagerror::trace::MapErrTrace;
+
+use builtin::header::field_eq::FieldEq;
+use error::EntryFilterErrorKind as EFEK;
+use error::MapErrInto;
+use result::Result;
+use tags::HasTag;
+
+use filters::filter::Filter;
+use toml::Value;
+
+/// Start a query over the entries of the module `module`
+pub fn query<'a>(store: &'a Store, module: &str) -> EntryQuery<'a> {
+    EntryQuery {
+        store: store,
+        module: String::from(module),
+        tags: vec![],
+        links: vec![],
+        header: vec![],
+    }
+}
+
+/// A query over the entries of a module, see `query()`
+///
+/// An entry matches the query if it satisfies all predicates. A query without predicates matches
+/// all entries of the module.
+pub struct EntryQuery<'a> {
+    store: &'a Store,
+    module: String,
+    tags: Vec<HasTag>,
+    links: Vec<StoreId>,
+    header: Vec<FieldEq>,
+}
+
+impl<'a> EntryQuery<'a> {
+
+    /// Only match entries which have the tag `tag`
+    pub fn tagged(mut self, tag: Tag) -> EntryQuery<'a> {
+        self.tags.push(HasTag::new(tag));
+        self
+    }
+
+    /// Only match entries which have an internal link to `id`
+    pub fn linked_to(mut self, id: StoreId) -> EntryQuery<'a> {
+        self.links.push(id.without_base());
+        self
+    }
+
+    /// Only match entries whose header value at `key` equals `val`
+    pub fn header_eq(mut self, key: &str, val: Value) -> EntryQuery<'a> {
+        self.header.push(FieldEq::new(String::from(key), val));
+        self
+    }
+
+    /// Run the query and get all matching entries
+    ///
+    /// Entries which cannot be loaded or whose links cannot be read are skipped, the error is
+    /// traced.
+    pub fn run(self) -> Result<Vec<FileLockEntry<'a>>> {
+        let ids = try!(self.store
+                       .retrieve_for_module(&self.module)
+                       .map_err_into(EFEK::StoreReadError));
+
+        let mut entries = vec![];
+        for id in ids {
+            let entry = match self.store.get(id).map_err_into(EFEK::StoreReadError).map_err_trace() {
+                Ok(Some(entry)) => entry,
+                Ok(None)        => continue,
+                Err(_)          => continue,
+            };
+
+            if self.matches(&entry) {
+                entries.push(entry);
+            }
+        }
+
+        Ok(entries)
+    }
+
+    fn matches(&self, entry: &FileLockEntry) -> bool {
+        if !self.tags.iter().all(|f| f.filter(entry)) {
+            return false;
+        }
+
+        if !self.header.iter().all(|f| f.filter(entry)) {
+            return false;
+        }
+
+        if self.links.is_empty() {
+            return true;
+        }
+
+        let links = match entry.get_internal_links().map_err_trace() {
+            Ok(links) => links.map(|l| l.get_store_id().clone().without_base()).collect::<Vec<_>>(),
+            Err(_)    => return false,
+        };
+
+        self.links.iter().all(|id| links.contains(id))
+    }
+
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use toml::Value;
+
+    use libimagstore::store::Store;
+    use libimagstore::storeid::StoreId;
+    use libimagstore::toml_ext::TomlValueExt;
+    use libimagentrytag::tagable::Tagable;
+    use libimagentrylink::internal::InternalLinker;
+
+    use super::query;
+    use super::EntryQuery;
+
+    fn id(name: &str) -> StoreId {
+        StoreId::new_baseless(PathBuf::from(name)).unwrap()
+    }
+
+    /// Create the entry `name` with the tags `tags`, linked to `test/target` if `linked`
+    fn create(store: &Store, name: &str, tags: &[&str], linked: bool) {
+        let mut entry = store.create(id(name)).unwrap();
+        for tag in tags {
+            entry.add_tag(String::from(*tag)).unwrap();
+        }
+        entry.get_header_mut().insert("test", Value::Table(Default::default())).unwrap();
+        entry.get_header_mut().set("test.linked", Value::Boolean(linked)).unwrap();
+
+        if linked {
+            let mut target = store.retrieve(id("target/x")).unwrap();
+            entry.add_internal_link(&mut target).unwrap();
+        }
+    }
+
+    fn setup() -> Store {
+        let store = Store::new_in_memory(PathBuf::from("/"), None).unwrap();
+        create(&store, "test/tagged_and_linked", &["work", "imag"], true);
+        create(&store, "test/tagged", &["work"], false);
+        create(&store, "test/linked", &["home"], true);
+        create(&store, "test/nothing", &[], false);
+        create(&store, "other/tagged_and_linked", &["work"], true);
+        store
+    }
+
+    fn names(q: EntryQuery) -> Vec<String> {
+        let mut names : Vec<String> = q.run()
+            .unwrap()
+            .iter()
+            .map(|e| e.get_location().local().to_str().unwrap().to_owned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_query_tag_and_link() {
+        let store = setup();
+
+        let q = query(&store, "test").tagged(String::from("work")).linked_to(id("target/x"));
+        assert_eq!(names(q), vec!["test/tagged_and_linked"]);
+    }
+
+    #[test]
+    fn test_query_single_predicates() {
+        let store = setup();
+
+        assert_eq!(names(query(&store, "test").tagged(String::from("work"))),
+                   vec!["test/tagged", "test/tagged_and_linked"]);
+        assert_eq!(names(query(&store, "test").linked_to(id("target/x"))),
+                   vec!["test/linked", "test/tagged_and_linked"]);
+        assert_eq!(names(query(&store, "test").header_eq("test.linked", Value::Boolean(false))),
+                   vec!["test/nothing", "test/tagged"]);
+        assert_eq!(names(query(&store, "test")).len(), 4);
+    }
+
+    #[test]
+    fn test_query_no_match() {
+        let store = setup();
+
+        let q = query(&store, "test")
+            .tagged(String::from("home"))
+            .header_eq("test.linked", Value::Boolean(false));
+        assert!(names(q).is_empty());
+
+        let q = query(&store, "test").tagged(String::from("work")).linked_to(id("target/y"));
+        assert!(names(q).is_empty());
+    }
+
+}
@@ -0,0 +1,205 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015, 2016 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Consistency checks for internal links
+//!
+//! Internal links are always two-way links. Moving or deleting entries can leave links which point
+//! to entries which do not exist anymore or which do not link back. These half-links can be found
+//! with `check_link_consistency()` and removed with `repair_link_inconsistencies()`.
+
+use std::collections::HashMap;
+
+use libimagstore::store::Store;
+use libimagstore::storeid::StoreId;
+use libimagstore::toml_ext::TomlValueExt;
+use libimagerror::trace::MapErrTrace;
+
+use error::LinkErrorKind as LEK;
+use error::MapErrInto;
+use result::Result;
+use internal::InternalLinker;
+use internal::iter::IntoValues;
+
+use toml::Value;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LinkInconsistency {
+    /// `from` links to `to`, but there is no entry `to`
+    TargetMissing { from: StoreId, to: StoreId },
+
+    /// `from` links to `to`, but `to` does not link back to `from`
+    NoBackLink { from: StoreId, to: StoreId },
+}
+
+impl LinkInconsistency {
+
+    /// The entry which holds the inconsistent link
+    pub fn from(&self) -> &StoreId {
+        match *self {
+            LinkInconsistency::TargetMissing { ref from, .. } => from,
+            LinkInconsistency::NoBackLink { ref from, .. }    => from,
+        }
+    }
+
+    /// The target of the inconsistent link
+    pub fn to(&self) -> &StoreId {
+        match *self {
+            LinkInconsistency::TargetMissing { ref to, .. } => to,
+            LinkInconsistency::NoBackLink { ref to, .. }    => to,
+        }
+    }
+
+}
+
+/// Check the internal links of all entries in the store
+///
+/// Entries which cannot be loaded are skipped, the error is traced.
+pub fn check_link_consistency(store: &Store) -> Result<Vec<LinkInconsistency>> {
+    // StoreIds are compared without their base, so we remove it everywhere
+    let mut links : HashMap<StoreId, Vec<StoreId>> = HashMap::new();
+
+    for id in try!(store.entries().map_err_into(LEK::StoreReadError)) {
+        let entry = match store.get(id).map_err_into(LEK::StoreReadError).map_err_trace() {
+            Ok(Some(entry)) => entry,
+            _               => continue,
+        };
+
+        let targets = match entry.get_internal_links().map_err_trace() {
+            Ok(iter) => iter.map(|link| link.get_store_id().clone().without_base()).collect(),
+            Err(_)   => continue,
+        };
+
+        links.insert(entry.get_location().clone().without_base(), targets);
+    }
+
+    let mut inconsistencies = vec![];
+    for (from, targets) in links.iter() {
+        for to in targets {
+            match links.get(to) {
+                Some(back) => if !back.contains(from) {
+                    inconsistencies.push(LinkInconsistency::NoBackLink {
+                        from: from.clone(),
+                        to: to.clone(),
+                    });
+                },
+                None => {
+                    let exists = try!(to.clone()
+                                      .with_base(store.path().clone())
+                                      .exists()
+                                      .map_err_into(LEK::StoreReadError));
+
+                    if exists {
+                        debug!("{:?} exists but could not be loaded, not checking it", to);
+                    } else {
+                        inconsistencies.push(LinkInconsistency::TargetMissing {
+                            from: from.clone(),
+                            to: to.clone(),
+                        });
+                    }
+                },
+            }
+        }
+    }
+
+    inconsistencies.sort_by(|a, b| (a.from(), a.to()).cmp(&(b.from(), b.to())));
+    Ok(inconsistencies)
+}
+
+/// Remove the inconsistent half-links from the entries holding them
+pub fn repair_link_inconsistencies(store: &Store, inconsistencies: &[LinkInconsistency])
+    -> Result<()>
+{
+    for inc in inconsistencies {
+        let mut entry = match try!(store.get(inc.from().clone()).map_err_into(LEK::StoreReadError)) {
+            Some(entry) => entry,
+            None        => continue,
+        };
+
+        debug!("Removing link from {:?} to {:?}", inc.from(), inc.to());
+        let links = try!(try!(entry.get_internal_links())
+            .filter(|link| *link.get_store_id() != *inc.to())
+            .into_values()
+            .into_iter()
+            .collect::<Result<Vec<Value>>>());
+
+        try!(entry.get_header_mut()
+             .set("imag.links", Value::Array(links))
+             .map_err_into(LEK::EntryHeaderWriteError));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use toml::Value;
+
+    use libimagstore::store::Store;
+    use libimagstore::storeid::StoreId;
+    use libimagstore::toml_ext::TomlValueExt;
+
+    use internal::InternalLinker;
+    use super::*;
+
+    fn get_store() -> Store {
+        Store::new_in_memory(PathBuf::from("/"), None).unwrap()
+    }
+
+    fn id(s: &str) -> StoreId {
+        StoreId::new_baseless(PathBuf::from(s)).unwrap()
+    }
+
+    #[test]
+    fn test_broken_links_are_reported_and_repaired() {
+        let store = get_store();
+
+        {
+            let mut a = store.create(id("test/a")).unwrap();
+            let mut b = store.create(id("test/b")).unwrap();
+            let mut c = store.create(id("test/c")).unwrap();
+            let mut d = store.create(id("test/d")).unwrap();
+
+            assert!(a.add_internal_link(&mut b).is_ok());
+            assert!(a.add_internal_link(&mut c).is_ok());
+            assert!(a.add_internal_link(&mut d).is_ok());
+
+            // break the link b -> a
+            assert!(b.get_header_mut().set("imag.links", Value::Array(vec![])).is_ok());
+        }
+
+        // delete c without unlinking it
+        assert!(store.delete(id("test/c")).is_ok());
+
+        let incs = check_link_consistency(&store).unwrap();
+        assert_eq!(incs, vec![
+            LinkInconsistency::NoBackLink { from: id("test/a"), to: id("test/b") },
+            LinkInconsistency::TargetMissing { from: id("test/a"), to: id("test/c") },
+        ]);
+
+        assert!(repair_link_inconsistencies(&store, &incs).is_ok());
+        assert!(check_link_consistency(&store).unwrap().is_empty());
+
+        let a = store.get(id("test/a")).unwrap().unwrap();
+        let links : Vec<StoreId> = a.get_internal_links().unwrap().map(|l| l.into()).collect();
+        assert_eq!(links, vec![id("test/d")]);
+    }
+
+}
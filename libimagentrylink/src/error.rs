@@ -30,7 +30,8 @@ generate_error_module!(
         InvalidUri              => "URI is not valid",
         StoreReadError          => "Store read error",
         StoreWriteError         => "Store write error",
-        StoreIdError            => "StoreId handling error"
+        StoreIdError            => "StoreId handling error",
+        GraphWriteError         => "Error while writing the link graph"
     );
 );
 
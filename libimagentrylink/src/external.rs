@@ -289,6 +289,20 @@ pub fn is_external_link_storeid<A: AsRef<StoreId> + Debug>(id: A) -> bool {
     id.as_ref().local().starts_with("links/external")
 }
 
+/// Get the StoreId of the entry which holds the URL `link`, `links/external/<SHA1 of the URL>`
+fn external_link_storeid(link: &Url) -> Result<StoreId> {
+    let hash = {
+        let mut s = Sha1::new();
+        s.input_str(&link.as_str()[..]);
+        s.result_str()
+    };
+
+    ModuleEntryPath::new(format!("external/{}", hash))
+        .into_storeid()
+        .map_err_into(LEK::StoreWriteError)
+        .map_dbg_err(|_| format!("Failed to build StoreId for this hash '{:?}'", hash))
+}
+
 fn get_external_link_from_file(entry: &FileLockEntry) -> Result<Url> {
     Link::get_link_uri_from_filelockentry(entry) // TODO: Do not hide error by using this function
         .ok_or(LE::new(LEK::StoreReadError, None))
@@ -320,21 +334,9 @@ impl ExternalLinker for Entry {
 
         debug!("Iterating {} links = {:?}", links.len(), links);
         for link in links { // for all links
-            let hash = {
-                let mut s = Sha1::new();
-                s.input_str(&link.as_str()[..]);
-                s.result_str()
-            };
-            let file_id = try!(
-                ModuleEntryPath::new(format!("external/{}", hash)).into_storeid()
-                    .map_err_into(LEK::StoreWriteError)
-                    .map_dbg_err(|_| {
-                        format!("Failed to build StoreId for this hash '{:?}'", hash)
-                    })
-                );
+            let file_id = try!(external_link_storeid(&link));
 
             debug!("Link    = '{:?}'", link);
-            debug!("Hash    = '{:?}'", hash);
             debug!("StoreId = '{:?}'", file_id);
 
             // retrieve the file from the store, which implicitely creates the entry if it does not
@@ -400,17 +402,70 @@ impl ExternalLinker for Entry {
 
     /// Remove an external link from the implementor object
     fn remove_external_link(&mut self, store: &Store, link: Url) -> Result<()> {
-        // get external links, remove this one, save them
-        self.get_external_links(store)
-            .and_then(|links| {
+        // Remove the internal link to the entry holding this URL, so the URL does not show up in
+        // the external links anymore
+        let file_id = try!(external_link_storeid(&link));
+
+        match try!(store.get(file_id).map_err_into(LEK::StoreReadError)) {
+            Some(mut file) => {
                 debug!("Removing link = '{:?}'", link);
-                let links = links
-                    .filter_map(Result::ok)
-                    .filter(|l| l.as_str() != link.as_str())
-                    .collect::<Vec<_>>();
-                self.set_external_links(store, links)
-            })
+                self.remove_internal_link(file.deref_mut())
+            },
+            None => {
+                debug!("No entry for link = '{:?}', nothing to remove", link);
+                Ok(())
+            },
+        }
     }
 
 }
 
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use libimagstore::store::Store;
+
+    use url::Url;
+
+    use super::ExternalLinker;
+
+    fn get_store() -> Store {
+        Store::new_in_memory(PathBuf::from("/"), None).unwrap()
+    }
+
+    fn setup_logging() {
+        use env_logger;
+        let _ = env_logger::init().unwrap_or(());
+    }
+
+    fn urls_of(links: Vec<Url>) -> Vec<String> {
+        let mut v : Vec<String> = links.into_iter().map(|u| u.into_string()).collect();
+        v.sort();
+        v
+    }
+
+    #[test]
+    fn test_external_links_add_list_remove() {
+        setup_logging();
+        let store = get_store();
+        let mut e = store.create(PathBuf::from("test_external_links_add_list_remove")).unwrap();
+
+        let first  = Url::parse("https://imag-pim.org/").unwrap();
+        let second = Url::parse("https://example.com/some/page").unwrap();
+
+        assert!(e.get_external_links(&store).unwrap().next().is_none());
+
+        assert!(e.add_external_link(&store, first.clone()).is_ok());
+        assert!(e.add_external_link(&store, second.clone()).is_ok());
+
+        let links = e.get_external_links(&store).unwrap().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(urls_of(links), vec!["https://example.com/some/page", "https://imag-pim.org/"]);
+
+        assert!(e.remove_external_link(&store, first).is_ok());
+
+        let links = e.get_external_links(&store).unwrap().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(urls_of(links), vec!["https://example.com/some/page"]);
+    }
+
+}
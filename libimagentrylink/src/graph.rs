@@ -0,0 +1,159 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015, 2016 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+
+//! Export of the internal link graph
+//!
+//! The internal links of the entries in the store can be exported in the Graphviz DOT format, for
+//! example to render them with `dot -Tpng`.
+
+use std::collections::BTreeMap;
+use std::io::Write;
+
+use libimagstore::store::Store;
+use libimagstore::storeid::StoreId;
+use libimagerror::trace::MapErrTrace;
+
+use error::LinkErrorKind as LEK;
+use error::MapErrInto;
+use result::Result;
+use internal::InternalLinker;
+
+/// Write the internal link graph of all entries in the store to `w`, as DOT `digraph`
+///
+/// Each entry is a node, labeled with its path relative to the store. Each internal link is an
+/// edge. As internal links are two-way links, two entries which are linked are connected by two
+/// edges.
+pub fn export_link_graph_dot<W: Write>(store: &Store, w: W) -> Result<()> {
+    export_link_graph_dot_for_module(store, w, None)
+}
+
+/// Same as `export_link_graph_dot()`, but optionally restricted to the entries of one module
+///
+/// If a module is passed, only links between entries of this module are exported.
+pub fn export_link_graph_dot_for_module<W: Write>(store: &Store, mut w: W, module: Option<&str>)
+    -> Result<()>
+{
+    let ids = try!(match module {
+        Some(m) => store.retrieve_for_module(m),
+        None    => store.entries(),
+    }.map_err_into(LEK::StoreReadError));
+
+    // StoreIds are compared without their base, so we remove it everywhere
+    let mut graph : BTreeMap<StoreId, Vec<StoreId>> = BTreeMap::new();
+    for id in ids {
+        let entry = match store.get(id).map_err_into(LEK::StoreReadError).map_err_trace() {
+            Ok(Some(entry)) => entry,
+            _               => continue,
+        };
+
+        let targets = match entry.get_internal_links().map_err_trace() {
+            Ok(iter) => iter.map(|link| link.get_store_id().clone().without_base()).collect(),
+            Err(_)   => continue,
+        };
+
+        graph.insert(entry.get_location().clone().without_base(), targets);
+    }
+
+    try!(writeln!(w, "digraph imag {{").map_err_into(LEK::GraphWriteError));
+
+    for id in graph.keys() {
+        let name = escape(&format!("{}", id));
+        try!(writeln!(w, "    \"{}\" [label=\"{}\"];", name, name).map_err_into(LEK::GraphWriteError));
+    }
+
+    for (from, targets) in graph.iter() {
+        let from = escape(&format!("{}", from));
+        for to in targets.iter().filter(|to| module.is_none() || graph.contains_key(to)) {
+            let to = escape(&format!("{}", to));
+            try!(writeln!(w, "    \"{}\" -> \"{}\";", from, to).map_err_into(LEK::GraphWriteError));
+        }
+    }
+
+    writeln!(w, "}}").map_err_into(LEK::GraphWriteError)
+}
+
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use libimagstore::store::Store;
+    use libimagstore::storeid::StoreId;
+
+    use internal::InternalLinker;
+    use super::*;
+
+    fn id(s: &str) -> StoreId {
+        StoreId::new_baseless(PathBuf::from(s)).unwrap()
+    }
+
+    fn setup_store() -> Store {
+        let store = Store::new_in_memory(PathBuf::from("/"), None).unwrap();
+
+        {
+            let mut a = store.create(id("notes/a")).unwrap();
+            let mut b = store.create(id("notes/b")).unwrap();
+            let mut c = store.create(id("diary/c")).unwrap();
+
+            assert!(a.add_internal_link(&mut b).is_ok());
+            assert!(a.add_internal_link(&mut c).is_ok());
+        }
+
+        store
+    }
+
+    fn export(store: &Store, module: Option<&str>) -> String {
+        let mut buf = vec![];
+        assert!(export_link_graph_dot_for_module(store, &mut buf, module).is_ok());
+        String::from_utf8(buf).unwrap()
+    }
+
+    #[test]
+    fn test_dot_export() {
+        let store = setup_store();
+        let dot   = export(&store, None);
+
+        assert!(dot.starts_with("digraph imag {\n"));
+        assert!(dot.ends_with("}\n"));
+
+        assert!(dot.contains("    \"notes/a\" [label=\"notes/a\"];\n"));
+        assert!(dot.contains("    \"notes/b\" [label=\"notes/b\"];\n"));
+        assert!(dot.contains("    \"diary/c\" [label=\"diary/c\"];\n"));
+
+        assert!(dot.contains("    \"notes/a\" -> \"notes/b\";\n"));
+        assert!(dot.contains("    \"notes/b\" -> \"notes/a\";\n"));
+        assert!(dot.contains("    \"notes/a\" -> \"diary/c\";\n"));
+        assert!(dot.contains("    \"diary/c\" -> \"notes/a\";\n"));
+    }
+
+    #[test]
+    fn test_dot_export_for_module() {
+        let store = setup_store();
+        let dot   = export(&store, Some("notes"));
+
+        assert!(dot.contains("    \"notes/a\" -> \"notes/b\";\n"));
+        assert!(dot.contains("    \"notes/b\" -> \"notes/a\";\n"));
+        assert!(!dot.contains("diary/c"));
+    }
+
+}
@@ -24,6 +24,7 @@ use std::path::PathBuf;
 use libimagstore::storeid::StoreId;
 use libimagstore::storeid::IntoStoreId;
 use libimagstore::store::Entry;
+use libimagstore::store::Store;
 use libimagstore::store::Result as StoreResult;
 use libimagstore::toml_ext::TomlValueExt;
 use libimagerror::into::IntoError;
@@ -114,6 +115,26 @@ impl Link {
         }
     }
 
+    /// Like `Link::to_value()`, but a `Link::Id` is converted to a table without annotation
+    ///
+    /// TOML does not allow arrays with mixed types, so this is used for all links of an entry as
+    /// soon as one of them is annotated.
+    fn to_table_value(&self) -> Result<Value> {
+        match self {
+            &Link::Id { link: ref s } => {
+                s.to_str()
+                    .map(Value::String)
+                    .map_err_into(LEK::InternalConversionError)
+                    .map(|link| {
+                        let mut tab = BTreeMap::new();
+                        tab.insert("link".to_owned(), link);
+                        Value::Table(tab)
+                    })
+            },
+            &Link::Annotated { .. } => self.to_value(),
+        }
+    }
+
 }
 
 impl ::std::cmp::PartialEq for Link {
@@ -178,6 +199,140 @@ pub trait InternalLinker {
 
     /// Add internal annotated link
     fn add_internal_annotated_link(&mut self, link: &mut Entry, annotation: String) -> Result<()>;
+
+    /// Add an internal link with a label, for example "supersedes"
+    ///
+    /// The label is stored as annotation of the link. The link back from `link` to the
+    /// implementor object is not labeled.
+    fn add_internal_link_with_label(&mut self, link: &mut Entry, label: &str) -> Result<()>;
+
+    /// Get the internal links with their labels, where unlabeled links have `None` as label
+    fn get_internal_links_with_labels(&self) -> Result<Vec<(StoreId, Option<String>)>>;
+
+    /// Remove all internal links from the implementor object, including the links back from the
+    /// linked entries
+    ///
+    /// Returns the number of removed links. Call this before `Store::delete()`ing an entry, so no
+    /// dangling links to the deleted entry remain.
+    ///
+    /// # Warning
+    ///
+    /// The linked entries are fetched from the store, so this fails if one of them is currently
+    /// borrowed.
+    fn unlink_all(&mut self, store: &Store) -> Result<usize>;
+}
+
+/// Extension for the Store to move entries without leaving dangling links behind
+pub trait StoreLinkMover {
+
+    /// Move the entry `old_id` to `new_id` like `Store::move_by_id()` does, but rewrite the links
+    /// of all linked entries to point to `new_id` afterwards
+    ///
+    /// The linked entries are borrowed from the store _before_ the entry is moved and only
+    /// released after their links are rewritten, so nobody can see them linking to the old id.
+    ///
+    /// # Warning
+    ///
+    /// This fails if one of the linked entries (or the moved entry itself) is currently borrowed.
+    /// Linked entries which do not exist in the store are ignored.
+    fn move_by_id_relinking(&self, old_id: StoreId, new_id: StoreId) -> Result<()>;
+
+}
+
+impl StoreLinkMover for Store {
+
+    fn move_by_id_relinking(&self, old_id: StoreId, new_id: StoreId) -> Result<()> {
+        let old_loc = old_id.clone().without_base();
+        let new_loc = new_id.clone().without_base();
+
+        let links = match try!(self.get(old_id.clone()).map_err_into(LEK::StoreReadError)) {
+            Some(entry) => try!(entry.get_internal_links()).collect::<Vec<Link>>(),
+            None        => return Err(LEK::LinkTargetDoesNotExist.into_error()),
+        };
+
+        let mut partners = vec![];
+        for link in links.iter().filter(|l| !l.eq_store_id(&old_loc)) {
+            let id = link.get_store_id().clone();
+            match try!(self.get(id.clone()).map_err_into(LEK::StoreReadError)) {
+                Some(partner) => partners.push(partner),
+                None          => debug!("Linked entry {:?} does not exist, not relinking", id),
+            }
+        }
+
+        try!(self.move_by_id(old_id, new_id.clone()).map_err_into(LEK::StoreWriteError));
+
+        for partner in partners.iter_mut() {
+            debug!("Relinking {:?} from {:?} to {:?}", partner.get_location(), old_loc, new_loc);
+            let links = try!(partner.get_internal_links())
+                .map(|l| relink(l, &old_loc, &new_loc))
+                .collect::<Vec<Link>>();
+            try!(rewrite_links(partner.get_header_mut(), links.into_iter()));
+        }
+
+        if links.iter().any(|l| l.eq_store_id(&old_loc)) {
+            debug!("Relinking self-link of {:?}", new_loc);
+            let mut entry = try!(self.get(new_id)
+                                 .map_err_into(LEK::StoreReadError)
+                                 .and_then(|o| o.ok_or(LEK::LinkTargetDoesNotExist.into_error())));
+            let links = try!(entry.get_internal_links())
+                .map(|l| relink(l, &old_loc, &new_loc))
+                .collect::<Vec<Link>>();
+            try!(rewrite_links(entry.get_header_mut(), links.into_iter()));
+        }
+
+        Ok(())
+    }
+
+}
+
+/// Extension for the Store to delete entries without leaving dangling links behind
+pub trait StoreLinkDeleter {
+
+    /// Remove all internal links of the entry `id` (see `InternalLinker::unlink_all()`), then
+    /// delete it with `Store::delete()`
+    ///
+    /// This is the safe way to delete an entry which might be linked. Use `Store::delete()` if the
+    /// links are known to be taken care of.
+    ///
+    /// # Warning
+    ///
+    /// This fails if one of the linked entries (or the entry itself) is currently borrowed. If
+    /// unlinking fails, the entry is not deleted.
+    fn delete_with_unlink(&self, id: StoreId) -> Result<()>;
+
+}
+
+impl StoreLinkDeleter for Store {
+
+    fn delete_with_unlink(&self, id: StoreId) -> Result<()> {
+        {
+            let mut entry = match try!(self.get(id.clone()).map_err_into(LEK::StoreReadError)) {
+                Some(entry) => entry,
+                None        => return Err(LEK::LinkTargetDoesNotExist.into_error()),
+            };
+
+            let n = try!(entry.unlink_all(self));
+            debug!("Removed {} links from {:?} before deleting it", n, id);
+        } // entry is released here, so it can be deleted
+
+        self.delete(id).map_err_into(LEK::StoreWriteError)
+    }
+
+}
+
+/// Replace `old` with `new` in the link, keeping the annotation
+fn relink(link: Link, old: &StoreId, new: &StoreId) -> Link {
+    if !link.eq_store_id(old) {
+        return link;
+    }
+
+    match link {
+        Link::Id { .. }                      => Link::Id { link: new.clone() },
+        Link::Annotated { annotation, .. }   => Link::Annotated {
+            link: new.clone(),
+            annotation: annotation,
+        },
+    }
 }
 
 pub mod iter {
@@ -222,11 +377,15 @@ pub mod iter {
 
     impl<I: Iterator<Item = Link>> IntoValues for I {
         fn into_values(self) -> Vec<Result<Value>> {
-            self.map(|s| s.without_base())
+            let links = self.map(|s| s.without_base())
                 .unique()
-                .sorted()
-                .into_iter() // Cannot sort toml::Value, hence uglyness here
-                .map(|link| link.to_value().map_err_into(LEK::InternalConversionError))
+                .sorted(); // Cannot sort toml::Value, hence uglyness here
+
+            let as_tables = links.iter().any(|l| is_match!(*l, Link::Annotated { .. }));
+
+            links.into_iter()
+                .map(|link| if as_tables { link.to_table_value() } else { link.to_value() })
+                .map(|v| v.map_err_into(LEK::InternalConversionError))
                 .collect()
         }
     }
@@ -456,6 +615,44 @@ impl InternalLinker for Entry {
         add_internal_link_with_instance(self, link, new_link)
     }
 
+    fn add_internal_link_with_label(&mut self, link: &mut Entry, label: &str) -> Result<()> {
+        self.add_internal_annotated_link(link, String::from(label))
+    }
+
+    fn get_internal_links_with_labels(&self) -> Result<Vec<(StoreId, Option<String>)>> {
+        self.get_internal_links()
+            .map(|links| {
+                links.map(|link| match link {
+                    Link::Id { link }                    => (link, None),
+                    Link::Annotated { link, annotation } => (link, Some(annotation)),
+                })
+                .collect()
+            })
+    }
+
+    fn unlink_all(&mut self, store: &Store) -> Result<usize> {
+        let own_loc = self.get_location().clone().without_base();
+        let links   = try!(self.get_internal_links()).collect::<Vec<Link>>();
+
+        for link in links.iter() {
+            let id = link.get_store_id().clone();
+
+            match try!(store.get(id.clone()).map_err_into(LEK::StoreReadError)) {
+                Some(mut other) => {
+                    debug!("Removing link from {:?} to {:?}", id, own_loc);
+                    let other_links = try!(other.get_internal_links())
+                        .filter(|l| !l.eq_store_id(&own_loc));
+                    try!(rewrite_links(other.get_header_mut(), other_links));
+                },
+                None => debug!("Linked entry {:?} does not exist, nothing to unlink", id),
+            }
+        }
+
+        debug!("Removing all links from {:?}", own_loc);
+        try!(rewrite_links(self.get_header_mut(), LinkIter::new(vec![])));
+        Ok(links.len())
+    }
+
 }
 
 fn add_internal_link_with_instance(this: &mut Entry, link: &mut Entry, instance: Link) -> Result<()> {
@@ -549,20 +746,18 @@ fn process_rw_result(links: StoreResult<Option<Value>>) -> Result<LinkIter> {
                     ,
                 Value::Table(mut tab) => {
                     debug!("Destructuring table");
-                    if !tab.contains_key("link")
-                    || !tab.contains_key("annotation") {
+                    if !tab.contains_key("link") {
                         debug!("Things missing... returning Error instance");
                         Err(LEK::LinkParserError.into_error())
                     } else {
                         let link = try!(tab.remove("link")
                             .ok_or(LEK::LinkParserFieldMissingError.into_error()));
 
-                        let anno = try!(tab.remove("annotation")
-                            .ok_or(LEK::LinkParserFieldMissingError.into_error()));
-
+                        // A table without annotation is written for a `Link::Id` if other links
+                        // of the entry are annotated
                         debug!("Ok, here we go with building a Link::Annotated");
-                        match (link, anno) {
-                            (Value::String(link), Value::String(anno)) => {
+                        match (link, tab.remove("annotation")) {
+                            (Value::String(link), Some(Value::String(anno))) => {
                                 StoreId::new_baseless(PathBuf::from(link))
                                     .map_err_into(LEK::StoreIdError)
                                     .map(|link| {
@@ -572,6 +767,11 @@ fn process_rw_result(links: StoreResult<Option<Value>>) -> Result<LinkIter> {
                                         }
                                     })
                             },
+                            (Value::String(link), None) => {
+                                StoreId::new_baseless(PathBuf::from(link))
+                                    .map_err_into(LEK::StoreIdError)
+                                    .map(|link| Link::Id { link: link })
+                            },
                             _ => Err(LEK::LinkParserFieldTypeError.into_error()),
                         }
                     }
@@ -589,6 +789,7 @@ fn process_rw_result(links: StoreResult<Option<Value>>) -> Result<LinkIter> {
 mod test {
     use std::path::PathBuf;
 
+    use libimagstore::store::Entry;
     use libimagstore::store::Store;
 
     use super::InternalLinker;
@@ -599,7 +800,7 @@ mod test {
     }
 
     pub fn get_store() -> Store {
-        Store::new(PathBuf::from("/"), None).unwrap()
+        Store::new_in_memory(PathBuf::from("/"), None).unwrap()
     }
 
     #[test]
@@ -730,5 +931,137 @@ mod test {
 
     }
 
-}
+    #[test]
+    fn test_labeled_links() {
+        setup_logging();
+        let store = get_store();
+
+        let mut new  = store.retrieve(PathBuf::from("test_labeled_links_new")).unwrap();
+        let mut old  = store.retrieve(PathBuf::from("test_labeled_links_old")).unwrap();
+        let mut misc = store.retrieve(PathBuf::from("test_labeled_links_misc")).unwrap();
+
+        assert!(new.add_internal_link_with_label(&mut old, "supersedes").is_ok());
+        assert!(new.add_internal_link(&mut misc).is_ok());
+
+        // Reload the entry from its serialized form
+        let reloaded = Entry::from_str(new.get_location().clone(), &new.to_str()).unwrap();
+
+        let mut links = reloaded.get_internal_links_with_labels().unwrap();
+        links.sort();
+        let links : Vec<(String, Option<String>)> = links
+            .into_iter()
+            .map(|(id, label)| (id.to_str().unwrap(), label))
+            .collect();
+
+        assert_eq!(links, vec![
+            (String::from("test_labeled_links_misc"), None),
+            (String::from("test_labeled_links_old"), Some(String::from("supersedes"))),
+        ]);
+
+        // The link back is not labeled
+        let back = old.get_internal_links_with_labels().unwrap();
+        assert_eq!(back.len(), 1);
+        assert_eq!(back[0].1, None);
+    }
+
+    #[test]
+    fn test_unlink_all() {
+        setup_logging();
+        let store = get_store();
+
+        let mut center = store.retrieve(PathBuf::from("test_unlink_all_center")).unwrap();
+        {
+            let mut a = store.retrieve(PathBuf::from("test_unlink_all_a")).unwrap();
+            let mut b = store.retrieve(PathBuf::from("test_unlink_all_b")).unwrap();
+            let mut c = store.retrieve(PathBuf::from("test_unlink_all_c")).unwrap();
+
+            assert!(center.add_internal_link(&mut a).is_ok());
+            assert!(center.add_internal_link(&mut b).is_ok());
+            assert!(center.add_internal_link(&mut c).is_ok());
+
+            assert_eq!(a.get_internal_links().unwrap().count(), 1);
+        } // other entries are written back here, so unlink_all() can fetch them
+
+        assert_eq!(center.get_internal_links().unwrap().count(), 3);
+        assert_eq!(center.unlink_all(&store).unwrap(), 3);
+        assert_eq!(center.get_internal_links().unwrap().count(), 0);
+
+        for name in &["test_unlink_all_a", "test_unlink_all_b", "test_unlink_all_c"] {
+            let other = store.get(PathBuf::from(name)).unwrap().unwrap();
+            assert_eq!(other.get_internal_links().unwrap().count(), 0);
+        }
+    }
+
+    #[test]
+    fn test_delete_with_unlink() {
+        use libimagstore::storeid::StoreId;
+        use super::StoreLinkDeleter;
+
+        setup_logging();
+        let store = get_store();
+        let id    = |s: &str| StoreId::new_baseless(PathBuf::from(s)).unwrap();
+
+        {
+            let mut a = store.create(id("test_delete_unlink_a")).unwrap();
+            let mut b = store.create(id("test_delete_unlink_b")).unwrap();
+            let mut c = store.create(id("test_delete_unlink_c")).unwrap();
 
+            assert!(a.add_internal_link(&mut b).is_ok());
+            assert!(a.add_internal_link(&mut c).is_ok());
+            assert!(b.add_internal_link(&mut c).is_ok());
+        } // written back here, so they can be unlinked
+
+        assert!(store.delete_with_unlink(id("test_delete_unlink_a")).is_ok());
+        assert!(store.get(id("test_delete_unlink_a")).unwrap().is_none());
+
+        let b = store.get(id("test_delete_unlink_b")).unwrap().unwrap();
+        let c = store.get(id("test_delete_unlink_c")).unwrap().unwrap();
+
+        let links_of = |e: &::libimagstore::store::FileLockEntry| {
+            e.get_internal_links().unwrap().map(|l| l.get_store_id().clone()).collect::<Vec<_>>()
+        };
+        assert_eq!(links_of(&b), vec![id("test_delete_unlink_c")]);
+        assert_eq!(links_of(&c), vec![id("test_delete_unlink_b")]);
+    }
+
+    #[test]
+    fn test_delete_with_unlink_nonexistent() {
+        use libimagstore::storeid::StoreId;
+        use super::StoreLinkDeleter;
+
+        setup_logging();
+        let store = get_store();
+        let id    = StoreId::new_baseless(PathBuf::from("test_delete_unlink_missing")).unwrap();
+
+        assert!(store.delete_with_unlink(id).is_err());
+    }
+
+    #[test]
+    fn test_move_by_id_relinking() {
+        use libimagstore::storeid::StoreId;
+        use super::StoreLinkMover;
+
+        setup_logging();
+        let store = get_store();
+        let id    = |s: &str| StoreId::new_baseless(PathBuf::from(s)).unwrap();
+
+        {
+            let mut a = store.create(id("test_relink_a")).unwrap();
+            let mut b = store.create(id("test_relink_b")).unwrap();
+            assert!(a.add_internal_link_with_label(&mut b, "supersedes").is_ok());
+        } // written back here, so they can be moved
+
+        assert!(store.move_by_id_relinking(id("test_relink_b"), id("test_relink_c")).is_ok());
+        assert!(store.get(id("test_relink_b")).unwrap().is_none());
+
+        let a = store.get(id("test_relink_a")).unwrap().unwrap();
+        let c = store.get(id("test_relink_c")).unwrap().unwrap();
+
+        let a_links = a.get_internal_links_with_labels().unwrap();
+        assert_eq!(a_links, vec![(id("test_relink_c"), Some(String::from("supersedes")))]);
+
+        let c_links = c.get_internal_links_with_labels().unwrap();
+        assert_eq!(c_links, vec![(id("test_relink_a"), None)]);
+    }
+
+}
@@ -48,8 +48,10 @@ extern crate libimagutil;
 
 module_entry_path_mod!("links");
 
+pub mod consistency;
 pub mod error;
 pub mod external;
+pub mod graph;
 pub mod internal;
 pub mod result;
 
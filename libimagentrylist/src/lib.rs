@@ -37,9 +37,11 @@ extern crate clap;
 #[macro_use] extern crate log;
 extern crate toml;
 extern crate prettytable;
+extern crate serde_json;
 
 extern crate libimagstore;
 extern crate libimagutil;
+extern crate libimagentrytag;
 #[macro_use] extern crate libimagerror;
 
 pub mod cli;
@@ -17,13 +17,26 @@
 // Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
 //
 
+use std::io::stdout;
+use std::io::Write;
+
 use libimagstore::store::FileLockEntry;
 
 use result::Result;
 
 pub trait Lister : Sized {
 
-    fn list<'a, I: Iterator<Item = FileLockEntry<'a>>>(&self, entries: I) -> Result<()>;
+    /// List the entries to `w`
+    fn list_to<'a, I, W>(&self, entries: I, w: &mut W) -> Result<()>
+        where I: Iterator<Item = FileLockEntry<'a>>,
+              W: Write;
+
+    /// List the entries to stdout
+    fn list<'a, I: Iterator<Item = FileLockEntry<'a>>>(&self, entries: I) -> Result<()> {
+        let out = stdout();
+        let mut lock = out.lock();
+        self.list_to(entries, &mut lock)
+    }
 
 }
 
@@ -17,7 +17,6 @@
 // Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
 //
 
-use std::io::stdout;
 use std::io::Write;
 
 use lister::Lister;
@@ -42,7 +41,10 @@ impl<T: Fn(&Entry) -> String> CoreLister<T> {
 
 impl<T: Fn(&Entry) -> String> Lister for CoreLister<T> {
 
-    fn list<'b, I: Iterator<Item = FileLockEntry<'b>>>(&self, entries: I) -> Result<()> {
+    fn list_to<'b, I, W>(&self, entries: I, w: &mut W) -> Result<()>
+        where I: Iterator<Item = FileLockEntry<'b>>,
+              W: Write
+    {
         use error::ListError as LE;
         use error::ListErrorKind as LEK;
 
@@ -52,7 +54,7 @@ impl<T: Fn(&Entry) -> String> Lister for CoreLister<T> {
                 debug!("fold({:?}, {:?})", accu, entry);
                 let r = accu.and_then(|_| {
                         debug!("Listing Entry: {:?}", entry);
-                        write!(stdout(), "{:?}\n", (self.lister)(&entry))
+                        write!(w, "{:?}\n", (self.lister)(&entry))
                             .map_err(|e| LE::new(LEK::FormatError, Some(Box::new(e))))
                     });
                 (r, i + 1)
@@ -63,3 +65,42 @@ impl<T: Fn(&Entry) -> String> Lister for CoreLister<T> {
 
 }
 
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use libimagstore::store::Entry;
+    use libimagstore::store::Store;
+    use libimagstore::storeid::StoreId;
+
+    use lister::Lister;
+    use super::CoreLister;
+
+    fn get_store() -> Store {
+        Store::new_in_memory(PathBuf::from("/"), None).unwrap()
+    }
+
+    #[test]
+    fn test_core_lister() {
+        let store = get_store();
+        let entries = vec!["test/a", "test/b"]
+            .into_iter()
+            .map(|s| store.create(StoreId::new_baseless(PathBuf::from(s)).unwrap()).unwrap());
+
+        let mut buf = vec![];
+        let lister  = CoreLister::new(|e: &Entry| format!("{}", e.get_location()));
+        assert!(lister.list_to(entries, &mut buf).is_ok());
+
+        assert_eq!(buf, b"\"test/a\"\n\"test/b\"\n".to_vec());
+    }
+
+    #[test]
+    fn test_core_lister_no_entries() {
+        let mut buf = vec![];
+        let lister  = CoreLister::new(|e: &Entry| format!("{}", e.get_location()));
+        assert!(lister.list_to(vec![].into_iter(), &mut buf).is_ok());
+
+        assert!(buf.is_empty());
+    }
+
+}
@@ -0,0 +1,165 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015, 2016 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+use std::io::Write;
+
+use lister::Lister;
+use result::Result;
+
+use libimagstore::store::FileLockEntry;
+use libimagstore::toml_ext::TomlValueExt;
+use libimagentrytag::tag::Tag;
+use libimagentrytag::tagable::Tagable;
+use libimagerror::trace::MapErrTrace;
+
+use toml::Value;
+
+/// Lister adapter which only passes the entries matching a predicate to another lister
+pub struct FilterLister<L: Lister> {
+    inner: L,
+    filter: Box<Fn(&FileLockEntry) -> bool>,
+}
+
+impl<L: Lister> FilterLister<L> {
+
+    pub fn new(inner: L, filter: Box<Fn(&FileLockEntry) -> bool>) -> FilterLister<L> {
+        FilterLister {
+            inner: inner,
+            filter: filter,
+        }
+    }
+
+    /// Only list entries which are tagged with `tag`
+    ///
+    /// Entries where the tags cannot be read are not listed.
+    pub fn with_tag(inner: L, tag: Tag) -> FilterLister<L> {
+        FilterLister::new(inner, Box::new(move |e| {
+            e.has_tag(&tag).map_err_trace().unwrap_or(false)
+        }))
+    }
+
+    /// Only list entries where the header at `key` equals `value`
+    ///
+    /// Entries where the header cannot be read are not listed.
+    pub fn with_header_eq(inner: L, key: &str, value: Value) -> FilterLister<L> {
+        let key = String::from(key);
+        FilterLister::new(inner, Box::new(move |e| {
+            e.get_header()
+                .read(&key)
+                .map_err_trace()
+                .map(|v| v.map(|v| v == value).unwrap_or(false))
+                .unwrap_or(false)
+        }))
+    }
+
+}
+
+impl<L: Lister> Lister for FilterLister<L> {
+
+    fn list_to<'a, I, W>(&self, entries: I, w: &mut W) -> Result<()>
+        where I: Iterator<Item = FileLockEntry<'a>>,
+              W: Write
+    {
+        self.inner.list_to(entries.filter(|e| (self.filter)(e)), w)
+    }
+
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::RefCell;
+    use std::io::Write;
+    use std::path::PathBuf;
+
+    use toml::Value;
+
+    use libimagstore::store::FileLockEntry;
+    use libimagstore::store::Store;
+    use libimagstore::storeid::StoreId;
+    use libimagstore::toml_ext::TomlValueExt;
+    use libimagentrytag::tagable::Tagable;
+
+    use lister::Lister;
+    use result::Result;
+    use super::FilterLister;
+
+    fn get_store() -> Store {
+        Store::new_in_memory(PathBuf::from("/"), None).unwrap()
+    }
+
+    /// Lister which remembers the ids of the entries it was called with
+    struct IdCollector(RefCell<Vec<String>>);
+
+    impl Lister for IdCollector {
+        fn list_to<'a, I, W>(&self, entries: I, _: &mut W) -> Result<()>
+            where I: Iterator<Item = FileLockEntry<'a>>,
+                  W: Write
+        {
+            for e in entries {
+                self.0.borrow_mut().push(format!("{}", e.get_location()));
+            }
+            Ok(())
+        }
+    }
+
+    fn entries<'a>(store: &'a Store) -> Vec<FileLockEntry<'a>> {
+        vec![("test/a", "bob", true), ("test/b", "alice", true), ("test/c", "bob", false)]
+            .into_iter()
+            .map(|(id, author, tagged)| {
+                let mut e = store.create(StoreId::new_baseless(PathBuf::from(id)).unwrap()).unwrap();
+                assert!(e.get_header_mut().insert("author", Value::String(String::from(author))).is_ok());
+                if tagged {
+                    assert!(e.add_tag(String::from("x")).is_ok());
+                }
+                e
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_filter() {
+        let store  = get_store();
+        let lister = FilterLister::new(IdCollector(RefCell::new(vec![])), Box::new(|e| {
+            format!("{}", e.get_location()) != "test/b"
+        }));
+
+        assert!(lister.list(entries(&store).into_iter()).is_ok());
+        assert_eq!(*lister.inner.0.borrow(), vec!["test/a", "test/c"]);
+    }
+
+    #[test]
+    fn test_filter_with_tag() {
+        let store  = get_store();
+        let lister = FilterLister::with_tag(IdCollector(RefCell::new(vec![])), String::from("x"));
+
+        assert!(lister.list(entries(&store).into_iter()).is_ok());
+        assert_eq!(*lister.inner.0.borrow(), vec!["test/a", "test/b"]);
+    }
+
+    #[test]
+    fn test_filter_with_header_eq() {
+        let store  = get_store();
+        let bob    = Value::String(String::from("bob"));
+        let lister = FilterLister::with_header_eq(IdCollector(RefCell::new(vec![])), "author", bob);
+
+        assert!(lister.list(entries(&store).into_iter()).is_ok());
+        assert_eq!(*lister.inner.0.borrow(), vec!["test/a", "test/c"]);
+    }
+
+}
@@ -0,0 +1,169 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015, 2016 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+
+use std::collections::BTreeMap;
+use std::io::Write;
+
+use lister::Lister;
+use result::Result;
+use error::MapErrInto;
+use error::ListErrorKind as LEK;
+
+use libimagstore::store::FileLockEntry;
+
+use serde_json::Value as JsonValue;
+use serde_json::ser::to_string as json_to_string;
+use toml::Value as TomlValue;
+
+/// Lister which prints entries as JSON objects
+///
+/// Each entry is printed as `{ "id": "...", "header": {...}, "content": "..." }`. The objects are
+/// either printed as one JSON array or, if `newline_delimited` is set, as one object per line.
+pub struct JsonLister {
+    newline_delimited: bool,
+}
+
+impl JsonLister {
+
+    pub fn new(newline_delimited: bool) -> JsonLister {
+        JsonLister {
+            newline_delimited: newline_delimited,
+        }
+    }
+
+}
+
+impl Lister for JsonLister {
+
+    fn list_to<'a, I, W>(&self, entries: I, w: &mut W) -> Result<()>
+        where I: Iterator<Item = FileLockEntry<'a>>,
+              W: Write
+    {
+        let mut first = true;
+
+        if !self.newline_delimited {
+            try!(write!(w, "[").map_err_into(LEK::IOError));
+        }
+
+        for entry in entries {
+            let json = try!(json_to_string(&entry_to_json(&entry)).map_err_into(LEK::FormatError));
+
+            if self.newline_delimited {
+                try!(write!(w, "{}\n", json).map_err_into(LEK::IOError));
+            } else {
+                let sep = if first { "" } else { "," };
+                try!(write!(w, "{}{}", sep, json).map_err_into(LEK::IOError));
+            }
+            first = false;
+        }
+
+        if !self.newline_delimited {
+            try!(write!(w, "]\n").map_err_into(LEK::IOError));
+        }
+
+        Ok(())
+    }
+
+}
+
+fn entry_to_json(entry: &FileLockEntry) -> JsonValue {
+    let mut obj = BTreeMap::new();
+    obj.insert(String::from("id"), JsonValue::String(format!("{}", entry.get_location())));
+    obj.insert(String::from("header"), toml_to_json(entry.get_header()));
+    obj.insert(String::from("content"), JsonValue::String(entry.get_content().clone()));
+    JsonValue::Object(obj)
+}
+
+fn toml_to_json(v: &TomlValue) -> JsonValue {
+    match *v {
+        TomlValue::String(ref s)   => JsonValue::String(s.clone()),
+        TomlValue::Integer(i)      => JsonValue::I64(i),
+        TomlValue::Float(f)        => JsonValue::F64(f),
+        TomlValue::Boolean(b)      => JsonValue::Bool(b),
+        TomlValue::Datetime(ref d) => JsonValue::String(format!("{}", d)),
+        TomlValue::Array(ref a)    => JsonValue::Array(a.iter().map(toml_to_json).collect()),
+        TomlValue::Table(ref t)    => {
+            JsonValue::Object(t.iter().map(|(k, v)| (k.clone(), toml_to_json(v))).collect())
+        },
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use serde_json::Value as JsonValue;
+    use serde_json::de::from_str as json_from_str;
+
+    use libimagstore::store::Store;
+    use libimagstore::storeid::StoreId;
+
+    use lister::Lister;
+    use super::JsonLister;
+
+    fn get_store() -> Store {
+        Store::new_in_memory(PathBuf::from("/"), None).unwrap()
+    }
+
+    fn list(store: &Store, newline_delimited: bool) -> String {
+        let entries = vec!["test/a", "test/b"]
+            .into_iter()
+            .map(|s| {
+                let mut e = store.create(StoreId::new_baseless(PathBuf::from(s)).unwrap()).unwrap();
+                *e.get_content_mut() = format!("content of {}", s);
+                e
+            });
+
+        let mut buf = vec![];
+        assert!(JsonLister::new(newline_delimited).list_to(entries, &mut buf).is_ok());
+        String::from_utf8(buf).unwrap()
+    }
+
+    fn check_object(obj: &JsonValue, id: &str) {
+        assert_eq!(obj.find("id").and_then(|v| v.as_str()), Some(id));
+        assert_eq!(obj.find("content").and_then(|v| v.as_str()),
+                   Some(&format!("content of {}", id)[..]));
+        assert!(obj.lookup("header.imag.version").and_then(|v| v.as_str()).is_some());
+    }
+
+    #[test]
+    fn test_json_array() {
+        let store = get_store();
+        let out   = list(&store, false);
+
+        let json : JsonValue = json_from_str(&out).unwrap();
+        let arr = json.as_array().unwrap();
+        assert_eq!(arr.len(), 2);
+        check_object(&arr[0], "test/a");
+        check_object(&arr[1], "test/b");
+    }
+
+    #[test]
+    fn test_json_newline_delimited() {
+        let store = get_store();
+        let out   = list(&store, true);
+
+        let lines : Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 2);
+        check_object(&json_from_str(lines[0]).unwrap(), "test/a");
+        check_object(&json_from_str(lines[1]).unwrap(), "test/b");
+    }
+
+}
@@ -17,7 +17,6 @@
 // Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
 //
 
-use std::io::stdout;
 use std::io::Write;
 
 use lister::Lister;
@@ -42,13 +41,16 @@ impl<'a> LineLister<'a> {
 
 impl<'a> Lister for LineLister<'a> {
 
-    fn list<'b, I: Iterator<Item = FileLockEntry<'b>>>(&self, entries: I) -> Result<()> {
+    fn list_to<'b, I, W>(&self, entries: I, w: &mut W) -> Result<()>
+        where I: Iterator<Item = FileLockEntry<'b>>,
+              W: Write
+    {
         use error::ListError as LE;
         use error::ListErrorKind as LEK;
 
         entries.fold_result(|entry| {
             let s = entry.get_location().to_str().unwrap_or(String::from(self.unknown_output));
-            write!(stdout(), "{:?}\n", s).map_err(|e| LE::new(LEK::FormatError, Some(Box::new(e))))
+            write!(w, "{:?}\n", s).map_err(|e| LE::new(LEK::FormatError, Some(Box::new(e))))
         })
     }
 
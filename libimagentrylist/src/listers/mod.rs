@@ -18,6 +18,9 @@
 //
 
 pub mod core;
+pub mod filter;
+pub mod json;
 pub mod line;
 pub mod path;
+pub mod sorted;
 pub mod table;
@@ -17,8 +17,8 @@
 // Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
 //
 
-use std::io::stdout;
 use std::io::Write;
+use std::path::PathBuf;
 
 use lister::Lister;
 use result::Result;
@@ -29,6 +29,7 @@ use libimagutil::iter::FoldResult;
 
 pub struct PathLister {
     absolute: bool,
+    relative_to: Option<PathBuf>,
 }
 
 impl PathLister {
@@ -36,6 +37,17 @@ impl PathLister {
     pub fn new(absolute: bool) -> PathLister {
         PathLister {
             absolute: absolute,
+            relative_to: None,
+        }
+    }
+
+    /// List the paths relative to `store_root`, so `<store_root>/foo/bar` is listed as `foo/bar`
+    ///
+    /// Paths which are not inside `store_root` are listed in full.
+    pub fn relative_to(store_root: PathBuf) -> PathLister {
+        PathLister {
+            absolute: false,
+            relative_to: Some(store_root),
         }
     }
 
@@ -43,7 +55,10 @@ impl PathLister {
 
 impl Lister for PathLister {
 
-    fn list<'a, I: Iterator<Item = FileLockEntry<'a>>>(&self, entries: I) -> Result<()> {
+    fn list_to<'a, I, W>(&self, entries: I, w: &mut W) -> Result<()>
+        where I: Iterator<Item = FileLockEntry<'a>>,
+              W: Write
+    {
         use error::ListError as LE;
         use error::ListErrorKind as LEK;
 
@@ -57,8 +72,14 @@ impl Lister for PathLister {
                         Ok(pb.into())
                     }
                 })
+                .map(|pb| {
+                    let relative = self.relative_to
+                        .as_ref()
+                        .and_then(|root| pb.strip_prefix(root).ok().map(PathBuf::from));
+                    relative.unwrap_or(pb)
+                })
                 .and_then(|pb| {
-                    write!(stdout(), "{:?}\n", pb)
+                    write!(w, "{:?}\n", pb)
                         .map_err(|e| LE::new(LEK::FormatError, Some(Box::new(e))))
                 })
                 .map_err(|e| {
@@ -73,3 +94,59 @@ impl Lister for PathLister {
 
 }
 
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use libimagstore::store::Store;
+    use libimagstore::storeid::StoreId;
+
+    use lister::Lister;
+    use super::PathLister;
+
+    fn get_store() -> Store {
+        Store::new_in_memory(PathBuf::from("/"), None).unwrap()
+    }
+
+    #[test]
+    fn test_path_lister() {
+        let store = get_store();
+        let entries = vec!["test/a", "test/b"]
+            .into_iter()
+            .map(|s| store.create(StoreId::new_baseless(PathBuf::from(s)).unwrap()).unwrap());
+
+        let mut buf = vec![];
+        assert!(PathLister::new(false).list_to(entries, &mut buf).is_ok());
+
+        let expected = format!("{:?}\n{:?}\n", store.path().join("test/a"), store.path().join("test/b"));
+        assert_eq!(buf, expected.into_bytes());
+    }
+
+    #[test]
+    fn test_path_lister_relative_to_store() {
+        let store = get_store();
+        let entries = vec!["test/a", "test/b/c"]
+            .into_iter()
+            .map(|s| store.create(StoreId::new_baseless(PathBuf::from(s)).unwrap()).unwrap());
+
+        let mut buf = vec![];
+        assert!(PathLister::relative_to(store.path().clone()).list_to(entries, &mut buf).is_ok());
+
+        assert_eq!(buf, b"\"test/a\"\n\"test/b/c\"\n".to_vec());
+    }
+
+    #[test]
+    fn test_path_lister_relative_to_other_root() {
+        let store = get_store();
+        let entries = vec!["test/a"]
+            .into_iter()
+            .map(|s| store.create(StoreId::new_baseless(PathBuf::from(s)).unwrap()).unwrap());
+
+        let mut buf = vec![];
+        let lister  = PathLister::relative_to(PathBuf::from("/some/other/root"));
+        assert!(lister.list_to(entries, &mut buf).is_ok());
+
+        assert_eq!(buf, format!("{:?}\n", store.path().join("test/a")).into_bytes());
+    }
+
+}
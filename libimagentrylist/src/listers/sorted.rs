@@ -0,0 +1,167 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015, 2016 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+use std::io::Write;
+
+use lister::Lister;
+use result::Result;
+
+use libimagstore::store::FileLockEntry;
+use libimagstore::storeid::StoreId;
+use libimagstore::toml_ext::TomlValueExt;
+use libimagerror::trace::trace_error;
+
+use toml::Value;
+
+/// Lister adapter which sorts the entries before passing them to another lister
+///
+/// As the entries have to be sorted, all of them are loaded before the inner lister is called.
+pub struct SortedLister<L: Lister, K: Ord> {
+    inner: L,
+    key: Box<Fn(&FileLockEntry) -> K>,
+}
+
+impl<L: Lister, K: Ord> SortedLister<L, K> {
+
+    /// Sort the entries by the key `key` returns for them
+    pub fn new(inner: L, key: Box<Fn(&FileLockEntry) -> K>) -> SortedLister<L, K> {
+        SortedLister {
+            inner: inner,
+            key: key,
+        }
+    }
+
+}
+
+impl<L: Lister> SortedLister<L, StoreId> {
+
+    /// Sort the entries by their id
+    pub fn by_id(inner: L) -> SortedLister<L, StoreId> {
+        SortedLister::new(inner, Box::new(|e| e.get_location().clone().without_base()))
+    }
+
+}
+
+impl<L: Lister> SortedLister<L, Option<String>> {
+
+    /// Sort the entries by the value of the header at `key`
+    ///
+    /// Values are compared by their string representation. Entries where the header is missing
+    /// (or cannot be read) are listed first.
+    pub fn by_header_key(inner: L, key: &str) -> SortedLister<L, Option<String>> {
+        let key = String::from(key);
+        SortedLister::new(inner, Box::new(move |e| {
+            match e.get_header().read(&key) {
+                Ok(Some(Value::String(s))) => Some(s),
+                Ok(Some(v))                => Some(format!("{}", v)),
+                Ok(None)                   => None,
+                Err(e) => {
+                    trace_error(&e);
+                    None
+                },
+            }
+        }))
+    }
+
+}
+
+impl<L: Lister, K: Ord> Lister for SortedLister<L, K> {
+
+    fn list_to<'a, I, W>(&self, entries: I, w: &mut W) -> Result<()>
+        where I: Iterator<Item = FileLockEntry<'a>>,
+              W: Write
+    {
+        let mut entries : Vec<(K, FileLockEntry<'a>)> = entries
+            .map(|e| ((self.key)(&e), e))
+            .collect();
+
+        // stable, so entries with equal keys stay in iterator order
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+
+        self.inner.list_to(entries.into_iter().map(|(_, e)| e), w)
+    }
+
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::RefCell;
+    use std::io::Write;
+    use std::path::PathBuf;
+
+    use toml::Value;
+
+    use libimagstore::store::FileLockEntry;
+    use libimagstore::store::Store;
+    use libimagstore::storeid::StoreId;
+    use libimagstore::toml_ext::TomlValueExt;
+
+    use lister::Lister;
+    use result::Result;
+    use super::SortedLister;
+
+    fn get_store() -> Store {
+        Store::new_in_memory(PathBuf::from("/"), None).unwrap()
+    }
+
+    /// Lister which remembers the ids of the entries it was called with
+    struct IdCollector(RefCell<Vec<String>>);
+
+    impl Lister for IdCollector {
+        fn list_to<'a, I, W>(&self, entries: I, _: &mut W) -> Result<()>
+            where I: Iterator<Item = FileLockEntry<'a>>,
+                  W: Write
+        {
+            for e in entries {
+                self.0.borrow_mut().push(format!("{}", e.get_location()));
+            }
+            Ok(())
+        }
+    }
+
+    fn entries<'a>(store: &'a Store) -> Vec<FileLockEntry<'a>> {
+        vec![("test/b", "2017-01-03"), ("test/c", "2017-01-01"), ("test/a", "2017-01-02")]
+            .into_iter()
+            .map(|(id, date)| {
+                let mut e = store.create(StoreId::new_baseless(PathBuf::from(id)).unwrap()).unwrap();
+                assert!(e.get_header_mut().insert("date", Value::String(String::from(date))).is_ok());
+                e
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_sorted_by_id() {
+        let store  = get_store();
+        let lister = SortedLister::by_id(IdCollector(RefCell::new(vec![])));
+
+        assert!(lister.list(entries(&store).into_iter()).is_ok());
+        assert_eq!(*lister.inner.0.borrow(), vec!["test/a", "test/b", "test/c"]);
+    }
+
+    #[test]
+    fn test_sorted_by_header_key() {
+        let store  = get_store();
+        let lister = SortedLister::by_header_key(IdCollector(RefCell::new(vec![])), "date");
+
+        assert!(lister.list(entries(&store).into_iter()).is_ok());
+        assert_eq!(*lister.inner.0.borrow(), vec!["test/c", "test/a", "test/b"]);
+    }
+
+}
@@ -17,7 +17,7 @@
 // Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
 //
 
-use std::io::stdout;
+use std::io::Write;
 
 use lister::Lister;
 use result::Result;
@@ -61,7 +61,10 @@ impl<F: Fn(&FileLockEntry) -> Vec<String>> TableLister<F> {
 
 impl<F: Fn(&FileLockEntry) -> Vec<String>> Lister for TableLister<F> {
 
-    fn list<'b, I: Iterator<Item = FileLockEntry<'b>>>(&self, entries: I) -> Result<()> {
+    fn list_to<'b, I, W>(&self, entries: I, w: &mut W) -> Result<()>
+        where I: Iterator<Item = FileLockEntry<'b>>,
+              W: Write
+    {
         use error::ListErrorKind as LEK;
 
         let mut table = Table::new();
@@ -101,10 +104,7 @@ impl<F: Fn(&FileLockEntry) -> Vec<String>> Lister for TableLister<F> {
                 Ok(table)
             })
         })
-        .and_then(|tbl| {
-            let mut io = stdout();
-            tbl.print(&mut io).map_err_into(LEK::IOError)
-        })
+        .and_then(|tbl| tbl.print(w).map_err_into(LEK::IOError))
     }
 
 }
@@ -0,0 +1,131 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015, 2016 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Hierarchical tags
+//!
+//! A tag containing a `/` is hierarchical, for example `project/imag/docs` is a child of
+//! `project/imag`, which itself is a child of `project`.
+
+use std::collections::BTreeMap;
+
+use tag::{Tag, TagSlice};
+
+/// The separator between the levels of a hierarchical tag
+pub const SEPARATOR: char = '/';
+
+/// Check whether `tag` is `parent` or a descendant of `parent`
+pub fn is_self_or_descendant(tag: TagSlice, parent: TagSlice) -> bool {
+    tag == parent || (tag.starts_with(parent) && tag[parent.len()..].starts_with(SEPARATOR))
+}
+
+/// A tree of tags, built from hierarchical tags
+///
+/// The root of the tree has an empty name, its children are the top-level tags.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TagTree {
+    name: String,
+    children: BTreeMap<String, TagTree>,
+}
+
+impl TagTree {
+
+    fn new(name: String) -> TagTree {
+        TagTree {
+            name: name,
+            children: BTreeMap::new(),
+        }
+    }
+
+    /// Build a tree from the passed tags
+    pub fn from_tags(tags: &[Tag]) -> TagTree {
+        let mut root = TagTree::new(String::new());
+        for tag in tags {
+            root.insert(tag);
+        }
+        root
+    }
+
+    fn insert(&mut self, tag: TagSlice) {
+        let mut parts = tag.splitn(2, SEPARATOR);
+        let name      = parts.next().unwrap_or(""); // splitn() always yields at least one part
+
+        let child = self.children
+            .entry(String::from(name))
+            .or_insert_with(|| TagTree::new(String::from(name)));
+
+        if let Some(rest) = parts.next() {
+            child.insert(rest);
+        }
+    }
+
+    /// The name of this level of the tag, e.g. "docs" for `project/imag/docs`
+    pub fn name(&self) -> &String {
+        &self.name
+    }
+
+    /// The children of this node, sorted by name
+    pub fn children(&self) -> Vec<&TagTree> {
+        self.children.values().collect()
+    }
+
+    /// Get the node for the (hierarchical) tag `tag`
+    pub fn get(&self, tag: TagSlice) -> Option<&TagTree> {
+        tag.split(SEPARATOR).fold(Some(self), |node, part| node.and_then(|n| n.children.get(part)))
+    }
+
+}
+
+#[cfg(test)]
+mod test {
+    use super::is_self_or_descendant;
+    use super::TagTree;
+
+    #[test]
+    fn test_is_self_or_descendant() {
+        assert!(is_self_or_descendant("project", "project"));
+        assert!(is_self_or_descendant("project/imag", "project"));
+        assert!(is_self_or_descendant("project/imag/docs", "project"));
+        assert!(is_self_or_descendant("project/imag/docs", "project/imag"));
+
+        assert!(!is_self_or_descendant("projects", "project"));
+        assert!(!is_self_or_descendant("project", "project/imag"));
+        assert!(!is_self_or_descendant("other/project", "project"));
+    }
+
+    #[test]
+    fn test_tag_tree() {
+        let tags = vec![
+            String::from("project/imag/docs"),
+            String::from("project/imag"),
+            String::from("project/other"),
+            String::from("home"),
+        ];
+
+        let tree = TagTree::from_tags(&tags);
+        let top : Vec<&String> = tree.children().into_iter().map(TagTree::name).collect();
+        assert_eq!(top, vec!["home", "project"]);
+
+        let project = tree.get("project").unwrap();
+        assert_eq!(project.children().len(), 2);
+        assert_eq!(tree.get("project/imag").unwrap().children()[0].name(), "docs");
+        assert!(tree.get("home").unwrap().children().is_empty());
+        assert!(tree.get("project/missing").is_none());
+    }
+
+}
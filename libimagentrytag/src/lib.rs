@@ -32,11 +32,8 @@
 )]
 
 extern crate clap;
-extern crate itertools;
 #[macro_use] extern crate log;
-extern crate regex;
 extern crate toml;
-#[macro_use] extern crate is_match;
 
 extern crate libimagstore;
 #[macro_use] extern crate libimagerror;
@@ -44,9 +41,13 @@ extern crate libimagutil;
 
 pub mod error;
 pub mod exec;
+pub mod hierarchy;
+pub mod ops;
 pub mod result;
 pub mod tag;
 pub mod tagable;
 pub mod util;
 pub mod ui;
 
+#[cfg(test)] mod test_util;
+
@@ -0,0 +1,464 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015, 2016 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+
+//! Operations on the tags of all entries in the store
+
+use std::collections::BTreeMap;
+
+use libimagstore::store::FileLockEntry;
+use libimagstore::store::Store;
+use libimagstore::storeid::StoreId;
+use libimagerror::into::IntoError;
+use libimagerror::trace::MapErrTrace;
+
+use error::TagErrorKind;
+use error::MapErrInto;
+use result::Result;
+use tag::Tag;
+use tagable::Tagable;
+use util::is_tag;
+
+/// Rename the tag `old` to `new` in all entries of the store
+///
+/// Returns the number of entries which were changed. If `new` is not a valid tag, nothing is
+/// changed. Entries which cannot be loaded or whose tags cannot be read or written are skipped,
+/// the error is traced.
+pub fn rename_tag(store: &Store, old: &Tag, new: &Tag) -> Result<usize> {
+    if !is_tag(new) {
+        debug!("Not a tag: '{}'", new);
+        return Err(TagErrorKind::NotATag.into_error());
+    }
+
+    let mut changed = 0;
+    for id in try!(store.entries().map_err_into(TagErrorKind::StoreReadError)) {
+        let mut entry = match store.get(id).map_err_trace() {
+            Ok(Some(entry)) => entry,
+            Ok(None)        => continue,
+            Err(_)          => continue,
+        };
+
+        match entry.has_tag(old).map_err_trace() {
+            Ok(true) => {},
+            _        => continue,
+        }
+
+        let renamed = entry.get_tags()
+            .map(|tags| {
+                tags.into_iter()
+                    .map(|t| if &t == old { new.clone() } else { t })
+                    .collect::<Vec<Tag>>()
+            })
+            .and_then(|tags| entry.set_tags(&tags[..]))
+            .map_err_trace();
+
+        if renamed.is_ok() {
+            changed += 1;
+        }
+    }
+
+    Ok(changed)
+}
+
+/// Add the tags `add` to and remove the tags `remove` from the entry `id`
+///
+/// The tags are changed like with `Tagable::add_tags()` and `Tagable::remove_tags()` on the
+/// entry, so they are normalized according to the store configuration. The entry is written once
+/// when it is dropped at the end of the function, so the update hooks are executed. Tags which are
+/// in both `add` and `remove` are removed. If any of the tags is not a valid tag, the entry is not
+/// changed. If there is no entry `id`, this fails and no entry is created.
+pub fn tag_by_id(store: &Store, id: StoreId, add: &[Tag], remove: &[Tag]) -> Result<()> {
+    if let Some(t) = add.iter().chain(remove.iter()).find(|t| !is_tag(t)) {
+        debug!("Not a tag: '{}'", t);
+        return Err(TagErrorKind::NotATag.into_error());
+    }
+
+    let mut entry = match try!(store.get(id).map_err_into(TagErrorKind::StoreReadError)) {
+        Some(entry) => entry,
+        None        => return Err(TagErrorKind::StoreReadError.into_error()),
+    };
+
+    try!(entry.add_tags(add));
+    entry.remove_tags(remove)
+}
+
+/// Collect all tags used in the store, with the number of entries which carry them
+///
+/// Entries which cannot be loaded or whose tags cannot be read are skipped, the error is traced.
+pub fn collect_tags(store: &Store) -> Result<BTreeMap<Tag, usize>> {
+    let mut map = BTreeMap::new();
+
+    for id in try!(store.entries().map_err_into(TagErrorKind::StoreReadError)) {
+        let tags = store.get(id)
+            .map_err_into(TagErrorKind::StoreReadError)
+            .and_then(|o| match o {
+                Some(entry) => entry.get_tags(),
+                None        => Ok(vec![]),
+            })
+            .map_err_trace()
+            .unwrap_or_else(|_| vec![]);
+
+        for tag in tags {
+            *map.entry(tag).or_insert(0) += 1;
+        }
+    }
+
+    Ok(map)
+}
+
+/// Count how often each pair of tags appears together on the entries of the module `module`
+///
+/// The pairs are ordered, so the smaller tag always comes first and `(a, b)` and `(b, a)` are
+/// counted as the same pair. Entries with less than two tags do not contribute to the result.
+///
+/// Entries which cannot be loaded or whose tags cannot be read are skipped, the error is traced.
+pub fn tag_cooccurrence(store: &Store, module: &str) -> Result<BTreeMap<(Tag, Tag), usize>> {
+    let mut map = BTreeMap::new();
+
+    for id in try!(store.retrieve_for_module(module).map_err_into(TagErrorKind::StoreReadError)) {
+        let mut tags = store.get(id)
+            .map_err_into(TagErrorKind::StoreReadError)
+            .and_then(|o| match o {
+                Some(entry) => entry.get_tags(),
+                None        => Ok(vec![]),
+            })
+            .map_err_trace()
+            .unwrap_or_else(|_| vec![]);
+
+        tags.sort();
+        tags.dedup();
+
+        for (i, a) in tags.iter().enumerate() {
+            for b in tags.iter().skip(i + 1) {
+                *map.entry((a.clone(), b.clone())).or_insert(0) += 1;
+            }
+        }
+    }
+
+    Ok(map)
+}
+
+/// How `tag_filter()` matches the tags of an entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TagFilterMode {
+    /// The entry must have all of the tags. Every entry matches an empty list of tags.
+    All,
+
+    /// The entry must have at least one of the tags. No entry matches an empty list of tags.
+    Any,
+}
+
+/// Get all entries of the module `module` which have the `tags`, as specified by `mode`
+///
+/// Entries which cannot be loaded or whose tags cannot be read are skipped, the error is traced.
+pub fn tag_filter<'a>(store: &'a Store, module: &str, tags: &[Tag], mode: TagFilterMode)
+    -> Result<Vec<FileLockEntry<'a>>>
+{
+    let mut entries = vec![];
+
+    for id in try!(store.retrieve_for_module(module).map_err_into(TagErrorKind::StoreReadError)) {
+        let entry = match store.get(id).map_err_into(TagErrorKind::StoreReadError).map_err_trace() {
+            Ok(Some(entry)) => entry,
+            Ok(None)        => continue,
+            Err(_)          => continue,
+        };
+
+        let matches = entry.get_tags().map(|ts| match mode {
+            TagFilterMode::All => tags.iter().all(|t| ts.contains(t)),
+            TagFilterMode::Any => tags.iter().any(|t| ts.contains(t)),
+        });
+
+        if let Ok(true) = matches.map_err_trace() {
+            entries.push(entry);
+        }
+    }
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use libimagstore::store::Store;
+    use libimagstore::storeid::StoreId;
+
+    use tagable::Tagable;
+    use test_util::get_store;
+    use super::rename_tag;
+    use super::tag_by_id;
+    use super::collect_tags;
+    use super::tag_cooccurrence;
+    use super::tag_filter;
+    use super::TagFilterMode;
+
+    pub fn create_with_tags(store: &Store, name: &str, tags: &[&str]) {
+        let id        = StoreId::new_baseless(PathBuf::from(name)).unwrap();
+        let mut entry = store.create(id).unwrap();
+        for tag in tags {
+            entry.add_tag(String::from(*tag)).unwrap();
+        }
+    }
+
+    pub fn get_tags(store: &Store, name: &str) -> Vec<String> {
+        let id = StoreId::new_baseless(PathBuf::from(name)).unwrap();
+        store.get(id).unwrap().unwrap().get_tags().unwrap()
+    }
+
+    #[test]
+    fn test_rename_tag() {
+        let store = get_store();
+
+        create_with_tags(&store, "test/a", &["tpyo", "other"]);
+        create_with_tags(&store, "test/b", &["tpyo"]);
+        create_with_tags(&store, "test/sub/c", &["tpyo"]);
+        create_with_tags(&store, "test/d", &["other"]);
+
+        let changed = rename_tag(&store, &String::from("tpyo"), &String::from("typo")).unwrap();
+        assert_eq!(changed, 3);
+
+        for name in &["test/a", "test/b", "test/sub/c"] {
+            let tags = get_tags(&store, name);
+            assert!(tags.contains(&String::from("typo")));
+            assert!(!tags.contains(&String::from("tpyo")));
+        }
+        assert_eq!(get_tags(&store, "test/d"), vec![String::from("other")]);
+    }
+
+    fn tags(ts: &[&str]) -> Vec<String> {
+        ts.iter().map(|t| String::from(*t)).collect()
+    }
+
+    #[test]
+    fn test_tag_by_id_equals_entry_path() {
+        let store = get_store();
+
+        create_with_tags(&store, "test/by_id", &["old", "keep"]);
+        create_with_tags(&store, "test/by_entry", &["old", "keep"]);
+
+        let id = StoreId::new_baseless(PathBuf::from("test/by_id")).unwrap();
+        tag_by_id(&store, id, &tags(&["new", "keep", "more"]), &tags(&["old"])).unwrap();
+
+        {
+            let id        = StoreId::new_baseless(PathBuf::from("test/by_entry")).unwrap();
+            let mut entry = store.get(id).unwrap().unwrap();
+            for t in tags(&["new", "keep", "more"]) {
+                entry.add_tag(t).unwrap();
+            }
+            entry.remove_tag(String::from("old")).unwrap();
+        }
+
+        assert_eq!(get_tags(&store, "test/by_id"), get_tags(&store, "test/by_entry"));
+        assert_eq!(get_tags(&store, "test/by_id"), tags(&["keep", "new", "more"]));
+    }
+
+    #[test]
+    fn test_tag_by_id_normalizes_like_entry_path() {
+        use test_util::get_normalizing_store;
+
+        let store = get_normalizing_store();
+
+        create_with_tags(&store, "test/by_id", &["old", "keep"]);
+        create_with_tags(&store, "test/by_entry", &["old", "keep"]);
+
+        let id = StoreId::new_baseless(PathBuf::from("test/by_id")).unwrap();
+        tag_by_id(&store, id, &tags(&["New", "KEEP"]), &tags(&["Old"])).unwrap();
+
+        {
+            let id        = StoreId::new_baseless(PathBuf::from("test/by_entry")).unwrap();
+            let mut entry = store.get(id).unwrap().unwrap();
+            entry.add_tags(&tags(&["New", "KEEP"])).unwrap();
+            entry.remove_tags(&tags(&["Old"])).unwrap();
+        }
+
+        assert_eq!(get_tags(&store, "test/by_id"), get_tags(&store, "test/by_entry"));
+        assert_eq!(get_tags(&store, "test/by_id"), tags(&["keep", "new"]));
+    }
+
+    #[test]
+    fn test_tag_by_id_missing_entry() {
+        let store = get_store();
+
+        let id = StoreId::new_baseless(PathBuf::from("test/missing")).unwrap();
+        assert!(tag_by_id(&store, id.clone(), &tags(&["new"]), &[]).is_err());
+        assert!(store.get(id).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_tag_by_id_keeps_content() {
+        let store = get_store();
+
+        {
+            let id        = StoreId::new_baseless(PathBuf::from("test/a")).unwrap();
+            let mut entry = store.create(id).unwrap();
+            *entry.get_content_mut() = String::from("content");
+        }
+
+        let id = StoreId::new_baseless(PathBuf::from("test/a")).unwrap();
+        tag_by_id(&store, id.clone(), &tags(&["work"]), &[]).unwrap();
+
+        let entry = store.get(id).unwrap().unwrap();
+        assert_eq!(entry.get_tags().unwrap(), tags(&["work"]));
+        assert_eq!(entry.get_content(), "content");
+    }
+
+    #[test]
+    fn test_tag_by_id_invalid_tag() {
+        let store = get_store();
+
+        create_with_tags(&store, "test/a", &["tag"]);
+
+        let id = StoreId::new_baseless(PathBuf::from("test/a")).unwrap();
+        assert!(tag_by_id(&store, id, &tags(&["new", "not a tag"]), &[]).is_err());
+        assert_eq!(get_tags(&store, "test/a"), tags(&["tag"]));
+    }
+
+    #[test]
+    fn test_collect_tags() {
+        let store = get_store();
+
+        create_with_tags(&store, "test/a", &["work", "imag"]);
+        create_with_tags(&store, "test/b", &["work"]);
+        create_with_tags(&store, "other/c", &["imag", "home", "work"]);
+        create_with_tags(&store, "other/d", &[]);
+
+        let tags = collect_tags(&store).unwrap();
+        let tags : Vec<(&str, usize)> = tags.iter().map(|(t, c)| (&t[..], *c)).collect();
+        assert_eq!(tags, vec![("home", 1), ("imag", 2), ("work", 3)]);
+    }
+
+    #[test]
+    fn test_tag_cooccurrence() {
+        let store = get_store();
+
+        create_with_tags(&store, "test/a", &["work", "imag"]);
+        create_with_tags(&store, "test/b", &["imag", "work"]);
+        create_with_tags(&store, "test/c", &["work", "rust", "imag"]);
+        create_with_tags(&store, "test/d", &["rust"]);
+        create_with_tags(&store, "test/e", &[]);
+        create_with_tags(&store, "other/f", &["work", "imag"]);
+
+        let pairs = tag_cooccurrence(&store, "test").unwrap();
+        let pairs : Vec<(&str, &str, usize)> = pairs
+            .iter()
+            .map(|(&(ref a, ref b), c)| (&a[..], &b[..], *c))
+            .collect();
+
+        assert_eq!(pairs, vec![("imag", "rust", 1), ("imag", "work", 3), ("rust", "work", 1)]);
+    }
+
+    #[test]
+    fn test_tag_cooccurrence_without_pairs() {
+        let store = get_store();
+
+        create_with_tags(&store, "test/a", &["work"]);
+        create_with_tags(&store, "test/b", &[]);
+
+        assert!(tag_cooccurrence(&store, "test").unwrap().is_empty());
+        assert!(tag_cooccurrence(&store, "empty").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_rename_tag_skips_broken_entries() {
+        use toml::Value;
+        use libimagstore::toml_ext::TomlValueExt;
+
+        let store = get_store();
+
+        create_with_tags(&store, "test/a", &["tpyo"]);
+        {
+            let id        = StoreId::new_baseless(PathBuf::from("test/broken")).unwrap();
+            let mut entry = store.create(id).unwrap();
+            entry.get_header_mut().set("imag.tags", Value::Integer(1)).unwrap();
+        }
+        create_with_tags(&store, "test/z", &["tpyo"]);
+
+        let changed = rename_tag(&store, &String::from("tpyo"), &String::from("typo")).unwrap();
+        assert_eq!(changed, 2);
+        assert_eq!(get_tags(&store, "test/a"), vec![String::from("typo")]);
+        assert_eq!(get_tags(&store, "test/z"), vec![String::from("typo")]);
+    }
+
+    #[test]
+    fn test_rename_tag_to_invalid_tag() {
+        let store = get_store();
+
+        create_with_tags(&store, "test/a", &["tag"]);
+
+        assert!(rename_tag(&store, &String::from("tag"), &String::from("not a tag")).is_err());
+        assert_eq!(get_tags(&store, "test/a"), vec![String::from("tag")]);
+    }
+
+    fn filtered(store: &Store, tags: &[&str], mode: TagFilterMode) -> Vec<String> {
+        let tags : Vec<String> = tags.iter().map(|t| String::from(*t)).collect();
+        let mut names : Vec<String> = tag_filter(store, "test", &tags, mode)
+            .unwrap()
+            .iter()
+            .map(|e| e.get_location().local().to_str().unwrap().to_owned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    fn create_filter_entries(store: &Store) {
+        use toml::Value;
+        use libimagstore::toml_ext::TomlValueExt;
+
+        create_with_tags(store, "test/a", &["work", "imag"]);
+        create_with_tags(store, "test/b", &["work"]);
+        create_with_tags(store, "test/c", &["home"]);
+        create_with_tags(store, "test/d", &[]);
+        create_with_tags(store, "other/e", &["work", "imag"]);
+
+        // An entry whose tags cannot be read
+        let id        = StoreId::new_baseless(PathBuf::from("test/broken")).unwrap();
+        let mut entry = store.create(id).unwrap();
+        entry.get_header_mut().set("imag.tags", Value::Integer(1)).unwrap();
+    }
+
+    #[test]
+    fn test_tag_filter_all() {
+        let store = get_store();
+        create_filter_entries(&store);
+
+        assert_eq!(filtered(&store, &["work", "imag"], TagFilterMode::All), vec!["test/a"]);
+        assert_eq!(filtered(&store, &["work"], TagFilterMode::All), vec!["test/a", "test/b"]);
+        assert!(filtered(&store, &["work", "home"], TagFilterMode::All).is_empty());
+    }
+
+    #[test]
+    fn test_tag_filter_any() {
+        let store = get_store();
+        create_filter_entries(&store);
+
+        assert_eq!(filtered(&store, &["imag", "home"], TagFilterMode::Any), vec!["test/a", "test/c"]);
+        assert!(filtered(&store, &["unknown"], TagFilterMode::Any).is_empty());
+    }
+
+    #[test]
+    fn test_tag_filter_empty_tag_list() {
+        let store = get_store();
+        create_filter_entries(&store);
+
+        assert_eq!(filtered(&store, &[], TagFilterMode::All),
+                   vec!["test/a", "test/b", "test/c", "test/d"]);
+        assert!(filtered(&store, &[], TagFilterMode::Any).is_empty());
+    }
+
+}
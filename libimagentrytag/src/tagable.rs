@@ -17,62 +17,91 @@
 // Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
 //
 
-use itertools::Itertools;
+use std::ascii::AsciiExt;
+use std::collections::BTreeMap;
+use std::collections::HashSet;
 
 use libimagstore::store::Entry;
+use libimagstore::store::FileLockEntry;
+use libimagstore::error::StoreErrorKind as SEK;
 use libimagerror::into::IntoError;
 use libimagstore::toml_ext::TomlValueExt;
 
 use error::TagErrorKind;
 use error::MapErrInto;
+use hierarchy::{TagTree, is_self_or_descendant};
 use result::Result;
 use tag::{Tag, TagSlice};
 use util::is_tag;
+use util::normalize_tags;
 
 use toml::Value;
 
 pub trait Tagable {
 
     fn get_tags(&self) -> Result<Vec<Tag>>;
+
+    /// Set the tags of the entry to `ts`
+    ///
+    /// The order of `ts` is kept, only later duplicates of a tag are removed. As `add_tag()` and
+    /// `add_tags()` append to the existing tags, the tags are stored in the order they were first
+    /// added, so rewriting an entry does not reorder them (which keeps VCS diffs small).
     fn set_tags(&mut self, ts: &[Tag]) -> Result<()>;
 
     fn add_tag(&mut self, t: Tag) -> Result<()>;
     fn remove_tag(&mut self, t: Tag) -> Result<()>;
 
+    /// Add all tags in `ts` with a single write of the header
+    ///
+    /// All tags are validated before anything is changed, so if one of them is not a valid tag,
+    /// none of them is added.
+    fn add_tags(&mut self, ts: &[Tag]) -> Result<()>;
+
+    /// Remove all tags in `ts` with a single write of the header, see `Tagable::add_tags()`
+    fn remove_tags(&mut self, ts: &[Tag]) -> Result<()>;
+
+    /// Remove all tags of the entry. Does nothing if the entry has no tags.
+    fn clear_tags(&mut self) -> Result<()>;
+
+    /// Get the number of tags of the entry
+    fn tag_count(&self) -> Result<usize>;
+
     fn has_tag(&self, t: TagSlice) -> Result<bool>;
     fn has_tags(&self, ts: &[Tag]) -> Result<bool>;
 
+    /// Like `Tagable::has_tag()`, but compares the tags ASCII-case-insensitive
+    fn has_tag_ci(&self, t: TagSlice) -> Result<bool>;
+
+    /// Check whether the entry has the tag `t` or any tag which is a descendant of `t` in the tag
+    /// hierarchy, so `project` matches `project/imag/docs`.
+    fn has_tag_hierarchical(&self, t: TagSlice) -> Result<bool>;
+
+    /// Get the tags of the entry as tree, built from the hierarchical tags
+    fn get_tag_tree(&self) -> Result<TagTree>;
+
+    /// Set the valued tag `key` to `value`, like `priority=high`
+    ///
+    /// Valued tags are stored in `imag.tagvalues`, separated from the plain tags, so they do not
+    /// show up in `Tagable::get_tags()`. The key must be a valid tag.
+    fn set_tag_value(&mut self, key: TagSlice, value: &str) -> Result<()>;
+    fn get_tag_value(&self, key: TagSlice) -> Result<Option<String>>;
+    fn remove_tag_value(&mut self, key: TagSlice) -> Result<Option<String>>;
+
 }
 
 impl Tagable for Value {
 
     fn get_tags(&self) -> Result<Vec<Tag>> {
-        let tags = try!(self.read("imag.tags").map_err_into(TagErrorKind::HeaderReadError));
-
-        match tags {
-            Some(Value::Array(tags)) => {
-                if !tags.iter().all(|t| is_match!(*t, Value::String(_))) {
-                    return Err(TagErrorKind::TagTypeError.into());
-                }
-                if tags.iter().any(|t| match *t {
-                    Value::String(ref s) => !is_tag(s),
-                    _ => unreachable!()})
-                {
+        match self.read_string_array("imag.tags") {
+            Ok(Some(tags)) => {
+                if tags.iter().any(|t| !is_tag(t)) {
                     return Err(TagErrorKind::NotATag.into());
                 }
-
-                Ok(tags.iter()
-                    .cloned()
-                    .map(|t| {
-                        match t {
-                           Value::String(s) => s,
-                           _ => unreachable!(),
-                        }
-                    })
-                    .collect())
+                Ok(tags)
             },
-            None => Ok(vec![]),
-            _ => Err(TagErrorKind::TagTypeError.into()),
+            Ok(None) => Ok(vec![]),
+            Err(ref e) if e.err_type() == SEK::HeaderTypeFailure => Err(TagErrorKind::TagTypeError.into()),
+            Err(e) => Err(e).map_err_into(TagErrorKind::HeaderReadError),
         }
     }
 
@@ -82,7 +111,7 @@ impl Tagable for Value {
             return Err(TagErrorKind::NotATag.into());
         }
 
-        let a = ts.iter().unique().map(|t| Value::String(t.clone())).collect();
+        let a = unique_in_order(ts).into_iter().map(Value::String).collect();
         self.set("imag.tags", Value::Array(a))
             .map(|_| ())
             .map_err(Box::new)
@@ -98,7 +127,7 @@ impl Tagable for Value {
         self.get_tags()
             .map(|mut tags| {
                 tags.push(t);
-                self.set_tags(&tags.into_iter().unique().collect::<Vec<_>>()[..])
+                self.set_tags(&tags[..])
             })
             .map(|_| ())
     }
@@ -117,21 +146,47 @@ impl Tagable for Value {
             .map(|_| ())
     }
 
-    fn has_tag(&self, t: TagSlice) -> Result<bool> {
-        let tags = try!(self.read("imag.tags").map_err_into(TagErrorKind::HeaderReadError));
+    fn add_tags(&mut self, ts: &[Tag]) -> Result<()> {
+        if let Some(t) = ts.iter().find(|t| !is_tag(t)) {
+            debug!("Not a tag: '{}'", t);
+            return Err(TagErrorKind::NotATag.into());
+        }
+
+        let mut tags = try!(self.get_tags());
+        tags.extend(ts.iter().cloned());
+        self.set_tags(&tags[..])
+    }
 
-        if !tags.iter().all(|t| is_match!(*t, Value::String(_))) {
-            return Err(TagErrorKind::TagTypeError.into());
+    fn remove_tags(&mut self, ts: &[Tag]) -> Result<()> {
+        if let Some(t) = ts.iter().find(|t| !is_tag(t)) {
+            debug!("Not a tag: '{}'", t);
+            return Err(TagErrorKind::NotATag.into());
         }
 
-        Ok(tags
-           .iter()
-           .any(|tag| {
-               match *tag {
-                   Value::String(ref s) => { s == t },
-                   _ => unreachable!()
-               }
-           }))
+        let mut tags = try!(self.get_tags());
+        tags.retain(|tag| !ts.contains(tag));
+        self.set_tags(&tags[..])
+    }
+
+    fn clear_tags(&mut self) -> Result<()> {
+        match try!(self.read("imag.tags").map_err_into(TagErrorKind::HeaderReadError)) {
+            Some(_) => self.delete("imag.tags")
+                .map(|_| ())
+                .map_err_into(TagErrorKind::HeaderWriteError),
+            None    => Ok(()),
+        }
+    }
+
+    fn tag_count(&self) -> Result<usize> {
+        self.get_tags().map(|tags| tags.len())
+    }
+
+    fn has_tag(&self, t: TagSlice) -> Result<bool> {
+        self.get_tags().map(|tags| tags.iter().any(|tag| tag == t))
+    }
+
+    fn has_tag_ci(&self, t: TagSlice) -> Result<bool> {
+        self.get_tags().map(|tags| tags.iter().any(|tag| tag.eq_ignore_ascii_case(t)))
     }
 
     fn has_tags(&self, tags: &[Tag]) -> Result<bool> {
@@ -143,6 +198,66 @@ impl Tagable for Value {
         Ok(result)
     }
 
+    fn has_tag_hierarchical(&self, t: TagSlice) -> Result<bool> {
+        self.get_tags().map(|tags| tags.iter().any(|tag| is_self_or_descendant(tag, t)))
+    }
+
+    fn get_tag_tree(&self) -> Result<TagTree> {
+        self.get_tags().map(|tags| TagTree::from_tags(&tags[..]))
+    }
+
+    fn set_tag_value(&mut self, key: TagSlice, value: &str) -> Result<()> {
+        if !is_tag(key) {
+            debug!("Not a tag: '{}'", key);
+            return Err(TagErrorKind::NotATag.into());
+        }
+
+        match try!(self.read("imag.tagvalues").map_err_into(TagErrorKind::HeaderReadError)) {
+            Some(Value::Table(_)) => {},
+            Some(_) => return Err(TagErrorKind::TagTypeError.into()),
+            None    => {
+                try!(self.set("imag.tagvalues", Value::Table(BTreeMap::new()))
+                     .map_err_into(TagErrorKind::HeaderWriteError));
+            },
+        }
+
+        self.set(&format!("imag.tagvalues.{}", key), Value::String(String::from(value)))
+            .map(|_| ())
+            .map_err_into(TagErrorKind::HeaderWriteError)
+    }
+
+    fn get_tag_value(&self, key: TagSlice) -> Result<Option<String>> {
+        if !is_tag(key) {
+            debug!("Not a tag: '{}'", key);
+            return Err(TagErrorKind::NotATag.into());
+        }
+
+        match try!(self.read("imag.tagvalues").map_err_into(TagErrorKind::HeaderReadError)) {
+            Some(Value::Table(t)) => match t.get(key) {
+                Some(&Value::String(ref s)) => Ok(Some(s.clone())),
+                Some(_) => Err(TagErrorKind::TagTypeError.into()),
+                None    => Ok(None),
+            },
+            Some(_) => Err(TagErrorKind::TagTypeError.into()),
+            None    => Ok(None),
+        }
+    }
+
+    fn remove_tag_value(&mut self, key: TagSlice) -> Result<Option<String>> {
+        let old = try!(self.get_tag_value(key));
+        if old.is_some() {
+            try!(self.delete(&format!("imag.tagvalues.{}", key))
+                 .map_err_into(TagErrorKind::HeaderWriteError));
+        }
+        Ok(old)
+    }
+
+}
+
+/// Remove duplicates from `ts`, keeping the first occurrence of each tag and the order of the tags
+fn unique_in_order(ts: &[Tag]) -> Vec<Tag> {
+    let mut seen = HashSet::new();
+    ts.iter().filter(|t| seen.insert(*t)).cloned().collect()
 }
 
 impl Tagable for Entry {
@@ -163,6 +278,22 @@ impl Tagable for Entry {
         self.get_header_mut().remove_tag(t)
     }
 
+    fn add_tags(&mut self, ts: &[Tag]) -> Result<()> {
+        self.get_header_mut().add_tags(ts)
+    }
+
+    fn remove_tags(&mut self, ts: &[Tag]) -> Result<()> {
+        self.get_header_mut().remove_tags(ts)
+    }
+
+    fn clear_tags(&mut self) -> Result<()> {
+        self.get_header_mut().clear_tags()
+    }
+
+    fn tag_count(&self) -> Result<usize> {
+        self.get_header().tag_count()
+    }
+
     fn has_tag(&self, t: TagSlice) -> Result<bool> {
         self.get_header().has_tag(t)
     }
@@ -171,5 +302,338 @@ impl Tagable for Entry {
         self.get_header().has_tags(ts)
     }
 
+    fn has_tag_ci(&self, t: TagSlice) -> Result<bool> {
+        self.get_header().has_tag_ci(t)
+    }
+
+    fn has_tag_hierarchical(&self, t: TagSlice) -> Result<bool> {
+        self.get_header().has_tag_hierarchical(t)
+    }
+
+    fn get_tag_tree(&self) -> Result<TagTree> {
+        self.get_header().get_tag_tree()
+    }
+
+    fn set_tag_value(&mut self, key: TagSlice, value: &str) -> Result<()> {
+        self.get_header_mut().set_tag_value(key, value)
+    }
+
+    fn get_tag_value(&self, key: TagSlice) -> Result<Option<String>> {
+        self.get_header().get_tag_value(key)
+    }
+
+    fn remove_tag_value(&mut self, key: TagSlice) -> Result<Option<String>> {
+        self.get_header_mut().remove_tag_value(key)
+    }
+
 }
 
+/// Tagable for entries of a store
+///
+/// Other than for `Entry` and the header, the tags which are set, added or removed are normalized
+/// according to the configuration of the store the entry belongs to, see
+/// `util::normalize_tags()`.
+impl<'a> Tagable for FileLockEntry<'a> {
+
+    fn get_tags(&self) -> Result<Vec<Tag>> {
+        (**self).get_tags()
+    }
+
+    fn set_tags(&mut self, ts: &[Tag]) -> Result<()> {
+        let ts = normalize_tags(ts.to_vec(), self.store().config());
+        (**self).set_tags(&ts[..])
+    }
+
+    fn add_tag(&mut self, t: Tag) -> Result<()> {
+        let mut ts = normalize_tags(vec![t], self.store().config());
+        (**self).add_tag(ts.remove(0))
+    }
+
+    fn remove_tag(&mut self, t: Tag) -> Result<()> {
+        let mut ts = normalize_tags(vec![t], self.store().config());
+        (**self).remove_tag(ts.remove(0))
+    }
+
+    fn add_tags(&mut self, ts: &[Tag]) -> Result<()> {
+        let ts = normalize_tags(ts.to_vec(), self.store().config());
+        (**self).add_tags(&ts[..])
+    }
+
+    fn remove_tags(&mut self, ts: &[Tag]) -> Result<()> {
+        let ts = normalize_tags(ts.to_vec(), self.store().config());
+        (**self).remove_tags(&ts[..])
+    }
+
+    fn clear_tags(&mut self) -> Result<()> {
+        (**self).clear_tags()
+    }
+
+    fn tag_count(&self) -> Result<usize> {
+        (**self).tag_count()
+    }
+
+    fn has_tag(&self, t: TagSlice) -> Result<bool> {
+        (**self).has_tag(t)
+    }
+
+    fn has_tags(&self, ts: &[Tag]) -> Result<bool> {
+        (**self).has_tags(ts)
+    }
+
+    fn has_tag_ci(&self, t: TagSlice) -> Result<bool> {
+        (**self).has_tag_ci(t)
+    }
+
+    fn has_tag_hierarchical(&self, t: TagSlice) -> Result<bool> {
+        (**self).has_tag_hierarchical(t)
+    }
+
+    fn get_tag_tree(&self) -> Result<TagTree> {
+        (**self).get_tag_tree()
+    }
+
+    fn set_tag_value(&mut self, key: TagSlice, value: &str) -> Result<()> {
+        (**self).set_tag_value(key, value)
+    }
+
+    fn get_tag_value(&self, key: TagSlice) -> Result<Option<String>> {
+        (**self).get_tag_value(key)
+    }
+
+    fn remove_tag_value(&mut self, key: TagSlice) -> Result<Option<String>> {
+        (**self).remove_tag_value(key)
+    }
+
+}
+
+#[cfg(test)]
+mod test {
+    use libimagstore::store::Entry;
+    use libimagstore::toml_ext::TomlValueExt;
+
+    use super::Tagable;
+
+    fn header_with_tags(tags: &[&str]) -> ::toml::Value {
+        let mut header = Entry::default_header();
+        let tags : Vec<String> = tags.iter().map(|t| String::from(*t)).collect();
+        header.set_tags(&tags[..]).unwrap();
+        header
+    }
+
+    #[test]
+    fn test_has_tag_hierarchical_parent_matches_child() {
+        let header = header_with_tags(&["project/imag/docs"]);
+
+        assert!(header.has_tag_hierarchical("project").unwrap());
+        assert!(header.has_tag_hierarchical("project/imag").unwrap());
+        assert!(header.has_tag_hierarchical("project/imag/docs").unwrap());
+        assert!(!header.has_tag_hierarchical("project/imag/docs/old").unwrap());
+        assert!(!header.has_tag_hierarchical("proj").unwrap());
+    }
+
+    #[test]
+    fn test_has_tag_stays_strict() {
+        let header = header_with_tags(&["project/imag/docs"]);
+
+        assert!(header.has_tag("project/imag/docs").unwrap());
+        assert!(!header.has_tag("project").unwrap());
+        assert!(!header.has_tag("project/imag").unwrap());
+    }
+
+    #[test]
+    fn test_has_tag_ci() {
+        let header = header_with_tags(&["Work"]);
+
+        assert!(header.has_tag_ci("work").unwrap());
+        assert!(header.has_tag_ci("WORK").unwrap());
+        assert!(header.has_tag_ci("Work").unwrap());
+        assert!(!header.has_tag_ci("works").unwrap());
+        assert!(!header.has_tag("work").unwrap());
+    }
+
+    #[test]
+    fn test_normalizing_add_tag() {
+        use std::path::PathBuf;
+        use libimagstore::storeid::StoreId;
+        use test_util::get_normalizing_store;
+
+        let store = get_normalizing_store();
+        let id    = StoreId::new_baseless(PathBuf::from("test/normalize")).unwrap();
+
+        let mut entry = store.create(id).unwrap();
+        entry.add_tag(String::from("Work")).unwrap();
+        entry.add_tags(&tags(&["IMAG", "Rust"])).unwrap();
+
+        assert_eq!(entry.get_tags().unwrap(), tags(&["work", "imag", "rust"]));
+
+        entry.remove_tag(String::from("RUST")).unwrap();
+        assert_eq!(entry.get_tags().unwrap(), tags(&["work", "imag"]));
+
+        entry.set_tags(&tags(&["Home"])).unwrap();
+        assert_eq!(entry.get_tags().unwrap(), tags(&["home"]));
+    }
+
+    #[test]
+    fn test_entry_without_normalizing_config_keeps_case() {
+        use std::path::PathBuf;
+        use libimagstore::storeid::StoreId;
+        use test_util::get_store;
+
+        let store = get_store();
+        let id    = StoreId::new_baseless(PathBuf::from("test/normalize")).unwrap();
+
+        let mut entry = store.create(id).unwrap();
+        entry.add_tag(String::from("Work")).unwrap();
+        assert_eq!(entry.get_tags().unwrap(), tags(&["Work"]));
+    }
+
+    fn tags(ts: &[&str]) -> Vec<String> {
+        ts.iter().map(|t| String::from(*t)).collect()
+    }
+
+    #[test]
+    fn test_add_tags() {
+        let mut header = header_with_tags(&["work"]);
+        header.add_tags(&tags(&["imag", "work", "rust", "imag"])).unwrap();
+
+        assert_eq!(header.get_tags().unwrap(), tags(&["work", "imag", "rust"]));
+        assert_eq!(header.read("imag.tags").unwrap(),
+                   Some(::toml::Value::Array(tags(&["work", "imag", "rust"])
+                                             .into_iter()
+                                             .map(::toml::Value::String)
+                                             .collect())));
+    }
+
+    #[test]
+    fn test_add_tags_invalid_tag_changes_nothing() {
+        let mut header = header_with_tags(&["work"]);
+
+        assert!(header.add_tags(&tags(&["imag", "not a tag"])).is_err());
+        assert_eq!(header.get_tags().unwrap(), tags(&["work"]));
+    }
+
+    #[test]
+    fn test_remove_tags() {
+        let mut header = header_with_tags(&["work", "imag", "rust", "home"]);
+        header.remove_tags(&tags(&["imag", "home", "missing"])).unwrap();
+
+        assert_eq!(header.get_tags().unwrap(), tags(&["work", "rust"]));
+        assert!(header.remove_tags(&tags(&["work", "not a tag"])).is_err());
+        assert_eq!(header.get_tags().unwrap(), tags(&["work", "rust"]));
+    }
+
+    #[test]
+    fn test_unicode_tags() {
+        let mut header = header_with_tags(&["café"]);
+        header.add_tag(String::from("日本語")).unwrap();
+
+        assert_eq!(header.get_tags().unwrap(), tags(&["café", "日本語"]));
+        assert!(header.has_tag("日本語").unwrap());
+        assert!(header.add_tag(String::from("café crème")).is_err());
+    }
+
+    #[test]
+    fn test_set_tags_keeps_order() {
+        let header = header_with_tags(&["zeta", "alpha", "zeta", "mid", "alpha"]);
+        assert_eq!(header.get_tags().unwrap(), tags(&["zeta", "alpha", "mid"]));
+    }
+
+    #[test]
+    fn test_tag_order_survives_reload() {
+        use std::path::PathBuf;
+
+        use test_util::get_store;
+
+        let store = get_store();
+
+        {
+            let mut entry = store.create(PathBuf::from("test/ordered")).unwrap();
+            for tag in &["zeta", "alpha", "mid"] {
+                entry.add_tag(String::from(*tag)).unwrap();
+            }
+            entry.add_tags(&tags(&["beta", "alpha"])).unwrap();
+            entry.add_tag(String::from("zeta")).unwrap();
+        }
+
+        let expected = tags(&["zeta", "alpha", "mid", "beta"]);
+        for _ in 0..2 {
+            let mut entry = store.get(PathBuf::from("test/ordered")).unwrap().unwrap();
+            assert_eq!(entry.get_tags().unwrap(), expected);
+
+            // Rewriting the unchanged tags must not reorder them
+            let current = entry.get_tags().unwrap();
+            entry.set_tags(&current[..]).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_clear_tags() {
+        let mut header = header_with_tags(&["work", "imag"]);
+        assert_eq!(header.tag_count().unwrap(), 2);
+
+        header.clear_tags().unwrap();
+        assert!(header.get_tags().unwrap().is_empty());
+        assert_eq!(header.tag_count().unwrap(), 0);
+        assert_eq!(header.read("imag.tags").unwrap(), None);
+    }
+
+    #[test]
+    fn test_clear_tags_untagged() {
+        let mut header = Entry::default_header();
+        assert_eq!(header.tag_count().unwrap(), 0);
+
+        assert!(header.clear_tags().is_ok());
+        assert!(header.get_tags().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_clear_tags_keeps_tag_values() {
+        let mut header = header_with_tags(&["work"]);
+        header.set_tag_value("priority", "high").unwrap();
+
+        header.clear_tags().unwrap();
+        assert_eq!(header.tag_count().unwrap(), 0);
+        assert_eq!(header.get_tag_value("priority").unwrap(), Some(String::from("high")));
+    }
+
+    #[test]
+    fn test_tag_values_roundtrip() {
+        let mut header = header_with_tags(&["work"]);
+
+        header.set_tag_value("priority", "high").unwrap();
+        header.set_tag_value("due", "2016-12-01").unwrap();
+        header.set_tag_value("project/imag", "docs").unwrap();
+
+        assert_eq!(header.get_tag_value("priority").unwrap(), Some(String::from("high")));
+        assert_eq!(header.get_tag_value("due").unwrap(), Some(String::from("2016-12-01")));
+        assert_eq!(header.get_tag_value("project/imag").unwrap(), Some(String::from("docs")));
+        assert_eq!(header.get_tag_value("missing").unwrap(), None);
+
+        header.set_tag_value("priority", "low").unwrap();
+        assert_eq!(header.get_tag_value("priority").unwrap(), Some(String::from("low")));
+
+        assert_eq!(header.remove_tag_value("priority").unwrap(), Some(String::from("low")));
+        assert_eq!(header.get_tag_value("priority").unwrap(), None);
+        assert_eq!(header.remove_tag_value("priority").unwrap(), None);
+    }
+
+    #[test]
+    fn test_tag_values_do_not_leak_into_tags() {
+        let mut header = header_with_tags(&["work"]);
+        header.set_tag_value("priority", "high").unwrap();
+
+        assert_eq!(header.get_tags().unwrap(), vec![String::from("work")]);
+        assert!(!header.has_tag("priority").unwrap());
+        assert!(header.set_tag_value("not a tag", "value").is_err());
+    }
+
+    #[test]
+    fn test_get_tag_tree() {
+        let header = header_with_tags(&["project/imag/docs", "project/imag/code", "home"]);
+        let tree   = header.get_tag_tree().unwrap();
+
+        assert_eq!(tree.children().len(), 2);
+        assert_eq!(tree.get("project/imag").unwrap().children().len(), 2);
+    }
+
+}
@@ -0,0 +1,55 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015, 2016 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Store setup shared by the tests of this crate
+
+use std::path::PathBuf;
+
+use toml::de::from_str;
+
+use libimagstore::store::Store;
+
+/// Create an in-memory store with the default configuration
+pub fn get_store() -> Store {
+    Store::new_in_memory(PathBuf::from("/"), None).unwrap()
+}
+
+/// Create an in-memory store which is configured to normalize tags to lowercase
+pub fn get_normalizing_store() -> Store {
+    let config = from_str(r#"
+        tag-normalize-case         = true
+        store-unload-hook-aspects  = [ ]
+        pre-create-hook-aspects    = [ ]
+        post-create-hook-aspects   = [ ]
+        pre-move-hook-aspects      = [ ]
+        post-move-hook-aspects     = [ ]
+        pre-retrieve-hook-aspects  = [ ]
+        post-retrieve-hook-aspects = [ ]
+        pre-update-hook-aspects    = [ ]
+        post-update-hook-aspects   = [ ]
+        pre-delete-hook-aspects    = [ ]
+        post-delete-hook-aspects   = [ ]
+
+        [hooks]
+
+        [aspects]
+    "#).unwrap();
+
+    Store::new_in_memory(PathBuf::from("/"), Some(config)).unwrap()
+}
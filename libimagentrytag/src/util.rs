@@ -17,8 +17,99 @@
 // Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
 //
 
-use regex::Regex;
+use toml::Value;
 
+use libimagutil::cli_validators::is_valid_tag;
+
+use tag::Tag;
+
+/// Check whether `s` is a valid tag
+///
+/// A tag may be hierarchical, with the levels separated by `/`, like `project/imag/docs`. Tags may
+/// contain non-ASCII letters and digits. The rule is shared with the commandline validator, see
+/// `libimagutil::cli_validators::is_valid_tag()`.
 pub fn is_tag(s: &str) -> bool {
-    Regex::new("^[a-zA-Z]([a-zA-Z0-9_-]*)$").unwrap().captures(s).is_some()
+    is_valid_tag(s)
+}
+
+/// Check whether tags should be normalized to lowercase before they are stored
+///
+/// This is configured via the boolean `tag-normalize-case` key in the store configuration and is
+/// opt-in (off by default), so tags in existing stores are not changed unexpectedly.
+pub fn is_case_normalizing(store_config: Option<&Value>) -> bool {
+    match store_config {
+        Some(&Value::Table(ref t)) => match t.get("tag-normalize-case") {
+            Some(&Value::Boolean(b)) => b,
+            Some(_) => {
+                warn!("Key 'tag-normalize-case' does not contain a Boolean value");
+                false
+            },
+            None => false,
+        },
+        _ => false,
+    }
+}
+
+/// Normalize the tags according to the store configuration, see `is_case_normalizing()`
+///
+/// `Tagable` for `FileLockEntry` does this when tags are set, added or removed. Call this before
+/// passing user supplied tags to the `Tagable` functions of an `Entry` or a header.
+pub fn normalize_tags(ts: Vec<Tag>, store_config: Option<&Value>) -> Vec<Tag> {
+    if is_case_normalizing(store_config) {
+        ts.into_iter().map(|t| t.to_lowercase()).collect()
+    } else {
+        ts
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+
+    use toml::Value;
+
+    use super::is_tag;
+    use super::normalize_tags;
+
+    #[test]
+    fn test_is_tag() {
+        assert!(is_tag("imag"));
+        assert!(is_tag("project/imag/docs"));
+
+        assert!(!is_tag(""));
+        assert!(!is_tag("with whitespace"));
+        assert!(!is_tag("project/with whitespace"));
+        assert!(!is_tag("/project"));
+        assert!(!is_tag("project/"));
+        assert!(!is_tag("project//imag"));
+    }
+
+    #[test]
+    fn test_is_tag_unicode() {
+        assert!(is_tag("café"));
+        assert!(is_tag("日本語"));
+        assert!(is_tag("projekt/übersicht"));
+        assert!(is_tag("東京/2017"));
+
+        assert!(!is_tag("café crème"));
+        assert!(!is_tag("日本語\t"));
+        assert!(!is_tag("work,home"));
+        assert!(!is_tag("\u{1b}escape"));
+    }
+
+    fn config(normalize: bool) -> Value {
+        let mut t = BTreeMap::new();
+        t.insert(String::from("tag-normalize-case"), Value::Boolean(normalize));
+        Value::Table(t)
+    }
+
+    #[test]
+    fn test_normalize_tags() {
+        let tags = vec![String::from("Work"), String::from("IMAG")];
+
+        assert_eq!(normalize_tags(tags.clone(), Some(&config(true))), vec!["work", "imag"]);
+        assert_eq!(normalize_tags(tags.clone(), Some(&config(false))), vec!["Work", "IMAG"]);
+        assert_eq!(normalize_tags(tags.clone(), None), vec!["Work", "IMAG"]);
+    }
+
 }
@@ -0,0 +1,156 @@
+//! Module for mail attachments
+//!
+//! An `Attachment` is a (decoded) part of a multipart mail which is either marked as attachment via
+//! its `Content-Disposition` header or carries a filename.
+//!
+
+use std::io::Write;
+use std::result::Result as RResult;
+
+use mailparse::{MailHeader, MailParseError, ParsedMail};
+
+use result::Result;
+use error::{MapErrInto, MailErrorKind as MEK};
+
+#[derive(Debug)]
+pub struct Attachment {
+    filename: Option<String>,
+    content_type: String,
+    data: Vec<u8>,
+}
+
+impl Attachment {
+
+    /// The filename of the attachment, if the mail specifies one
+    pub fn filename(&self) -> Option<&String> {
+        self.filename.as_ref()
+    }
+
+    /// The mimetype of the attachment, for example "application/pdf"
+    pub fn content_type(&self) -> &String {
+        &self.content_type
+    }
+
+    /// Write the decoded attachment to `w`
+    pub fn write_to<W: Write>(&self, w: &mut W) -> Result<()> {
+        w.write_all(&self.data[..]).map_err_into(MEK::IOError)
+    }
+
+}
+
+/// Walk the (sub)parts of `mail` recursively and collect all parts which are attachments
+pub fn collect_attachments(mail: &ParsedMail) -> RResult<Vec<Attachment>, MailParseError> {
+    let mut v = vec![];
+    try!(collect_into(mail, &mut v));
+    Ok(v)
+}
+
+fn collect_into(mail: &ParsedMail, v: &mut Vec<Attachment>) -> RResult<(), MailParseError> {
+    if !mail.subparts.is_empty() {
+        for part in mail.subparts.iter() {
+            try!(collect_into(part, v));
+        }
+        return Ok(());
+    }
+
+    let (is_attachment, disposition_filename) = match try!(get_header(&mail.headers, "Content-Disposition")) {
+        Some(disposition) => parse_disposition(&disposition),
+        None              => (false, None),
+    };
+
+    let filename = disposition_filename.or_else(|| mail.ctype.params.get("name").cloned());
+
+    if is_attachment || filename.is_some() {
+        let data = try!(mail.get_body_raw());
+        v.push(Attachment {
+            filename: filename,
+            content_type: mail.ctype.mimetype.clone(),
+            data: data,
+        });
+    }
+
+    Ok(())
+}
+
+fn get_header(headers: &[MailHeader], key: &str) -> RResult<Option<String>, MailParseError> {
+    for hdr in headers {
+        if try!(hdr.get_key()).to_lowercase() == key.to_lowercase() {
+            return hdr.get_value().map(Some);
+        }
+    }
+    Ok(None)
+}
+
+/// Parse a `Content-Disposition` header value into "is this an attachment" and the filename
+/// parameter, if any
+fn parse_disposition(value: &str) -> (bool, Option<String>) {
+    let mut parts = value.split(';').map(str::trim);
+
+    let is_attachment = parts
+        .next()
+        .map(|disp| disp.to_lowercase() == "attachment")
+        .unwrap_or(false);
+
+    let filename = parts
+        .filter_map(|param| {
+            let mut kv = param.splitn(2, '=');
+            match (kv.next(), kv.next()) {
+                (Some(k), Some(v)) if k.trim().to_lowercase() == "filename" => {
+                    Some(String::from(v.trim().trim_matches('"')))
+                },
+                _ => None,
+            }
+        })
+        .next();
+
+    (is_attachment, filename)
+}
+
+#[cfg(test)]
+mod test {
+    use mailparse::parse_mail;
+
+    use super::collect_attachments;
+    use super::parse_disposition;
+
+    #[test]
+    fn test_parse_disposition() {
+        assert_eq!(parse_disposition("inline"), (false, None));
+        assert_eq!(parse_disposition("attachment"), (true, None));
+        assert_eq!(parse_disposition("attachment; filename=\"doc.pdf\""),
+                   (true, Some(String::from("doc.pdf"))));
+    }
+
+    #[test]
+    fn test_multipart_with_pdf_attachment() {
+        let mail = "From: alice@example.com\r\n\
+                    Subject: Document\r\n\
+                    Content-Type: multipart/mixed; boundary=\"XXX\"\r\n\
+                    \r\n\
+                    --XXX\r\n\
+                    Content-Type: text/plain; charset=utf-8\r\n\
+                    \r\n\
+                    See the attached document\r\n\
+                    --XXX\r\n\
+                    Content-Type: application/pdf; name=\"doc.pdf\"\r\n\
+                    Content-Disposition: attachment; filename=\"doc.pdf\"\r\n\
+                    Content-Transfer-Encoding: base64\r\n\
+                    \r\n\
+                    JVBERi0xLjQK\r\n\
+                    --XXX--\r\n";
+
+        let parsed      = parse_mail(mail.as_bytes()).unwrap();
+        let attachments = collect_attachments(&parsed).unwrap();
+
+        assert_eq!(attachments.len(), 1);
+
+        let att = &attachments[0];
+        assert_eq!(att.filename(), Some(&String::from("doc.pdf")));
+        assert_eq!(att.content_type(), "application/pdf");
+
+        let mut out = vec![];
+        assert!(att.write_to(&mut out).is_ok());
+        assert_eq!(out, b"%PDF-1.4\n".to_vec());
+    }
+
+}
@@ -3,9 +3,13 @@ generate_error_module!(
         RefCreationError => "Error creating a reference to a file/directory",
         RefHandlingError => "Error while handling the internal reference object",
         MailParsingError => "Error while parsing mail",
+        AttachmentError  => "Error while handling mail attachment",
+        NoMessageIdFoundError => "No Message-ID found",
+        MessageIdIndexError   => "Error while accessing the Message-ID index",
 
         FetchByHashError => "Error fetching mail from Store by hash",
         FetchError       => "Error fetching mail from Store",
+        BodyNotLoadedError => "Body of the mail is not loaded",
         IOError => "IO Error"
     );
 );
@@ -30,7 +30,12 @@ impl Hasher for MailHasher {
         "default_mail_hasher"
     }
 
-    fn create_hash<R: Read>(&mut self, pb: &PathBuf, c: &mut R) -> RResult<String> {
+    fn create_hash<R: Read>(&mut self, _: &PathBuf, c: &mut R) -> RResult<String> {
+        self.hash_reader(c)
+    }
+
+    /// The mail has to be parsed to find the headers, so it is read into memory completely
+    fn hash_reader<R: Read>(&self, mut c: R) -> RResult<String> {
         use filters::filter::Filter;
 
         let mut s = String::new();
@@ -60,7 +65,7 @@ impl Hasher for MailHasher {
                 }
                 let s : String = v.join("");
 
-                self.defaulthasher.create_hash(pb, &mut s.as_bytes())
+                self.defaulthasher.hash_reader(s.as_bytes())
             })
     }
 
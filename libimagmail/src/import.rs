@@ -0,0 +1,261 @@
+//! Module for importing many mails at once and reporting the results
+//!
+
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
+use std::fmt::Error as FmtError;
+use std::path::Path;
+use std::path::PathBuf;
+
+use libimagstore::store::Store;
+use libimagstore::storeid::StoreId;
+use libimagref::reference::Ref;
+
+use error::MailError;
+use error::MailErrorKind as MEK;
+use error::MapErrInto;
+use mail::Mail;
+use result::Result;
+
+/// Importer for directories of mails, for example Maildirs
+///
+/// By default, symlinks to directories are not followed and the directory is searched without a
+/// depth limit. Symlinks to files are always imported.
+#[derive(Debug, Clone, Default)]
+pub struct MailImporter {
+    follow_links: bool,
+    max_depth: Option<usize>,
+    dedup: bool,
+}
+
+impl MailImporter {
+
+    pub fn new() -> MailImporter {
+        MailImporter::default()
+    }
+
+    /// Descend into symlinked directories
+    ///
+    /// Each directory is only searched once, so symlink loops do not cause endless recursion.
+    pub fn follow_links(mut self, b: bool) -> MailImporter {
+        self.follow_links = b;
+        self
+    }
+
+    /// Only import files up to `depth` directory levels below the imported directory
+    ///
+    /// With a depth of 1, only the files directly in the directory are imported.
+    pub fn max_depth(mut self, depth: Option<usize>) -> MailImporter {
+        self.max_depth = depth;
+        self
+    }
+
+    /// Skip mails whose Message-ID was imported before, see `Mail::import_from_path_dedup()`
+    pub fn dedup(mut self, b: bool) -> MailImporter {
+        self.dedup = b;
+        self
+    }
+
+    /// Import all mails from the directory at `p`
+    ///
+    /// Each file is returned with its result, in the order of the (sorted) paths, so it can be
+    /// passed to `ImportReport::from_results()`. For the imported mails, the id of the entry is
+    /// returned, so the entries are not kept borrowed while the rest of the directory is imported.
+    ///
+    /// The returned Err is only for failures while reading the directory.
+    pub fn import<P: AsRef<Path>>(&self, store: &Store, p: P)
+        -> Result<Vec<(PathBuf, Result<Option<StoreId>>)>>
+    {
+        let paths = try!(self.collect_files(p.as_ref()));
+
+        Ok(paths
+           .into_iter()
+           .map(|path| {
+               let res = if self.dedup {
+                   Mail::import_from_path_dedup(store, &path)
+               } else {
+                   Mail::import_from_path(store, &path).map(Some)
+               };
+               let id = res.map(|o| o.map(|mail| {
+                   let rf : Ref = mail.into();
+                   rf.get_location().clone()
+               }));
+               (path, id)
+           })
+           .collect())
+    }
+
+    /// Get the (sorted) paths of all files which would be imported from the directory at `dir`
+    pub fn collect_files(&self, dir: &Path) -> Result<Vec<PathBuf>> {
+        let mut paths   = vec![];
+        let mut visited = HashSet::new();
+        try!(self.walk(dir, 0, &mut visited, &mut paths).map_err_into(MEK::IOError));
+        paths.sort();
+        Ok(paths)
+    }
+
+    fn walk(&self, dir: &Path, depth: usize, visited: &mut HashSet<PathBuf>, paths: &mut Vec<PathBuf>)
+        -> ::std::io::Result<()>
+    {
+        use std::fs::read_dir;
+        use std::fs::symlink_metadata;
+
+        if !visited.insert(try!(dir.canonicalize())) {
+            debug!("Already searched {:?}, skipping", dir);
+            return Ok(());
+        }
+
+        if self.max_depth.map(|max| depth >= max).unwrap_or(false) {
+            return Ok(());
+        }
+
+        for entry in try!(read_dir(dir)) {
+            let path    = try!(entry).path();
+            let is_link = try!(symlink_metadata(&path)).file_type().is_symlink();
+
+            if !path.is_dir() {
+                paths.push(path);
+            } else if is_link && !self.follow_links {
+                debug!("Not following symlink {:?}", path);
+            } else {
+                try!(self.walk(&path, depth + 1, visited, paths));
+            }
+        }
+        Ok(())
+    }
+
+}
+
+/// Summary of an import, see `MailImporter::import()`
+///
+/// Mails are counted as imported (`Ok(Some(_))`), skipped because they were imported before
+/// (`Ok(None)`) or failed (`Err(_)`). The paths of the failed mails are kept together with the
+/// error.
+#[derive(Debug)]
+pub struct ImportReport {
+    imported: usize,
+    skipped: usize,
+    failed: Vec<(PathBuf, MailError)>,
+}
+
+impl ImportReport {
+
+    /// Build the report from `(path, result)` pairs
+    pub fn from_results<T, I>(results: I) -> ImportReport
+        where I: Iterator<Item = (PathBuf, Result<Option<T>>)>
+    {
+        let mut report = ImportReport {
+            imported: 0,
+            skipped: 0,
+            failed: vec![],
+        };
+
+        for (path, res) in results {
+            match res {
+                Ok(Some(_)) => report.imported += 1,
+                Ok(None)    => report.skipped += 1,
+                Err(e)      => report.failed.push((path, e)),
+            }
+        }
+
+        report
+    }
+
+    pub fn imported(&self) -> usize {
+        self.imported
+    }
+
+    pub fn skipped(&self) -> usize {
+        self.skipped
+    }
+
+    pub fn failed(&self) -> &Vec<(PathBuf, MailError)> {
+        &self.failed
+    }
+
+}
+
+impl Display for ImportReport {
+
+    fn fmt(&self, fmt: &mut Formatter) -> ::std::result::Result<(), FmtError> {
+        write!(fmt, "imported={} skipped={} failed={}", self.imported, self.skipped, self.failed.len())
+    }
+
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use libimagerror::into::IntoError;
+
+    use error::MailErrorKind as MEK;
+    use result::Result;
+
+    use super::ImportReport;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlinked_dirs_only_followed_if_enabled() {
+        use std::fs::File;
+        use std::fs::create_dir_all;
+        use std::os::unix::fs::symlink;
+
+        use tempdir::TempDir;
+
+        use super::MailImporter;
+
+        let tmp     = TempDir::new("imag-mail-test").unwrap();
+        let maildir = tmp.path().join("maildir");
+        let other   = tmp.path().join("other");
+        create_dir_all(maildir.join("cur")).unwrap();
+        create_dir_all(&other).unwrap();
+        File::create(maildir.join("cur").join("a")).unwrap();
+        File::create(other.join("b")).unwrap();
+        symlink(&other, maildir.join("linked")).unwrap();
+        symlink(&maildir, other.join("loop")).unwrap();
+
+        let files = MailImporter::new().collect_files(&maildir).unwrap();
+        assert_eq!(files, vec![maildir.join("cur").join("a")]);
+
+        let files = MailImporter::new().follow_links(true).collect_files(&maildir).unwrap();
+        assert_eq!(files, vec![maildir.join("cur").join("a"), maildir.join("linked").join("b")]);
+
+        let files = MailImporter::new()
+            .follow_links(true)
+            .max_depth(Some(1))
+            .collect_files(&maildir)
+            .unwrap();
+        assert!(files.is_empty());
+    }
+
+    #[test]
+    fn test_report_mixed_results() {
+        let results : Vec<(PathBuf, Result<Option<i32>>)> = vec![
+            (PathBuf::from("a"), Ok(Some(1))),
+            (PathBuf::from("b"), Ok(None)),
+            (PathBuf::from("c"), Err(MEK::MailParsingError.into_error())),
+            (PathBuf::from("d"), Ok(Some(2))),
+            (PathBuf::from("e"), Err(MEK::IOError.into_error())),
+            (PathBuf::from("f"), Ok(Some(3))),
+        ];
+
+        let report = ImportReport::from_results(results.into_iter());
+
+        assert_eq!(report.imported(), 3);
+        assert_eq!(report.skipped(), 1);
+        assert_eq!(report.failed().len(), 2);
+        assert_eq!(report.failed()[0].0, PathBuf::from("c"));
+        assert_eq!(report.failed()[0].1.err_type(), MEK::MailParsingError);
+        assert_eq!(report.failed()[1].0, PathBuf::from("e"));
+        assert_eq!(format!("{}", report), "imported=3 skipped=1 failed=2");
+    }
+
+    #[test]
+    fn test_report_empty() {
+        let results : Vec<(PathBuf, Result<Option<i32>>)> = vec![];
+        let report  = ImportReport::from_results(results.into_iter());
+        assert_eq!(format!("{}", report), "imported=0 skipped=0 failed=0");
+    }
+
+}
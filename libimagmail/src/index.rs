@@ -0,0 +1,70 @@
+//! Module for the Message-ID index
+//!
+//! For each mail imported with deduplication, an entry `mail/message-id/<message-id>` is created in
+//! the store, holding the message-id in `mail.message_id` and the id of the reference to the mail
+//! in `mail.ref`. As the index lives in the store, it survives across imports.
+//!
+
+use std::collections::BTreeMap;
+
+use libimagstore::store::Store;
+use libimagstore::storeid::StoreId;
+use libimagstore::storeid::IntoStoreId;
+use libimagstore::toml_ext::TomlValueExt;
+
+use toml::Value;
+
+use module_path::ModuleEntryPath;
+use result::Result;
+use error::MapErrInto;
+use error::MailErrorKind as MEK;
+
+/// Get the StoreId of the index entry for `message_id`
+///
+/// The angle brackets around the message-id are removed and slashes are replaced, so the
+/// message-id can be used as a path component.
+pub fn index_id(message_id: &str) -> Result<StoreId> {
+    let name = message_id
+        .trim()
+        .trim_left_matches('<')
+        .trim_right_matches('>')
+        .replace('/', "_");
+
+    ModuleEntryPath::new(format!("message-id/{}", name))
+        .into_storeid()
+        .map_err_into(MEK::MessageIdIndexError)
+}
+
+/// Check whether a mail with `message_id` is already in the index
+pub fn is_indexed(store: &Store, message_id: &str) -> Result<bool> {
+    index_id(message_id)
+        .and_then(|id| store.get(id).map_err_into(MEK::MessageIdIndexError))
+        .map(|o| o.is_some())
+}
+
+/// Add `message_id` to the index, pointing to the reference `ref_id`
+pub fn add_to_index(store: &Store, message_id: &str, ref_id: &StoreId) -> Result<()> {
+    let id        = try!(index_id(message_id));
+    let mut entry = try!(store.retrieve(id).map_err_into(MEK::MessageIdIndexError));
+
+    let mut table = BTreeMap::new();
+    table.insert(String::from("message_id"), Value::String(String::from(message_id)));
+    table.insert(String::from("ref"), Value::String(format!("{}", ref_id)));
+
+    entry.get_header_mut()
+        .set("mail", Value::Table(table))
+        .map(|_| ())
+        .map_err_into(MEK::MessageIdIndexError)
+}
+
+#[cfg(test)]
+mod test {
+    use super::index_id;
+
+    #[test]
+    fn test_index_id_is_path_safe() {
+        let id = index_id("<abc/def@example.com>").unwrap();
+        assert_eq!(format!("{}", id), "mail/message-id/abc_def@example.com");
+    }
+
+}
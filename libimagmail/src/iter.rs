@@ -7,12 +7,14 @@
 
 use mail::Mail;
 use result::Result;
+use error::MailError;
 
 use libimagref::reference::Ref;
+use libimagerror::trace::trace_error;
 
 use std::marker::PhantomData;
 
-struct MailIter<'a, I: 'a + Iterator<Item = Ref<'a>>> {
+pub struct MailIter<'a, I: 'a + Iterator<Item = Ref<'a>>> {
     _marker: PhantomData<&'a I>,
     i: I,
 }
@@ -23,6 +25,16 @@ impl<'a, I: Iterator<Item = Ref<'a>>> MailIter<'a, I> {
         MailIter { _marker: PhantomData, i: i }
     }
 
+    /// Collect the iterator, separating the mails from the errors
+    pub fn collect_results(self) -> (Vec<Mail<'a>>, Vec<MailError>) {
+        partition_results(self)
+    }
+
+    /// Get an iterator over the mails only, errors are traced and dropped
+    pub fn oks(self) -> Oks<Self> {
+        Oks(self)
+    }
+
 }
 
 impl<'a, I: Iterator<Item = Ref<'a>>> Iterator for MailIter<'a, I> {
@@ -35,3 +47,73 @@ impl<'a, I: Iterator<Item = Ref<'a>>> Iterator for MailIter<'a, I> {
 
 }
 
+
+/// Iterator adapter which yields the `Ok` values of an iterator over `Result`s, tracing and
+/// dropping the errors
+pub struct Oks<I>(I);
+
+impl<T, I: Iterator<Item = Result<T>>> Iterator for Oks<I> {
+
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        while let Some(r) = self.0.next() {
+            match r {
+                Ok(t)  => return Some(t),
+                Err(e) => trace_error(&e),
+            }
+        }
+        None
+    }
+
+}
+
+fn partition_results<T, I: Iterator<Item = Result<T>>>(i: I) -> (Vec<T>, Vec<MailError>) {
+    let mut oks  = vec![];
+    let mut errs = vec![];
+    for r in i {
+        match r {
+            Ok(t)  => oks.push(t),
+            Err(e) => errs.push(e),
+        }
+    }
+    (oks, errs)
+}
+
+#[cfg(test)]
+mod test {
+    use libimagerror::into::IntoError;
+
+    use error::MailErrorKind as MEK;
+    use result::Result;
+
+    use super::Oks;
+    use super::partition_results;
+
+    fn mixed() -> Vec<Result<i32>> {
+        vec![
+            Ok(1),
+            Err(MEK::MailParsingError.into_error()),
+            Ok(2),
+            Err(MEK::IOError.into_error()),
+            Ok(3),
+        ]
+    }
+
+    #[test]
+    fn test_partition_results() {
+        let (oks, errs) = partition_results(mixed().into_iter());
+
+        assert_eq!(oks, vec![1, 2, 3]);
+        assert_eq!(errs.len(), 2);
+        assert_eq!(errs[0].err_type(), MEK::MailParsingError);
+        assert_eq!(errs[1].err_type(), MEK::IOError);
+    }
+
+    #[test]
+    fn test_oks() {
+        let oks : Vec<i32> = Oks(mixed().into_iter()).collect();
+        assert_eq!(oks, vec![1, 2, 3]);
+    }
+
+}
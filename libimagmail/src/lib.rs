@@ -1,16 +1,25 @@
 #[macro_use] extern crate log;
+extern crate chrono;
 extern crate mailparse;
 extern crate semver;
 extern crate toml;
 extern crate filters;
+#[cfg(test)] extern crate tempdir;
 
 #[macro_use] extern crate libimagerror;
-extern crate libimagstore;
+#[macro_use] extern crate libimagstore;
 extern crate libimagref;
 
+module_entry_path_mod!("mail");
+
+pub mod attachment;
 pub mod error;
 pub mod hasher;
+pub mod import;
+pub mod index;
 pub mod iter;
 pub mod mail;
 pub mod result;
+pub mod stats;
+pub mod thread;
 
@@ -2,29 +2,54 @@ use std::result::Result as RResult;
 use std::path::Path;
 use std::path::PathBuf;
 use std::fs::File;
+use std::fs::create_dir_all;
+use std::io::BufRead;
+use std::io::BufReader;
 use std::io::Read;
+use std::io::Write;
 
 use libimagstore::store::{FileLockEntry, Store};
+use libimagstore::storeid::StoreId;
 use libimagref::reference::Ref;
 use libimagref::flags::RefFlags;
+use libimagerror::into::IntoError;
 
-use mailparse::{MailParseError, ParsedMail, parse_mail};
+use chrono::{DateTime, FixedOffset};
+use mailparse::{MailHeader, MailParseError, ParsedMail, parse_mail};
 
+use attachment::{Attachment, collect_attachments};
+use import::MailImporter;
+use index;
 use hasher::MailHasher;
 use result::Result;
 use error::{MapErrInto, MailErrorKind as MEK};
 
-struct Buffer(String);
+pub type MessageId = String;
+
+struct Buffer {
+    data: String,
+
+    /// Whether `data` is the whole mail, or only its headers
+    complete: bool,
+}
 
 impl Buffer {
+    pub fn headers_only(data: String) -> Buffer {
+        Buffer { data: data, complete: false }
+    }
+
     pub fn parsed<'a>(&'a self) -> RResult<ParsedMail<'a>, MailParseError> {
-        parse_mail(self.0.as_bytes())
+        parse_mail(self.data.as_bytes())
+    }
+
+    pub fn raw(&self) -> &str {
+        &self.data
     }
 }
 
 impl From<String> for Buffer {
     fn from(data: String) -> Buffer {
-        Buffer(data)
+        Buffer { data: data, complete: true }
     }
 }
 
@@ -33,6 +58,8 @@ pub struct Mail<'a>(Ref<'a>, Buffer);
 impl<'a> Mail<'a> {
 
     /// Imports a mail from the Path passed
+    ///
+    /// The content type of the reference entry is set to `message/rfc822`.
     pub fn import_from_path<P: AsRef<Path>>(store: &Store, p: P) -> Result<Mail> {
         let h = MailHasher::new();
         let f = RefFlags::default().with_content_hashing(true).with_permission_tracking(false);
@@ -40,7 +67,9 @@ impl<'a> Mail<'a> {
 
         Ref::create_with_hasher(store, p, f, h)
             .map_err_into(MEK::RefCreationError)
-            .and_then(|reference| {
+            .and_then(|mut reference| {
+                try!(reference.set_content_type("message/rfc822").map_err_into(MEK::RefHandlingError));
+
                 reference.fs_file()
                     .map_err_into(MEK::RefHandlingError)
                     .and_then(|path| File::open(path).map_err_into(MEK::IOError))
@@ -55,6 +84,116 @@ impl<'a> Mail<'a> {
             })
     }
 
+    /// Imports a mail from the Path passed, unless a mail with the same Message-ID was imported
+    /// before
+    ///
+    /// The Message-IDs of the mails imported with this function are kept in an index in the store
+    /// (see the `index` module). Returns `Ok(None)` if the mail was skipped. Mails without
+    /// Message-ID cannot be deduplicated and are always imported.
+    pub fn import_from_path_dedup<P: AsRef<Path>>(store: &Store, p: P) -> Result<Option<Mail>> {
+        let mut s = String::new();
+        try!(File::open(p.as_ref())
+             .and_then(|mut file| file.read_to_string(&mut s))
+             .map_err_into(MEK::IOError));
+
+        let message_id = try!(parse_mail(s.as_bytes())
+            .and_then(|parsed| find_header_value(&parsed.headers, "Message-ID"))
+            .map_err_into(MEK::MailParsingError));
+
+        match message_id {
+            Some(id) => {
+                if try!(index::is_indexed(store, &id)) {
+                    debug!("Mail with Message-ID {} already imported, skipping", id);
+                    return Ok(None);
+                }
+
+                let mail = try!(Mail::import_from_path(store, p));
+                try!(index::add_to_index(store, &id, mail.0.get_location()));
+                Ok(Some(mail))
+            },
+            None => Mail::import_from_path(store, p).map(Some),
+        }
+    }
+
+    /// Imports all mails from the directory at the Path passed, recursively
+    ///
+    /// If `dedup` is set, mails are imported with `Mail::import_from_path_dedup()`, otherwise with
+    /// `Mail::import_from_path()` (and never skipped). Symlinked directories are not followed, use
+    /// a `MailImporter` to configure this.
+    ///
+    /// See `MailImporter::import()` for the return value.
+    pub fn import_from_dir<P: AsRef<Path>>(store: &Store, p: P, dedup: bool)
+        -> Result<Vec<(PathBuf, Result<Option<StoreId>>)>>
+    {
+        MailImporter::new().dedup(dedup).import(store, p)
+    }
+
+    /// Imports all mails from the mbox file at the Path passed
+    ///
+    /// As a reference needs a file to point to, each message is written to its own file
+    /// `<mbox name>-<i>.eml` in the directory `out`, which is then imported via
+    /// `Mail::import_from_path()`. The directory is created if it does not exist. It should be a
+    /// directory which is kept as long as the store is used, as the mails are not copied into the
+    /// store.
+    ///
+    /// If `dedup` is set, messages whose Message-ID was imported before are skipped (returned as
+    /// `Ok(None)`) and not written to `out`, see `Mail::import_from_path_dedup()`.
+    ///
+    /// The ids of the imported mails are returned, so the entries are not kept borrowed, use
+    /// `Mail::open()` to work with a mail. The returned Err is only for failures which affect the
+    /// whole mbox (reading it, creating the directory). Messages which cannot be imported are
+    /// returned as `Err` in the Vec.
+    pub fn import_from_mbox<P, D>(store: &Store, p: P, out: D, dedup: bool)
+        -> Result<Vec<Result<Option<StoreId>>>>
+        where P: AsRef<Path>,
+              D: AsRef<Path>
+    {
+        let p    = PathBuf::from(p.as_ref());
+        let dir  = PathBuf::from(out.as_ref());
+        let name = p.file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| String::from("mbox"));
+
+        let mut s = String::new();
+        try!(File::open(&p)
+             .and_then(|mut file| file.read_to_string(&mut s))
+             .map_err_into(MEK::IOError));
+
+        try!(create_dir_all(&dir).map_err_into(MEK::IOError));
+
+        let mails = split_mbox(&s)
+            .into_iter()
+            .enumerate()
+            .map(|(i, message)| {
+                let message_id = try!(parse_mail(message.as_bytes())
+                    .and_then(|parsed| find_header_value(&parsed.headers, "Message-ID"))
+                    .map_err_into(MEK::MailParsingError));
+
+                if let (true, Some(id)) = (dedup, message_id) {
+                    if try!(index::is_indexed(store, &id)) {
+                        debug!("Mail with Message-ID {} already imported, skipping", id);
+                        return Ok(None);
+                    }
+                }
+
+                let mut path = dir.clone();
+                path.push(format!("{}-{}.eml", name, i));
+
+                try!(File::create(&path)
+                     .and_then(|mut file| file.write_all(message.as_bytes()))
+                     .map_err_into(MEK::IOError));
+
+                if dedup {
+                    Mail::import_from_path_dedup(store, path).map(|o| o.map(|mail| mail.0.get_location().clone()))
+                } else {
+                    Mail::import_from_path(store, path).map(|mail| Some(mail.0.get_location().clone()))
+                }
+            })
+            .collect();
+
+        Ok(mails)
+    }
+
     /// Opens a mail by the passed hash
     pub fn open<S: AsRef<str>>(store: &Store, hash: S) -> Result<Option<Mail>> {
         Ref::get_by_hash(store, String::from(hash.as_ref()))
@@ -67,6 +206,36 @@ impl<'a> Mail<'a> {
 
     }
 
+    /// Search all mails in the store for mails where the header `field` contains `needle`
+    ///
+    /// The comparison is case-insensitive. Mails are references, so all references in the store
+    /// are opened as mails. References which cannot be read or parsed as mail are skipped, the
+    /// error is traced.
+    pub fn search_store(store: &'a Store, field: &str, needle: &str) -> Result<Vec<Mail<'a>>> {
+        use libimagerror::trace::MapErrTrace;
+
+        let needle = needle.to_lowercase();
+        let iter   = try!(store.retrieve_for_module("ref").map_err_into(MEK::FetchError));
+
+        Ok(iter
+           .filter_map(|id| {
+               Ref::get(store, id)
+                   .map_err_into(MEK::RefHandlingError)
+                   .and_then(Mail::from_ref)
+                   .map_err_trace()
+                   .ok()
+           })
+           .filter(|mail| {
+               mail.get_field(field)
+                   .map_err_trace()
+                   .ok()
+                   .and_then(|o| o)
+                   .map(|value| value.to_lowercase().contains(&needle))
+                   .unwrap_or(false)
+           })
+           .collect())
+    }
+
     /// Implement me as TryFrom as soon as it is stable
     pub fn from_ref(r: Ref<'a>) -> Result<Mail> {
         r.fs_file()
@@ -82,19 +251,107 @@ impl<'a> Mail<'a> {
             .map(|buffer| Mail(r, buffer))
     }
 
+    /// Opens a mail by the passed hash, reading only the headers of the mail
+    ///
+    /// See `Mail::from_ref_headers_only()`.
+    pub fn open_headers_only<S: AsRef<str>>(store: &Store, hash: S) -> Result<Option<Mail>> {
+        Ref::get_by_hash(store, String::from(hash.as_ref()))
+            .map_err_into(MEK::FetchByHashError)
+            .map_err_into(MEK::FetchError)
+            .and_then(|o| match o {
+                Some(r) => Mail::from_ref_headers_only(r).map(Some),
+                None => Ok(None),
+            })
+    }
+
+    /// Like `Mail::from_ref()`, but reads the referenced file only up to the empty line which
+    /// separates the headers from the body
+    ///
+    /// The header accessors work as usual. Everything which needs the body (`Mail::get_body()`,
+    /// `Mail::build_reply()`, `Mail::attachments()` and `Mail::export_to_path()`) fails with
+    /// `BodyNotLoadedError` until `Mail::load_body()` was called.
+    pub fn from_ref_headers_only(r: Ref<'a>) -> Result<Mail> {
+        r.fs_file()
+            .map_err_into(MEK::RefHandlingError)
+            .and_then(|path| File::open(path).map_err_into(MEK::IOError))
+            .and_then(|file| read_headers(BufReader::new(file)))
+            .map(Buffer::headers_only)
+            .map(|buffer| Mail(r, buffer))
+    }
+
+    /// Whether the whole mail is loaded, see `Mail::from_ref_headers_only()`
+    pub fn is_body_loaded(&self) -> bool {
+        self.1.complete
+    }
+
+    /// Read the whole mail, if only the headers were read so far
+    pub fn load_body(&mut self) -> Result<()> {
+        if self.1.complete {
+            return Ok(());
+        }
+
+        let buffer = try!(self.0
+            .fs_file()
+            .map_err_into(MEK::RefHandlingError)
+            .and_then(|path| File::open(path).map_err_into(MEK::IOError))
+            .and_then(|mut file| {
+                let mut s = String::new();
+                file.read_to_string(&mut s)
+                    .map(|_| s)
+                    .map_err_into(MEK::IOError)
+            })
+            .map(Buffer::from));
+
+        self.1 = buffer;
+        Ok(())
+    }
+
+    /// Get the buffer of the mail, failing if only the headers are loaded
+    fn complete_buffer(&self) -> Result<&Buffer> {
+        if self.1.complete {
+            Ok(&self.1)
+        } else {
+            Err(MEK::BodyNotLoadedError.into_error())
+        }
+    }
+
+    /// Write the mail to the file at `p`, exactly as it was read from the referenced file
+    pub fn export_to_path<P: AsRef<Path>>(&self, p: P) -> Result<()> {
+        let buffer = try!(self.complete_buffer());
+
+        File::create(p.as_ref())
+            .and_then(|mut file| file.write_all(buffer.raw().as_bytes()))
+            .map_err_into(MEK::IOError)
+    }
+
+    /// Get the value of the header `field`, with RFC 2047 encoded-words decoded
+    ///
+    /// The header name is matched case-insensitively. Use `Mail::get_field_raw()` to get the value
+    /// as it is written in the mail.
     pub fn get_field(&self, field: &str) -> Result<Option<String>> {
-        use mailparse::MailHeader;
+        self.1
+            .parsed()
+            .and_then(|parsed| find_header_value(&parsed.headers, field))
+            .map_err_into(MEK::MailParsingError)
+    }
 
+    /// Get all headers of the mail as `(name, value)` pairs, with the values decoded
+    ///
+    /// In contrast to `Mail::get_field()`, which returns the first match only, this keeps the order
+    /// of the headers in the mail and includes headers which appear more than once, like
+    /// `Received`.
+    pub fn headers(&self) -> Result<Vec<(String, String)>> {
         self.1
             .parsed()
+            .and_then(|parsed| all_header_values(&parsed.headers))
             .map_err_into(MEK::MailParsingError)
-            .map(|parsed| {
-                parsed.headers
-                    .iter()
-                    .filter(|hdr| hdr.get_key().map(|n| n == field).unwrap_or(false))
-                    .next()
-                    .and_then(|field| field.get_value().ok())
-            })
+    }
+
+    /// Get the value of the header `field` exactly as it appears in the mail, without decoding
+    ///
+    /// Folded header lines are unfolded by removing the line breaks, the whitespace is kept.
+    pub fn get_field_raw(&self, field: &str) -> Result<Option<String>> {
+        Ok(find_raw_header_value(self.1.raw(), field))
     }
 
     pub fn get_from(&self) -> Result<Option<String>> {
@@ -105,6 +362,28 @@ impl<'a> Mail<'a> {
         self.get_field("To")
     }
 
+    pub fn get_cc(&self) -> Result<Option<String>> {
+        self.get_field("Cc")
+    }
+
+    pub fn get_bcc(&self) -> Result<Option<String>> {
+        self.get_field("Bcc")
+    }
+
+    /// Get all recipients (To, Cc and Bcc) of the mail as individual addresses
+    ///
+    /// The addresses are returned as written in the header, so including a display name if there
+    /// is one, e.g. `"Doe, John" <john@example.com>`.
+    pub fn get_recipients(&self) -> Result<Vec<String>> {
+        let mut v = vec![];
+        for field in &["To", "Cc", "Bcc"] {
+            if let Some(list) = try!(self.get_field(field)) {
+                v.append(&mut split_addresses(&list));
+            }
+        }
+        Ok(v)
+    }
+
     pub fn get_subject(&self) -> Result<Option<String>> {
         self.get_field("Subject")
     }
@@ -117,4 +396,891 @@ impl<'a> Mail<'a> {
         self.get_field("In-Reply-To")
     }
 
+    /// Get the `Date` header of the mail, parsed as RFC 2822 date
+    ///
+    /// A missing or malformed `Date` header yields `Ok(None)`, so a single broken mail does not
+    /// break the processing of a whole set of mails.
+    pub fn get_date(&self) -> Result<Option<DateTime<FixedOffset>>> {
+        self.get_field("Date").map(|o| o.and_then(|date| parse_date(&date)))
+    }
+
+    /// Get the message-ids from the `References` header, oldest first
+    pub fn get_references(&self) -> Result<Vec<MessageId>> {
+        self.get_field("References")
+            .map(|o| o.map(|refs| parse_references(&refs)).unwrap_or_else(|| vec![]))
+    }
+
+    /// Get the decoded body of the mail
+    ///
+    /// For multipart mails, the first `text/plain` part is returned. If there is none, the first
+    /// `text/html` part is returned with the tags stripped. If neither is present, the raw body of
+    /// the mail is returned.
+    pub fn get_body(&self) -> Result<String> {
+        try!(self.complete_buffer())
+            .parsed()
+            .and_then(|parsed| find_body(&parsed))
+            .map_err_into(MEK::MailParsingError)
+    }
+
+    /// Build the skeleton of a reply to the mail
+    ///
+    /// The reply is addressed to the sender of the mail, the subject is prefixed with `Re: ` (if it
+    /// is not already) and `In-Reply-To` and `References` point to the mail. The body of the mail
+    /// is quoted with `> `.
+    pub fn build_reply(&self) -> Result<String> {
+        try!(self.complete_buffer())
+            .parsed()
+            .and_then(|parsed| build_reply(&parsed))
+            .map_err_into(MEK::MailParsingError)
+    }
+
+    /// Get all attachments of the mail
+    ///
+    /// Every (sub)part of the mail which has a `Content-Disposition: attachment` header or carries a
+    /// filename is considered an attachment.
+    pub fn attachments(&self) -> Result<Vec<Attachment>> {
+        try!(self.complete_buffer())
+            .parsed()
+            .and_then(|parsed| collect_attachments(&parsed))
+            .map_err_into(MEK::MailParsingError)
+            .map_err_into(MEK::AttachmentError)
+    }
+
+}
+
+impl<'a> Into<Ref<'a>> for Mail<'a> {
+
+    fn into(self) -> Ref<'a> {
+        self.0
+    }
+
+}
+
+/// Read the header section of a mail, up to and including the empty line which ends it
+///
+/// Both CRLF and LF line endings are accepted. If there is no empty line, the whole input is read.
+fn read_headers<R: BufRead>(mut reader: R) -> Result<String> {
+    let mut s = String::new();
+
+    loop {
+        let mut line = String::new();
+        let n = try!(reader.read_line(&mut line).map_err_into(MEK::IOError));
+        s.push_str(&line);
+
+        if n == 0 || line == "\r\n" || line == "\n" {
+            return Ok(s);
+        }
+    }
+}
+
+/// Build the reply skeleton for `mail`, see `Mail::build_reply()`
+fn build_reply(mail: &ParsedMail) -> RResult<String, MailParseError> {
+    let from       = try!(find_header_value(&mail.headers, "From"));
+    let subject    = try!(find_header_value(&mail.headers, "Subject"));
+    let message_id = try!(find_header_value(&mail.headers, "Message-ID"));
+    let body       = try!(find_body(mail));
+
+    // Without a References header, the In-Reply-To header of the mail continues the chain
+    let mut references = match try!(find_header_value(&mail.headers, "References")) {
+        Some(refs) => parse_references(&refs),
+        None       => try!(find_header_value(&mail.headers, "In-Reply-To"))
+            .map(|irt| parse_references(&irt))
+            .unwrap_or_else(|| vec![]),
+    };
+
+    let mut reply = vec![];
+    reply.push(format!("To: {}", from.unwrap_or_else(String::new)));
+    reply.push(format!("Subject: {}", reply_subject(&subject.unwrap_or_else(String::new))));
+
+    if let Some(id) = message_id {
+        let id = String::from(id.trim());
+        reply.push(format!("In-Reply-To: {}", id));
+        references.push(id);
+    }
+
+    if !references.is_empty() {
+        reply.push(format!("References: {}", references.join(" ")));
+    }
+
+    reply.push(String::new());
+    reply.push(quote_body(&body));
+
+    Ok(reply.join("\n"))
+}
+
+/// Prefix `subject` with `Re: `, unless it already starts with it (in any case)
+fn reply_subject(subject: &str) -> String {
+    let subject = subject.trim();
+    if subject.to_lowercase().starts_with("re:") {
+        String::from(subject)
+    } else {
+        format!("Re: {}", subject)
+    }
+}
+
+/// Quote every line of `body` with `> `
+fn quote_body(body: &str) -> String {
+    body.trim_right()
+        .lines()
+        .map(|line| if line.is_empty() { String::from(">") } else { format!("> {}", line) })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Find the decoded value of the header `field` in `headers`, matching the name case-insensitively
+///
+/// `MailHeader::get_value()` takes care of decoding RFC 2047 encoded-words, including the removal of
+/// whitespace between adjacent encoded-words.
+fn find_header_value(headers: &[MailHeader], field: &str) -> RResult<Option<String>, MailParseError> {
+    for hdr in headers {
+        if try!(hdr.get_key()).to_lowercase() == field.to_lowercase() {
+            return hdr.get_value().map(Some);
+        }
+    }
+    Ok(None)
+}
+
+/// Get the names and decoded values of all `headers`, in order
+fn all_header_values(headers: &[MailHeader]) -> RResult<Vec<(String, String)>, MailParseError> {
+    let mut v = vec![];
+    for hdr in headers {
+        v.push((try!(hdr.get_key()), try!(hdr.get_value())));
+    }
+    Ok(v)
+}
+
+/// Find the undecoded value of the header `field` in the header block of the raw mail `mail`
+fn find_raw_header_value(mail: &str, field: &str) -> Option<String> {
+    let field     = field.to_lowercase();
+    let mut value : Option<String> = None;
+
+    for line in mail.lines().map(|l| l.trim_right_matches('\r')) {
+        if line.is_empty() {
+            break; // end of header block
+        }
+
+        let is_continuation = line.starts_with(' ') || line.starts_with('\t');
+        match value {
+            Some(ref mut v) if is_continuation => {
+                v.push_str(line);
+                continue;
+            },
+            Some(_) => break,
+            None    => {},
+        }
+
+        let mut kv = line.splitn(2, ':');
+        if let (Some(k), Some(v)) = (kv.next(), kv.next()) {
+            if k.trim().to_lowercase() == field {
+                value = Some(String::from(v.trim_left()));
+            }
+        }
+    }
+
+    value
+}
+
+/// Split the content of a mbox file into the individual messages
+///
+/// A message starts with a `From ` line which is either the first line or preceded by an empty
+/// line. The `From ` line itself is not part of the message. Quoted `>From ` lines in the message
+/// bodies are unescaped by removing one `>`.
+fn split_mbox(mbox: &str) -> Vec<String> {
+    let mut messages       = vec![];
+    let mut current        = None;
+    let mut previous_empty = true;
+
+    for line in mbox.lines() {
+        if previous_empty && line.starts_with("From ") {
+            if let Some(msg) = current.take() {
+                messages.push(msg);
+            }
+            current        = Some(String::new());
+            previous_empty = false;
+            continue;
+        }
+
+        previous_empty = line.trim_right_matches('\r').is_empty();
+
+        if let Some(ref mut msg) = current {
+            if line.trim_left_matches('>').starts_with("From ") && line.starts_with('>') {
+                msg.push_str(&line[1..]);
+            } else {
+                msg.push_str(line);
+            }
+            msg.push('\n');
+        }
+    }
+
+    if let Some(msg) = current {
+        messages.push(msg);
+    }
+
+    messages
+}
+
+/// Split a comma-separated list of addresses into the individual addresses
+///
+/// Commas inside quoted display names, comments and angle brackets do not split the list.
+fn split_addresses(list: &str) -> Vec<String> {
+    let mut v         = vec![];
+    let mut current   = String::new();
+    let mut in_quotes = false;
+    let mut escaped   = false;
+    let mut depth     = 0; // nesting of () and <>
+
+    for c in list.chars() {
+        if escaped {
+            escaped = false;
+            current.push(c);
+            continue;
+        }
+
+        match c {
+            '\\' if in_quotes => escaped = true,
+            '"'                => in_quotes = !in_quotes,
+            '(' | '<' if !in_quotes => depth += 1,
+            ')' | '>' if !in_quotes && depth > 0 => depth -= 1,
+            ',' if !in_quotes && depth == 0 => {
+                push_address(&mut v, &current);
+                current.clear();
+                continue;
+            },
+            _ => {},
+        }
+
+        current.push(c);
+    }
+    push_address(&mut v, &current);
+
+    v
+}
+
+fn push_address(v: &mut Vec<String>, addr: &str) {
+    let addr = addr.trim();
+    if !addr.is_empty() {
+        v.push(String::from(addr));
+    }
+}
+
+/// Split the value of a `References` header into the individual message-ids
+///
+/// The ids are separated by whitespace (and possibly folded over several lines). If the ids are
+/// enclosed in angle brackets, everything outside the brackets (e.g. comments) is ignored.
+fn parse_references(refs: &str) -> Vec<MessageId> {
+    if refs.contains('<') {
+        refs.split('<')
+            .skip(1)
+            .filter_map(|part| part.find('>').map(|end| &part[..end]))
+            .map(|id| format!("<{}>", id.trim()))
+            .collect()
+    } else {
+        refs.split_whitespace().map(String::from).collect()
+    }
+}
+
+/// Parse a RFC 2822 date, ignoring a trailing comment like in "... +0000 (UTC)"
+fn parse_date(date: &str) -> Option<DateTime<FixedOffset>> {
+    let date = match date.find('(') {
+        Some(pos) => &date[..pos],
+        None      => date,
+    };
+
+    DateTime::parse_from_rfc2822(date.trim()).ok()
+}
+
+/// Find the body text in a (possibly multipart) parsed mail
+fn find_body(mail: &ParsedMail) -> RResult<String, MailParseError> {
+    if let Some(part) = find_part(mail, "text/plain") {
+        return part.get_body();
+    }
+
+    if let Some(part) = find_part(mail, "text/html") {
+        return part.get_body().map(|html| strip_html_tags(&html));
+    }
+
+    mail.get_body()
+}
+
+/// Find the first leaf part of `mail` with the mimetype `mimetype`, walking multipart structures
+/// depth-first
+fn find_part<'a, 'b>(mail: &'b ParsedMail<'a>, mimetype: &str) -> Option<&'b ParsedMail<'a>> {
+    if mail.subparts.is_empty() {
+        if mail.ctype.mimetype == mimetype {
+            Some(mail)
+        } else {
+            None
+        }
+    } else {
+        mail.subparts.iter().filter_map(|part| find_part(part, mimetype)).next()
+    }
+}
+
+/// Naive removal of HTML tags, good enough for getting a readable text out of a `text/html` body
+fn strip_html_tags(html: &str) -> String {
+    let mut in_tag = false;
+    html.chars()
+        .filter(|c| match *c {
+            '<' => { in_tag = true; false },
+            '>' => { let was = in_tag; in_tag = false; !was },
+            _   => !in_tag,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs::File;
+    use std::io::Write;
+    use std::path::PathBuf;
+
+    use mailparse::parse_mail;
+    use tempdir::TempDir;
+
+    use libimagstore::store::Store;
+
+    use super::Mail;
+
+    use super::all_header_values;
+    use super::build_reply;
+    use super::find_body;
+    use super::find_header_value;
+    use super::parse_date;
+    use super::split_addresses;
+    use super::split_mbox;
+    use super::parse_references;
+    use super::find_raw_header_value;
+    use super::read_headers;
+
+    fn get_store() -> Store {
+        Store::new_in_memory(PathBuf::from("/"), None).unwrap()
+    }
+
+    fn write_mail(dir: &TempDir, name: &str, subject: &str) -> PathBuf {
+        let path = dir.path().join(name);
+        let mut file = File::create(&path).unwrap();
+        write!(file, "From: alice@example.com\r\n\
+                      To: bob@example.com\r\n\
+                      Subject: {}\r\n\
+                      Message-ID: <1234@example.com>\r\n\
+                      \r\n\
+                      Hello\r\n", subject).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_import_dedup() {
+        let tmp   = TempDir::new("imag-mail-test").unwrap();
+        let first = write_mail(&tmp, "first.eml", "Hello");
+        let copy  = write_mail(&tmp, "copy.eml", "Fwd: Hello");
+
+        let store = get_store();
+
+        assert!(Mail::import_from_path_dedup(&store, first).unwrap().is_some());
+        assert!(Mail::import_from_path_dedup(&store, copy).unwrap().is_none());
+
+        assert_eq!(store.retrieve_for_module("ref").unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_export_is_byte_equal() {
+        use std::io::Read;
+
+        let tmp    = TempDir::new("imag-mail-test").unwrap();
+        let source = write_mail(&tmp, "source.eml", "Grüße");
+        let target = tmp.path().join("exported.eml");
+
+        let store = get_store();
+
+        let mail = Mail::import_from_path(&store, &source).unwrap();
+        assert_eq!(mail.0.get_content_type(), Some(String::from("message/rfc822")));
+        assert!(mail.export_to_path(&target).is_ok());
+
+        let read = |p: &PathBuf| {
+            let mut v = vec![];
+            File::open(p).unwrap().read_to_end(&mut v).unwrap();
+            v
+        };
+        assert_eq!(read(&source), read(&target));
+    }
+
+    #[test]
+    fn test_read_headers() {
+        let crlf = "Subject: a\r\nFrom: b\r\n\r\nbody\r\n\r\nmore\r\n";
+        assert_eq!(read_headers(crlf.as_bytes()).unwrap(), "Subject: a\r\nFrom: b\r\n\r\n");
+
+        let lf = "Subject: a\nFrom: b\n\nbody\n";
+        assert_eq!(read_headers(lf.as_bytes()).unwrap(), "Subject: a\nFrom: b\n\n");
+
+        let no_body = "Subject: a\r\nFrom: b\r\n";
+        assert_eq!(read_headers(no_body.as_bytes()).unwrap(), no_body);
+    }
+
+    #[test]
+    fn test_open_headers_only() {
+        use error::MailErrorKind;
+
+        let tmp    = TempDir::new("imag-mail-test").unwrap();
+        let source = write_mail(&tmp, "source.eml", "Headers only");
+        let target = tmp.path().join("exported.eml");
+
+        let store = get_store();
+
+        let hash = Mail::import_from_path(&store, &source).unwrap().0.get_path_hash().unwrap();
+
+        let mut mail = Mail::open_headers_only(&store, &hash).unwrap().unwrap();
+        assert!(!mail.is_body_loaded());
+        assert!(!mail.1.raw().contains("Hello"));
+        assert_eq!(mail.get_subject().unwrap(), Some(String::from("Headers only")));
+        assert_eq!(mail.get_from().unwrap(), Some(String::from("alice@example.com")));
+        assert_eq!(mail.get_field_raw("Message-ID").unwrap(), Some(String::from("<1234@example.com>")));
+
+        assert_eq!(mail.get_body().unwrap_err().err_type(), MailErrorKind::BodyNotLoadedError);
+        assert_eq!(mail.attachments().unwrap_err().err_type(), MailErrorKind::BodyNotLoadedError);
+        assert_eq!(mail.export_to_path(&target).unwrap_err().err_type(),
+                   MailErrorKind::BodyNotLoadedError);
+        assert!(!target.exists());
+
+        assert!(mail.load_body().is_ok());
+        assert!(mail.is_body_loaded());
+        assert_eq!(mail.get_body().unwrap().trim(), "Hello");
+        assert_eq!(mail.get_subject().unwrap(), Some(String::from("Headers only")));
+    }
+
+    #[test]
+    fn test_search_store() {
+        let tmp = TempDir::new("imag-mail-test").unwrap();
+        let m1  = write_mail(&tmp, "m1.eml", "Meeting on Monday");
+        let m2  = write_mail(&tmp, "m2.eml", "Lunch");
+        let m3  = {
+            let path = tmp.path().join("m3.eml");
+            let mut file = File::create(&path).unwrap();
+            write!(file, "From: Carol <carol@example.com>\r\n\
+                          To: bob@example.com\r\n\
+                          Subject: Re: meeting\r\n\
+                          \r\n\
+                          Hi\r\n").unwrap();
+            path
+        };
+
+        let store = get_store();
+
+        for path in vec![m1, m2, m3] {
+            assert!(Mail::import_from_path(&store, path).is_ok());
+        }
+
+        let by_from = Mail::search_store(&store, "From", "ALICE@example").unwrap();
+        assert_eq!(by_from.len(), 2);
+
+        let by_subject = Mail::search_store(&store, "Subject", "meeting").unwrap();
+        assert_eq!(by_subject.len(), 2);
+
+        let by_subject = Mail::search_store(&store, "Subject", "lunch").unwrap();
+        assert_eq!(by_subject.len(), 1);
+        assert_eq!(by_subject[0].get_subject().unwrap(), Some(String::from("Lunch")));
+
+        assert!(Mail::search_store(&store, "Subject", "dinner").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_import_from_dir_returns_unborrowed_ids() {
+        let mails = TempDir::new("imag-mail-test-maildir").unwrap();
+        write_mail(&mails, "a.eml", "A");
+        write_mail(&mails, "b.eml", "B");
+
+        let store = get_store();
+
+        let results = Mail::import_from_dir(&store, mails.path(), false).unwrap();
+        assert_eq!(results.len(), 2);
+
+        for (_, res) in results {
+            let id = res.unwrap().unwrap();
+            assert!(store.get(id).unwrap().is_some());
+        }
+    }
+
+    #[test]
+    fn test_import_from_mbox() {
+        let tmp  = TempDir::new("imag-mail-test").unwrap();
+        let mbox = tmp.path().join("inbox");
+        {
+            let mut file = File::create(&mbox).unwrap();
+            write!(file, "From alice@example.com Thu Jan  1 00:00:00 2017\n\
+                          From: alice@example.com\n\
+                          Subject: First\n\
+                          \n\
+                          Hello\n\
+                          \n\
+                          From bob@example.com Thu Jan  1 00:00:00 2017\n\
+                          From: bob@example.com\n\
+                          Subject: Second\n\
+                          \n\
+                          Hi\n").unwrap();
+        }
+
+        let store = get_store();
+
+        let out   = tmp.path().join("mails");
+        let mails = Mail::import_from_mbox(&store, &mbox, &out, false).unwrap();
+        assert_eq!(mails.len(), 2);
+        assert!(mails.iter().all(|m| match *m { Ok(Some(_)) => true, _ => false }));
+
+        assert!(out.join("inbox-0.eml").is_file());
+        assert!(out.join("inbox-1.eml").is_file());
+        assert!(!tmp.path().join("inbox.d").exists());
+    }
+
+    #[test]
+    fn test_import_from_mbox_dedup() {
+        use std::io::Read;
+
+        let tmp  = TempDir::new("imag-mail-test").unwrap();
+        let mail = write_mail(&tmp, "single.eml", "Hello");
+        let mbox = tmp.path().join("inbox");
+        {
+            let mut content = String::new();
+            File::open(&mail).unwrap().read_to_string(&mut content).unwrap();
+
+            let mut file = File::create(&mbox).unwrap();
+            write!(file, "From alice@example.com Thu Jan  1 00:00:00 2017\n{}\n", content).unwrap();
+        }
+
+        let store = get_store();
+
+        assert!(Mail::import_from_path_dedup(&store, &mail).unwrap().is_some());
+
+        let out   = tmp.path().join("mails");
+        let mails = Mail::import_from_mbox(&store, &mbox, &out, true).unwrap();
+        assert_eq!(mails.len(), 1);
+        assert!(mails[0].as_ref().unwrap().is_none());
+        assert!(!out.join("inbox-0.eml").exists());
+
+        assert_eq!(store.retrieve_for_module("ref").unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_split_mbox_two_messages() {
+        let mbox = "From alice@example.com Thu Jan  1 00:00:00 2017\n\
+                    From: alice@example.com\n\
+                    Subject: First\n\
+                    \n\
+                    Hello\n\
+                    \n\
+                    From bob@example.com Thu Jan  1 00:00:01 2017\n\
+                    From: bob@example.com\n\
+                    Subject: Second\n\
+                    \n\
+                    World\n";
+
+        let messages = split_mbox(mbox);
+        assert_eq!(messages.len(), 2);
+        assert!(messages[0].starts_with("From: alice@example.com\n"));
+        assert!(messages[0].contains("Hello"));
+        assert!(messages[1].starts_with("From: bob@example.com\n"));
+        assert!(messages[1].contains("World"));
+
+        let subject = parse_mail(messages[1].as_bytes()).unwrap().headers[1].get_value().unwrap();
+        assert_eq!(subject, "Second");
+    }
+
+    #[test]
+    fn test_split_mbox_from_in_body() {
+        let mbox = "From alice@example.com Thu Jan  1 00:00:00 2017\n\
+                    From: alice@example.com\n\
+                    Subject: Quoting\n\
+                    \n\
+                    >From the beginning, this was escaped\n\
+                    >>From here on, this was quoted and escaped\n\
+                    and this\n\
+                    From line is not preceded by an empty line\n";
+
+        let messages = split_mbox(mbox);
+        assert_eq!(messages.len(), 1);
+        assert!(messages[0].contains("\nFrom the beginning, this was escaped\n"));
+        assert!(messages[0].contains("\n>From here on, this was quoted and escaped\n"));
+        assert!(messages[0].contains("\nFrom line is not preceded by an empty line\n"));
+    }
+
+    #[test]
+    fn test_parse_references() {
+        let refs = parse_references("<a@example.com> <b@example.com>\r\n <c@example.com>");
+        assert_eq!(refs, vec!["<a@example.com>", "<b@example.com>", "<c@example.com>"]);
+    }
+
+    #[test]
+    fn test_parse_references_without_brackets() {
+        let refs = parse_references("a@example.com  b@example.com");
+        assert_eq!(refs, vec!["a@example.com", "b@example.com"]);
+        assert!(parse_references("").is_empty());
+    }
+
+    #[test]
+    fn test_split_addresses_multiple() {
+        let v = split_addresses("alice@example.com, Bob <bob@example.com>,carol@example.com");
+        assert_eq!(v, vec!["alice@example.com", "Bob <bob@example.com>", "carol@example.com"]);
+    }
+
+    #[test]
+    fn test_split_addresses_comma_in_display_name() {
+        let v = split_addresses("\"Doe, John\" <john@example.com>, \"A \\\"quoted, name\\\"\" <a@b.c>");
+        assert_eq!(v, vec!["\"Doe, John\" <john@example.com>",
+                           "\"A \\\"quoted, name\\\"\" <a@b.c>"]);
+    }
+
+    #[test]
+    fn test_split_addresses_empty() {
+        assert!(split_addresses("").is_empty());
+        assert!(split_addresses("  ").is_empty());
+        assert!(split_addresses(" , ,").is_empty());
+    }
+
+    #[test]
+    fn test_parse_date() {
+        let date = parse_date("Tue, 1 Jul 2003 10:52:37 +0200");
+        assert!(date.is_some());
+        assert_eq!(date.unwrap().to_rfc3339(), "2003-07-01T10:52:37+02:00");
+    }
+
+    #[test]
+    fn test_parse_date_with_comment() {
+        let date = parse_date("Fri, 21 Nov 1997 09:55:06 -0600 (CST)");
+        assert!(date.is_some());
+        assert_eq!(date.unwrap().to_rfc3339(), "1997-11-21T09:55:06-06:00");
+    }
+
+    #[test]
+    fn test_parse_date_named_zone() {
+        let gmt = parse_date("Thu, 13 Feb 1969 23:32:54 GMT");
+        assert!(gmt.is_some());
+        assert_eq!(gmt.unwrap().to_rfc3339(), "1969-02-13T23:32:54+00:00");
+
+        let est = parse_date("Thu, 13 Feb 1969 23:32:54 EST");
+        assert!(est.is_some());
+        assert_eq!(est.unwrap().to_rfc3339(), "1969-02-13T23:32:54-05:00");
+    }
+
+    #[test]
+    fn test_parse_date_garbage() {
+        assert!(parse_date("").is_none());
+        assert!(parse_date("yesterday, around noon").is_none());
+        assert!(parse_date("Thu, 45 Foo 1969 99:32:54 +0000").is_none());
+    }
+
+    fn subject_of(mail: &str) -> Option<String> {
+        let parsed = parse_mail(mail.as_bytes()).unwrap();
+        find_header_value(&parsed.headers, "Subject").unwrap()
+    }
+
+    #[test]
+    fn test_all_headers_in_order_with_duplicates() {
+        let mail = "Received: from a.example.com by b.example.com\r\n\
+                    Received: from b.example.com by c.example.com\r\n\
+                    From: alice@example.com\r\n\
+                    Subject: =?UTF-8?Q?Gr=C3=BC=C3=9Fe?=\r\n\
+                    \r\n\
+                    Body\r\n";
+
+        let parsed  = parse_mail(mail.as_bytes()).unwrap();
+        let headers = all_header_values(&parsed.headers).unwrap();
+        let s       = |k: &str, v: &str| (String::from(k), String::from(v));
+
+        assert_eq!(headers, vec![
+            s("Received", "from a.example.com by b.example.com"),
+            s("Received", "from b.example.com by c.example.com"),
+            s("From", "alice@example.com"),
+            s("Subject", "Grüße"),
+        ]);
+    }
+
+    #[test]
+    fn test_subject_base64_utf8() {
+        let mail = "From: alice@example.com\r\n\
+                    Subject: =?UTF-8?B?R3LDvMOfZSBhdXMgTcO8bmNoZW4=?=\r\n\
+                    \r\n\
+                    Body\r\n";
+
+        assert_eq!(subject_of(mail), Some(String::from("Grüße aus München")));
+    }
+
+    #[test]
+    fn test_subject_q_utf8() {
+        let mail = "From: alice@example.com\r\n\
+                    Subject: =?UTF-8?Q?Gr=C3=BC=C3=9Fe_aus_M=C3=BCnchen?=\r\n\
+                    \r\n\
+                    Body\r\n";
+
+        assert_eq!(subject_of(mail), Some(String::from("Grüße aus München")));
+    }
+
+    #[test]
+    fn test_subject_base64_latin1() {
+        let mail = "From: alice@example.com\r\n\
+                    Subject: =?ISO-8859-1?B?R3L832U=?=\r\n\
+                    \r\n\
+                    Body\r\n";
+
+        assert_eq!(subject_of(mail), Some(String::from("Grüße")));
+    }
+
+    #[test]
+    fn test_subject_q_latin1() {
+        let mail = "From: alice@example.com\r\n\
+                    Subject: =?ISO-8859-1?Q?Gr=FC=DFe?=\r\n\
+                    \r\n\
+                    Body\r\n";
+
+        assert_eq!(subject_of(mail), Some(String::from("Grüße")));
+    }
+
+    #[test]
+    fn test_subject_mixed_and_adjacent_encoded_words() {
+        let mail = "From: alice@example.com\r\n\
+                    Subject: Re: =?UTF-8?Q?Gr=C3=BC?= =?UTF-8?Q?=C3=9Fe?= from Munich\r\n\
+                    \r\n\
+                    Body\r\n";
+
+        assert_eq!(subject_of(mail), Some(String::from("Re: Grüße from Munich")));
+    }
+
+    #[test]
+    fn test_raw_header_value_is_not_decoded() {
+        let mail = "From: alice@example.com\r\n\
+                    subject: =?UTF-8?Q?Gr=C3=BC?=\r\n =?UTF-8?Q?=C3=9Fe?=\r\n\
+                    To: bob@example.com\r\n\
+                    \r\n\
+                    Subject: not a header\r\n";
+
+        assert_eq!(find_raw_header_value(mail, "Subject"),
+                   Some(String::from("=?UTF-8?Q?Gr=C3=BC?= =?UTF-8?Q?=C3=9Fe?=")));
+        assert_eq!(find_raw_header_value(mail, "Cc"), None);
+    }
+
+    #[test]
+    fn test_body_plain() {
+        let mail = "From: alice@example.com\r\n\
+                    Subject: Hello\r\n\
+                    \r\n\
+                    Hello World\r\n";
+
+        let parsed = parse_mail(mail.as_bytes()).unwrap();
+        assert_eq!(find_body(&parsed).unwrap().trim(), "Hello World");
+    }
+
+    #[test]
+    fn test_body_quoted_printable() {
+        let mail = "From: alice@example.com\r\n\
+                    Subject: Hello\r\n\
+                    Content-Type: text/plain; charset=utf-8\r\n\
+                    Content-Transfer-Encoding: quoted-printable\r\n\
+                    \r\n\
+                    Gr=C3=BC=C3=9Fe aus M=C3=BCnchen\r\n";
+
+        let parsed = parse_mail(mail.as_bytes()).unwrap();
+        assert_eq!(find_body(&parsed).unwrap().trim(), "Grüße aus München");
+    }
+
+    #[test]
+    fn test_body_multipart_alternative() {
+        let mail = "From: alice@example.com\r\n\
+                    Subject: Hello\r\n\
+                    Content-Type: multipart/alternative; boundary=\"XXX\"\r\n\
+                    \r\n\
+                    --XXX\r\n\
+                    Content-Type: text/html; charset=utf-8\r\n\
+                    \r\n\
+                    <p>Hello <b>HTML</b></p>\r\n\
+                    --XXX\r\n\
+                    Content-Type: text/plain; charset=utf-8\r\n\
+                    \r\n\
+                    Hello plain\r\n\
+                    --XXX--\r\n";
+
+        let parsed = parse_mail(mail.as_bytes()).unwrap();
+        assert_eq!(find_body(&parsed).unwrap().trim(), "Hello plain");
+    }
+
+    fn reply_to(mail: &str) -> Vec<String> {
+        let parsed = parse_mail(mail.as_bytes()).unwrap();
+        build_reply(&parsed).unwrap().lines().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_build_reply() {
+        let mail = "From: Alice <alice@example.com>\r\n\
+                    To: bob@example.com\r\n\
+                    Subject: Meeting\r\n\
+                    Message-ID: <2@example.com>\r\n\
+                    \r\n\
+                    Hello Bob,\r\n\
+                    \r\n\
+                    see you on monday.\r\n";
+
+        assert_eq!(reply_to(mail), vec![
+            "To: Alice <alice@example.com>",
+            "Subject: Re: Meeting",
+            "In-Reply-To: <2@example.com>",
+            "References: <2@example.com>",
+            "",
+            "> Hello Bob,",
+            ">",
+            "> see you on monday.",
+        ]);
+    }
+
+    #[test]
+    fn test_build_reply_does_not_double_prefix() {
+        for subject in &["Re: Meeting", "RE: Meeting", "re:Meeting"] {
+            let mail = format!("From: alice@example.com\r\n\
+                                Subject: {}\r\n\
+                                \r\n\
+                                Hello\r\n", subject);
+
+            let reply = reply_to(&mail);
+            assert_eq!(reply[1], format!("Subject: {}", subject));
+            assert!(!reply.iter().any(|l| l.starts_with("In-Reply-To") || l.starts_with("References")));
+        }
+    }
+
+    #[test]
+    fn test_build_reply_chains_references() {
+        let mail = "From: alice@example.com\r\n\
+                    Subject: Re: Meeting\r\n\
+                    Message-ID: <3@example.com>\r\n\
+                    In-Reply-To: <2@example.com>\r\n\
+                    References: <1@example.com>\r\n <2@example.com>\r\n\
+                    \r\n\
+                    Hello\r\n";
+
+        let reply = reply_to(mail);
+        assert!(reply.contains(&String::from("In-Reply-To: <3@example.com>")));
+        assert!(reply.contains(&String::from("References: <1@example.com> <2@example.com> <3@example.com>")));
+
+        // Without References, the In-Reply-To header is used
+        let mail = "From: alice@example.com\r\n\
+                    Subject: Re: Meeting\r\n\
+                    Message-ID: <3@example.com>\r\n\
+                    In-Reply-To: <2@example.com>\r\n\
+                    \r\n\
+                    Hello\r\n";
+
+        let reply = reply_to(mail);
+        assert!(reply.contains(&String::from("References: <2@example.com> <3@example.com>")));
+    }
+
+    #[test]
+    fn test_body_html_only_is_stripped() {
+        let mail = "From: alice@example.com\r\n\
+                    Subject: Hello\r\n\
+                    Content-Type: multipart/alternative; boundary=\"XXX\"\r\n\
+                    \r\n\
+                    --XXX\r\n\
+                    Content-Type: text/html; charset=utf-8\r\n\
+                    \r\n\
+                    <p>Hello <b>HTML</b></p>\r\n\
+                    --XXX--\r\n";
+
+        let parsed = parse_mail(mail.as_bytes()).unwrap();
+        assert_eq!(find_body(&parsed).unwrap().trim(), "Hello HTML");
+    }
+
 }
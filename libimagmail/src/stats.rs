@@ -0,0 +1,215 @@
+//! Module for aggregating statistics over all mails in the store
+//!
+//! `MailStats::collect()` walks all references in the store, opens them as mails and counts them
+//! by sender and by year. Mails which cannot be read or parsed are counted as `parse_failures`
+//! instead of aborting the whole run.
+//!
+
+use std::collections::HashMap;
+
+use chrono::Datelike;
+
+use libimagstore::store::Store;
+use libimagref::reference::Ref;
+
+use mail::Mail;
+use result::Result;
+use error::{MapErrInto, MailErrorKind as MEK};
+
+/// Aggregated numbers over the mails in a store
+#[derive(Debug, Default)]
+pub struct MailStats {
+    total: usize,
+    per_sender: HashMap<String, usize>,
+    per_year: HashMap<i32, usize>,
+    without_message_id: usize,
+    parse_failures: usize,
+}
+
+impl MailStats {
+
+    /// Collect the statistics over all mails in `store`
+    ///
+    /// Only fetching the list of references from the store can fail. Mails without `From` header
+    /// are not counted per sender, mails without (parseable) `Date` header are not counted per
+    /// year.
+    pub fn collect(store: &Store) -> Result<MailStats> {
+        let iter      = try!(store.retrieve_for_module("ref").map_err_into(MEK::FetchError));
+        let mut stats = MailStats::default();
+
+        for id in iter {
+            let res = Ref::get(store, id)
+                .map_err_into(MEK::RefHandlingError)
+                .and_then(Mail::from_ref_headers_only)
+                .and_then(|mail| stats.add(&mail));
+
+            if let Err(e) = res {
+                debug!("Counting mail as parse failure: {:?}", e);
+                stats.parse_failures += 1;
+            }
+        }
+
+        Ok(stats)
+    }
+
+    /// Add a single mail to the statistics
+    ///
+    /// All headers are read before anything is counted, so a mail which fails to parse does not
+    /// leave the statistics half-updated.
+    fn add(&mut self, mail: &Mail) -> Result<()> {
+        let from   = try!(mail.get_from());
+        let date   = try!(mail.get_date());
+        let msg_id = try!(mail.get_message_id());
+
+        self.total += 1;
+
+        if let Some(from) = from {
+            *self.per_sender.entry(from).or_insert(0) += 1;
+        }
+
+        if let Some(date) = date {
+            *self.per_year.entry(date.year()).or_insert(0) += 1;
+        }
+
+        if msg_id.is_none() {
+            self.without_message_id += 1;
+        }
+
+        Ok(())
+    }
+
+    /// Number of mails which could be read and parsed
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    pub fn per_sender(&self) -> &HashMap<String, usize> {
+        &self.per_sender
+    }
+
+    pub fn per_year(&self) -> &HashMap<i32, usize> {
+        &self.per_year
+    }
+
+    pub fn without_message_id(&self) -> usize {
+        self.without_message_id
+    }
+
+    pub fn parse_failures(&self) -> usize {
+        self.parse_failures
+    }
+
+    /// The `n` senders with the most mails, most mails first
+    ///
+    /// Senders with the same number of mails are sorted by name, so the result is stable.
+    pub fn top_senders(&self, n: usize) -> Vec<(&String, usize)> {
+        let mut senders = self.per_sender
+            .iter()
+            .map(|(sender, count)| (sender, *count))
+            .collect::<Vec<_>>();
+
+        senders.sort_by(|a, b| (b.1, a.0).cmp(&(a.1, b.0)));
+        senders.truncate(n);
+        senders
+    }
+
+    /// The number of mails per year, sorted by year
+    pub fn years(&self) -> Vec<(i32, usize)> {
+        let mut years = self.per_year
+            .iter()
+            .map(|(year, count)| (*year, *count))
+            .collect::<Vec<_>>();
+
+        years.sort();
+        years
+    }
+
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs::File;
+    use std::io::Write;
+    use std::path::PathBuf;
+
+    use tempdir::TempDir;
+
+    use libimagstore::store::Store;
+
+    use mail::Mail;
+    use super::MailStats;
+
+    fn write_mail(dir: &TempDir, name: &str, from: &str, date: &str, msgid: Option<&str>) -> PathBuf {
+        let path     = dir.path().join(name);
+        let mut file = File::create(&path).unwrap();
+        write!(file, "From: {}\r\nTo: bob@example.com\r\nSubject: {}\r\nDate: {}\r\n", from, name, date)
+            .unwrap();
+        if let Some(msgid) = msgid {
+            write!(file, "Message-ID: {}\r\n", msgid).unwrap();
+        }
+        write!(file, "\r\nHello\r\n").unwrap();
+        path
+    }
+
+    fn get_store() -> Store {
+        Store::new_in_memory(PathBuf::from("/"), None).unwrap()
+    }
+
+    #[test]
+    fn test_collect() {
+        let tmp   = TempDir::new("imag-mail-test").unwrap();
+        let store = get_store();
+
+        let mails = vec![
+            write_mail(&tmp, "a.eml", "alice@example.com", "Mon, 2 Jan 2017 10:00:00 +0100", Some("<a@example.com>")),
+            write_mail(&tmp, "b.eml", "alice@example.com", "Tue, 3 Jan 2017 10:00:00 +0100", Some("<b@example.com>")),
+            write_mail(&tmp, "c.eml", "carol@example.com", "Thu, 3 Mar 2016 10:00:00 +0100", None),
+            write_mail(&tmp, "d.eml", "bob@example.com", "Fri, 4 Mar 2016 10:00:00 +0100", Some("<d@example.com>")),
+            write_mail(&tmp, "e.eml", "alice@example.com", "Sat, 5 Mar 2016 10:00:00 +0100", None),
+        ];
+        for path in mails {
+            assert!(Mail::import_from_path(&store, path).is_ok());
+        }
+
+        let stats = MailStats::collect(&store).unwrap();
+        assert_eq!(stats.total(), 5);
+        assert_eq!(stats.without_message_id(), 2);
+        assert_eq!(stats.parse_failures(), 0);
+        assert_eq!(stats.years(), vec![(2016, 3), (2017, 2)]);
+
+        let alice = String::from("alice@example.com");
+        let bob   = String::from("bob@example.com");
+        assert_eq!(stats.top_senders(2), vec![(&alice, 3), (&bob, 1)]);
+        assert_eq!(stats.top_senders(10).len(), 3);
+    }
+
+    #[test]
+    fn test_unreadable_mail_is_counted_as_parse_failure() {
+        let tmp   = TempDir::new("imag-mail-test").unwrap();
+        let store = get_store();
+
+        let kept = write_mail(&tmp, "kept.eml", "alice@example.com", "Mon, 2 Jan 2017 10:00:00 +0100", Some("<k@example.com>"));
+        let gone = write_mail(&tmp, "gone.eml", "bob@example.com", "Mon, 2 Jan 2017 10:00:00 +0100", Some("<g@example.com>"));
+        assert!(Mail::import_from_path(&store, kept).is_ok());
+        assert!(Mail::import_from_path(&store, &gone).is_ok());
+
+        // The entry only references the mail file, so removing the file breaks the mail
+        ::std::fs::remove_file(gone).unwrap();
+
+        let stats = MailStats::collect(&store).unwrap();
+        assert_eq!(stats.total(), 1);
+        assert_eq!(stats.parse_failures(), 1);
+        assert_eq!(stats.per_sender().len(), 1);
+    }
+
+    #[test]
+    fn test_empty_store() {
+        let stats = MailStats::collect(&get_store()).unwrap();
+
+        assert_eq!(stats.total(), 0);
+        assert_eq!(stats.parse_failures(), 0);
+        assert!(stats.top_senders(5).is_empty());
+        assert!(stats.years().is_empty());
+    }
+
+}
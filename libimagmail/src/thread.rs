@@ -0,0 +1,441 @@
+//! Module for building mail threads
+//!
+//! The `ThreadBuilder` collects message-ids and the message-ids of the mails they reply to and
+//! builds a forest of `ThreadNode`s from that, where each root is a mail without a (known) parent.
+//!
+//! The parent of a mail is taken from its `In-Reply-To` header or, if that is missing, from the
+//! last entry of its `References` header, as MUAs do it.
+//!
+//! Optionally, mails without `Message-ID` are linked by their subject, see
+//! `ThreadBuilder::link_by_subject()`.
+//!
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+use libimagerror::into::IntoError;
+
+use mail::Mail;
+use mail::MessageId;
+use result::Result;
+use error::MailErrorKind as MEK;
+
+/// A mail in a thread, identified by its message-id, with all replies to it
+#[derive(Debug, PartialEq, Eq)]
+pub struct ThreadNode {
+    message_id: String,
+    replies: Vec<ThreadNode>,
+}
+
+impl ThreadNode {
+
+    pub fn message_id(&self) -> &String {
+        &self.message_id
+    }
+
+    pub fn replies(&self) -> &Vec<ThreadNode> {
+        &self.replies
+    }
+
+    /// Find the node for `message_id` in this node or in its replies, recursively
+    pub fn find(&self, message_id: &str) -> Option<&ThreadNode> {
+        if self.message_id == message_id {
+            return Some(self);
+        }
+        self.replies.iter().filter_map(|r| r.find(message_id)).next()
+    }
+
+}
+
+/// Prefix of the message-ids which are made up for mails without `Message-ID`
+const NOMSGID_PREFIX : &'static str = "<no-message-id-";
+
+#[derive(Debug)]
+pub struct ThreadBuilder {
+    // message-id -> message-id of the parent
+    hm: HashMap<String, Option<String>>,
+    ignore_nomsgid: bool,
+
+    link_by_subject: bool,
+    // normalized subject -> message-id of the first mail with that subject
+    subjects: HashMap<String, String>,
+    nomsgid_count: usize,
+}
+
+impl ThreadBuilder {
+
+    pub fn new() -> ThreadBuilder {
+        ThreadBuilder {
+            hm: HashMap::new(),
+            ignore_nomsgid: false,
+            link_by_subject: false,
+            subjects: HashMap::new(),
+            nomsgid_count: 0,
+        }
+    }
+
+    /// Skip mails without a `Message-ID` (with a warning) instead of failing on them
+    pub fn ignore_nomsgid(mut self, b: bool) -> ThreadBuilder {
+        self.ignore_nomsgid = b;
+        self
+    }
+
+    /// Link mails without a `Message-ID` to the first mail with the same subject
+    ///
+    /// The subjects are compared case-insensitively, without `Re:`/`Fwd:` prefixes. Mails without
+    /// `Message-ID` get a made-up message-id of the form `<no-message-id-N@imag>`. Mails whose
+    /// subject is empty are not linked, they are treated as if this was not set.
+    ///
+    /// This is a best-effort fallback for broken archives: a parent from the `In-Reply-To` or
+    /// `References` header of the mail is always preferred.
+    pub fn link_by_subject(mut self, b: bool) -> ThreadBuilder {
+        self.link_by_subject = b;
+        self
+    }
+
+    /// Add a message-id and the message-id it replies to, if any
+    pub fn add(&mut self, message_id: String, parent: Option<String>) {
+        self.hm.insert(message_id, parent);
+    }
+
+    /// Add a mail, using its `Message-ID` and `In-Reply-To` (or `References`) headers
+    ///
+    /// Fails with `MailErrorKind::NoMessageIdFoundError` if the mail has no `Message-ID`, unless
+    /// `ThreadBuilder::ignore_nomsgid()` was set.
+    pub fn add_mail(&mut self, mail: &Mail) -> Result<()> {
+        let id          = try!(mail.get_message_id());
+        let in_reply_to = try!(mail.get_in_reply_to());
+        let references  = try!(mail.get_references());
+        let parent      = select_parent(in_reply_to, references);
+
+        if self.link_by_subject {
+            let subject = try!(mail.get_subject());
+            self.add_by_subject(id, parent, subject)
+        } else {
+            self.add_maybe(id, parent)
+        }
+    }
+
+    fn add_by_subject(&mut self, message_id: Option<String>, parent: Option<String>,
+                      subject: Option<String>) -> Result<()>
+    {
+        let key = match subject.map(|s| normalize_subject(&s)) {
+            Some(ref k) if !k.is_empty() => k.clone(),
+            _ => return self.add_maybe(message_id, parent),
+        };
+
+        let id = match message_id {
+            Some(id) => id,
+            None     => {
+                self.nomsgid_count += 1;
+                let id     = format!("{}{}@imag>", NOMSGID_PREFIX, self.nomsgid_count);
+                let parent = parent.or_else(|| self.subjects.get(&key).cloned());
+                debug!("Linking mail without Message-ID as {} to {:?}", id, parent);
+
+                self.subjects.entry(key).or_insert_with(|| id.clone());
+                self.add(id, parent);
+                return Ok(());
+            },
+        };
+
+        self.subjects.entry(key).or_insert_with(|| id.clone());
+        self.add(id, parent);
+        Ok(())
+    }
+
+    fn add_maybe(&mut self, message_id: Option<String>, parent: Option<String>) -> Result<()> {
+        match message_id {
+            Some(id) => {
+                self.add(id, parent);
+                Ok(())
+            },
+            None if self.ignore_nomsgid => {
+                warn!("Ignoring mail without Message-ID");
+                Ok(())
+            },
+            None => Err(MEK::NoMessageIdFoundError.into_error()),
+        }
+    }
+
+    /// Build the thread forest
+    ///
+    /// Mails which have no parent or whose parent is unknown are the roots. Mails which are only
+    /// reachable through a cycle of replies are made roots as well (the cycle is broken at the
+    /// smallest message-id), so every added mail appears exactly once in the forest.
+    pub fn threads(&self) -> Vec<ThreadNode> {
+        let mut children : HashMap<&String, Vec<&String>> = HashMap::new();
+        let mut roots    = vec![];
+
+        for (id, parent) in self.hm.iter() {
+            match *parent {
+                Some(ref p) if self.hm.contains_key(p) && p != id => {
+                    children.entry(p).or_insert_with(|| vec![]).push(id)
+                },
+                _ => roots.push(id),
+            }
+        }
+
+        for v in children.values_mut() {
+            v.sort();
+        }
+        roots.sort();
+
+        let mut visited = HashSet::new();
+        let mut forest : Vec<ThreadNode> = roots
+            .into_iter()
+            .map(|root| build_node(root, &children, &mut visited))
+            .collect();
+
+        // Everything not visited by now is part of (or hangs off) a cycle
+        let mut rest : Vec<&String> = self.hm.keys().filter(|id| !visited.contains(id)).collect();
+        rest.sort();
+        for id in rest {
+            if !visited.contains(id) {
+                forest.push(build_node(id, &children, &mut visited));
+            }
+        }
+
+        forest
+    }
+
+}
+
+fn select_parent(in_reply_to: Option<MessageId>, mut references: Vec<MessageId>) -> Option<MessageId> {
+    in_reply_to.or_else(|| references.pop())
+}
+
+/// Normalize a subject for comparison: lowercase, without `Re:`/`Fwd:`/`Fw:` prefixes and with
+/// whitespace collapsed
+fn normalize_subject(subject: &str) -> String {
+    let mut s = subject.trim().to_lowercase();
+
+    loop {
+        let stripped = ["re:", "fwd:", "fw:"]
+            .iter()
+            .filter(|p| s.starts_with(*p))
+            .map(|p| String::from(s[p.len()..].trim_left()))
+            .next();
+
+        match stripped {
+            Some(rest) => s = rest,
+            None       => break,
+        }
+    }
+
+    s.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn build_node<'a>(id: &'a String,
+                  children: &HashMap<&'a String, Vec<&'a String>>,
+                  visited: &mut HashSet<&'a String>)
+    -> ThreadNode
+{
+    visited.insert(id);
+
+    let mut replies = vec![];
+    if let Some(ch) = children.get(id) {
+        for c in ch {
+            // a reply might have been visited already if the replies form a cycle
+            if !visited.contains(c) {
+                replies.push(build_node(c, children, visited));
+            }
+        }
+    }
+
+    ThreadNode {
+        message_id: id.clone(),
+        replies: replies,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ThreadBuilder;
+    use super::ThreadNode;
+    use super::select_parent;
+    use super::normalize_subject;
+    use error::MailErrorKind;
+
+    fn s(s: &str) -> String {
+        String::from(s)
+    }
+
+    fn count(nodes: &[ThreadNode]) -> usize {
+        nodes.iter().map(|n| 1 + count(n.replies())).sum()
+    }
+
+    fn batch() -> Vec<(Option<String>, Option<String>)> {
+        vec![
+            (Some(s("root")), None),
+            (None, Some(s("root"))),
+            (Some(s("reply")), Some(s("root"))),
+        ]
+    }
+
+    #[test]
+    fn test_nomsgid_is_skipped_if_ignored() {
+        let mut b = ThreadBuilder::new().ignore_nomsgid(true);
+        for (id, parent) in batch() {
+            assert!(b.add_maybe(id, parent).is_ok());
+        }
+
+        let threads = b.threads();
+        assert_eq!(count(&threads), 2);
+        assert_eq!(threads[0].replies()[0].message_id(), "reply");
+    }
+
+    #[test]
+    fn test_nomsgid_errors_if_not_ignored() {
+        let mut b = ThreadBuilder::new();
+        let res   = batch()
+            .into_iter()
+            .map(|(id, parent)| b.add_maybe(id, parent))
+            .collect::<Result<Vec<()>, _>>();
+
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().err_type(), MailErrorKind::NoMessageIdFoundError);
+    }
+
+    fn subject_batch() -> Vec<(Option<String>, Option<String>, Option<String>)> {
+        vec![
+            (None, None, Some(s("Meeting on Monday"))),
+            (None, None, Some(s("Re: Meeting on Monday"))),
+            (None, None, Some(s("RE: Fwd:  meeting on monday"))),
+            (None, None, Some(s("Lunch"))),
+        ]
+    }
+
+    #[test]
+    fn test_normalize_subject() {
+        assert_eq!(normalize_subject("Re: Fwd: RE:Meeting  on\tMonday "), "meeting on monday");
+        assert_eq!(normalize_subject("Fw: Re:"), "");
+        assert_eq!(normalize_subject("Regarding the meeting"), "regarding the meeting");
+    }
+
+    #[test]
+    fn test_link_by_subject() {
+        let mut b = ThreadBuilder::new().link_by_subject(true);
+        for (id, parent, subject) in subject_batch() {
+            assert!(b.add_by_subject(id, parent, subject).is_ok());
+        }
+
+        let threads = b.threads();
+        assert_eq!(threads.len(), 2);
+        assert_eq!(count(&threads), 4);
+
+        let meeting = threads.iter().find(|t| t.replies().len() == 2).unwrap();
+        assert_eq!(meeting.message_id(), "<no-message-id-1@imag>");
+        assert!(meeting.find("<no-message-id-2@imag>").is_some());
+        assert!(meeting.find("<no-message-id-3@imag>").is_some());
+        assert!(meeting.find("<no-message-id-4@imag>").is_none());
+    }
+
+    #[test]
+    fn test_no_link_by_subject_without_flag() {
+        let mut b = ThreadBuilder::new().ignore_nomsgid(true);
+        for (id, parent, _) in subject_batch() {
+            assert!(b.add_maybe(id, parent).is_ok());
+        }
+        assert!(b.threads().is_empty());
+
+        let mut b = ThreadBuilder::new();
+        for (id, parent, _) in subject_batch() {
+            assert!(b.add_maybe(id, parent).is_err());
+        }
+    }
+
+    #[test]
+    fn test_link_by_subject_to_mail_with_msgid() {
+        let mut b = ThreadBuilder::new().link_by_subject(true);
+        assert!(b.add_by_subject(Some(s("<root>")), None, Some(s("Meeting"))).is_ok());
+        assert!(b.add_by_subject(None, None, Some(s("Re: Meeting"))).is_ok());
+        assert!(b.add_by_subject(None, Some(s("<root>")), Some(s("Something else"))).is_ok());
+
+        let threads = b.threads();
+        assert_eq!(threads.len(), 1);
+        assert_eq!(threads[0].message_id(), "<root>");
+        assert_eq!(threads[0].replies().len(), 2);
+    }
+
+    #[test]
+    fn test_link_by_subject_ignores_empty_subjects() {
+        let mut b = ThreadBuilder::new().link_by_subject(true).ignore_nomsgid(true);
+        assert!(b.add_by_subject(None, None, Some(s("Re:"))).is_ok());
+        assert!(b.add_by_subject(None, None, Some(s("Re:"))).is_ok());
+        assert!(b.add_by_subject(None, None, None).is_ok());
+
+        assert!(b.threads().is_empty());
+    }
+
+    #[test]
+    fn test_parent_from_references() {
+        let mut b = ThreadBuilder::new();
+        b.add(s("<root>"), None);
+        b.add(s("<reply>"), select_parent(None, vec![s("<root>")]));
+        b.add(s("<reply-reply>"), select_parent(None, vec![s("<root>"), s("<reply>")]));
+
+        let threads = b.threads();
+        assert_eq!(threads.len(), 1);
+        assert_eq!(threads[0].replies()[0].message_id(), "<reply>");
+        assert_eq!(threads[0].replies()[0].replies()[0].message_id(), "<reply-reply>");
+    }
+
+    #[test]
+    fn test_in_reply_to_wins_over_references() {
+        let parent = select_parent(Some(s("<a>")), vec![s("<b>"), s("<c>")]);
+        assert_eq!(parent, Some(s("<a>")));
+    }
+
+    #[test]
+    fn test_thread_shape() {
+        let mut b = ThreadBuilder::new();
+        b.add(s("root"), None);
+        b.add(s("reply-1"), Some(s("root")));
+        b.add(s("reply-2"), Some(s("root")));
+        b.add(s("reply-1-1"), Some(s("reply-1")));
+
+        let threads = b.threads();
+        assert_eq!(threads.len(), 1);
+
+        let root = &threads[0];
+        assert_eq!(root.message_id(), "root");
+        assert_eq!(root.replies().len(), 2);
+        assert_eq!(root.replies()[0].message_id(), "reply-1");
+        assert_eq!(root.replies()[1].message_id(), "reply-2");
+        assert_eq!(root.replies()[0].replies().len(), 1);
+        assert_eq!(root.replies()[0].replies()[0].message_id(), "reply-1-1");
+        assert!(root.replies()[1].replies().is_empty());
+
+        assert!(root.find("reply-1-1").is_some());
+        assert!(root.find("unknown").is_none());
+    }
+
+    #[test]
+    fn test_unknown_parent_is_root() {
+        let mut b = ThreadBuilder::new();
+        b.add(s("a"), Some(s("not-imported")));
+        b.add(s("b"), None);
+
+        let threads = b.threads();
+        assert_eq!(threads.len(), 2);
+        assert_eq!(threads[0].message_id(), "a");
+        assert_eq!(threads[1].message_id(), "b");
+    }
+
+    #[test]
+    fn test_cycles_terminate() {
+        let mut b = ThreadBuilder::new();
+        b.add(s("a"), Some(s("c")));
+        b.add(s("b"), Some(s("a")));
+        b.add(s("c"), Some(s("b")));
+        b.add(s("d"), Some(s("d")));
+        b.add(s("e"), Some(s("b")));
+
+        let threads = b.threads();
+        assert_eq!(count(&threads), 5);
+        assert_eq!(threads.len(), 2);
+        assert_eq!(threads[0].message_id(), "d");
+        assert_eq!(threads[1].message_id(), "a");
+    }
+
+}
@@ -44,8 +44,10 @@ generate_error_module!(
         RefTargetCannotBeHashed     => "Ref Target cannot be hashed (is it a directory?)",
         RefTargetFileCannotBeOpened => "Ref Target File cannot be open()ed",
         RefTargetCannotReadPermissions => "Ref Target: Cannot read permissions",
+        RefTargetHashMismatch       => "Ref Target content hash does not match the stored hash",
 
-        RefHashingError => "Error while hashing"
+        RefHashingError => "Error while hashing",
+        UnknownHashAlgo => "Unknown hash algorithm"
     );
 );
 
@@ -27,6 +27,7 @@ use result::Result;
 pub struct RefFlags {
     content_hashing:       bool,
     permission_tracking:   bool,
+    relative_paths:        bool,
 }
 
 impl RefFlags {
@@ -49,9 +50,17 @@ impl RefFlags {
                 })
         }
 
+        // optional, as older Refs do not have it
+        let relative_paths = match get_field(v, "ref.flags.relative_paths") {
+            Ok(b) => b,
+            Err(ref e) if e.err_type() == REK::HeaderFieldMissingError => false,
+            Err(e) => return Err(e),
+        };
+
         Ok(RefFlags {
             content_hashing:     try!(get_field(v, "ref.flags.content_hashing")),
             permission_tracking: try!(get_field(v, "ref.flags.permission_tracking")),
+            relative_paths:      relative_paths,
         })
     }
 
@@ -70,6 +79,15 @@ impl RefFlags {
         self
     }
 
+    /// Store the path of the referenced file relative to the store path
+    ///
+    /// This way, the store can be moved together with the referenced files, for example to
+    /// another machine, without breaking the Ref.
+    pub fn with_relative_paths(mut self, b: bool) -> RefFlags {
+        self.relative_paths = b;
+        self
+    }
+
 
     pub fn get_content_hashing(&self) -> bool {
         self.content_hashing
@@ -79,6 +97,10 @@ impl RefFlags {
         self.permission_tracking
     }
 
+    pub fn get_relative_paths(&self) -> bool {
+        self.relative_paths
+    }
+
 }
 
 impl Into<Value> for RefFlags {
@@ -90,6 +112,7 @@ impl Into<Value> for RefFlags {
         let mut btm = BTreeMap::new();
         btm.insert(String::from("content_hashing"),     Value::Boolean(self.content_hashing));
         btm.insert(String::from("permission_tracking"), Value::Boolean(self.permission_tracking));
+        btm.insert(String::from("relative_paths"),      Value::Boolean(self.relative_paths));
         return Value::Table(btm)
     }
 
@@ -101,6 +124,7 @@ impl Default for RefFlags {
         RefFlags {
             content_hashing: false,
             permission_tracking: false,
+            relative_paths: false,
         }
     }
 }
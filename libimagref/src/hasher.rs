@@ -18,12 +18,15 @@
 //
 
 use std::path::PathBuf;
+use std::io::ErrorKind;
 use std::io::Read;
 
 use error::RefErrorKind as REK;
 use error::MapErrInto;
 
 use crypto::sha1::Sha1;
+use crypto::sha2::Sha256;
+use crypto::blake2b::Blake2b;
 use crypto::digest::Digest;
 
 use result::Result;
@@ -36,6 +39,12 @@ pub trait Hasher {
     fn hash_name(&self) -> &'static str;
     fn create_hash<R: Read>(&mut self, pb: &PathBuf, contents: &mut R) -> Result<String>;
 
+    /// Create the hash of everything `r` yields, with the same result as `Hasher::create_hash()`
+    ///
+    /// Hashers should read `r` in chunks here, so big files do not have to be loaded into memory
+    /// at once. This is used wherever only the hash of a file is needed, like when creating a Ref.
+    fn hash_reader<R: Read>(&self, r: R) -> Result<String>;
+
 }
 
 pub struct DefaultHasher {
@@ -63,5 +72,154 @@ impl Hasher for DefaultHasher {
         Ok(self.hasher.result_str())
     }
 
+    /// In contrast to `DefaultHasher::create_hash()`, the content does not have to be UTF-8
+    fn hash_reader<R: Read>(&self, r: R) -> Result<String> {
+        digest_reader(Sha1::new(), r)
+    }
+
+}
+
+/// The standard hash algorithms for content hashes
+///
+/// The algorithm a Ref was created with is stored in its header as `ref.hash_algo`, so the hash
+/// can be recomputed with the same algorithm later. Refs without this header field are hashed
+/// with `HashAlgo::Sha1`, which is what the `DefaultHasher` does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashAlgo {
+    Sha1,
+    Sha256,
+    Blake2,
+}
+
+impl HashAlgo {
+
+    /// The name of the algorithm, as stored in the header
+    pub fn name(&self) -> &'static str {
+        match *self {
+            HashAlgo::Sha1   => "sha1",
+            HashAlgo::Sha256 => "sha256",
+            HashAlgo::Blake2 => "blake2",
+        }
+    }
+
+    /// Get the algorithm by its name, see `HashAlgo::name()`
+    pub fn from_name(name: &str) -> Option<HashAlgo> {
+        match name {
+            "sha1"   => Some(HashAlgo::Sha1),
+            "sha256" => Some(HashAlgo::Sha256),
+            "blake2" => Some(HashAlgo::Blake2),
+            _        => None,
+        }
+    }
+
+}
+
+impl Default for HashAlgo {
+
+    fn default() -> HashAlgo {
+        HashAlgo::Sha1
+    }
+
 }
 
+/// Hasher which hashes the complete content of a file with one of the `HashAlgo`s
+///
+/// With `HashAlgo::Sha1`, this produces the same hashes (under the same name) as the
+/// `DefaultHasher`, but does not require the content to be UTF-8.
+pub struct AlgoHasher {
+    algo: HashAlgo,
+}
+
+impl AlgoHasher {
+
+    pub fn new(algo: HashAlgo) -> AlgoHasher {
+        AlgoHasher { algo: algo }
+    }
+
+    pub fn algo(&self) -> HashAlgo {
+        self.algo
+    }
+
+}
+
+impl Hasher for AlgoHasher {
+
+    fn hash_name(&self) -> &'static str {
+        match self.algo {
+            HashAlgo::Sha1 => "default",
+            other          => other.name(),
+        }
+    }
+
+    fn create_hash<R: Read>(&mut self, _: &PathBuf, c: &mut R) -> Result<String> {
+        self.hash_reader(c)
+    }
+
+    fn hash_reader<R: Read>(&self, r: R) -> Result<String> {
+        match self.algo {
+            HashAlgo::Sha1   => digest_reader(Sha1::new(), r),
+            HashAlgo::Sha256 => digest_reader(Sha256::new(), r),
+            HashAlgo::Blake2 => digest_reader(Blake2b::new(64), r),
+        }
+    }
+
+}
+
+/// Feed everything `r` yields to `d` in chunks and return the hex representation of the hash
+pub fn digest_reader<D: Digest, R: Read>(mut d: D, mut r: R) -> Result<String> {
+    let mut buf = [0; 8192];
+    loop {
+        match r.read(&mut buf) {
+            Ok(0)  => break,
+            Ok(n)  => d.input(&buf[..n]),
+            Err(ref e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e).map_err_into(REK::IOError),
+        }
+    }
+    Ok(d.result_str())
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs::File;
+    use std::io::Read;
+    use std::io::Write;
+
+    use crypto::digest::Digest;
+    use crypto::sha2::Sha256;
+    use tempdir::TempDir;
+
+    use super::AlgoHasher;
+    use super::DefaultHasher;
+    use super::HashAlgo;
+    use super::Hasher;
+
+    #[test]
+    fn test_hash_reader_large_file() {
+        let dir  = TempDir::new("imag-ref-test-files").unwrap();
+        let path = dir.path().join("large");
+        {
+            let mut f = File::create(&path).unwrap();
+            for i in 0..100_000 {
+                assert!(write!(f, "line {} of a large file\n", i).is_ok());
+            }
+        }
+
+        let mut content = vec![];
+        assert!(File::open(&path).unwrap().read_to_end(&mut content).is_ok());
+        assert!(content.len() > 8192 * 100);
+
+        let streamed  = DefaultHasher::new().hash_reader(File::open(&path).unwrap()).unwrap();
+        let in_memory = DefaultHasher::new().create_hash(&path, &mut File::open(&path).unwrap()).unwrap();
+        assert_eq!(streamed, in_memory);
+
+        let streamed  = AlgoHasher::new(HashAlgo::Sha256).hash_reader(File::open(&path).unwrap()).unwrap();
+        let in_memory = {
+            let mut d = Sha256::new();
+            d.input(&content);
+            d.result_str()
+        };
+        assert_eq!(streamed, in_memory);
+    }
+
+}
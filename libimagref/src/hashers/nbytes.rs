@@ -67,5 +67,19 @@ impl Hasher for NBytesHasher {
         Ok(self.hasher.result_str())
     }
 
+    /// Only the first `n` bytes are read, so this does not load more into memory either
+    fn hash_reader<R: Read>(&self, r: R) -> Result<String> {
+        let mut v = vec![];
+        try!(r.take(self.n as u64).read_to_end(&mut v).map_err_into(REK::IOError));
+        let s = try!(String::from_utf8(v)
+                     .map_err(Box::new)
+                     .map_err(|e| REK::UTF8Error.into_error_with_cause(e))
+                     .map_err_into(REK::IOError));
+
+        let mut hasher = Sha1::new();
+        hasher.input_str(&s[..]);
+        Ok(hasher.result_str())
+    }
+
 }
 
@@ -38,6 +38,7 @@ extern crate semver;
 extern crate toml;
 extern crate version;
 extern crate walkdir;
+#[cfg(test)] extern crate tempdir;
 
 #[macro_use] extern crate libimagstore;
 #[macro_use] extern crate libimagerror;
@@ -18,7 +18,6 @@
 //
 
 use std::default::Default;
-use std::io::stdout;
 use std::io::Write;
 
 use libimagentrylist::lister::Lister;
@@ -81,7 +80,10 @@ impl Default for RefLister {
 
 impl Lister for RefLister {
 
-    fn list<'b, I: Iterator<Item = FileLockEntry<'b>>>(&self, entries: I) -> Result<()> {
+    fn list_to<'b, I, W>(&self, entries: I, w: &mut W) -> Result<()>
+        where I: Iterator<Item = FileLockEntry<'b>>,
+              W: Write
+    {
 
         debug!("Called list()");
         let (r, n) = entries.fold((Ok(()), 0), |(accu, i), entry| {
@@ -94,7 +96,7 @@ impl Lister for RefLister {
                               self.check_changed_content,
                               self.check_changed_permiss)
                         .and_then(|s| {
-                            write!(stdout(), "{}\n", s)
+                            write!(w, "{}\n", s)
                                 .map_err(Box::new)
                                 .map_err(|e| LEK::FormatError.into_error_with_cause(e))
                         })
@@ -20,6 +20,7 @@
 //! The Ref object is a helper over the link functionality, so one is able to create references to
 //! files outside of the imag store.
 
+use std::path::Path;
 use std::path::PathBuf;
 use std::ops::Deref;
 use std::ops::DerefMut;
@@ -35,6 +36,7 @@ use libimagstore::storeid::IntoStoreId;
 use libimagstore::store::Store;
 use libimagstore::toml_ext::TomlValueExt;
 use libimagerror::into::IntoError;
+use libimagerror::trace::MapErrTrace;
 
 use toml::Value;
 
@@ -45,6 +47,19 @@ use result::Result;
 use hasher::*;
 use module_path::ModuleEntryPath;
 
+/// The result of checking a Ref, see `Ref::verify()`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RefStatus {
+    /// The referenced file exists and its content hash (if any) matches
+    Valid,
+
+    /// The referenced file does not exist anymore
+    Dangling,
+
+    /// The content of the referenced file does not match the stored content hash
+    Changed,
+}
+
 #[derive(Debug)]
 pub struct Ref<'a>(FileLockEntry<'a>);
 
@@ -66,6 +81,9 @@ impl<'a> Ref<'a> {
 
     /// Get a Ref object from the store by hash.
     ///
+    /// This is the hash of the path of the referenced file (see `Ref::get_path_hash()`), which is
+    /// always SHA1, independent of the `HashAlgo` used for the content hash.
+    ///
     /// Returns None if the hash cannot be found.
     pub fn get_by_hash(store: &'a Store, hash: String) -> Result<Option<Ref<'a>>> {
         ModuleEntryPath::new(hash)
@@ -87,6 +105,73 @@ impl<'a> Ref<'a> {
             .map_err(|e| REK::StoreWriteError.into_error_with_cause(e))
     }
 
+    /// Get all Refs from the store
+    ///
+    /// Entries in the ref module which cannot be read as Ref are skipped, the error is traced.
+    pub fn all_refs(store: &'a Store) -> Result<Vec<Ref<'a>>> {
+        store.retrieve_for_module("ref")
+            .map_err(Box::new)
+            .map_err(|e| REK::StoreReadError.into_error_with_cause(e))
+            .map(|iter| iter.filter_map(|id| Ref::get(store, id).map_err_trace().ok()).collect())
+    }
+
+    /// Check all Refs in the store, see `Ref::verify()`
+    ///
+    /// If a Ref cannot be checked, the error is reported as the status of this Ref and the other
+    /// Refs are checked nevertheless.
+    pub fn verify_all(store: &'a Store) -> Result<Vec<(StoreId, Result<RefStatus>)>> {
+        let refs = try!(Ref::all_refs(store));
+        Ok(refs.iter().map(|r| (r.get_location().clone(), r.verify())).collect())
+    }
+
+    /// Check whether the referenced file exists and, if the Ref has a content hash, whether the
+    /// content hash still matches
+    pub fn verify(&self) -> Result<RefStatus> {
+        if !try!(self.exists_on_fs()) {
+            return Ok(RefStatus::Dangling);
+        }
+
+        match self.get_stored_hash() {
+            Ok(stored) => self.get_current_hash().map(|current| if stored == current {
+                RefStatus::Valid
+            } else {
+                RefStatus::Changed
+            }),
+            Err(ref e) if e.err_type() == REK::HeaderFieldMissingError => Ok(RefStatus::Valid),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Delete the Ref from the store
+    ///
+    /// This removes the store entry only, the referenced file is not touched. Use
+    /// `Ref::delete_with_target()` to remove the referenced file as well.
+    pub fn delete(self, store: &Store) -> Result<()> {
+        let id = self.0.get_location().clone();
+        drop(self); // the entry must not be borrowed while it is deleted
+
+        store.delete(id)
+            .map_err(Box::new)
+            .map_err(|e| REK::StoreWriteError.into_error_with_cause(e))
+    }
+
+    /// Delete the Ref from the store and remove the referenced file from the filesystem
+    ///
+    /// # Warning
+    ///
+    /// This deletes the file outside of the store. The store entry is deleted first, so if
+    /// removing the file fails, the file is left without a Ref.
+    pub fn delete_with_target(self, store: &Store) -> Result<()> {
+        use std::fs::remove_file;
+
+        let target = try!(self.fs_file());
+        try!(self.delete(store));
+
+        remove_file(target)
+            .map_err(Box::new)
+            .map_err(|e| REK::IOError.into_error_with_cause(e))
+    }
+
     fn read_reference(fle: &FileLockEntry<'a>) -> Result<PathBuf> {
         match fle.get_header().read("ref.path") {
             Ok(Some(Value::String(s))) => Ok(PathBuf::from(s)),
@@ -96,7 +181,7 @@ impl<'a> Ref<'a> {
         }
     }
 
-    pub fn create_with_hasher<H: Hasher>(store: &'a Store, pb: PathBuf, flags: RefFlags, mut h: H)
+    pub fn create_with_hasher<H: Hasher>(store: &'a Store, pb: PathBuf, flags: RefFlags, h: H)
         -> Result<Ref<'a>>
     {
         if !pb.exists() {
@@ -113,9 +198,9 @@ impl<'a> Ref<'a> {
 
                 // If we were able to open this file,
                 // we hash the contents of the file and return (file, hash)
-                .and_then(|mut file| {
+                .and_then(|file| {
                     let opt_contenthash = if flags.get_content_hashing() {
-                        Some(try!(h.create_hash(&pb, &mut file)))
+                        Some(try!(h.hash_reader(&file)))
                     } else {
                         None
                     };
@@ -187,12 +272,29 @@ impl<'a> Ref<'a> {
             )
         };
 
+        let (stored_path, relative) = if flags.get_relative_paths() {
+            let base = try!(store.path()
+                .canonicalize()
+                .map_err(Box::new)
+                .map_err(|e| REK::PathCanonicalizationError.into_error_with_cause(e)));
+
+            let rel = relative_path(&base, Path::new(&canonical_path));
+            match rel.to_str().map(String::from) {
+                Some(rel) => (rel, Some(true)),
+                None      => return Err(REK::PathUTF8Error.into_error()),
+            }
+        } else {
+            (canonical_path, None)
+        };
+
         for tpl in [
                 Some((String::from("ref"),              Value::Table(BTreeMap::new()))),
                 Some((String::from("ref.permissions"),  Value::Table(BTreeMap::new()))),
-                Some((String::from("ref.path"),         Value::String(canonical_path))),
+                Some((String::from("ref.path"),         Value::String(stored_path))),
                 Some((String::from("ref.content_hash"), Value::Table(BTreeMap::new()))),
 
+                relative.map(|b| (String::from("ref.relative"), Value::Boolean(b))),
+
                 content_hash.map(|hash| {
                     (format!("ref.content_hash.{}", h.hash_name()), Value::String(hash))
                 }),
@@ -232,6 +334,41 @@ impl<'a> Ref<'a> {
         Ref::create_with_hasher(store, pb, flags, DefaultHasher::new())
     }
 
+    /// Create a Ref object which refers to `pb`, using `algo` for the content hash
+    ///
+    /// The algorithm is stored in the header, so the functions which do not take a custom hasher
+    /// (like `Ref::is_stale()` or `Ref::update_hash()`) use it as well.
+    pub fn create_with_algo(store: &'a Store, pb: PathBuf, flags: RefFlags, algo: HashAlgo)
+        -> Result<Ref<'a>>
+    {
+        let mut r = try!(Ref::create_with_hasher(store, pb, flags, AlgoHasher::new(algo)));
+        try!(r.0
+             .get_header_mut()
+             .set("ref.hash_algo", Value::String(String::from(algo.name())))
+             .map_err(Box::new)
+             .map_err(|e| REK::HeaderFieldWriteError.into_error_with_cause(e)));
+        Ok(r)
+    }
+
+    /// Get the algorithm the content hash of this Ref was created with
+    ///
+    /// Refs without the `ref.hash_algo` header field were created with the `DefaultHasher`, which
+    /// is `HashAlgo::Sha1`.
+    pub fn hash_algo(&self) -> Result<HashAlgo> {
+        match self.0.get_header().read("ref.hash_algo") {
+            Ok(Some(Value::String(s))) => HashAlgo::from_name(&s)
+                .ok_or(REK::UnknownHashAlgo.into_error()),
+            Ok(Some(_)) => Err(REK::HeaderTypeError.into_error()),
+            Ok(None)    => Ok(HashAlgo::default()),
+            Err(e)      => Err(REK::StoreReadError.into_error_with_cause(Box::new(e))),
+        }
+    }
+
+    /// Get the hasher for the algorithm of this Ref, see `Ref::hash_algo()`
+    fn hasher(&self) -> Result<AlgoHasher> {
+        self.hash_algo().map(AlgoHasher::new)
+    }
+
     /// Creates a Hash from a PathBuf by making the PathBuf absolute and then running a hash
     /// algorithm on it
     fn hash_path(pb: &PathBuf) -> Result<String> {
@@ -266,7 +403,7 @@ impl<'a> Ref<'a> {
 
     /// Get the hash of the link target which is stored in the ref object
     pub fn get_stored_hash(&self) -> Result<String> {
-        self.get_stored_hash_with_hasher(&DefaultHasher::new())
+        self.hasher().and_then(|h| self.get_stored_hash_with_hasher(&h))
     }
 
     /// Get the hahs of the link target which is stored in the ref object, which is hashed with a
@@ -289,20 +426,19 @@ impl<'a> Ref<'a> {
 
     /// Get the hash of the link target by reading the link target and hashing the contents
     pub fn get_current_hash(&self) -> Result<String> {
-        self.get_current_hash_with_hasher(DefaultHasher::new())
+        self.hasher().and_then(|h| self.get_current_hash_with_hasher(h))
     }
 
     /// Get the hash of the link target by reading the link target and hashing the contents with the
     /// custom hasher
-    pub fn get_current_hash_with_hasher<H: Hasher>(&self, mut h: H) -> Result<String> {
+    pub fn get_current_hash_with_hasher<H: Hasher>(&self, h: H) -> Result<String> {
         self.fs_file()
             .and_then(|pb| {
-                File::open(pb.clone())
-                    .map(|f| (pb, f))
+                File::open(pb)
                     .map_err(Box::new)
                     .map_err(|e| REK::IOError.into_error_with_cause(e))
             })
-            .and_then(|(path, mut file)| h.create_hash(&path, &mut file))
+            .and_then(|file| h.hash_reader(file))
     }
 
     /// Get the permissions of the file which are present
@@ -327,6 +463,11 @@ impl<'a> Ref<'a> {
         self.fs_file().map(|pathbuf| pathbuf.exists())
     }
 
+    /// Alias for `Ref::fs_link_exists()`
+    pub fn exists_on_fs(&self) -> Result<bool> {
+        self.fs_link_exists()
+    }
+
     /// Alias for `r.fs_link_exists() && r.deref().is_file()`
     pub fn is_ref_to_file(&self) -> Result<bool> {
         self.fs_file().map(|pathbuf| pathbuf.is_file())
@@ -385,13 +526,13 @@ impl<'a> Ref<'a> {
     /// Update the Ref by re-checking the file from FS
     /// This errors if the file is not present or cannot be read()
     pub fn update_ref(&mut self) -> Result<()> {
-        self.update_ref_with_hasher(&DefaultHasher::new())
+        self.hasher().and_then(|h| self.update_ref_with_hasher(&h))
     }
 
     /// Update the Ref by re-checking the file from FS using the passed Hasher instance
     /// This errors if the file is not present or cannot be read()
     pub fn update_ref_with_hasher<H: Hasher>(&mut self, h: &H) -> Result<()> {
-        let current_hash = try!(self.get_current_hash()); // uses the default hasher
+        let current_hash = try!(self.get_current_hash()); // uses the hasher of self.hash_algo()
         let current_perm = try!(self.get_current_permissions());
 
         try!(self.0
@@ -411,12 +552,103 @@ impl<'a> Ref<'a> {
         Ok(())
     }
 
+    /// Re-hash the referenced file and store the new hash in the Ref
+    ///
+    /// In contrast to `Ref::update_ref()`, this does only touch the content hash, not the
+    /// permissions.
+    pub fn update_hash(&mut self) -> Result<()> {
+        self.hasher().and_then(|h| self.update_hash_with_hasher(h))
+    }
+
+    /// Re-hash the referenced file with the custom hasher and store the new hash in the Ref
+    pub fn update_hash_with_hasher<H: Hasher>(&mut self, h: H) -> Result<()> {
+        let key          = format!("ref.content_hash.{}", h.hash_name());
+        let current_hash = try!(self.get_current_hash_with_hasher(h));
+
+        self.0
+            .get_header_mut()
+            .set(&key[..], Value::String(current_hash))
+            .map(|_| ())
+            .map_err(Box::new)
+            .map_err(|e| REK::StoreWriteError.into_error_with_cause(e))
+    }
+
+    /// Check whether the stored hash differs from the hash of the referenced file
+    pub fn is_stale(&self) -> Result<bool> {
+        self.fs_link_valid_hash().map(|b| !b)
+    }
+
+    /// Check whether the stored hash differs from the hash of the referenced file, using the custom
+    /// hasher
+    pub fn is_stale_with_hasher<H: Hasher>(&self, h: H) -> Result<bool> {
+        let stored_hash  = try!(self.get_stored_hash_with_hasher(&h));
+        let current_hash = try!(self.get_current_hash_with_hasher(h));
+        Ok(stored_hash != current_hash)
+    }
+
+    /// Point the Ref to `new_path`, for example after the referenced file was moved
+    ///
+    /// The content hash of the file at `new_path` must match the stored content hash, otherwise
+    /// this fails with `RefErrorKind::RefTargetHashMismatch`, so only Refs with content hashing
+    /// can be relocated.
+    ///
+    /// The StoreId of the Ref is not changed, it still contains the hash of the old path.
+    pub fn relocate<P: AsRef<Path>>(&mut self, new_path: P) -> Result<()> {
+        self.hasher().and_then(|h| self.relocate_with_hasher(new_path, h))
+    }
+
+    /// Same as `Ref::relocate()`, but compares the content hashes from the custom hasher
+    pub fn relocate_with_hasher<P: AsRef<Path>, H: Hasher>(&mut self, new_path: P, h: H)
+        -> Result<()>
+    {
+        let stored_hash = try!(self.get_stored_hash_with_hasher(&h));
+        let new_path    = try!(new_path
+            .as_ref()
+            .canonicalize()
+            .map_err(Box::new)
+            .map_err(|e| REK::PathCanonicalizationError.into_error_with_cause(e)));
+
+        let new_hash = try!(File::open(&new_path)
+            .map_err(Box::new)
+            .map_err(|e| REK::RefTargetFileCannotBeOpened.into_error_with_cause(e))
+            .and_then(|file| h.hash_reader(file)));
+
+        if new_hash != stored_hash {
+            return Err(REK::RefTargetHashMismatch.into_error());
+        }
+
+        let new_path = try!(new_path.to_str().map(String::from).ok_or(REK::PathUTF8Error.into_error()));
+
+        self.0
+            .get_header_mut()
+            .set("ref.path", Value::String(new_path))
+            .map(|_| ())
+            .map_err(Box::new)
+            .map_err(|e| REK::StoreWriteError.into_error_with_cause(e))
+    }
+
     /// Get the path of the file which is reffered to by this Ref
+    ///
+    /// If the path is stored relative to the store (see `RefFlags::with_relative_paths()`), it is
+    /// resolved against the path of the store the Ref was loaded from.
     pub fn fs_file(&self) -> Result<PathBuf> {
-        match self.0.get_header().read("ref.path") {
-            Ok(Some(Value::String(ref s))) => Ok(PathBuf::from(s)),
+        let path = match self.0.get_header().read("ref.path") {
+            Ok(Some(Value::String(ref s))) => PathBuf::from(s),
+            Ok(Some(_)) => return Err(REK::HeaderTypeError.into_error()),
+            Ok(None)    => return Err(REK::HeaderFieldMissingError.into_error()),
+            Err(e)      => return Err(REK::StoreReadError.into_error_with_cause(Box::new(e))),
+        };
+
+        match self.0.get_header().read("ref.relative") {
+            Ok(Some(Value::Boolean(true))) => {
+                self.0
+                    .get_location()
+                    .base()
+                    .map(|base| base.join(path))
+                    .ok_or(REK::StoreIdError.into_error())
+            },
+            Ok(Some(Value::Boolean(false))) | Ok(None) => Ok(path),
             Ok(Some(_)) => Err(REK::HeaderTypeError.into_error()),
-            Ok(None)    => Err(REK::HeaderFieldMissingError.into_error()),
             Err(e)      => Err(REK::StoreReadError.into_error_with_cause(Box::new(e))),
         }
     }
@@ -484,10 +716,10 @@ impl<'a> Ref<'a> {
     ///
     /// This option causes heavy I/O as it recursively searches the Filesystem.
     pub fn refind(&self, search_roots: Option<Vec<PathBuf>>) -> Result<PathBuf> {
-        self.refind_with_hasher(search_roots, DefaultHasher::new())
+        self.hasher().and_then(|h| self.refind_with_hasher(search_roots, h))
     }
 
-    pub fn refind_with_hasher<H: Hasher>(&self, search_roots: Option<Vec<PathBuf>>, mut h: H)
+    pub fn refind_with_hasher<H: Hasher>(&self, search_roots: Option<Vec<PathBuf>>, h: H)
         -> Result<PathBuf>
     {
         use itertools::Itertools;
@@ -513,7 +745,7 @@ impl<'a> Ref<'a> {
                                             .map_err(|e| REK::IOError.into_error_with_cause(e))
                                             .map(|f| (pb, f))
                                     })
-                                    .and_then(|(p, mut f)|  h.create_hash(&p, &mut f).map(|h| (p, h)))
+                                    .and_then(|(p, f)| h.hash_reader(f).map(|h| (p, h)))
                                     .map(|(path, hash)| {
                                         if hash == stored_hash {
                                             Some(path)
@@ -536,6 +768,27 @@ impl<'a> Ref<'a> {
 
 }
 
+/// Build the path to `target` relative to `base`, both have to be absolute
+fn relative_path(base: &Path, target: &Path) -> PathBuf {
+    let mut base_comps   = base.components().peekable();
+    let mut target_comps = target.components().peekable();
+
+    // skip the common prefix
+    while base_comps.peek().is_some() && base_comps.peek() == target_comps.peek() {
+        base_comps.next();
+        target_comps.next();
+    }
+
+    let mut rel = PathBuf::new();
+    for _ in base_comps {
+        rel.push("..");
+    }
+    for c in target_comps {
+        rel.push(c.as_os_str());
+    }
+    rel
+}
+
 impl<'a> Deref for Ref<'a> {
     type Target = FileLockEntry<'a>;
 
@@ -575,3 +828,271 @@ impl<'a> Into<FileLockEntry<'a>> for Ref<'a> {
 
 }
 
+#[cfg(test)]
+mod test {
+    use std::fs::File;
+    use std::fs::remove_file;
+    use std::fs::rename;
+    use std::io::Write;
+    use std::path::Path;
+    use std::path::PathBuf;
+
+    use tempdir::TempDir;
+    use toml::Value;
+
+    use libimagstore::store::Store;
+    use libimagstore::storeid::StoreId;
+    use libimagstore::toml_ext::TomlValueExt;
+
+    use flags::RefFlags;
+    use hasher::HashAlgo;
+    use super::Ref;
+    use super::relative_path;
+    use super::RefStatus;
+
+    fn get_store() -> Store {
+        Store::new_in_memory(PathBuf::from("/"), None).unwrap()
+    }
+
+    fn write_file(path: &PathBuf, content: &str) {
+        let mut f = File::create(path).unwrap();
+        assert!(f.write_all(content.as_bytes()).is_ok());
+    }
+
+    #[test]
+    fn test_stale_ref_is_rehashed() {
+        let files_dir = TempDir::new("imag-ref-test-files").unwrap();
+        let store     = get_store();
+        let file      = files_dir.path().join("file");
+        write_file(&file, "original content");
+
+        let flags   = RefFlags::default().with_content_hashing(true);
+        let mut rf  = Ref::create(&store, file.clone(), flags).unwrap();
+        let created = rf.get_stored_hash().unwrap();
+        assert!(!rf.is_stale().unwrap());
+
+        write_file(&file, "changed content");
+        assert!(rf.is_stale().unwrap());
+        assert_eq!(rf.get_stored_hash().unwrap(), created);
+
+        assert!(rf.update_hash().is_ok());
+        assert!(!rf.is_stale().unwrap());
+        assert!(rf.get_stored_hash().unwrap() != created);
+        assert_eq!(rf.get_stored_hash().unwrap(), rf.get_current_hash().unwrap());
+    }
+
+    fn check_algo(algo: HashAlgo, hash_len: usize) {
+        let files_dir = TempDir::new("imag-ref-test-files").unwrap();
+        let store     = get_store();
+        let file      = files_dir.path().join("file");
+        write_file(&file, "original content");
+
+        let flags  = RefFlags::default().with_content_hashing(true);
+        let mut rf = Ref::create_with_algo(&store, file.clone(), flags, algo).unwrap();
+
+        assert_eq!(rf.hash_algo().unwrap(), algo);
+        assert_eq!(rf.get_stored_hash().unwrap().len(), hash_len);
+        assert_eq!(rf.verify().unwrap(), RefStatus::Valid);
+        assert!(!rf.is_stale().unwrap());
+
+        write_file(&file, "changed content");
+        assert!(rf.is_stale().unwrap());
+        assert_eq!(rf.verify().unwrap(), RefStatus::Changed);
+
+        assert!(rf.update_hash().is_ok());
+        assert!(!rf.is_stale().unwrap());
+    }
+
+    #[test]
+    fn test_hash_algo_sha256() {
+        check_algo(HashAlgo::Sha256, 64);
+    }
+
+    #[test]
+    fn test_hash_algo_blake2() {
+        check_algo(HashAlgo::Blake2, 128);
+    }
+
+    #[test]
+    fn test_hash_algo_default_is_backwards_compatible() {
+        let files_dir = TempDir::new("imag-ref-test-files").unwrap();
+        let store     = get_store();
+        let file      = files_dir.path().join("file");
+        write_file(&file, "content");
+
+        let flags = RefFlags::default().with_content_hashing(true);
+        let old   = Ref::create(&store, file.clone(), flags).unwrap();
+        let old_hash = old.get_stored_hash().unwrap();
+        assert!(old.get_header().read("ref.hash_algo").unwrap().is_none());
+        assert_eq!(old.hash_algo().unwrap(), HashAlgo::Sha1);
+        assert!(!old.is_stale().unwrap());
+        assert!(old.delete(&store).is_ok());
+
+        let flags = RefFlags::default().with_content_hashing(true);
+        let new   = Ref::create_with_algo(&store, file, flags, HashAlgo::Sha1).unwrap();
+        assert_eq!(new.get_stored_hash().unwrap(), old_hash);
+    }
+
+    #[test]
+    fn test_all_refs_and_verify_all() {
+        let files_dir = TempDir::new("imag-ref-test-files").unwrap();
+        let store     = get_store();
+
+        let paths : Vec<PathBuf> = vec!["a", "b", "c"]
+            .into_iter()
+            .map(|name| {
+                let path = files_dir.path().join(name);
+                write_file(&path, name);
+                path
+            })
+            .collect();
+
+        let ids : Vec<_> = paths
+            .iter()
+            .map(|p| {
+                let flags = RefFlags::default().with_content_hashing(true);
+                Ref::create(&store, p.clone(), flags).unwrap().get_location().clone()
+            })
+            .collect();
+
+        assert_eq!(Ref::all_refs(&store).unwrap().len(), 3);
+
+        write_file(&paths[1], "tampered");
+        assert!(remove_file(&paths[2]).is_ok());
+
+        let report = Ref::verify_all(&store).unwrap();
+        assert_eq!(report.len(), 3);
+
+        let status_of = |id: &StoreId| report
+            .iter()
+            .find(|&&(ref i, _)| i == id)
+            .and_then(|&(_, ref s)| s.as_ref().ok().cloned());
+        assert_eq!(status_of(&ids[0]), Some(RefStatus::Valid));
+        assert_eq!(status_of(&ids[1]), Some(RefStatus::Changed));
+        assert_eq!(status_of(&ids[2]), Some(RefStatus::Dangling));
+    }
+
+    #[test]
+    fn test_verify_all_reports_errors_per_ref() {
+        let files_dir = TempDir::new("imag-ref-test-files").unwrap();
+        let store     = get_store();
+
+        let ids : Vec<_> = vec!["a", "b"]
+            .into_iter()
+            .map(|name| {
+                let path = files_dir.path().join(name);
+                write_file(&path, name);
+                let flags = RefFlags::default().with_content_hashing(true);
+                Ref::create(&store, path, flags).unwrap().get_location().clone()
+            })
+            .collect();
+
+        {
+            let mut broken = store.get(ids[0].clone()).unwrap().unwrap();
+            let _ = broken.get_header_mut().set("ref.content_hash.sha1", Value::Integer(1)).unwrap();
+        }
+
+        let report = Ref::verify_all(&store).unwrap();
+        assert_eq!(report.len(), 2);
+
+        let status_of = |id: &StoreId| report.iter().find(|&&(ref i, _)| i == id).map(|&(_, ref s)| s);
+        assert!(status_of(&ids[0]).unwrap().is_err());
+        assert_eq!(status_of(&ids[1]).unwrap().as_ref().ok(), Some(&RefStatus::Valid));
+    }
+
+    #[test]
+    fn test_delete_keeps_target() {
+        let files_dir = TempDir::new("imag-ref-test-files").unwrap();
+        let store     = get_store();
+        let file      = files_dir.path().join("file");
+        write_file(&file, "content");
+
+        let rf = Ref::create(&store, file.clone(), RefFlags::default()).unwrap();
+        let id = rf.get_location().clone();
+
+        assert!(rf.delete(&store).is_ok());
+        assert!(store.get(id).unwrap().is_none());
+        assert!(file.exists());
+    }
+
+    #[test]
+    fn test_delete_with_target() {
+        let files_dir = TempDir::new("imag-ref-test-files").unwrap();
+        let store     = get_store();
+        let file      = files_dir.path().join("file");
+        write_file(&file, "content");
+
+        let rf = Ref::create(&store, file.clone(), RefFlags::default()).unwrap();
+        let id = rf.get_location().clone();
+
+        assert!(rf.delete_with_target(&store).is_ok());
+        assert!(store.get(id).unwrap().is_none());
+        assert!(!file.exists());
+    }
+
+    #[test]
+    fn test_relative_path() {
+        let rel = relative_path(Path::new("/home/user/.imag/store"), Path::new("/home/user/mail/a"));
+        assert_eq!(rel, PathBuf::from("../../mail/a"));
+
+        let rel = relative_path(Path::new("/store"), Path::new("/store/files/a"));
+        assert_eq!(rel, PathBuf::from("files/a"));
+    }
+
+    #[test]
+    fn test_relative_ref_survives_moving_the_store() {
+        let tmp     = TempDir::new("imag-ref-test").unwrap();
+        let old_dir = tmp.path().join("old");
+        let new_dir = tmp.path().join("new");
+        assert!(create_dir_all(old_dir.join("store")).is_ok());
+        assert!(create_dir_all(old_dir.join("mail")).is_ok());
+        write_file(&old_dir.join("mail").join("file"), "content");
+
+        let id = {
+            let store = Store::new(old_dir.join("store"), None).unwrap();
+            let flags = RefFlags::default().with_relative_paths(true);
+            let rf    = Ref::create(&store, old_dir.join("mail").join("file"), flags).unwrap();
+            assert_eq!(rf.get_header().read("ref.path").unwrap(),
+                       Some(Value::String(String::from("../mail/file"))));
+            rf.get_location().clone().without_base()
+        };
+
+        assert!(rename(&old_dir, &new_dir).is_ok());
+
+        let store = Store::new(new_dir.join("store"), None).unwrap();
+        let rf    = Ref::get(&store, id).unwrap();
+        let path  = rf.fs_file().unwrap();
+
+        assert!(rf.fs_link_exists().unwrap());
+        assert_eq!(path.canonicalize().unwrap(),
+                   new_dir.join("mail").join("file").canonicalize().unwrap());
+    }
+
+    #[test]
+    fn test_relocate_moved_target() {
+        let files_dir = TempDir::new("imag-ref-test-files").unwrap();
+        let store     = get_store();
+        let file      = files_dir.path().join("file");
+        let moved     = files_dir.path().join("moved");
+        let other     = files_dir.path().join("other");
+        write_file(&file, "content");
+        write_file(&other, "other content");
+
+        let flags  = RefFlags::default().with_content_hashing(true);
+        let mut rf = Ref::create(&store, file.clone(), flags).unwrap();
+
+        assert!(rename(&file, &moved).is_ok());
+        assert!(!rf.exists_on_fs().unwrap());
+        assert!(rf.get_current_hash().is_err());
+
+        // a file with other content is rejected
+        assert!(rf.relocate(&other).is_err());
+        assert!(!rf.exists_on_fs().unwrap());
+
+        assert!(rf.relocate(&moved).is_ok());
+        assert!(rf.exists_on_fs().unwrap());
+        assert_eq!(rf.fs_file().unwrap(), moved.canonicalize().unwrap());
+        assert_eq!(rf.get_current_hash().unwrap(), rf.get_stored_hash().unwrap());
+    }
+
+}
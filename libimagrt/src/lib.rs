@@ -39,6 +39,8 @@ extern crate itertools;
 extern crate env_logger;
 extern crate tempfile;
 extern crate ansi_term;
+extern crate lazycell;
+#[cfg(test)] extern crate tempdir;
 
 extern crate clap;
 extern crate toml;
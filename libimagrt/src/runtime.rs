@@ -17,7 +17,10 @@
 // Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
 //
 
+use std::collections::BTreeMap;
+use std::fmt::{Debug, Error as FmtError, Formatter};
 use std::path::PathBuf;
+use std::result::Result as RResult;
 use std::process::Command;
 use std::env;
 use std::io::stderr;
@@ -26,8 +29,10 @@ use std::io::Write;
 pub use clap::App;
 
 use clap::{Arg, ArgMatches};
+use lazycell::LazyCell;
 use log;
 use log::LogLevelFilter;
+use toml::Value;
 
 use configuration::Configuration;
 use error::RuntimeError;
@@ -43,9 +48,11 @@ use libimagstore::store::Store;
 #[derive(Debug)]
 pub struct Runtime<'a> {
     rtp: PathBuf,
+    storepath: PathBuf,
     configuration: Option<Configuration>,
     cli_matches: ArgMatches<'a>,
     store: Store,
+    additional_stores: BTreeMap<String, AdditionalStore>,
 }
 
 impl<'a> Runtime<'a> {
@@ -55,21 +62,15 @@ impl<'a> Runtime<'a> {
     /// and builds the Runtime object with it.
     ///
     /// The cli_spec object should be initially build with the ::get_default_cli_builder() function.
+    ///
+    /// The store path is resolved as described for `Runtime::store_path()`.
     pub fn new(mut cli_spec: App<'a, 'a>) -> Result<Runtime<'a>, RuntimeError> {
         use std::env;
         use std::io::stdout;
 
         use clap::Shell;
 
-        use libimagstore::hook::position::HookPosition as HP;
-        use libimagstore::hook::Hook;
-        use libimagstore::error::StoreErrorKind;
-        use libimagstorestdhook::debug::DebugHook;
-        use libimagstorestdhook::vcs::git::delete::DeleteHook as GitDeleteHook;
-        use libimagstorestdhook::vcs::git::update::UpdateHook as GitUpdateHook;
-        use libimagstorestdhook::vcs::git::store_unload::StoreUnloadHook as GitStoreUnloadHook;
         use libimagerror::trace::trace_error;
-        use libimagerror::trace::trace_error_dbg;
         use libimagerror::into::IntoError;
 
         use configuration::error::ConfigErrorKind;
@@ -77,6 +78,7 @@ impl<'a> Runtime<'a> {
         let matches = cli_spec.clone().get_matches();
 
         let is_debugging = matches.is_present("debugging");
+        let is_dry_run   = matches.is_present(Runtime::arg_dry_run_name());
         let is_verbose   = matches.is_present("verbosity");
         let colored      = !matches.is_present("no-color-output");
 
@@ -101,13 +103,6 @@ impl<'a> Runtime<'a> {
                         panic!("You seem to be $HOME-less. Please get a $HOME before using this software. We are sorry for you and hope you have some accommodation anyways.");
                     })
             }, PathBuf::from);
-        let storepath = matches.value_of("storepath")
-                                .map_or_else(|| {
-                                    let mut spath = rtp.clone();
-                                    spath.push("store");
-                                    spath
-                                }, PathBuf::from);
-
         let configpath = matches.value_of("config")
                                 .map_or_else(|| rtp.clone(), PathBuf::from);
 
@@ -142,57 +137,23 @@ impl<'a> Runtime<'a> {
             write!(stderr(), "Store-config: {:?}\n", store_config).ok();
         }
 
-        Store::new(storepath.clone(), store_config).map(|mut store| {
-            // If we are debugging, generate hooks for all positions
-            if is_debugging {
-                let hooks : Vec<(Box<Hook>, &str, HP)> = vec![
-                    (Box::new(DebugHook::new(HP::PreCreate))          , "debug", HP::PreCreate),
-                    (Box::new(DebugHook::new(HP::PostCreate))         , "debug", HP::PostCreate),
-                    (Box::new(DebugHook::new(HP::PreRetrieve))        , "debug", HP::PreRetrieve),
-                    (Box::new(DebugHook::new(HP::PostRetrieve))       , "debug", HP::PostRetrieve),
-                    (Box::new(DebugHook::new(HP::PreUpdate))          , "debug", HP::PreUpdate),
-                    (Box::new(DebugHook::new(HP::PostUpdate))         , "debug", HP::PostUpdate),
-                    (Box::new(DebugHook::new(HP::PreDelete))          , "debug", HP::PreDelete),
-                    (Box::new(DebugHook::new(HP::PostDelete))         , "debug", HP::PostDelete),
-                ];
-
-                // If hook registration fails, trace the error and warn, but continue.
-                for (hook, aspectname, position) in hooks {
-                    if let Err(e) = store.register_hook(position, &String::from(aspectname), hook) {
-                        if e.err_type() == StoreErrorKind::HookRegisterError {
-                            trace_error_dbg(&e);
-                            warn!("Registering debug hook with store failed");
-                        } else {
-                            trace_error(&e);
-                        };
-                    }
-                }
-            }
+        let additional_stores = additional_stores_from_config(cfg.as_ref().map(|c| c.config()));
+        let storepath = resolve_storepath(matches.value_of(Runtime::arg_storepath_name()),
+                                          cfg.as_ref().map(|c| c.config()),
+                                          &rtp);
+        debug!("Store path: {:?}", storepath);
 
-            let sp = storepath;
-
-            let hooks : Vec<(Box<Hook>, &str, HP)> = vec![
-                (Box::new(GitDeleteHook::new(sp.clone(), HP::PostDelete)), "vcs", HP::PostDelete),
-                (Box::new(GitUpdateHook::new(sp.clone(), HP::PostUpdate)), "vcs", HP::PostUpdate),
-                (Box::new(GitStoreUnloadHook::new(sp)),                    "vcs", HP::StoreUnload),
-            ];
-
-            for (hook, aspectname, position) in hooks {
-                if let Err(e) = store.register_hook(position, &String::from(aspectname), hook) {
-                    if e.err_type() == StoreErrorKind::HookRegisterError {
-                        trace_error_dbg(&e);
-                        warn!("Registering git hook with store failed");
-                    } else {
-                        trace_error(&e);
-                    };
-                }
-            }
+        Store::new(storepath.clone(), store_config).map(|mut store| {
+            register_hooks(&mut store, storepath.clone(), is_debugging);
+            store.set_dry_run(is_dry_run);
 
             Runtime {
                 cli_matches: matches,
                 configuration: cfg,
                 rtp: rtp,
+                storepath: storepath,
                 store: store,
+                additional_stores: additional_stores,
             }
         })
         .map_err_into(RuntimeErrorKind::Instantiate)
@@ -207,6 +168,7 @@ impl<'a> Runtime<'a> {
     ///   * -c <file> | --config <file> for alternative configuration file
     ///   * -r <path> | --rtp <path> for alternative runtimepath
     ///   * --store <path> for alternative store path
+    ///   * --dry-run for not writing to the store
     /// Each has the appropriate help text included.
     ///
     /// The `appname` shall be "imag-<command>".
@@ -259,7 +221,7 @@ impl<'a> Runtime<'a> {
 
             .arg(Arg::with_name(Runtime::arg_storepath_name())
                 .long("store")
-                .help("Alternative storepath. Must be specified as full path, can be outside of the RTP")
+                .help("Alternative storepath. Must be specified as full path, can be outside of the RTP. Overrides $IMAG_STORE")
                 .required(false)
                 .takes_value(true))
 
@@ -269,6 +231,12 @@ impl<'a> Runtime<'a> {
                 .required(false)
                 .takes_value(true))
 
+            .arg(Arg::with_name(Runtime::arg_dry_run_name())
+                .long("dry-run")
+                .help("Do not write to the store, only print what would be done")
+                .required(false)
+                .takes_value(false))
+
             .arg(Arg::with_name(Runtime::arg_generate_compl())
                 .long("generate-commandline-completion")
                 .help("Generate the commandline completion for bash or zsh or fish")
@@ -290,6 +258,7 @@ impl<'a> Runtime<'a> {
             Runtime::arg_runtimepath_name(),
             Runtime::arg_storepath_name(),
             Runtime::arg_editor_name(),
+            Runtime::arg_dry_run_name(),
         ]
     }
 
@@ -333,6 +302,11 @@ impl<'a> Runtime<'a> {
         "editor"
     }
 
+    /// Get the dry-run argument name for the Runtime
+    pub fn arg_dry_run_name() -> &'static str {
+        "dry-run"
+    }
+
     /// Get the argument name for generating the completion
     pub fn arg_generate_compl() -> &'static str {
         "generate-completion"
@@ -376,11 +350,31 @@ impl<'a> Runtime<'a> {
         self.cli_matches.is_present("debugging")
     }
 
+    /// Get the dry-run flag value
+    ///
+    /// If set, the store does not write to the filesystem, see `Store::set_dry_run()`.
+    pub fn is_dry_run(&self) -> bool {
+        self.cli_matches.is_present(Runtime::arg_dry_run_name())
+    }
+
     /// Get the runtimepath
     pub fn rtp(&self) -> &PathBuf {
         &self.rtp
     }
 
+    /// Get the path of the store
+    ///
+    /// The path is taken from the first of these which is set:
+    ///
+    ///   1. the `--store` commandline flag
+    ///   2. the `IMAG_STORE` environment variable
+    ///   3. `store.location` in the configuration file
+    ///   4. `<rtp>/store`
+    ///
+    pub fn store_path(&self) -> &PathBuf {
+        &self.storepath
+    }
+
     /// Get the commandline interface matches
     pub fn cli(&self) -> &ArgMatches {
         &self.cli_matches
@@ -396,6 +390,24 @@ impl<'a> Runtime<'a> {
         &self.store
     }
 
+    /// Get an additional store by its name
+    ///
+    /// Additional stores are configured in the `[stores.<name>]` sections of the configuration,
+    /// where `location` is the path of the store. They use the same store configuration as the
+    /// default store, but each store gets its own instances of the hooks, so hooks of one store
+    /// never see entries of another store.
+    ///
+    /// The store is instantiated when it is requested the first time. Returns `None` if there is
+    /// no store with this name or if the store could not be instantiated (the error is traced).
+    pub fn additional_store(&self, name: &str) -> Option<&Store> {
+        let store_config = self.configuration.as_ref().and_then(|c| c.store_config().cloned());
+        get_additional_store(&self.additional_stores,
+                             name,
+                             store_config,
+                             self.is_debugging(),
+                             self.is_dry_run())
+    }
+
     /// Get a editor command object which can be called to open the $EDITOR
     pub fn editor(&self) -> Option<Command> {
         self.cli()
@@ -412,6 +424,194 @@ impl<'a> Runtime<'a> {
     }
 }
 
+/// Resolve the store path, see `Runtime::store_path()` for the precedence
+///
+/// An empty `IMAG_STORE` is treated as if it was not set.
+fn resolve_storepath(cli: Option<&str>, config: Option<&Value>, rtp: &PathBuf) -> PathBuf {
+    let from_config = || match config {
+        Some(&Value::Table(ref t)) => match t.get("store") {
+            Some(&Value::Table(ref store)) => store.get("location")
+                .and_then(Value::as_str)
+                .map(PathBuf::from),
+            _ => None,
+        },
+        _ => None,
+    };
+
+    cli.map(PathBuf::from)
+        .or_else(|| {
+            env::var("IMAG_STORE")
+                .ok()
+                .and_then(|s| if s.is_empty() { None } else { Some(PathBuf::from(s)) })
+        })
+        .or_else(from_config)
+        .unwrap_or_else(|| {
+            let mut spath = rtp.clone();
+            spath.push("store");
+            spath
+        })
+}
+
+/// Register the debug hooks (if debugging) and the git hooks with `store`
+fn register_hooks(store: &mut Store, storepath: PathBuf, is_debugging: bool) {
+    use libimagstore::hook::position::HookPosition as HP;
+    use libimagstore::hook::Hook;
+    use libimagstore::error::StoreErrorKind;
+    use libimagstorestdhook::debug::DebugHook;
+    use libimagerror::trace::trace_error;
+    use libimagerror::trace::trace_error_dbg;
+
+    // If we are debugging, generate hooks for all positions
+    if is_debugging {
+        let hooks : Vec<(Box<Hook>, &str, HP)> = vec![
+            (Box::new(DebugHook::new(HP::PreCreate))          , "debug", HP::PreCreate),
+            (Box::new(DebugHook::new(HP::PostCreate))         , "debug", HP::PostCreate),
+            (Box::new(DebugHook::new(HP::PreRetrieve))        , "debug", HP::PreRetrieve),
+            (Box::new(DebugHook::new(HP::PostRetrieve))       , "debug", HP::PostRetrieve),
+            (Box::new(DebugHook::new(HP::PreUpdate))          , "debug", HP::PreUpdate),
+            (Box::new(DebugHook::new(HP::PostUpdate))         , "debug", HP::PostUpdate),
+            (Box::new(DebugHook::new(HP::PreDelete))          , "debug", HP::PreDelete),
+            (Box::new(DebugHook::new(HP::PostDelete))         , "debug", HP::PostDelete),
+            (Box::new(DebugHook::new(HP::PreRetrieveCopy))    , "debug", HP::PreRetrieveCopy),
+            (Box::new(DebugHook::new(HP::PostRetrieveCopy))   , "debug", HP::PostRetrieveCopy),
+            (Box::new(DebugHook::new(HP::PreSaveTo))          , "debug", HP::PreSaveTo),
+            (Box::new(DebugHook::new(HP::PostSaveTo))         , "debug", HP::PostSaveTo),
+            (Box::new(DebugHook::new(HP::PreSaveAs))          , "debug", HP::PreSaveAs),
+            (Box::new(DebugHook::new(HP::PostSaveAs))         , "debug", HP::PostSaveAs),
+        ];
+
+        // If hook registration fails, trace the error and warn, but continue.
+        for (hook, aspectname, position) in hooks {
+            if let Err(e) = store.register_hook(position, &String::from(aspectname), hook) {
+                if e.err_type() == StoreErrorKind::HookRegisterError {
+                    trace_error_dbg(&e);
+                    warn!("Registering debug hook with store failed");
+                } else {
+                    trace_error(&e);
+                };
+            }
+        }
+    }
+
+    register_git_hooks(store, storepath)
+}
+
+/// Register the git hooks (the "vcs" aspect) with `store`
+///
+/// The store has no hook positions for moving entries, so moves are not committed.
+#[cfg(feature = "git")]
+fn register_git_hooks(store: &mut Store, storepath: PathBuf) {
+    use libimagstore::hook::position::HookPosition as HP;
+    use libimagstore::hook::Hook;
+    use libimagstore::error::StoreErrorKind;
+    use libimagstorestdhook::vcs::git::delete::DeleteHook as GitDeleteHook;
+    use libimagstorestdhook::vcs::git::update::UpdateHook as GitUpdateHook;
+    use libimagstorestdhook::vcs::git::store_unload::StoreUnloadHook as GitStoreUnloadHook;
+    use libimagerror::trace::trace_error;
+    use libimagerror::trace::trace_error_dbg;
+
+    let sp = storepath;
+
+    let hooks : Vec<(Box<Hook>, &str, HP)> = vec![
+        (Box::new(GitUpdateHook::new(sp.clone(), HP::PostCreate)), "vcs", HP::PostCreate),
+        (Box::new(GitDeleteHook::new(sp.clone(), HP::PostDelete)), "vcs", HP::PostDelete),
+        (Box::new(GitUpdateHook::new(sp.clone(), HP::PostUpdate)), "vcs", HP::PostUpdate),
+        (Box::new(GitStoreUnloadHook::new(sp)),                    "vcs", HP::StoreUnload),
+    ];
+
+    for (hook, aspectname, position) in hooks {
+        if let Err(e) = store.register_hook(position, &String::from(aspectname), hook) {
+            if e.err_type() == StoreErrorKind::HookRegisterError {
+                trace_error_dbg(&e);
+                warn!("Registering git hook with store failed");
+            } else {
+                trace_error(&e);
+            };
+        }
+    }
+}
+
+#[cfg(not(feature = "git"))]
+fn register_git_hooks(_: &mut Store, _: PathBuf) {
+    debug!("Built without the \"git\" feature, not registering the git hooks");
+}
+
+/// A store from the `[stores.<name>]` section of the configuration, which is instantiated lazily
+struct AdditionalStore {
+    location: PathBuf,
+    store: LazyCell<Store>,
+}
+
+impl Debug for AdditionalStore {
+
+    fn fmt(&self, fmt: &mut Formatter) -> RResult<(), FmtError> {
+        write!(fmt, "AdditionalStore({:?}, instantiated: {})", self.location, self.store.filled())
+    }
+
+}
+
+fn additional_stores_from_config(config: Option<&Value>) -> BTreeMap<String, AdditionalStore> {
+    let stores = match config {
+        Some(&Value::Table(ref t)) => match t.get("stores") {
+            Some(&Value::Table(ref stores)) => stores,
+            Some(_) => {
+                warn!("Configuration: 'stores' should be a table, ignoring it");
+                return BTreeMap::new();
+            },
+            None => return BTreeMap::new(),
+        },
+        _ => return BTreeMap::new(),
+    };
+
+    stores.iter()
+        .filter_map(|(name, cfg)| match cfg.get("location") {
+            Some(&Value::String(ref location)) => {
+                let store = AdditionalStore {
+                    location: PathBuf::from(location),
+                    store: LazyCell::new(),
+                };
+                Some((name.clone(), store))
+            },
+            _ => {
+                warn!("Configuration: 'stores.{}.location' missing or not a string, ignoring", name);
+                None
+            },
+        })
+        .collect()
+}
+
+fn get_additional_store<'a>(stores: &'a BTreeMap<String, AdditionalStore>,
+                            name: &str,
+                            store_config: Option<Value>,
+                            is_debugging: bool,
+                            is_dry_run: bool)
+    -> Option<&'a Store>
+{
+    use libimagerror::trace::trace_error;
+
+    let additional = match stores.get(name) {
+        Some(additional) => additional,
+        None             => return None,
+    };
+
+    if !additional.store.filled() {
+        debug!("Instantiating store '{}' at {:?}", name, additional.location);
+        match Store::new(additional.location.clone(), store_config) {
+            Ok(mut store) => {
+                register_hooks(&mut store, additional.location.clone(), is_debugging);
+                store.set_dry_run(is_dry_run);
+                let _ = additional.store.fill(store);
+            },
+            Err(e) => {
+                trace_error(&e);
+                return None;
+            },
+        }
+    }
+
+    additional.store.borrow()
+}
+
 fn get_override_specs(matches: &ArgMatches) -> Vec<String> {
     matches
         .values_of("config-override")
@@ -428,3 +628,86 @@ fn get_override_specs(matches: &ArgMatches) -> Vec<String> {
         .unwrap_or(vec![])
 }
 
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use tempdir::TempDir;
+    use toml::Value;
+    use toml::de::from_str as toml_from_str;
+
+    use libimagstore::storeid::StoreId;
+
+    use super::additional_stores_from_config;
+    use super::get_additional_store;
+    use super::resolve_storepath;
+
+    #[test]
+    fn test_additional_stores() {
+        let first  = TempDir::new("imag-rt-test-first").unwrap();
+        let second = TempDir::new("imag-rt-test-second").unwrap();
+
+        let config : Value = toml_from_str(&format!(r#"
+            [stores.first]
+            location = "{}"
+
+            [stores.second]
+            location = "{}"
+        "#, first.path().display(), second.path().display())).unwrap();
+
+        let stores = additional_stores_from_config(Some(&config));
+        assert_eq!(stores.len(), 2);
+        assert!(get_additional_store(&stores, "unknown", None, false, false).is_none());
+
+        let id = StoreId::new_baseless(PathBuf::from("test/entry")).unwrap();
+        {
+            let store = get_additional_store(&stores, "first", None, false, false).unwrap();
+            assert_eq!(store.path(), &PathBuf::from(first.path()));
+            assert!(store.create(id.clone()).is_ok());
+        }
+
+        // the same instance is returned on the second call
+        let first_store  = get_additional_store(&stores, "first", None, false, false).unwrap();
+        let second_store = get_additional_store(&stores, "second", None, false, false).unwrap();
+        assert_eq!(second_store.path(), &PathBuf::from(second.path()));
+
+        assert!(first_store.get(id.clone()).unwrap().is_some());
+        assert!(second_store.get(id).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_additional_stores_without_location_are_ignored() {
+        let config : Value = toml_from_str(r#"
+            [stores.broken]
+            path = "/tmp"
+        "#).unwrap();
+
+        assert!(additional_stores_from_config(Some(&config)).is_empty());
+        assert!(additional_stores_from_config(None).is_empty());
+    }
+
+    #[test]
+    fn test_storepath_precedence() {
+        use std::env;
+
+        let config : Value = toml_from_str(r#"
+            [store]
+            location = "/from/config"
+        "#).unwrap();
+        let rtp = PathBuf::from("/rtp");
+
+        // All assertions are in one test, as the environment is shared between the test threads
+        env::set_var("IMAG_STORE", "/from/env");
+        assert_eq!(resolve_storepath(Some("/from/cli"), Some(&config), &rtp),
+                   PathBuf::from("/from/cli"));
+        assert_eq!(resolve_storepath(None, Some(&config), &rtp), PathBuf::from("/from/env"));
+
+        env::set_var("IMAG_STORE", "");
+        assert_eq!(resolve_storepath(None, Some(&config), &rtp), PathBuf::from("/from/config"));
+
+        env::remove_var("IMAG_STORE");
+        assert_eq!(resolve_storepath(None, Some(&config), &rtp), PathBuf::from("/from/config"));
+        assert_eq!(resolve_storepath(None, None, &rtp), PathBuf::from("/rtp/store"));
+    }
+
+}
@@ -0,0 +1,189 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015, 2016 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+
+//! Exporting the store to and importing it from tar archives
+//!
+//! This is only available with the `tar` feature.
+
+use std::io::Read;
+use std::io::Write;
+
+use tar::{Archive, Builder, Header};
+
+use error::StoreErrorKind as SEK;
+use error::MapErrInto;
+use store::{Entry, Result, Store};
+use storeid::StoreId;
+
+impl Store {
+
+    /// Write all entries of the store into a tar archive
+    ///
+    /// Each entry is written with its path relative to the store root. Hidden files, like lock
+    /// files or the `.git` directory of a version controlled store, are not exported, see
+    /// `Store::entries()`.
+    ///
+    /// # Return value
+    ///
+    /// On success: ()
+    ///
+    /// On error:
+    ///  - ExportTarCallError(_) wrapping the errors of `Store::entries()` and
+    ///    `Store::retrieve_copy()`, which fails if an entry is borrowed
+    ///  - ExportTarCallError(ArchiveError(_)) if writing the archive failed
+    ///
+    pub fn export_tar<W: Write>(&self, w: W) -> Result<()> {
+        self.entries()
+            .and_then(|ids| self.export_tar_ids(ids, w))
+            .map_err_into(SEK::ExportTarCallError)
+    }
+
+    fn export_tar_ids<I, W>(&self, ids: I, w: W) -> Result<()>
+        where I: Iterator<Item = StoreId>,
+              W: Write
+    {
+        let mut builder = Builder::new(w);
+
+        for id in ids {
+            debug!("Exporting {}", id);
+            let bytes      = try!(self.retrieve_copy(id.clone())).to_bytes();
+            let mut header = Header::new_gnu();
+            header.set_size(bytes.len() as u64);
+            header.set_mode(0o644);
+
+            // append_data() sets the path (with a GNU long name entry for paths which do not fit
+            // into the header) and the checksum
+            try!(builder.append_data(&mut header, id.local(), &bytes[..]).map_err_into(SEK::ArchiveError));
+        }
+
+        builder.finish().map_err_into(SEK::ArchiveError)
+    }
+
+    /// Create the entries from a tar archive, as written by `Store::export_tar()`
+    ///
+    /// Each file in the archive is parsed as an entry, so malformed headers are rejected, and
+    /// created with `Store::create()`, which executes the create hooks. Directories and other
+    /// members which are not files are skipped.
+    ///
+    /// # Return value
+    ///
+    /// On success: The ids of the imported entries
+    ///
+    /// On error:
+    ///  - ImportTarCallError(ArchiveError(_)) if the archive cannot be read
+    ///  - ImportTarCallError(_) wrapping the errors of `StoreId::new_baseless()`,
+    ///    `Entry::from_bytes()`, `Store::create()` (for example if the entry exists already) and
+    ///    `Store::update()`
+    ///
+    /// The import stops at the first error, the entries imported before stay in the store.
+    ///
+    pub fn import_tar<R: Read>(&self, r: R) -> Result<Vec<StoreId>> {
+        self._import_tar(r).map_err_into(SEK::ImportTarCallError)
+    }
+
+    fn _import_tar<R: Read>(&self, r: R) -> Result<Vec<StoreId>> {
+        let mut archive = Archive::new(r);
+        let mut ids     = vec![];
+
+        for member in try!(archive.entries().map_err_into(SEK::ArchiveError)) {
+            let mut member = try!(member.map_err_into(SEK::ArchiveError));
+            if !member.header().entry_type().is_file() {
+                continue;
+            }
+
+            let path = try!(member.path().map(|p| p.into_owned()).map_err_into(SEK::ArchiveError));
+            let id   = try!(StoreId::new_baseless(path)).with_base(self.path().clone());
+            debug!("Importing {}", id);
+
+            let mut bytes = vec![];
+            try!(member.read_to_end(&mut bytes).map_err_into(SEK::ArchiveError));
+
+            let entry   = try!(Entry::from_bytes(id.clone(), &bytes));
+            let mut fle = try!(self.create(id.clone()));
+            *fle = entry;
+            try!(self.update(&mut fle));
+
+            ids.push(id);
+        }
+
+        Ok(ids)
+    }
+
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use store::Store;
+
+    #[test]
+    fn test_export_import_tar() {
+        let source = Store::new_in_memory(PathBuf::from("/"), None).unwrap();
+        let ids    = vec![("tar-test/a", "content a"), ("tar-test/sub/b", "content b"), ("tar-test/c", "")]
+            .into_iter()
+            .map(|(name, content)| {
+                let mut entry = source.create(PathBuf::from(name)).unwrap();
+                *entry.get_content_mut() = String::from(content);
+                entry.get_location().clone()
+            })
+            .collect::<Vec<_>>();
+
+        let mut archive = vec![];
+        assert!(source.export_tar(&mut archive).is_ok());
+
+        let target   = Store::new_in_memory(PathBuf::from("/target"), None).unwrap();
+        let imported = target.import_tar(&archive[..]).unwrap();
+        assert_eq!(imported.len(), 3);
+
+        for id in ids {
+            let original = source.retrieve_copy(id.clone()).unwrap();
+            let copy     = target.retrieve_copy(id.without_base()).unwrap();
+            assert_eq!(original, copy);
+        }
+    }
+
+    #[test]
+    fn test_export_import_tar_long_path() {
+        let source = Store::new_in_memory(PathBuf::from("/"), None).unwrap();
+        let long   = |c: &str| ::std::iter::repeat(c).take(80).collect::<String>();
+        let name   = format!("tar-test/{}/{}", long("d"), long("e"));
+        let id     = {
+            let mut entry = source.create(PathBuf::from(name)).unwrap();
+            *entry.get_content_mut() = String::from("long");
+            entry.get_location().clone()
+        };
+
+        let mut archive = vec![];
+        assert!(source.export_tar(&mut archive).is_ok());
+
+        let target = Store::new_in_memory(PathBuf::from("/target"), None).unwrap();
+        assert_eq!(target.import_tar(&archive[..]).unwrap().len(), 1);
+        assert_eq!(source.retrieve_copy(id.clone()).unwrap(),
+                   target.retrieve_copy(id.without_base()).unwrap());
+    }
+
+    #[test]
+    fn test_import_tar_rejects_garbage() {
+        let store = Store::new_in_memory(PathBuf::from("/"), None).unwrap();
+        assert!(store.import_tar(&b"this is not a tar archive"[..]).is_err());
+    }
+
+}
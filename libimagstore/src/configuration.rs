@@ -17,6 +17,8 @@
 // Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
 //
 
+use std::collections::HashMap;
+
 use toml::Value;
 
 use libimagerror::into::IntoError;
@@ -207,6 +209,63 @@ pub fn config_implicit_store_create_allowed(config: Option<&Value>) -> bool {
     }).unwrap_or(false)
 }
 
+/// Checks whether the store configuration has a key "skip-hooks-when-unchanged" which maps to a
+/// boolean value. If that key is present, the boolean is returned, otherwise false is returned.
+///
+/// If set, `Store::update()` (and dropping a `FileLockEntry`) does not execute the update hooks for
+/// entries which did not change.
+pub fn config_skip_hooks_when_unchanged(config: Option<&Value>) -> bool {
+    match config {
+        Some(&Value::Table(ref t)) => match t.get("skip-hooks-when-unchanged") {
+            Some(&Value::Boolean(b)) => b,
+            Some(_) => {
+                warn!("Key 'skip-hooks-when-unchanged' does not contain a Boolean value");
+                false
+            },
+            None => false,
+        },
+        _ => false,
+    }
+}
+
+/// Collects the file extensions configured for the modules of the store
+///
+/// The extension of a module is configured as `store.modules.<module>.extension`, for example
+/// `extension = "md"` in `[store.modules.notes]`. Modules without (valid) extension are not
+/// contained in the returned map. A leading dot is stripped from the configured extension.
+pub fn config_module_extensions(config: Option<&Value>) -> HashMap<String, String> {
+    let modules = match config {
+        Some(&Value::Table(ref t)) => match t.get("modules") {
+            Some(&Value::Table(ref modules)) => modules,
+            _ => return HashMap::new(),
+        },
+        _ => return HashMap::new(),
+    };
+
+    modules
+        .iter()
+        .filter_map(|(module, cfg)| match *cfg {
+            Value::Table(ref t) => match t.get("extension") {
+                Some(&Value::String(ref ext)) => {
+                    let ext = ext.trim_left_matches('.');
+                    if ext.is_empty() {
+                        warn!("Key 'extension' for module '{}' is empty", module);
+                        None
+                    } else {
+                        Some((module.clone(), String::from(ext)))
+                    }
+                },
+                Some(_) => {
+                    warn!("Key 'extension' for module '{}' does not contain a String value", module);
+                    None
+                },
+                None => None,
+            },
+            _ => None,
+        })
+        .collect()
+}
+
 pub fn get_store_unload_aspect_names(value: &Option<Value>) -> Vec<String> {
     get_aspect_names_for_aspect_position("store-unload-hook-aspects", value)
 }
@@ -251,6 +310,30 @@ pub fn get_post_move_aspect_names(value: &Option<Value>) -> Vec<String> {
     get_aspect_names_for_aspect_position("post-move-hook-aspects", value)
 }
 
+pub fn get_pre_retrieve_copy_aspect_names(value: &Option<Value>) -> Vec<String> {
+    get_aspect_names_for_aspect_position("pre-retrieve-copy-hook-aspects", value)
+}
+
+pub fn get_post_retrieve_copy_aspect_names(value: &Option<Value>) -> Vec<String> {
+    get_aspect_names_for_aspect_position("post-retrieve-copy-hook-aspects", value)
+}
+
+pub fn get_pre_save_to_aspect_names(value: &Option<Value>) -> Vec<String> {
+    get_aspect_names_for_aspect_position("pre-save-to-hook-aspects", value)
+}
+
+pub fn get_post_save_to_aspect_names(value: &Option<Value>) -> Vec<String> {
+    get_aspect_names_for_aspect_position("post-save-to-hook-aspects", value)
+}
+
+pub fn get_pre_save_as_aspect_names(value: &Option<Value>) -> Vec<String> {
+    get_aspect_names_for_aspect_position("pre-save-as-hook-aspects", value)
+}
+
+pub fn get_post_save_as_aspect_names(value: &Option<Value>) -> Vec<String> {
+    get_aspect_names_for_aspect_position("post-save-as-hook-aspects", value)
+}
+
 #[derive(Debug)]
 pub struct AspectConfig {
     parallel: bool,
@@ -303,6 +386,10 @@ impl AspectConfig {
         self.mutable_hooks
     }
 
+    pub fn parallel(&self) -> bool {
+        self.parallel
+    }
+
     /// Get the aspect configuration for an aspect.
     ///
     /// Pass the store configuration object, this searches in `[aspects][<aspect_name>]`.
@@ -358,6 +445,43 @@ mod tests {
     use toml::de::from_str as toml_from_str;
     use configuration::*;
 
+    #[test]
+    fn test_skip_hooks_when_unchanged() {
+        assert!(!config_skip_hooks_when_unchanged(None));
+        assert!(!config_skip_hooks_when_unchanged(Some(&toml_from_str("").unwrap())));
+
+        let config = toml_from_str("skip-hooks-when-unchanged = true").unwrap();
+        assert!(config_skip_hooks_when_unchanged(Some(&config)));
+
+        let config = toml_from_str("skip-hooks-when-unchanged = \"yes\"").unwrap();
+        assert!(!config_skip_hooks_when_unchanged(Some(&config)));
+    }
+
+    #[test]
+    fn test_module_extensions() {
+        assert!(config_module_extensions(None).is_empty());
+        assert!(config_module_extensions(Some(&toml_from_str("").unwrap())).is_empty());
+
+        let config = toml_from_str(r#"
+            [modules.notes]
+            extension = "md"
+
+            [modules.mail]
+            extension = ".eml"
+
+            [modules.bookmark]
+            extension = 1
+
+            [modules.todo]
+            other = "key"
+        "#).unwrap();
+
+        let exts = config_module_extensions(Some(&config));
+        assert_eq!(exts.len(), 2);
+        assert_eq!(exts.get("notes"), Some(&String::from("md")));
+        assert_eq!(exts.get("mail"), Some(&String::from("eml")));
+    }
+
     #[test]
     fn test_implicit_store_create_allowed_no_toml() {
         assert!(!config_implicit_store_create_allowed(None));
@@ -663,6 +787,38 @@ mod tests {
         assert_eq!("example", names.iter().next().unwrap());
     }
 
+    #[test]
+    fn test_get_pre_retrieve_copy_aspect_names_not_existent() {
+        let config = toml_from_str("").unwrap();
+        assert!(get_pre_retrieve_copy_aspect_names(&Some(config)).is_empty());
+    }
+
+    #[test]
+    fn test_get_pre_retrieve_copy_aspect_names_one_elem() {
+        let config = toml_from_str(r#"
+            pre-retrieve-copy-hook-aspects = [ "example" ]
+        "#).unwrap();
+        let names = get_pre_retrieve_copy_aspect_names(&Some(config));
+        assert_eq!(1, names.len());
+        assert_eq!("example", names.iter().next().unwrap());
+    }
+
+    #[test]
+    fn test_get_post_retrieve_copy_aspect_names_not_existent() {
+        let config = toml_from_str("").unwrap();
+        assert!(get_post_retrieve_copy_aspect_names(&Some(config)).is_empty());
+    }
+
+    #[test]
+    fn test_get_post_retrieve_copy_aspect_names_one_elem() {
+        let config = toml_from_str(r#"
+            post-retrieve-copy-hook-aspects = [ "example" ]
+        "#).unwrap();
+        let names = get_post_retrieve_copy_aspect_names(&Some(config));
+        assert_eq!(1, names.len());
+        assert_eq!("example", names.iter().next().unwrap());
+    }
+
     #[test]
     fn test_get_aspect_names_for_aspect_position_arbitrary_empty() {
         let config = toml_from_str(r#"
@@ -57,12 +57,16 @@ generate_custom_error_types!(StoreError, StoreErrorKind, CustomErrorData,
     LockError               => "Error locking datastructure",
     LockPoisoned            => "The internal Store Lock has been poisoned",
     EntryAlreadyBorrowed    => "Entry is already borrowed",
+    LockTimeout             => "Timeout while waiting for a borrowed entry",
+    EntryNotBorrowed        => "Entry is not borrowed",
     EntryAlreadyExists      => "Entry already exists",
     MalformedEntry          => "Entry has invalid formatting, missing header",
     HeaderPathSyntaxError   => "Syntax error in accessor string",
     HeaderPathTypeFailure   => "Header has wrong type for path",
     HeaderKeyNotFound       => "Header Key not found",
     HeaderTypeFailure       => "Header type is wrong",
+    HeaderVersionError      => "Header has no valid imag.version",
+    HeaderMigrationError    => "Migrating the header failed",
     HookRegisterError       => "Hook register error",
     AspectNameNotFoundError => "Aspect name not found",
     HookExecutionError      => "Hook execution error",
@@ -77,22 +81,177 @@ generate_custom_error_types!(StoreError, StoreErrorKind, CustomErrorData,
     StoreIdLocalPartAbsoluteError => "StoreId 'id' part is absolute (starts with '/') which is not allowed",
     StoreIdBuildFromFullPathError => "Building StoreId from full file path failed",
     StoreIdHasNoBaseError   => "StoreId has no 'base' part",
+    StoreIdEmptyError       => "StoreId 'id' part is empty",
+    StoreIdParentDirError   => "StoreId 'id' part contains '..' which is not allowed",
+    StoreIdEmptyComponentError => "StoreId 'id' part contains an empty component",
+    ArchiveError            => "Error while reading or writing an archive",
 
     CreateCallError            => "Error when calling create()",
     RetrieveCallError          => "Error when calling retrieve()",
     GetCallError               => "Error when calling get()",
     GetAllVersionsCallError    => "Error when calling get_all_versions()",
     RetrieveForModuleCallError => "Error when calling retrieve_for_module()",
+    EntriesCallError           => "Error when calling entries()",
     UpdateCallError            => "Error when calling update()",
+    UpdateAllCallError         => "Error when calling update_all()",
     RetrieveCopyCallError      => "Error when calling retrieve_copy()",
     DeleteCallError            => "Error when calling delete()",
     MoveCallError              => "Error when calling move()",
-    MoveByIdCallError          => "Error when calling move_by_id()"
+    MoveByIdCallError          => "Error when calling move_by_id()",
+    ExportTarCallError         => "Error when calling export_tar()",
+    ImportTarCallError         => "Error when calling import_tar()"
 );
 
 generate_result_helper!(StoreError, StoreErrorKind);
 generate_option_helper!(StoreError, StoreErrorKind);
 
+/// Stable categories of store errors
+///
+/// The `StoreErrorKind`s are an implementation detail and change whenever the store changes.
+/// Integrators who want to react on failures should `match` on the category of an error, see
+/// `StoreError::category()`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum StoreErrorCategory {
+    /// The entry (or a file or header key) does not exist
+    NotFound,
+
+    /// The entry is borrowed or a lock could not be aquired
+    Locked,
+
+    /// The entry (or store path) exists already
+    AlreadyExists,
+
+    /// A hook aborted the operation
+    HookAborted,
+
+    /// Filesystem or IO error
+    Io,
+
+    /// An entry, header or id is malformed
+    Malformed,
+
+    /// The store configuration is invalid
+    Config,
+
+    /// Anything else
+    Other,
+}
+
+impl StoreErrorKind {
+
+    /// Get the category of this kind, `None` for kinds which only wrap other errors, like the
+    /// `*CallError` kinds
+    fn category(&self) -> Option<StoreErrorCategory> {
+        use self::StoreErrorKind::*;
+        use self::StoreErrorCategory as C;
+
+        match *self {
+            ConfigurationError                |
+            ConfigTypeError                   |
+            ConfigKeyMissingError             |
+            ConfigKeyUnloadAspectsError       |
+            ConfigKeyPreCreateAspectsError    |
+            ConfigKeyPostCreateAspectsError   |
+            ConfigKeyPreRetrieveAspectsError  |
+            ConfigKeyPostRetrieveAspectsError |
+            ConfigKeyPreUpdateAspectsError    |
+            ConfigKeyPostUpdateAspectsError   |
+            ConfigKeyPreDeleteAspectsError    |
+            ConfigKeyPostDeleteAspectsError   |
+            HookRegisterError                 |
+            AspectNameNotFoundError           => Some(C::Config),
+
+            IdNotFound                        |
+            FileNotFound                      |
+            HeaderKeyNotFound                 => Some(C::NotFound),
+
+            IdLocked                          |
+            LockError                         |
+            LockPoisoned                      |
+            EntryAlreadyBorrowed              |
+            LockTimeout                       => Some(C::Locked),
+
+            EntryAlreadyExists                |
+            StorePathExists                   => Some(C::AlreadyExists),
+
+            HookExecutionError                |
+            PreHookExecuteError               |
+            PostHookExecuteError              => Some(C::HookAborted),
+
+            CreateStoreDirDenied              |
+            FileError                         |
+            IoError                           |
+            FileNotCreated                    |
+            FileNotWritten                    |
+            FileNotSeeked                     |
+            FileNotRemoved                    |
+            FileNotRenamed                    |
+            FileNotCopied                     |
+            DirNotCreated                     |
+            StorePathCreate                   |
+            GlobError                         |
+            EntryRenameError                  |
+            ArchiveError                      => Some(C::Io),
+
+            MalformedEntry                    |
+            HeaderPathSyntaxError             |
+            HeaderPathTypeFailure             |
+            HeaderTypeFailure                 |
+            HeaderVersionError                |
+            StorePathLacksVersion             |
+            EncodingError                     |
+            StorePathError                    |
+            StoreIdHandlingError              |
+            StoreIdLocalPartAbsoluteError     |
+            StoreIdBuildFromFullPathError     |
+            StoreIdHasNoBaseError             |
+            StoreIdEmptyError                 |
+            StoreIdParentDirError             |
+            StoreIdEmptyComponentError        => Some(C::Malformed),
+
+            OutOfMemory                       |
+            EntryNotBorrowed                  => Some(C::Other),
+
+            CreateCallError                   |
+            RetrieveCallError                 |
+            GetCallError                      |
+            GetAllVersionsCallError           |
+            RetrieveForModuleCallError        |
+            EntriesCallError                  |
+            UpdateCallError                   |
+            UpdateAllCallError                |
+            RetrieveCopyCallError             |
+            DeleteCallError                   |
+            MoveCallError                     |
+            MoveByIdCallError                 |
+            HeaderMigrationError              |
+            ExportTarCallError                |
+            ImportTarCallError                => None,
+        }
+    }
+
+}
+
+impl StoreError {
+
+    /// Get the category of this error
+    ///
+    /// Errors which only wrap other errors (like the errors returned from `Store::create()`) are
+    /// categorized by their cause. If there is no store error as cause, the category is
+    /// `StoreErrorCategory::Other`.
+    pub fn category(&self) -> StoreErrorCategory {
+        match self.err_type.category() {
+            Some(c) => c,
+            None    => self.cause
+                .as_ref()
+                .and_then(|c| c.downcast_ref::<StoreError>())
+                .map(|c| c.category())
+                .unwrap_or(StoreErrorCategory::Other),
+        }
+    }
+
+}
+
 generate_custom_error_types!(ParserError, ParserErrorKind, CustomErrorData,
     TOMLParserErrors    => "Several TOML-Parser-Errors",
     MissingMainSection  => "Missing main section",
@@ -111,6 +270,54 @@ impl From<ParserError> for StoreError {
     }
 }
 
+/// A syntax error in the TOML header of an entry
+///
+/// The line and column are 1-based and, if the header was parsed by `Entry::from_str()`, relative
+/// to the start of the entry, so they point to the offending line in the file.
+#[derive(Debug)]
+pub struct HeaderSyntaxError {
+    position: Option<(usize, usize)>,
+    msg: String,
+}
+
+impl HeaderSyntaxError {
+
+    pub fn new(position: Option<(usize, usize)>, msg: String) -> HeaderSyntaxError {
+        HeaderSyntaxError {
+            position: position,
+            msg: msg,
+        }
+    }
+
+    pub fn line(&self) -> Option<usize> {
+        self.position.map(|(line, _)| line)
+    }
+
+    pub fn column(&self) -> Option<usize> {
+        self.position.map(|(_, col)| col)
+    }
+
+}
+
+impl Display for HeaderSyntaxError {
+
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), FmtError> {
+        match self.position {
+            Some((line, col)) => write!(fmt, "line {}, column {}: {}", line, col, self.msg),
+            None              => write!(fmt, "{}", self.msg),
+        }
+    }
+
+}
+
+impl Error for HeaderSyntaxError {
+
+    fn description(&self) -> &str {
+        "Syntax error in header"
+    }
+
+}
+
 impl From<::std::io::Error> for StoreError {
     fn from(ps: ::std::io::Error) -> StoreError {
         StoreError {
@@ -121,3 +328,44 @@ impl From<::std::io::Error> for StoreError {
     }
 }
 
+#[cfg(test)]
+mod test {
+    use libimagerror::into::IntoError;
+
+    use super::StoreErrorKind as SEK;
+    use super::StoreErrorCategory;
+    use super::StoreError;
+
+    #[test]
+    fn test_category() {
+        assert_eq!(SEK::IdNotFound.into_error().category(), StoreErrorCategory::NotFound);
+        assert_eq!(SEK::EntryAlreadyBorrowed.into_error().category(), StoreErrorCategory::Locked);
+        assert_eq!(SEK::FileNotWritten.into_error().category(), StoreErrorCategory::Io);
+        assert_eq!(SEK::ConfigTypeError.into_error().category(), StoreErrorCategory::Config);
+    }
+
+    #[test]
+    fn test_category_of_wrapped_error() {
+        let e = SEK::CreateCallError.into_error_with_cause(Box::new(SEK::EntryAlreadyExists.into_error()));
+        assert_eq!(e.category(), StoreErrorCategory::AlreadyExists);
+
+        let hook = SEK::HookExecutionError.into_error_with_cause(Box::new(SEK::PreHookExecuteError.into_error()));
+        let e    = SEK::DeleteCallError.into_error_with_cause(Box::new(hook));
+        assert_eq!(e.category(), StoreErrorCategory::HookAborted);
+    }
+
+    #[test]
+    fn test_category_without_store_error_cause() {
+        use std::io::{Error, ErrorKind};
+
+        assert_eq!(SEK::GetCallError.into_error().category(), StoreErrorCategory::Other);
+
+        let io = Error::new(ErrorKind::Other, "io");
+        let e  = SEK::UpdateCallError.into_error_with_cause(Box::new(io));
+        assert_eq!(e.category(), StoreErrorCategory::Other);
+
+        let io = Error::new(ErrorKind::Other, "io");
+        assert_eq!(StoreError::from(io).category(), StoreErrorCategory::Io);
+    }
+
+}
@@ -17,197 +17,224 @@
 // Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
 //
 
-pub use self::fs::FileAbstraction;
+//! Abstraction over the place the files of a store live in
+//!
+//! A store keeps its entries either as files on the filesystem or in a map in memory, which is
+//! what tests use. The `Backend` decides which one is used and hands out `FileAbstraction` objects
+//! for single files.
 
-// TODO:
-// This whole thing can be written better with a trait based mechanism that is embedded into the
-// store. However it would mean rewriting most things to be generic which can be a pain in the ass.
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+use std::fmt::Error as FmtError;
+use std::fs::{File, OpenOptions, create_dir_all, remove_file, copy, rename};
+use std::io::{Cursor, Seek, SeekFrom, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 
-#[cfg(test)]
-mod fs {
-    use error::StoreError as SE;
-    use error::StoreErrorKind as SEK;
-    use std::io::Cursor;
-    use std::path::PathBuf;
-    use std::collections::HashMap;
-    use std::sync::Mutex;
+use libimagerror::into::IntoError;
 
-    use libimagerror::into::IntoError;
+use error::{MapErrInto, StoreError as SE, StoreErrorKind as SEK};
 
-    use error::MapErrInto;
+/// The files of an in-memory store, shared between the `Backend` and all `FileAbstraction`s
+#[derive(Clone)]
+pub struct InMemoryFiles(Arc<Mutex<HashMap<PathBuf, Vec<u8>>>>);
 
-    lazy_static! {
-        static ref MAP: Mutex<HashMap<PathBuf, Cursor<Vec<u8>>>> = {
-            Mutex::new(HashMap::new())
-        };
-    }
+impl Debug for InMemoryFiles {
 
-    /// `FileAbstraction` type, this is the Test version!
-    ///
-    /// A lazy file is either absent, but a path to it is available, or it is present.
-    #[derive(Debug)]
-    pub enum FileAbstraction {
-        Absent(PathBuf),
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), FmtError> {
+        write!(fmt, "InMemoryFiles")
     }
 
-    impl FileAbstraction {
-
-        /**
-         * Get the mutable file behind a FileAbstraction object
-         */
-        pub fn get_file_content(&mut self) -> Result<Cursor<Vec<u8>>, SE> {
-            debug!("Getting lazy file: {:?}", self);
-            match *self {
-                FileAbstraction::Absent(ref f) => {
-                    let map = try!(MAP.lock().map_err_into(SEK::LockPoisoned));
-                    return map.get(f).cloned().ok_or(SEK::FileNotFound.into_error());
-                },
-            };
-        }
+}
 
-        pub fn write_file_content(&mut self, buf: &[u8]) -> Result<(), SE> {
-            match *self {
-                FileAbstraction::Absent(ref f) => {
-                    let mut map = try!(MAP.lock().map_err_into(SEK::LockPoisoned));
-                    if let Some(ref mut cur) = map.get_mut(f) {
-                        let mut vec = cur.get_mut();
-                        vec.clear();
-                        vec.extend_from_slice(buf);
-                        return Ok(());
-                    }
-                    let vec = Vec::from(buf);
-                    map.insert(f.clone(), Cursor::new(vec));
-                    return Ok(());
-                },
-            };
-        }
+impl InMemoryFiles {
 
-        pub fn remove_file(path: &PathBuf) -> Result<(), SE> {
-            try!(MAP.lock().map_err_into(SEK::LockPoisoned))
-                .remove(path)
-                .map(|_| ())
-                .ok_or(SEK::FileNotFound.into_error())
-        }
+    fn with_map<T, F>(&self, f: F) -> Result<T, SE>
+        where F: FnOnce(&mut HashMap<PathBuf, Vec<u8>>) -> Result<T, SE>
+    {
+        let mut map = try!(self.0.lock().map_err(|_| SE::new(SEK::LockPoisoned, None)));
+        f(&mut map)
+    }
 
-        pub fn copy(from: &PathBuf, to: &PathBuf) -> Result<(), SE> {
-            let mut map = try!(MAP.lock().map_err_into(SEK::LockPoisoned));
-            let a = try!(map.get(from).cloned().ok_or(SEK::FileNotFound.into_error()));
-            map.insert(to.clone(), a);
-            Ok(())
-        }
+    /// Get the paths of all files below `dir`, sorted
+    pub fn files_below(&self, dir: &Path) -> Result<Vec<PathBuf>, SE> {
+        self.with_map(|map| {
+            let mut v : Vec<PathBuf> = map.keys()
+                .filter(|p| p.starts_with(dir) && p.as_path() != dir)
+                .cloned()
+                .collect();
+            v.sort();
+            Ok(v)
+        })
+    }
 
-        pub fn rename(from: &PathBuf, to: &PathBuf) -> Result<(), SE> {
-            let mut map = try!(MAP.lock().map_err_into(SEK::LockPoisoned));
-            let a = try!(map.get(from).cloned().ok_or(SEK::FileNotFound.into_error()));
-            map.insert(to.clone(), a);
-            Ok(())
-        }
+}
 
-        pub fn create_dir_all(_: &PathBuf) -> Result<(), SE> {
-            Ok(())
-        }
-    }
+/// The place the files of a store live in
+#[derive(Debug, Clone)]
+pub enum Backend {
+    FileSystem,
+    InMemory(InMemoryFiles),
 }
 
-#[cfg(not(test))]
-mod fs {
-    use error::{MapErrInto, StoreError as SE, StoreErrorKind as SEK};
-    use std::io::{Seek, SeekFrom, Read};
-    use std::path::{Path, PathBuf};
-    use std::fs::{File, OpenOptions, create_dir_all, remove_file, copy, rename};
+impl Backend {
 
-    /// `FileAbstraction` type
-    ///
-    /// A lazy file is either absent, but a path to it is available, or it is present.
-    #[derive(Debug)]
-    pub enum FileAbstraction {
-        Absent(PathBuf),
-        File(File, PathBuf)
+    /// Create a new, empty in-memory backend
+    pub fn in_memory() -> Backend {
+        Backend::InMemory(InMemoryFiles(Arc::new(Mutex::new(HashMap::new()))))
     }
 
-    fn open_file<A: AsRef<Path>>(p: A) -> ::std::io::Result<File> {
-        OpenOptions::new().write(true).read(true).open(p)
+    /// Get the (not yet opened) file at `path`
+    pub fn file(&self, path: PathBuf) -> FileAbstraction {
+        match *self {
+            Backend::FileSystem          => FileAbstraction::Absent(path),
+            Backend::InMemory(ref files) => FileAbstraction::InMemory(path, files.clone()),
+        }
     }
 
-    fn create_file<A: AsRef<Path>>(p: A) -> ::std::io::Result<File> {
-        if let Some(parent) = p.as_ref().parent() {
-            debug!("Implicitely creating directory: {:?}", parent);
-            if let Err(e) = create_dir_all(parent) {
-                return Err(e);
-            }
+    pub fn remove_file(&self, path: &PathBuf) -> Result<(), SE> {
+        match *self {
+            Backend::FileSystem => remove_file(path).map_err_into(SEK::FileNotRemoved),
+            Backend::InMemory(ref files) => files.with_map(|map| {
+                map.remove(path)
+                    .map(|_| ())
+                    .ok_or(SEK::FileNotFound.into_error())
+                    .map_err_into(SEK::FileNotRemoved)
+            }),
         }
-        OpenOptions::new().write(true).read(true).create(true).open(p)
-    }
-
-    impl FileAbstraction {
-
-        /**
-         * Get the content behind this file
-         */
-        pub fn get_file_content(&mut self) -> Result<&mut Read, SE> {
-            debug!("Getting lazy file: {:?}", self);
-            let (file, path) = match *self {
-                FileAbstraction::File(ref mut f, _) => return {
-                    // We seek to the beginning of the file since we expect each
-                    // access to the file to be in a different context
-                    try!(f.seek(SeekFrom::Start(0))
-                        .map_err_into(SEK::FileNotSeeked));
-                    Ok(f)
-                },
-                FileAbstraction::Absent(ref p) => (try!(open_file(p).map_err_into(SEK::FileNotFound)),
-                                            p.clone()),
-            };
-            *self = FileAbstraction::File(file, path);
-            if let FileAbstraction::File(ref mut f, _) = *self {
-                return Ok(f);
-            }
-            unreachable!()
+    }
+
+    pub fn copy(&self, from: &PathBuf, to: &PathBuf) -> Result<(), SE> {
+        match *self {
+            Backend::FileSystem => copy(from, to).map_err_into(SEK::FileNotCopied).map(|_| ()),
+            Backend::InMemory(ref files) => files.with_map(|map| {
+                let content = try!(map.get(from)
+                    .cloned()
+                    .ok_or(SEK::FileNotFound.into_error())
+                    .map_err_into(SEK::FileNotCopied));
+                map.insert(to.clone(), content);
+                Ok(())
+            }),
         }
+    }
 
-        /**
-         * Write the content of this file
-         */
-        pub fn write_file_content(&mut self, buf: &[u8]) -> Result<(), SE> {
-            use std::io::Write;
-            let (file, path) = match *self {
-                FileAbstraction::File(ref mut f, _) => return {
-                    // We seek to the beginning of the file since we expect each
-                    // access to the file to be in a different context
-                    try!(f.seek(SeekFrom::Start(0))
-                        .map_err_into(SEK::FileNotCreated));
-                    f.write_all(buf).map_err_into(SEK::FileNotWritten)
-                },
-                FileAbstraction::Absent(ref p) => (try!(create_file(p).map_err_into(SEK::FileNotCreated)),
-                                            p.clone()),
-            };
-            *self = FileAbstraction::File(file, path);
-            if let FileAbstraction::File(ref mut f, _) = *self {
-                return f.write_all(buf).map_err_into(SEK::FileNotWritten);
-            }
-            unreachable!();
+    pub fn rename(&self, from: &PathBuf, to: &PathBuf) -> Result<(), SE> {
+        match *self {
+            Backend::FileSystem => rename(from, to).map_err_into(SEK::FileNotRenamed),
+            Backend::InMemory(ref files) => files.with_map(|map| {
+                let content = try!(map.remove(from)
+                    .ok_or(SEK::FileNotFound.into_error())
+                    .map_err_into(SEK::FileNotRenamed));
+                map.insert(to.clone(), content);
+                Ok(())
+            }),
         }
+    }
 
-        pub fn remove_file(path: &PathBuf) -> Result<(), SE> {
-            remove_file(path).map_err_into(SEK::FileNotRemoved)
+    pub fn create_dir_all(&self, path: &PathBuf) -> Result<(), SE> {
+        match *self {
+            Backend::FileSystem  => create_dir_all(path).map_err_into(SEK::DirNotCreated),
+            Backend::InMemory(_) => Ok(()), // directories are implicit in memory
         }
+    }
+
+}
 
-        pub fn copy(from: &PathBuf, to: &PathBuf) -> Result<(), SE> {
-            copy(from, to).map_err_into(SEK::FileNotCopied).map(|_| ())
+/// `FileAbstraction` type
+///
+/// A lazy file is either absent, but a path to it is available, or it is present. Files of an
+/// in-memory store are never opened, they are read from and written to the map directly.
+#[derive(Debug)]
+pub enum FileAbstraction {
+    Absent(PathBuf),
+    File(File, PathBuf),
+    InMemory(PathBuf, InMemoryFiles),
+}
+
+fn open_file<A: AsRef<Path>>(p: A) -> ::std::io::Result<File> {
+    OpenOptions::new().write(true).read(true).open(p)
+}
+
+fn create_file<A: AsRef<Path>>(p: A) -> ::std::io::Result<File> {
+    if let Some(parent) = p.as_ref().parent() {
+        debug!("Implicitely creating directory: {:?}", parent);
+        if let Err(e) = create_dir_all(parent) {
+            return Err(e);
         }
+    }
+    OpenOptions::new().write(true).read(true).create(true).truncate(true).open(p)
+}
 
-        pub fn rename(from: &PathBuf, to: &PathBuf) -> Result<(), SE> {
-            rename(from, to).map_err_into(SEK::FileNotRenamed)
+/// Replace the content of the opened file `f` with `buf`
+///
+/// The new content might be shorter than the old one, so the file is truncated before it is
+/// written.
+fn rewrite_file(f: &mut File, buf: &[u8]) -> ::std::io::Result<()> {
+    // We seek to the beginning of the file since we expect each
+    // access to the file to be in a different context
+    try!(f.seek(SeekFrom::Start(0)));
+    try!(f.set_len(0));
+    f.write_all(buf)
+}
+
+impl FileAbstraction {
+
+    /**
+     * Get the content behind this file
+     */
+    pub fn get_file_content<'a>(&'a mut self) -> Result<Box<Read + 'a>, SE> {
+        debug!("Getting lazy file: {:?}", self);
+        let (file, path) = match *self {
+            FileAbstraction::File(ref mut f, _) => return {
+                // We seek to the beginning of the file since we expect each
+                // access to the file to be in a different context
+                try!(f.seek(SeekFrom::Start(0))
+                    .map_err_into(SEK::FileNotSeeked));
+                Ok(Box::new(f) as Box<Read + 'a>)
+            },
+            FileAbstraction::InMemory(ref p, ref files) => return files.with_map(|map| {
+                map.get(p)
+                    .cloned()
+                    .map(|content| Box::new(Cursor::new(content)) as Box<Read>)
+                    .ok_or(SEK::FileNotFound.into_error())
+            }),
+            FileAbstraction::Absent(ref p) => (try!(open_file(p).map_err_into(SEK::FileNotFound)),
+                                        p.clone()),
+        };
+        *self = FileAbstraction::File(file, path);
+        if let FileAbstraction::File(ref mut f, _) = *self {
+            return Ok(Box::new(f) as Box<Read + 'a>);
         }
+        unreachable!()
+    }
 
-        pub fn create_dir_all(path: &PathBuf) -> Result<(), SE> {
-            create_dir_all(path).map_err_into(SEK::DirNotCreated)
+    /**
+     * Write the content of this file
+     */
+    pub fn write_file_content(&mut self, buf: &[u8]) -> Result<(), SE> {
+        let (file, path) = match *self {
+            FileAbstraction::File(ref mut f, _) => return {
+                rewrite_file(f, buf).map_err_into(SEK::FileNotWritten)
+            },
+            FileAbstraction::InMemory(ref p, ref files) => return files.with_map(|map| {
+                map.insert(p.clone(), Vec::from(buf));
+                Ok(())
+            }),
+            FileAbstraction::Absent(ref p) => (try!(create_file(p).map_err_into(SEK::FileNotCreated)),
+                                        p.clone()),
+        };
+        *self = FileAbstraction::File(file, path);
+        if let FileAbstraction::File(ref mut f, _) = *self {
+            return f.write_all(buf).map_err_into(SEK::FileNotWritten);
         }
+        unreachable!();
     }
+
 }
 
 #[cfg(test)]
 mod test {
-    use super::FileAbstraction;
+    use super::Backend;
     use std::io::Read;
     use std::path::PathBuf;
 
@@ -215,11 +242,58 @@ mod test {
     fn lazy_file() {
         let mut path = PathBuf::from("/tests");
         path.set_file_name("test1");
-        let mut lf = FileAbstraction::Absent(path);
+        let mut lf = Backend::in_memory().file(path);
         lf.write_file_content(b"Hello World").unwrap();
         let mut bah = Vec::new();
         lf.get_file_content().unwrap().read_to_end(&mut bah).unwrap();
         assert_eq!(bah, b"Hello World");
     }
 
+    #[test]
+    fn in_memory_files_are_shared() {
+        let backend = Backend::in_memory();
+        let a       = PathBuf::from("/store/a");
+        let b       = PathBuf::from("/store/b");
+
+        backend.file(a.clone()).write_file_content(b"content").unwrap();
+        backend.rename(&a, &b).unwrap();
+
+        let mut s = String::new();
+        backend.file(b).get_file_content().unwrap().read_to_string(&mut s).unwrap();
+        assert_eq!(s, "content");
+        assert!(backend.file(a).get_file_content().is_err());
+    }
+
+    #[test]
+    fn rewritten_file_is_truncated() {
+        use std::fs::{File, OpenOptions};
+        use tempdir::TempDir;
+        use super::rewrite_file;
+
+        let tmp   = TempDir::new("imag-store-test").unwrap();
+        let path  = tmp.path().join("file");
+        let mut f = OpenOptions::new().write(true).read(true).create(true).open(&path).unwrap();
+
+        rewrite_file(&mut f, b"long content").unwrap();
+        rewrite_file(&mut f, b"short").unwrap();
+
+        let mut s = String::new();
+        File::open(&path).unwrap().read_to_string(&mut s).unwrap();
+        assert_eq!(s, "short");
+    }
+
+    #[test]
+    fn fs_file_is_truncated_on_rewrite() {
+        use tempdir::TempDir;
+
+        let tmp    = TempDir::new("imag-store-test").unwrap();
+        let mut lf = Backend::FileSystem.file(tmp.path().join("file"));
+        lf.write_file_content(b"long content").unwrap();
+        lf.write_file_content(b"short").unwrap();
+
+        let mut s = String::new();
+        lf.get_file_content().unwrap().read_to_string(&mut s).unwrap();
+        assert_eq!(s, "short");
+    }
+
 }
@@ -23,6 +23,7 @@ use libimagutil::iter::FoldResult;
 use store::FileLockEntry;
 use storeid::StoreId;
 use hook::Hook;
+use hook::position::HookPosition;
 use hook::result::HookResult;
 use hook::accessor::{StoreIdAccessor, MutableHookDataAccessor, NonMutableHookDataAccessor};
 use hook::accessor::HookDataAccessor as HDA;
@@ -56,6 +57,32 @@ impl Aspect {
         self.hooks.push(h);
     }
 
+    /// Whether the aspect is configured to run its hooks in parallel
+    pub fn is_parallel(&self) -> bool {
+        self.cfg.as_ref().map(|c| c.parallel()).unwrap_or(false)
+    }
+
+    /// Get the names of the hooks registered in this aspect, in the order they are executed
+    pub fn hook_names(&self) -> Vec<String> {
+        self.hooks.iter().map(|h| String::from(h.name())).collect()
+    }
+
+}
+
+/// Information about an aspect registered in the store, see `Store::registered_aspects()`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AspectInfo {
+    /// The position the aspect is executed at
+    pub position: HookPosition,
+
+    /// The name of the aspect
+    pub name: String,
+
+    /// Whether the aspect is configured to run its hooks in parallel
+    pub parallel: bool,
+
+    /// The names of the hooks in the aspect, in the order they are executed
+    pub hooks: Vec<String>,
 }
 
 impl StoreIdAccessor for Aspect {
@@ -17,7 +17,7 @@
 // Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
 //
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum HookPosition {
     StoreUnload,
 
@@ -29,4 +29,16 @@ pub enum HookPosition {
     PostUpdate,
     PreDelete,
     PostDelete,
+
+    // Hooks for `Store::retrieve_copy()`, which only get the StoreId, as the copy cannot be used to
+    // alter the entry
+    PreRetrieveCopy,
+    PostRetrieveCopy,
+
+    // Hooks for `Store::save_to()` and `Store::save_as()`. They only get the StoreId, the pre
+    // hooks the one of the saved entry, the post hooks the one of the new location.
+    PreSaveTo,
+    PostSaveTo,
+    PreSaveAs,
+    PostSaveAs,
 }
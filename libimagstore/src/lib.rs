@@ -44,6 +44,7 @@ extern crate crossbeam;
 extern crate walkdir;
 extern crate itertools;
 #[macro_use] extern crate is_match;
+#[cfg(feature = "tar")] extern crate tar;
 
 #[macro_use] extern crate libimagerror;
 extern crate libimagutil;
@@ -57,4 +58,6 @@ pub mod store;
 mod configuration;
 mod file_abstraction;
 pub mod toml_ext;
+pub mod migration;
+#[cfg(feature = "tar")] mod archive;
 
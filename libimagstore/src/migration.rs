@@ -0,0 +1,183 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015, 2016 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Migrations of entry headers between versions of imag
+//!
+//! Each entry has its `imag.version` in the header. If the layout of the header changes, a module
+//! can bring old headers up to date with `migrate_header()`, passing one `Migration` for each
+//! step. This can be done by hand or from a hook, for example a post-retrieve hook.
+
+use std::fmt::{Debug, Formatter};
+use std::fmt::Error as FmtError;
+
+use semver::Version;
+use toml::Value;
+
+use libimagerror::into::IntoError;
+
+use error::StoreErrorKind as SEK;
+use error::MapErrInto;
+use store::Result;
+use toml_ext::TomlValueExt;
+
+/// A step which transforms a header of version `from_version` to a header of version `to_version`
+pub struct Migration {
+    from_version: Version,
+    to_version: Version,
+    transform: Box<Fn(&mut Value) -> Result<()>>,
+}
+
+impl Migration {
+
+    /// Create a new Migration
+    ///
+    /// `transform` only has to change the header, `imag.version` is set to `to_version` by
+    /// `migrate_header()` afterwards.
+    pub fn new<F>(from_version: Version, to_version: Version, transform: F) -> Migration
+        where F: Fn(&mut Value) -> Result<()> + 'static
+    {
+        Migration {
+            from_version: from_version,
+            to_version: to_version,
+            transform: Box::new(transform),
+        }
+    }
+
+    pub fn from_version(&self) -> &Version {
+        &self.from_version
+    }
+
+    pub fn to_version(&self) -> &Version {
+        &self.to_version
+    }
+
+}
+
+impl Debug for Migration {
+
+    fn fmt(&self, fmt: &mut Formatter) -> ::std::result::Result<(), FmtError> {
+        write!(fmt, "Migration({} -> {})", self.from_version, self.to_version)
+    }
+
+}
+
+/// Migrate `header` by applying `migrations` in order
+///
+/// A migration is applied if its `from_version` equals the `imag.version` of the header at that
+/// point, so migrations can be chained. Migrations which do not match are skipped.
+///
+/// # Return value
+///
+/// On success: Whether at least one migration was applied
+///
+/// On failure:
+///  - HeaderVersionError if the header has no valid `imag.version`
+///  - HeaderMigrationError if a transformation failed. The header might be partially migrated in
+///    this case.
+///
+pub fn migrate_header(header: &mut Value, migrations: &[Migration]) -> Result<bool> {
+    let mut changed = false;
+
+    for migration in migrations {
+        let version = try!(header_version(header));
+        if version != migration.from_version {
+            continue;
+        }
+
+        debug!("Migrating header: {:?}", migration);
+        try!((migration.transform)(header).map_err_into(SEK::HeaderMigrationError));
+
+        let to = Value::String(format!("{}", migration.to_version));
+        try!(header.set("imag.version", to).map_err_into(SEK::HeaderMigrationError));
+        changed = true;
+    }
+
+    Ok(changed)
+}
+
+fn header_version(header: &Value) -> Result<Version> {
+    header.read_string("imag.version")
+        .map_err_into(SEK::HeaderVersionError)
+        .and_then(|v| v.ok_or(SEK::HeaderVersionError.into_error()))
+        .and_then(|v| Version::parse(&v).map_err_into(SEK::HeaderVersionError))
+}
+
+#[cfg(test)]
+mod test {
+    use semver::Version;
+    use toml::Value;
+
+    use toml_ext::TomlValueExt;
+    use super::*;
+
+    fn header(version: &str) -> Value {
+        format!("[imag]\nversion = \"{}\"\n\n[note]\ntitle = \"a title\"\n", version)
+            .parse()
+            .unwrap()
+    }
+
+    fn v(s: &str) -> Version {
+        Version::parse(s).unwrap()
+    }
+
+    fn migrations() -> Vec<Migration> {
+        vec![
+            // rename "note.title" to "note.name"
+            Migration::new(v("0.0.2"), v("0.0.3-alpha"), |header| {
+                let title = try!(header.delete("note.title"));
+                header.insert("note.name", title.unwrap_or(Value::String(String::new())))
+                    .map(|_| ())
+            }),
+
+            // add "note.tags"
+            Migration::new(v("0.0.3-alpha"), v("0.0.3"), |header| {
+                header.insert("note.tags", Value::Array(vec![])).map(|_| ())
+            }),
+        ]
+    }
+
+    #[test]
+    fn test_chained_migrations() {
+        let mut header = header("0.0.2");
+        assert!(migrate_header(&mut header, &migrations()).unwrap());
+
+        assert_eq!(header.read_string("imag.version").unwrap(), Some(String::from("0.0.3")));
+        assert_eq!(header.read_string("note.name").unwrap(), Some(String::from("a title")));
+        assert!(!header.has_key("note.title"));
+        assert_eq!(header.read("note.tags").unwrap(), Some(Value::Array(vec![])));
+    }
+
+    #[test]
+    fn test_up_to_date_header_is_unchanged() {
+        let mut header = header("0.0.3");
+        let before     = header.clone();
+        assert!(!migrate_header(&mut header, &migrations()).unwrap());
+        assert_eq!(header, before);
+    }
+
+    #[test]
+    fn test_header_without_version_fails() {
+        use error::StoreErrorKind as SEK;
+
+        let mut header = Value::Table(::std::collections::BTreeMap::new());
+        let res        = migrate_header(&mut header, &migrations());
+        assert_eq!(res.unwrap_err().err_type(), SEK::HeaderVersionError);
+    }
+
+}
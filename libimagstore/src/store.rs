@@ -18,7 +18,10 @@
 //
 
 use std::collections::HashMap;
+use std::collections::BTreeMap;
+use std::collections::btree_map::IntoIter as BTreeMapIntoIter;
 use std::ops::Drop;
+use std::path::Path;
 use std::path::PathBuf;
 use std::result::Result as RResult;
 use std::sync::Arc;
@@ -27,6 +30,8 @@ use std::io::Read;
 use std::convert::From;
 use std::convert::Into;
 use std::sync::Mutex;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
 use std::ops::Deref;
 use std::ops::DerefMut;
 use std::fmt::Formatter;
@@ -36,16 +41,19 @@ use std::fmt::Error as FMTError;
 use toml::Value;
 use regex::Regex;
 use glob::glob;
+use glob::glob_with;
+use glob::MatchOptions;
 use walkdir::WalkDir;
 use walkdir::Iter as WalkDirIter;
 
-use error::{StoreError as SE, StoreErrorKind as SEK};
+use error::{StoreError as SE, StoreErrorKind as SEK, StoreErrorCategory};
 use error::MapErrInto;
 use storeid::{IntoStoreId, StoreId, StoreIdIterator};
-use file_abstraction::FileAbstraction;
+use file_abstraction::{Backend, FileAbstraction, InMemoryFiles};
 use toml_ext::*;
 
 use hook::aspect::Aspect;
+use hook::aspect::AspectInfo;
 use hook::error::HookErrorKind;
 use hook::result::HookResult;
 use hook::accessor::{ MutableHookDataAccessor,
@@ -60,6 +68,8 @@ use libimagutil::debug_result::*;
 
 use self::glob_store_iter::*;
 
+pub use self::glob_store_iter::GlobStoreIdResultIterator;
+
 /// The Result Type returned by any interaction with the store that could fail
 pub type Result<T> = RResult<T, SE>;
 
@@ -86,7 +96,17 @@ pub enum StoreObject {
 
 pub struct Walk {
     store_path: PathBuf,
-    dirwalker: WalkDirIter,
+    walker: Walker,
+
+    /// Whether collections are yielded relative to the store path, see `Walk::relative()`
+    relative: bool,
+}
+
+enum Walker {
+    Dir(WalkDirIter),
+
+    /// The paths below the module directory, mapped to whether they are a collection
+    InMemory(BTreeMapIntoIter<PathBuf, bool>),
 }
 
 impl Walk {
@@ -96,16 +116,49 @@ impl Walk {
         store_path.push(mod_name);
         Walk {
             store_path: pb,
-            dirwalker: WalkDir::new(store_path).into_iter(),
+            walker: Walker::Dir(WalkDir::new(store_path).into_iter()),
+            relative: false,
         }
     }
-}
 
-impl ::std::ops::Deref for Walk {
-    type Target = WalkDirIter;
+    fn in_memory(store_path: PathBuf, mod_name: &str, files: &InMemoryFiles) -> Walk {
+        let mut module_path = store_path.clone();
+        module_path.push(mod_name);
 
-    fn deref(&self) -> &Self::Target {
-        &self.dirwalker
+        let paths = files.files_below(&module_path).unwrap_or_else(|e| {
+            trace_error(&e);
+            vec![]
+        });
+
+        // Directories are implicit in memory, so each parent of a file is a collection, up to
+        // and including the module directory
+        let mut objects = BTreeMap::new();
+        for path in paths {
+            let mut dir = path.parent().map(Path::to_path_buf);
+            while let Some(d) = dir {
+                if !d.starts_with(&module_path) {
+                    break;
+                }
+                dir = d.parent().map(Path::to_path_buf);
+                objects.insert(d, true);
+            }
+            objects.insert(path, false);
+        }
+
+        Walk {
+            store_path: store_path,
+            walker: Walker::InMemory(objects.into_iter()),
+            relative: false,
+        }
+    }
+
+    /// Yield collections relative to the store path, like `module/sub`
+    ///
+    /// By default, `StoreObject::Collection` holds the absolute path of the collection, which
+    /// includes the location of the store.
+    pub fn relative(mut self) -> Walk {
+        self.relative = true;
+        self
     }
 }
 
@@ -113,41 +166,54 @@ impl Iterator for Walk {
     type Item = StoreObject;
 
     fn next(&mut self) -> Option<Self::Item> {
-        while let Some(something) = self.dirwalker.next() {
-            match something {
-                Ok(next) => if next.file_type().is_dir() {
-                                return Some(StoreObject::Collection(next.path().to_path_buf()))
-                            } else if next.file_type().is_file() {
-                                let n   = next.path().to_path_buf();
-                                let sid = match StoreId::new(Some(self.store_path.clone()), n) {
-                                    Err(e) => {
-                                        trace_error(&e);
-                                        continue;
-                                    },
-                                    Ok(o) => o,
-                                };
-                                return Some(StoreObject::Id(sid))
-                            },
-                Err(e) => {
-                    warn!("Error in Walker");
-                    debug!("{:?}", e);
-                    return None;
+        loop {
+            let (path, is_dir) = match self.walker {
+                Walker::Dir(ref mut dirwalker) => match dirwalker.next() {
+                    Some(Ok(next)) => if next.file_type().is_dir() {
+                                          (next.path().to_path_buf(), true)
+                                      } else if next.file_type().is_file() {
+                                          (next.path().to_path_buf(), false)
+                                      } else {
+                                          continue;
+                                      },
+                    Some(Err(e)) => {
+                        warn!("Error in Walker");
+                        debug!("{:?}", e);
+                        return None;
+                    },
+                    None => return None,
+                },
+                Walker::InMemory(ref mut objects) => match objects.next() {
+                    Some(next) => next,
+                    None       => return None,
+                },
+            };
+
+            if is_dir {
+                if self.relative {
+                    if let Ok(rel) = path.strip_prefix(&self.store_path) {
+                        return Some(StoreObject::Collection(rel.to_path_buf()));
+                    }
                 }
+                return Some(StoreObject::Collection(path));
             }
-        }
 
-        return None;
+            match StoreId::from_full_path(&self.store_path, path) {
+                Err(e) => trace_error(&e),
+                Ok(id) => return Some(StoreObject::Id(id)),
+            }
+        }
     }
 }
 
 
 impl StoreEntry {
 
-    fn new(id: StoreId) -> Result<StoreEntry> {
+    fn new(id: StoreId, backend: &Backend) -> Result<StoreEntry> {
         let pb = try!(id.clone().into_pathbuf());
         Ok(StoreEntry {
             id: id,
-            file: FileAbstraction::Absent(pb),
+            file: backend.file(pb),
             status: StoreEntryStatus::Present,
         })
     }
@@ -174,16 +240,41 @@ impl StoreEntry {
         }
     }
 
-    fn write_entry(&mut self, entry: &Entry) -> Result<()> {
+    /// Write the serialized entry to its file, unless the file already has exactly this content
+    ///
+    /// Skipping unchanged entries keeps the modification time of the file, so tools which watch
+    /// the store (like a VCS) do not see changes where there are none. This costs reading the
+    /// file back, use `StoreEntry::write_bytes()` if it is known to differ from `bytes`.
+    fn write_entry(&mut self, bytes: &[u8]) -> Result<()> {
+        if self.is_borrowed() && self.has_content(bytes) {
+            debug!("Entry {} is unchanged, not writing it", self.id);
+            return Ok(());
+        }
+
+        self.write_bytes(bytes)
+    }
+
+    /// Write the serialized entry to its file, without comparing it to the file first
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<()> {
         if self.is_borrowed() {
-            assert_eq!(self.id, entry.location);
-            self.file.write_file_content(entry.to_str().as_bytes())
+            self.file.write_file_content(bytes)
                 .map_err_into(SEK::FileError)
                 .map(|_| ())
         } else {
             Ok(())
         }
     }
+
+    /// Check whether the file of the entry exists and contains exactly `bytes`
+    ///
+    /// This reads the whole file.
+    fn has_content(&mut self, bytes: &[u8]) -> bool {
+        let mut current = vec![];
+        match self.file.get_file_content() {
+            Ok(mut content) => content.read_to_end(&mut current).is_ok() && &current[..] == bytes,
+            Err(_)          => false,
+        }
+    }
 }
 
 /// The Store itself, through this object one can interact with IMAG's entries
@@ -211,6 +302,12 @@ pub struct Store {
     post_delete_aspects   : Arc<Mutex<Vec<Aspect>>>,
     pre_move_aspects      : Arc<Mutex<Vec<Aspect>>>,
     post_move_aspects     : Arc<Mutex<Vec<Aspect>>>,
+    pre_retrieve_copy_aspects  : Arc<Mutex<Vec<Aspect>>>,
+    post_retrieve_copy_aspects : Arc<Mutex<Vec<Aspect>>>,
+    pre_save_to_aspects   : Arc<Mutex<Vec<Aspect>>>,
+    post_save_to_aspects  : Arc<Mutex<Vec<Aspect>>>,
+    pre_save_as_aspects   : Arc<Mutex<Vec<Aspect>>>,
+    post_save_as_aspects  : Arc<Mutex<Vec<Aspect>>>,
 
     ///
     /// Internal Path->File cache map
@@ -220,6 +317,20 @@ pub struct Store {
     /// Could be optimized for a threadsafe HashMap
     ///
     entries: Arc<RwLock<HashMap<StoreId, StoreEntry>>>,
+
+    /// If true, operations which write to the filesystem only log what they would do
+    dry_run: bool,
+
+    /// If true, the update hooks are not executed for entries which did not change
+    skip_hooks_when_unchanged: bool,
+
+    /// File extensions which are appended to the ids of entries of a module, by module name
+    ///
+    /// Empty unless configured, see `configuration::config_module_extensions()`.
+    module_extensions: HashMap<String, String>,
+
+    /// Where the files of the store live, on the filesystem or in memory
+    backend: Backend,
 }
 
 impl Store {
@@ -265,7 +376,7 @@ impl Store {
                     .map_err_into(SEK::IoError);
             }
 
-            try!(FileAbstraction::create_dir_all(&location)
+            try!(Backend::FileSystem.create_dir_all(&location)
                  .map_err_into(SEK::StorePathCreate)
                  .map_dbg_err_str("Failed"));
         } else if location.is_file() {
@@ -273,6 +384,35 @@ impl Store {
             return Err(SEK::StorePathExists.into_error());
         }
 
+        Store::new_with_backend(location, store_config, Backend::FileSystem)
+    }
+
+    /// Create a new Store object which keeps all files in memory
+    ///
+    /// Nothing is read from or written to the filesystem, `location` is only used to build the
+    /// paths of the entries. Apart from that, the store behaves like one created with
+    /// `Store::new()`, so this is meant for tests.
+    ///
+    /// # Return values
+    ///
+    /// - On success: Store object
+    /// - On Failure:
+    ///   - ConfigurationError if config is faulty
+    pub fn new_in_memory(location: PathBuf, store_config: Option<Value>) -> Result<Store> {
+        use configuration::config_is_valid;
+
+        debug!("Validating Store configuration");
+        let _ = try!(config_is_valid(&store_config).map_err_into(SEK::ConfigurationError));
+
+        debug!("Building new in-memory Store object");
+        Store::new_with_backend(location, store_config, Backend::in_memory())
+    }
+
+    fn new_with_backend(location: PathBuf, store_config: Option<Value>, backend: Backend)
+        -> Result<Store>
+    {
+        use configuration::*;
+
         let store_unload_aspects = get_store_unload_aspect_names(&store_config)
             .into_iter().map(|n| {
                 let cfg = AspectConfig::get_for(&store_config, n.clone());
@@ -339,6 +479,45 @@ impl Store {
                 Aspect::new(n, cfg)
             }).collect();
 
+        let pre_retrieve_copy_aspects = get_pre_retrieve_copy_aspect_names(&store_config)
+            .into_iter().map(|n| {
+                let cfg = AspectConfig::get_for(&store_config, n.clone());
+                Aspect::new(n, cfg)
+            }).collect();
+
+        let post_retrieve_copy_aspects = get_post_retrieve_copy_aspect_names(&store_config)
+            .into_iter().map(|n| {
+                let cfg = AspectConfig::get_for(&store_config, n.clone());
+                Aspect::new(n, cfg)
+            }).collect();
+
+        let pre_save_to_aspects = get_pre_save_to_aspect_names(&store_config)
+            .into_iter().map(|n| {
+                let cfg = AspectConfig::get_for(&store_config, n.clone());
+                Aspect::new(n, cfg)
+            }).collect();
+
+        let post_save_to_aspects = get_post_save_to_aspect_names(&store_config)
+            .into_iter().map(|n| {
+                let cfg = AspectConfig::get_for(&store_config, n.clone());
+                Aspect::new(n, cfg)
+            }).collect();
+
+        let pre_save_as_aspects = get_pre_save_as_aspect_names(&store_config)
+            .into_iter().map(|n| {
+                let cfg = AspectConfig::get_for(&store_config, n.clone());
+                Aspect::new(n, cfg)
+            }).collect();
+
+        let post_save_as_aspects = get_post_save_as_aspect_names(&store_config)
+            .into_iter().map(|n| {
+                let cfg = AspectConfig::get_for(&store_config, n.clone());
+                Aspect::new(n, cfg)
+            }).collect();
+
+        let skip_hooks_when_unchanged = config_skip_hooks_when_unchanged(store_config.as_ref());
+        let module_extensions         = config_module_extensions(store_config.as_ref());
+
         let store = Store {
             location: location.clone(),
             configuration: store_config,
@@ -355,7 +534,17 @@ impl Store {
             post_delete_aspects   : Arc::new(Mutex::new(post_delete_aspects)),
             pre_move_aspects    : Arc::new(Mutex::new(pre_move_aspects)),
             post_move_aspects   : Arc::new(Mutex::new(post_move_aspects)),
+            pre_retrieve_copy_aspects  : Arc::new(Mutex::new(pre_retrieve_copy_aspects)),
+            post_retrieve_copy_aspects : Arc::new(Mutex::new(post_retrieve_copy_aspects)),
+            pre_save_to_aspects   : Arc::new(Mutex::new(pre_save_to_aspects)),
+            post_save_to_aspects  : Arc::new(Mutex::new(post_save_to_aspects)),
+            pre_save_as_aspects   : Arc::new(Mutex::new(pre_save_as_aspects)),
+            post_save_as_aspects  : Arc::new(Mutex::new(post_save_as_aspects)),
             entries: Arc::new(RwLock::new(HashMap::new())),
+            dry_run: false,
+            skip_hooks_when_unchanged: skip_hooks_when_unchanged,
+            module_extensions: module_extensions,
+            backend: backend,
         };
 
         debug!("Store building succeeded");
@@ -366,11 +555,78 @@ impl Store {
         Ok(store)
     }
 
+    /// Turn `id` into the StoreId the store uses internally
+    ///
+    /// Sets the base of the id to the store path and, if an extension is configured for the module
+    /// of the entry (`store.modules.<module>.extension`), appends that extension unless the id
+    /// ends with it already. So `notes/foo` and `notes/foo.md` refer to the same entry if the
+    /// "notes" module is configured for "md".
+    fn normalize_id<S: IntoStoreId>(&self, id: S) -> Result<StoreId> {
+        let id = try!(id.into_storeid()).with_base(self.path().clone());
+
+        // The module itself (as in `notes`) is a collection, not an entry
+        if id.components().count() < 2 {
+            return Ok(id);
+        }
+
+        let ext = match id.components()
+            .next()
+            .and_then(|module| module.as_os_str().to_str())
+            .and_then(|module| self.module_extensions.get(module))
+        {
+            Some(ext) => format!(".{}", ext),
+            None      => return Ok(id),
+        };
+
+        let has_ext = id.local()
+            .file_name()
+            .and_then(|name| name.to_str())
+            .map(|name| name.ends_with(&ext))
+            .unwrap_or(true);
+
+        if has_ext {
+            return Ok(id);
+        }
+
+        let mut local = id.local().clone().into_os_string();
+        local.push(ext);
+        StoreId::new(id.base().cloned(), PathBuf::from(local))
+    }
+
     /// Get the store configuration
     pub fn config(&self) -> Option<&Value> {
         self.configuration.as_ref()
     }
 
+    /// Get a value from the store configuration by its dotted path, like `modules.mail.maildir`
+    ///
+    /// The path is relative to the store configuration, so the `store.` prefix of the
+    /// configuration file is not part of it. Returns `None` if a segment of the path does not
+    /// exist or is not a table.
+    pub fn config_value(&self, dotted_key: &str) -> Option<&Value> {
+        dotted_key.split('.').fold(self.config(), |value, key| match value {
+            Some(&Value::Table(ref tabl)) => tabl.get(key),
+            _                             => None,
+        })
+    }
+
+    /// Enable or disable the dry-run mode
+    ///
+    /// In dry-run mode, `create()`, `update()`, `delete()` and `move_by_id()` (and dropping a
+    /// `FileLockEntry`) only log what they would do without touching the filesystem. They still
+    /// fail if a real run would fail because of the state of the entries, e.g. when deleting an
+    /// entry which does not exist. No hooks are executed for these operations. Entries are still
+    /// tracked in memory, so an entry created in dry-run mode can be retrieved again from the same
+    /// `Store` object.
+    pub fn set_dry_run(&mut self, dry_run: bool) {
+        self.dry_run = dry_run;
+    }
+
+    /// Check whether the store is in dry-run mode
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
     /// Verify the store.
     ///
     /// This function is not intended to be called by normal programs but only by `imag-store`.
@@ -448,8 +704,35 @@ impl Store {
     ///  - CreateCallError(EntryAlreadyExists()) if the entry exists already.
     ///
     pub fn create<'a, S: IntoStoreId>(&'a self, id: S) -> Result<FileLockEntry<'a>> {
-        let id = try!(id.into_storeid()).with_base(self.path().clone());
-        if let Err(e) = self.execute_hooks_for_id(self.pre_create_aspects.clone(), &id) {
+        self._create(id, EntryContent::new())
+    }
+
+    /// Creates the Entry at the given location with `content` as content
+    ///
+    /// The content is set before the post create aspects are executed, so hooks see the entry as
+    /// it is written.
+    ///
+    /// # Executed Hooks
+    ///
+    /// See `Store::create()`.
+    ///
+    /// # Return value
+    ///
+    /// See `Store::create()`.
+    ///
+    pub fn create_with_content<'a, S: IntoStoreId>(&'a self, id: S, content: EntryContent)
+        -> Result<FileLockEntry<'a>>
+    {
+        self._create(id, content)
+    }
+
+    fn _create<'a, S: IntoStoreId>(&'a self, id: S, content: EntryContent)
+        -> Result<FileLockEntry<'a>>
+    {
+        let id = try!(self.normalize_id(id));
+        if self.dry_run {
+            info!("Dry run: Would create {}", id);
+        } else if let Err(e) = self.execute_hooks_for_id(self.pre_create_aspects.clone(), &id) {
             return Err(e)
                 .map_err_into(SEK::PreHookExecuteError)
                 .map_err_into(SEK::HookExecutionError)
@@ -466,13 +749,20 @@ impl Store {
                 return Err(SEK::EntryAlreadyExists.into_error()).map_err_into(SEK::CreateCallError);
             }
             hsmap.insert(id.clone(), {
-                let mut se = try!(StoreEntry::new(id.clone()));
+                let mut se = try!(StoreEntry::new(id.clone(), &self.backend));
                 se.status = StoreEntryStatus::Borrowed;
                 se
             });
         }
 
-        let mut fle = FileLockEntry::new(self, Entry::new(id));
+        let mut entry = Entry::new(id);
+        entry.content = content;
+
+        let mut fle = FileLockEntry::new(self, entry);
+        if self.dry_run {
+            return Ok(fle);
+        }
+
         self.execute_hooks_for_mut_file(self.post_create_aspects.clone(), &mut fle)
             .map_err_into(SEK::PostHookExecuteError)
             .map_err_into(SEK::HookExecutionError)
@@ -504,7 +794,7 @@ impl Store {
     ///  - RetrieveCallError(LockPoisoned()) if the internal lock is poisened.
     ///
     pub fn retrieve<'a, S: IntoStoreId>(&'a self, id: S) -> Result<FileLockEntry<'a>> {
-        let id = try!(id.into_storeid()).with_base(self.path().clone());
+        let id = try!(self.normalize_id(id));
         if let Err(e) = self.execute_hooks_for_id(self.pre_retrieve_aspects.clone(), &id) {
             return Err(e)
                 .map_err_into(SEK::PreHookExecuteError)
@@ -517,11 +807,15 @@ impl Store {
                 .write()
                 .map_err(|_| SE::new(SEK::LockPoisoned, None))
                 .and_then(|mut es| {
-                    let new_se = try!(StoreEntry::new(id.clone()));
+                    let new_se = try!(StoreEntry::new(id.clone(), &self.backend));
                     let mut se = es.entry(id.clone()).or_insert(new_se);
-                    let entry = se.get_entry();
+
+                    // Fails with EntryAlreadyBorrowed if there is a FileLockEntry for this id
+                    // already. Only mark the entry as borrowed if we actually hand it out, so a
+                    // failing read does not leave it borrowed forever.
+                    let entry = try!(se.get_entry());
                     se.status = StoreEntryStatus::Borrowed;
-                    entry
+                    Ok(entry)
                 })
                 .map_err_into(SEK::RetrieveCallError)
         });
@@ -534,6 +828,51 @@ impl Store {
             .and(Ok(fle))
     }
 
+    /// Borrow a given Entry, waiting for it if it is borrowed already
+    ///
+    /// Like `Store::retrieve()`, but if there is a `FileLockEntry` for `id` already (for example in
+    /// another thread), this waits until it is released, up to `dur`. The state of the entry is
+    /// polled, so it might take a few milliseconds after the release until the entry is handed out.
+    ///
+    /// # Return value
+    ///
+    /// On success: FileLockEntry
+    ///
+    /// On error:
+    ///  - LockTimeout() if the entry was not released within `dur`
+    ///  - Errors Store::retrieve() might return
+    ///
+    pub fn retrieve_timeout<'a, S: IntoStoreId>(&'a self, id: S, dur: Duration)
+        -> Result<FileLockEntry<'a>>
+    {
+        let id       = try!(self.normalize_id(id));
+        let deadline = Instant::now() + dur;
+
+        loop {
+            let borrowed = try!(self.entries
+                .read()
+                .map(|es| es.get(&id).map(|se| se.is_borrowed()).unwrap_or(false))
+                .map_err(|_| SE::new(SEK::LockPoisoned, None))
+                .map_err_into(SEK::RetrieveCallError));
+
+            if !borrowed {
+                match self.retrieve(id.clone()) {
+                    // someone else was faster, wait again
+                    Err(ref e) if e.category() == StoreErrorCategory::Locked => {},
+                    other => return other,
+                }
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                debug!("Timeout while waiting for {:?}", id);
+                return Err(SEK::LockTimeout.into_error());
+            }
+
+            sleep(::std::cmp::min(deadline - now, Duration::from_millis(10)));
+        }
+    }
+
     /// Get an entry from the store if it exists.
     ///
     /// # Executed Hooks
@@ -550,7 +889,7 @@ impl Store {
     ///  - Errors Store::retrieve() might return
     ///
     pub fn get<'a, S: IntoStoreId + Clone>(&'a self, id: S) -> Result<Option<FileLockEntry<'a>>> {
-        let id = try!(id.into_storeid()).with_base(self.path().clone());
+        let id = try!(self.normalize_id(id));
 
         let exists = try!(id.exists()) || try!(self.entries
             .read()
@@ -569,6 +908,9 @@ impl Store {
 
     /// Iterate over all StoreIds for one module name
     ///
+    /// The ids are yielded in the order the filesystem returns them, which is not stable across
+    /// platforms. Use `StoreIdIterator::sorted()` if the order matters.
+    ///
     /// # Returns
     ///
     /// On success: An iterator over all entries in the module
@@ -579,9 +921,32 @@ impl Store {
     ///  - GRetrieveForModuleCallError(GlobError(lobError())) if the glob() failed.
     ///
     pub fn retrieve_for_module(&self, mod_name: &str) -> Result<StoreIdIterator> {
+        self.retrieve_for_module_with_errors(mod_name)
+            .map(|iter| GlobStoreIdIterator::from(iter).into())
+    }
+
+    /// Iterate over all StoreIds for one module name, yielding errors instead of skipping them
+    ///
+    /// `Store::retrieve_for_module()` traces and skips paths which cannot be read or turned into
+    /// a StoreId, so the caller cannot know whether the listing is complete. This iterator yields
+    /// these errors, see `GlobStoreIdResultIterator`.
+    ///
+    /// # Returns
+    ///
+    /// See `Store::retrieve_for_module()`.
+    ///
+    pub fn retrieve_for_module_with_errors(&self, mod_name: &str)
+        -> Result<GlobStoreIdResultIterator>
+    {
         let mut path = self.path().clone();
         path.push(mod_name);
 
+        if let Backend::InMemory(ref files) = self.backend {
+            return files.files_below(&path)
+                .map(|paths| self.in_memory_paths_iter(paths))
+                .map_err_into(SEK::RetrieveForModuleCallError);
+        }
+
         path.to_str()
             .ok_or(SE::new(SEK::EncodingError, None))
             .and_then(|path| {
@@ -589,122 +954,430 @@ impl Store {
                 debug!("glob()ing with '{}'", path);
                 glob(&path[..]).map_err_into(SEK::GlobError)
             })
-            .map(|paths| GlobStoreIdIterator::new(paths, self.path().clone()).into())
+            .map(|paths| GlobStoreIdResultIterator::new(paths, self.path().clone()))
             .map_err_into(SEK::GlobError)
             .map_err_into(SEK::RetrieveForModuleCallError)
     }
 
-    /// Walk the store tree for the module
-    ///
-    /// The difference between a `Walk` and a `StoreIdIterator` is that with a `Walk`, one can find
-    /// "collections" (folders).
-    pub fn walk<'a>(&'a self, mod_name: &str) -> Walk {
-        Walk::new(self.path().clone(), mod_name)
-    }
-
-    /// Return the `FileLockEntry` and write to disk
+    /// Iterate over all StoreIds in the store, for all modules
     ///
-    /// See `Store::_update()`.
+    /// Only files are yielded, directories and hidden files (for example the `.git` directory of a
+    /// version controlled store) are skipped.
     ///
-    pub fn update<'a>(&'a self, entry: &mut FileLockEntry<'a>) -> Result<()> {
-        self._update(entry, false).map_err_into(SEK::UpdateCallError)
-    }
-
-    /// Internal method to write to the filesystem store.
+    /// # Returns
     ///
-    /// # Assumptions
+    /// On success: An iterator over all entries in the store
     ///
-    /// This method assumes that entry is dropped _right after_ the call, hence
-    /// it is not public.
+    /// On failure:
+    ///  - EntriesCallError(GlobError(EncodingError())) if the path string cannot be encoded
+    ///  - EntriesCallError(GlobError(GlobError())) if the glob() failed.
     ///
-    /// # Executed Hooks
+    pub fn entries(&self) -> Result<StoreIdIterator> {
+        let opts = MatchOptions {
+            case_sensitive: true,
+            require_literal_separator: false,
+            require_literal_leading_dot: true,
+        };
+
+        if let Backend::InMemory(ref files) = self.backend {
+            return files.files_below(self.path())
+                .map(|paths| GlobStoreIdIterator::from(self.in_memory_paths_iter(paths)).into())
+                .map_err_into(SEK::EntriesCallError);
+        }
+
+        self.path()
+            .to_str()
+            .ok_or(SE::new(SEK::EncodingError, None))
+            .and_then(|path| {
+                let path = [ path, "/**/*" ].join("");
+                debug!("glob()ing with '{}'", path);
+                glob_with(&path[..], &opts).map_err_into(SEK::GlobError)
+            })
+            .map(|paths| {
+                let iter = GlobStoreIdIterator::new(paths, self.path().clone())
+                    .filter(|id| id.clone().into_pathbuf().map(|p| p.is_file()).unwrap_or(false));
+                StoreIdIterator::new(Box::new(iter))
+            })
+            .map_err_into(SEK::GlobError)
+            .map_err_into(SEK::EntriesCallError)
+    }
+
+    fn in_memory_paths_iter(&self, paths: Vec<PathBuf>) -> GlobStoreIdResultIterator {
+        let paths = paths.into_iter().map(Ok);
+        GlobStoreIdResultIterator::from_paths(Box::new(paths), self.path().clone())
+    }
+
+    /// Find entries whose ids collide under different module roots
     ///
-    /// - Pre update aspects
-    /// - post update aspects
+    /// The first component of an id is the module which created the entry, so `notes/2016/note`
+    /// and `diary/2016/note` are both `2016/note` relative to their module root. Such collisions
+    /// are legal, but signal a misconfigured store, for example two modules sharing a root.
     ///
     /// # Return value
     ///
-    /// On success: Entry
+    /// On success: The colliding module-relative ids, each with the store-relative paths of the
+    /// entries which share it, sorted by id and path. Entries directly in the store root are not
+    /// considered.
     ///
     /// On error:
-    ///  - UpdateCallError(HookExecutionError(PreHookExecuteError(_)))
-    ///    of the first failing pre hook.
-    ///  - UpdateCallError(HookExecutionError(PostHookExecuteError(_)))
-    ///    of the first failing post hook.
-    ///  - UpdateCallError(LockPoisoned()) if the internal write lock cannot be aquierd.
-    ///  - IdNotFound() if the entry was not found in the stor
-    ///  - Errors Entry::verify() might return
-    ///  - Errors StoreEntry::write_entry() might return
+    ///  - Errors Store::entries() might return
     ///
-    fn _update<'a>(&'a self, mut entry: &mut FileLockEntry<'a>, modify_presence: bool) -> Result<()> {
-        let _ = try!(self.execute_hooks_for_mut_file(self.pre_update_aspects.clone(), &mut entry)
-            .map_err_into(SEK::PreHookExecuteError)
-            .map_err_into(SEK::HookExecutionError)
-            .map_err_into(SEK::UpdateCallError)
-        );
-
-        let mut hsmap = match self.entries.write() {
-            Err(_) => return Err(SE::new(SEK::LockPoisoned, None)),
-            Ok(e) => e,
-        };
-
-        let mut se = try!(hsmap.get_mut(&entry.location).ok_or(SE::new(SEK::IdNotFound, None)));
-
-        assert!(se.is_borrowed(), "Tried to update a non borrowed entry.");
+    pub fn find_id_collisions(&self) -> Result<Vec<(StoreId, Vec<PathBuf>)>> {
+        let mut ids : BTreeMap<PathBuf, Vec<PathBuf>> = BTreeMap::new();
 
-        debug!("Verifying Entry");
-        try!(entry.entry.verify());
+        for id in try!(self.entries()) {
+            let relative = id.components().skip(1).map(|c| c.as_os_str()).collect::<PathBuf>();
+            if relative.as_os_str().is_empty() {
+                continue;
+            }
 
-        debug!("Writing Entry");
-        try!(se.write_entry(&entry.entry));
-        if modify_presence {
-            se.status = StoreEntryStatus::Present;
+            ids.entry(relative).or_insert_with(Vec::new).push(id.local().clone());
         }
 
-        self.execute_hooks_for_mut_file(self.post_update_aspects.clone(), &mut entry)
-            .map_err_into(SEK::PostHookExecuteError)
-            .map_err_into(SEK::HookExecutionError)
-            .map_err_into(SEK::UpdateCallError)
+        ids.into_iter()
+            .filter(|&(_, ref paths)| paths.len() > 1)
+            .map(|(relative, mut paths)| {
+                paths.sort();
+                StoreId::new_baseless(relative).map(|id| (id, paths))
+            })
+            .collect()
     }
 
-    /// Retrieve a copy of a given entry, this cannot be used to mutate
-    /// the one on disk
-    ///
-    /// TODO: Create Hooks for retrieving a copy
+    /// Find all entries whose content hashes to `hash`
     ///
-    /// # Executed Hooks
-    ///
-    /// - (none yet)
+    /// `hasher` is called with the content of each entry and returns its hash, so any hashing
+    /// function can be used, for example one of the hashers of `libimagref`, wrapped in a closure.
+    /// The entries are read one after another via `Store::retrieve_copy()`, so they are not all
+    /// loaded into memory at once. Entries which are currently borrowed cannot be read and are
+    /// skipped.
     ///
     /// # Return value
     ///
-    /// On success: Entry
+    /// On success: The ids of all entries with matching content
     ///
     /// On error:
-    ///  - RetrieveCopyCallError(LockPoisoned()) if the internal write lock cannot be aquierd.
-    ///  - RetrieveCopyCallError(IdLocked()) if the Entry is borrowed currently
-    ///  - Errors StoreEntry::new() might return
+    ///  - Errors `Store::entries()` might return
+    ///  - LockPoisoned() if the internal read lock cannot be aquierd
+    ///  - Errors `Store::retrieve_copy()` might return
     ///
-    pub fn retrieve_copy<S: IntoStoreId>(&self, id: S) -> Result<Entry> {
-        let id = try!(id.into_storeid()).with_base(self.path().clone());
-        let entries = match self.entries.write() {
-            Err(_) => {
-                return Err(SE::new(SEK::LockPoisoned, None))
-                    .map_err_into(SEK::RetrieveCopyCallError);
-            },
-            Ok(e) => e,
-        };
-
-        // if the entry is currently modified by the user, we cannot drop it
-        if entries.get(&id).map(|e| e.is_borrowed()).unwrap_or(false) {
-            return Err(SE::new(SEK::IdLocked, None)).map_err_into(SEK::RetrieveCopyCallError);
+    pub fn find_by_content_hash<F>(&self, hasher: F, hash: &str) -> Result<Vec<StoreId>>
+        where F: Fn(&str) -> String
+    {
+        self.entries().and_then(|ids| self.filter_by_content_hash(ids, hasher, hash))
+    }
+
+    fn filter_by_content_hash<I, F>(&self, ids: I, hasher: F, hash: &str) -> Result<Vec<StoreId>>
+        where I: Iterator<Item = StoreId>,
+              F: Fn(&str) -> String
+    {
+        let mut found = vec![];
+        for id in ids {
+            if try!(self.is_borrowed(id.clone())) {
+                debug!("Entry {} is borrowed, skipping", id);
+                continue;
+            }
+
+            let entry = try!(self.retrieve_copy(id.clone()));
+            if hasher(entry.get_content()) == hash {
+                found.push(id);
+            }
+        }
+        Ok(found)
+    }
+
+    /// Check whether the entry `id` is borrowed currently
+    fn is_borrowed<S: IntoStoreId>(&self, id: S) -> Result<bool> {
+        let id = try!(self.normalize_id(id));
+        match self.entries.read() {
+            Err(_)      => Err(SE::new(SEK::LockPoisoned, None)),
+            Ok(entries) => Ok(entries.get(&id).map(|e| e.is_borrowed()).unwrap_or(false)),
+        }
+    }
+
+    /// Walk the store tree for the module
+    ///
+    /// The difference between a `Walk` and a `StoreIdIterator` is that with a `Walk`, one can find
+    /// "collections" (folders).
+    pub fn walk<'a>(&'a self, mod_name: &str) -> Walk {
+        match self.backend {
+            Backend::FileSystem          => Walk::new(self.path().clone(), mod_name),
+            Backend::InMemory(ref files) => Walk::in_memory(self.path().clone(), mod_name, files),
+        }
+    }
+
+    /// Return the `FileLockEntry` and write to disk
+    ///
+    /// See `Store::_update()`.
+    ///
+    pub fn update<'a>(&'a self, entry: &mut FileLockEntry<'a>) -> Result<()> {
+        self._update(entry, false).map_err_into(SEK::UpdateCallError)
+    }
+
+    /// Write many borrowed entries to disk at once
+    ///
+    /// In contrast to calling `Store::update()` for each entry, the internal write lock is only
+    /// aquired once. The pre update aspects are executed for all entries first, then all entries
+    /// are written, then the post update aspects are executed for all entries.
+    ///
+    /// # Return value
+    ///
+    /// On success: ()
+    ///
+    /// On error: UpdateAllCallError(_), with the same causes as for `Store::_update()`.
+    ///
+    /// The operation stops at the first error, the StoreId of the failing entry is logged. Entries
+    /// which were written before the error stay written.
+    ///
+    pub fn update_all<'a: 'b, 'b, I>(&'a self, entries: I) -> Result<()>
+        where I: Iterator<Item = &'b mut FileLockEntry<'a>>
+    {
+        let mut entries = entries.collect::<Vec<_>>();
+
+        if self.dry_run {
+            for entry in entries.iter() {
+                try!(self._update_dry_run(entry, false).map_err_into(SEK::UpdateAllCallError));
+            }
+            return Ok(());
+        }
+
+        for entry in entries.iter_mut() {
+            if let Err(e) = self.execute_hooks_for_mut_file(self.pre_update_aspects.clone(), entry) {
+                warn!("Pre-update hooks failed for {}", entry.get_location());
+                return Err(e)
+                    .map_err_into(SEK::PreHookExecuteError)
+                    .map_err_into(SEK::HookExecutionError)
+                    .map_err_into(SEK::UpdateAllCallError);
+            }
+        }
+
+        {
+            let mut hsmap = match self.entries.write() {
+                Err(_) => return Err(SE::new(SEK::LockPoisoned, None))
+                    .map_err_into(SEK::UpdateAllCallError),
+                Ok(e) => e,
+            };
+
+            for entry in entries.iter() {
+                let res = hsmap.get_mut(&entry.location)
+                    .ok_or(SE::new(SEK::IdNotFound, None))
+                    .and_then(|se| {
+                        if !se.is_borrowed() {
+                            return Err(SEK::EntryNotBorrowed.into_error());
+                        }
+                        try!(entry.entry.verify());
+                        se.write_entry(&entry.entry.to_bytes())
+                    });
+
+                if let Err(e) = res {
+                    warn!("Writing {} failed", entry.get_location());
+                    return Err(e).map_err_into(SEK::UpdateAllCallError);
+                }
+            }
+        }
+
+        for entry in entries.iter_mut() {
+            if let Err(e) = self.execute_hooks_for_mut_file(self.post_update_aspects.clone(), entry) {
+                warn!("Post-update hooks failed for {}", entry.get_location());
+                return Err(e)
+                    .map_err_into(SEK::PostHookExecuteError)
+                    .map_err_into(SEK::HookExecutionError)
+                    .map_err_into(SEK::UpdateAllCallError);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Internal method to write to the filesystem store.
+    ///
+    /// # Assumptions
+    ///
+    /// This method assumes that entry is dropped _right after_ the call, hence
+    /// it is not public.
+    ///
+    /// The file is only written if its content differs from the entry.
+    ///
+    /// # Executed Hooks
+    ///
+    /// - Pre update aspects
+    /// - post update aspects
+    ///
+    /// Both are not executed for unchanged entries if `skip-hooks-when-unchanged` is set in the
+    /// store configuration.
+    ///
+    /// # Return value
+    ///
+    /// On success: Entry
+    ///
+    /// On error:
+    ///  - UpdateCallError(HookExecutionError(PreHookExecuteError(_)))
+    ///    of the first failing pre hook.
+    ///  - UpdateCallError(HookExecutionError(PostHookExecuteError(_)))
+    ///    of the first failing post hook.
+    ///  - UpdateCallError(LockPoisoned()) if the internal write lock cannot be aquierd.
+    ///  - IdNotFound() if the entry was not found in the stor
+    ///  - EntryNotBorrowed() if the entry is not borrowed
+    ///  - Errors Entry::verify() might return
+    ///  - Errors StoreEntry::write_entry() might return
+    ///
+    fn _update<'a>(&'a self, mut entry: &mut FileLockEntry<'a>, modify_presence: bool) -> Result<()> {
+        if self.dry_run {
+            return self._update_dry_run(entry, modify_presence);
+        }
+
+        // the serialized entry, if it is known to differ from the file
+        let changed = if self.skip_hooks_when_unchanged {
+            match try!(self._update_if_unchanged(entry, modify_presence)) {
+                None        => return Ok(()),
+                Some(bytes) => Some(bytes),
+            }
+        } else {
+            None
+        };
+
+        let _ = try!(self.execute_hooks_for_mut_file(self.pre_update_aspects.clone(), &mut entry)
+            .map_err_into(SEK::PreHookExecuteError)
+            .map_err_into(SEK::HookExecutionError)
+            .map_err_into(SEK::UpdateCallError)
+        );
+
+        let mut hsmap = match self.entries.write() {
+            Err(_) => return Err(SE::new(SEK::LockPoisoned, None)),
+            Ok(e) => e,
+        };
+
+        let mut se = try!(hsmap.get_mut(&entry.location).ok_or(SE::new(SEK::IdNotFound, None)));
+
+        if !se.is_borrowed() {
+            debug!("Tried to update a non borrowed entry: {}", entry.location);
+            return Err(SEK::EntryNotBorrowed.into_error());
+        }
+
+        debug!("Verifying Entry");
+        try!(entry.entry.verify());
+
+        debug!("Writing Entry");
+        let bytes = entry.entry.to_bytes();
+        match changed {
+            // no hook altered the entry, so there is no need to compare it to the file again
+            Some(ref old) if *old == bytes => try!(se.write_bytes(&bytes)),
+            _                              => try!(se.write_entry(&bytes)),
+        }
+        if modify_presence {
+            se.status = StoreEntryStatus::Present;
+        }
+
+        self.execute_hooks_for_mut_file(self.post_update_aspects.clone(), &mut entry)
+            .map_err_into(SEK::PostHookExecuteError)
+            .map_err_into(SEK::HookExecutionError)
+            .map_err_into(SEK::UpdateCallError)
+    }
+
+    /// Finish the update of an entry without executing hooks, if its file already has the content of
+    /// the entry
+    ///
+    /// Returns `None` if the entry was unchanged, in which case nothing else has to be done.
+    /// Otherwise, the serialized entry is returned, which differs from the file if the entry is
+    /// borrowed.
+    fn _update_if_unchanged(&self, entry: &FileLockEntry, modify_presence: bool)
+        -> Result<Option<Vec<u8>>>
+    {
+        let mut hsmap = match self.entries.write() {
+            Err(_) => return Err(SE::new(SEK::LockPoisoned, None)),
+            Ok(e) => e,
+        };
+
+        let se    = try!(hsmap.get_mut(&entry.location).ok_or(SE::new(SEK::IdNotFound, None)));
+        let bytes = entry.entry.to_bytes();
+        if !se.is_borrowed() || !se.has_content(&bytes) {
+            return Ok(Some(bytes));
+        }
+
+        debug!("Entry {} is unchanged, skipping update hooks", entry.location);
+        if modify_presence {
+            se.status = StoreEntryStatus::Present;
+        }
+        Ok(None)
+    }
+
+    /// Dry-run version of `Store::_update()`, which only updates the internal state
+    fn _update_dry_run(&self, entry: &FileLockEntry, modify_presence: bool) -> Result<()> {
+        info!("Dry run: Would write {}", entry.get_location());
+
+        let mut hsmap = match self.entries.write() {
+            Err(_) => return Err(SE::new(SEK::LockPoisoned, None)),
+            Ok(e) => e,
+        };
+
+        let se = try!(hsmap.get_mut(&entry.location).ok_or(SE::new(SEK::IdNotFound, None)));
+        if modify_presence {
+            se.status = StoreEntryStatus::Present;
+        }
+
+        Ok(())
+    }
+
+    /// Retrieve a copy of a given entry, this cannot be used to mutate
+    /// the one on disk
+    ///
+    /// # Executed Hooks
+    ///
+    /// - Pre retrieve-copy aspects
+    /// - Post retrieve-copy aspects, if the operation succeeded
+    ///
+    /// Both only get the StoreId of the entry, as the copy cannot be used to alter the entry.
+    ///
+    /// # Return value
+    ///
+    /// On success: Entry
+    ///
+    /// On error:
+    ///  - RetrieveCopyCallError(HookExecutionError(PreHookExecuteError(_)))
+    ///    of the first failing pre hook.
+    ///  - RetrieveCopyCallError(HookExecutionError(PostHookExecuteError(_)))
+    ///    of the first failing post hook.
+    ///  - RetrieveCopyCallError(LockPoisoned()) if the internal write lock cannot be aquierd.
+    ///  - RetrieveCopyCallError(IdLocked()) if the Entry is borrowed currently
+    ///  - Errors StoreEntry::new() might return
+    ///
+    pub fn retrieve_copy<S: IntoStoreId>(&self, id: S) -> Result<Entry> {
+        let id = try!(self.normalize_id(id));
+        if let Err(e) = self.execute_hooks_for_id(self.pre_retrieve_copy_aspects.clone(), &id) {
+            return Err(e)
+                .map_err_into(SEK::PreHookExecuteError)
+                .map_err_into(SEK::HookExecutionError)
+                .map_err_into(SEK::RetrieveCopyCallError)
         }
 
-        try!(StoreEntry::new(id)).get_entry()
+        let entry = {
+            let entries = match self.entries.write() {
+                Err(_) => {
+                    return Err(SE::new(SEK::LockPoisoned, None))
+                        .map_err_into(SEK::RetrieveCopyCallError);
+                },
+                Ok(e) => e,
+            };
+
+            // if the entry is currently modified by the user, we cannot drop it
+            if entries.get(&id).map(|e| e.is_borrowed()).unwrap_or(false) {
+                return Err(SE::new(SEK::IdLocked, None)).map_err_into(SEK::RetrieveCopyCallError);
+            }
+
+            try!(try!(StoreEntry::new(id.clone(), &self.backend)).get_entry())
+        };
+
+        self.execute_hooks_for_id(self.post_retrieve_copy_aspects.clone(), &id)
+            .map_err_into(SEK::PostHookExecuteError)
+            .map_err_into(SEK::HookExecutionError)
+            .map_err_into(SEK::RetrieveCopyCallError)
+            .map(|_| entry)
     }
 
     /// Delete an entry
     ///
+    /// Links to the entry are not removed, use `StoreLinkDeleter::delete_with_unlink()` from
+    /// `libimagentrylink` to delete an entry without leaving dangling links behind.
+    ///
     /// # Executed Hooks
     ///
     /// - Pre delete aspects, if the id can be used
@@ -724,7 +1397,20 @@ impl Store {
     ///  - DeleteCallError(FileError()) if the internals failed to remove the file.
     ///
     pub fn delete<S: IntoStoreId>(&self, id: S) -> Result<()> {
-        let id = try!(id.into_storeid()).with_base(self.path().clone());
+        let id = try!(self.normalize_id(id));
+        if self.dry_run {
+            let entries = match self.entries.read() {
+                Err(_) => return Err(SE::new(SEK::LockPoisoned, None))
+                    .map_err_into(SEK::DeleteCallError),
+                Ok(e) => e,
+            };
+
+            // fail where a real run would fail, so a dry run can be trusted
+            try!(Store::check_deletable(&entries, &id));
+            info!("Dry run: Would delete {}", id);
+            return Ok(());
+        }
+
         if let Err(e) = self.execute_hooks_for_id(self.pre_delete_aspects.clone(), &id) {
             return Err(e)
                 .map_err_into(SEK::PreHookExecuteError)
@@ -739,20 +1425,12 @@ impl Store {
                 Ok(e) => e,
             };
 
-            // if the entry is currently modified by the user, we cannot drop it
-            match entries.get(&id) {
-                None => {
-                    return Err(SEK::FileNotFound.into_error()).map_err_into(SEK::DeleteCallError)
-                },
-                Some(e) => if e.is_borrowed() {
-                    return Err(SE::new(SEK::IdLocked, None)).map_err_into(SEK::DeleteCallError)
-                }
-            }
+            try!(Store::check_deletable(&entries, &id));
 
             // remove the entry first, then the file
             entries.remove(&id);
             let pb = try!(id.clone().with_base(self.path().clone()).into_pathbuf());
-            if let Err(e) = FileAbstraction::remove_file(&pb) {
+            if let Err(e) = self.backend.remove_file(&pb) {
                 return Err(SEK::FileError.into_error_with_cause(Box::new(e)))
                     .map_err_into(SEK::DeleteCallError);
             }
@@ -764,19 +1442,25 @@ impl Store {
             .map_err_into(SEK::DeleteCallError)
     }
 
+    /// Check that the entry `id` is known to the store and can be deleted
+    fn check_deletable(entries: &HashMap<StoreId, StoreEntry>, id: &StoreId) -> Result<()> {
+        // if the entry is currently modified by the user, we cannot drop it
+        match entries.get(id) {
+            None => Err(SEK::FileNotFound.into_error()),
+            Some(e) if e.is_borrowed() => Err(SE::new(SEK::IdLocked, None)),
+            Some(_) => Ok(()),
+        }.map_err_into(SEK::DeleteCallError)
+    }
+
     /// Save a copy of the Entry in another place
-    /// Executes the post_move_aspects for the new id
-    ///
-    /// TODO: Introduce new aspect for `save_to()`.
+    /// Executes the pre_save_to_aspects for the old id and the post_save_to_aspects for the new id
     pub fn save_to(&self, entry: &FileLockEntry, new_id: StoreId) -> Result<()> {
         self.save_to_other_location(entry, new_id, false)
     }
 
     /// Save an Entry in another place
     /// Removes the original entry
-    /// Executes the post_move_aspects for the new id
-    ///
-    /// TODO: Introduce new aspect for `save_as()`.
+    /// Executes the pre_save_as_aspects for the old id and the post_save_as_aspects for the new id
     pub fn save_as(&self, entry: FileLockEntry, new_id: StoreId) -> Result<()> {
         self.save_to_other_location(&entry, new_id, true)
     }
@@ -784,7 +1468,21 @@ impl Store {
     fn save_to_other_location(&self, entry: &FileLockEntry, new_id: StoreId, remove_old: bool)
         -> Result<()>
     {
-        let new_id = new_id.with_base(self.path().clone());
+        let (pre_aspects, post_aspects) = if remove_old {
+            (self.pre_save_as_aspects.clone(), self.post_save_as_aspects.clone())
+        } else {
+            (self.pre_save_to_aspects.clone(), self.post_save_to_aspects.clone())
+        };
+
+        let old_id = entry.get_location().clone();
+        if let Err(e) = self.execute_hooks_for_id(pre_aspects, &old_id) {
+            return Err(e)
+                .map_err_into(SEK::PreHookExecuteError)
+                .map_err_into(SEK::HookExecutionError)
+                .map_err_into(SEK::MoveCallError)
+        }
+
+        let new_id = try!(self.normalize_id(new_id));
         let hsmap = try!(
             self.entries
                 .write()
@@ -796,20 +1494,18 @@ impl Store {
             return Err(SEK::EntryAlreadyExists.into_error()).map_err_into(SEK::MoveCallError)
         }
 
-        let old_id = entry.get_location().clone();
-
         let old_id_as_path = try!(old_id.clone().with_base(self.path().clone()).into_pathbuf());
         let new_id_as_path = try!(new_id.clone().with_base(self.path().clone()).into_pathbuf());
-        FileAbstraction::copy(&old_id_as_path, &new_id_as_path)
+        self.backend.copy(&old_id_as_path, &new_id_as_path)
             .and_then(|_| {
                 if remove_old {
-                    FileAbstraction::remove_file(&old_id_as_path)
+                    self.backend.remove_file(&old_id_as_path)
                 } else {
                     Ok(())
                 }
             })
             .map_err_into(SEK::FileError)
-            .and_then(|_| self.execute_hooks_for_id(self.post_move_aspects.clone(), &new_id)
+            .and_then(|_| self.execute_hooks_for_id(post_aspects, &new_id)
                     .map_err_into(SEK::PostHookExecuteError)
                     .map_err_into(SEK::HookExecutionError))
             .map_err_into(SEK::MoveCallError)
@@ -851,9 +1547,28 @@ impl Store {
     ///
     /// So the link is _partly dangling_, so to say.
     ///
+    /// Use `StoreLinkMover::move_by_id_relinking()` from `libimagentrylink` to rewrite the links
+    /// of the linked entries as well.
+    ///
     pub fn move_by_id(&self, old_id: StoreId, new_id: StoreId) -> Result<()> {
-        let new_id = new_id.with_base(self.path().clone());
-        let old_id = old_id.with_base(self.path().clone());
+        let new_id = try!(self.normalize_id(new_id));
+        let old_id = try!(self.normalize_id(old_id));
+
+        if self.dry_run {
+            let hsmap = match self.entries.read() {
+                Err(_) => return Err(SE::new(SEK::LockPoisoned, None)),
+                Ok(m)  => m,
+            };
+
+            // fail where a real run would fail, so a dry run can be trusted
+            try!(Store::check_movable(&hsmap, &old_id, &new_id));
+            if !(hsmap.contains_key(&old_id) || try!(old_id.exists())) {
+                // a real run fails in `Backend::rename()`
+                return Err(SEK::FileNotFound.into_error()).map_err_into(SEK::EntryRenameError);
+            }
+            info!("Dry run: Would move {} to {}", old_id, new_id);
+            return Ok(());
+        }
 
         if let Err(e) = self.execute_hooks_for_id(self.pre_move_aspects.clone(), &old_id) {
             return Err(e)
@@ -868,21 +1583,13 @@ impl Store {
                 Ok(m)  => m,
             };
 
-            if hsmap.contains_key(&new_id) {
-                return Err(SEK::EntryAlreadyExists.into_error());
-            }
-
-            // if we do not have an entry here, we fail in `FileAbstraction::rename()` below.
-            // if we have one, but it is borrowed, we really should not rename it, as this might
-            // lead to strange errors
-            if hsmap.get(&old_id).map(|e| e.is_borrowed()).unwrap_or(false) {
-                return Err(SEK::EntryAlreadyBorrowed.into_error());
-            }
+            // if we do not have an entry here, we fail in `Backend::rename()` below.
+            try!(Store::check_movable(&hsmap, &old_id, &new_id));
 
             let old_id_pb = try!(old_id.clone().with_base(self.path().clone()).into_pathbuf());
             let new_id_pb = try!(new_id.clone().with_base(self.path().clone()).into_pathbuf());
 
-            match FileAbstraction::rename(&old_id_pb, &new_id_pb) {
+            match self.backend.rename(&old_id_pb, &new_id_pb) {
                 Err(e) => return Err(SEK::EntryRenameError.into_error_with_cause(Box::new(e))),
                 Ok(_) => {
                     debug!("Rename worked on filesystem");
@@ -893,6 +1600,9 @@ impl Store {
                             .remove(&old_id)
                             .and_then(|mut entry| {
                                 entry.id = new_id.clone();
+                                // An opened file follows the rename, a file of an in-memory store
+                                // does not
+                                entry.file = self.backend.file(new_id_pb.clone());
                                 hsmap.insert(new_id.clone(), entry)
                             }).is_none())
                 }
@@ -906,6 +1616,25 @@ impl Store {
             .map_err_into(SEK::MoveByIdCallError)
     }
 
+    /// Check that the entry `old_id` can be moved to `new_id`
+    ///
+    /// Whether `old_id` exists is not checked here.
+    fn check_movable(entries: &HashMap<StoreId, StoreEntry>, old_id: &StoreId, new_id: &StoreId)
+        -> Result<()>
+    {
+        if entries.contains_key(new_id) {
+            return Err(SEK::EntryAlreadyExists.into_error());
+        }
+
+        // if we have an entry, but it is borrowed, we really should not rename it, as this might
+        // lead to strange errors
+        if entries.get(old_id).map(|e| e.is_borrowed()).unwrap_or(false) {
+            return Err(SEK::EntryAlreadyBorrowed.into_error());
+        }
+
+        Ok(())
+    }
+
     /// Gets the path where this store is on the disk
     pub fn path(&self) -> &PathBuf {
         &self.location
@@ -936,18 +1665,7 @@ impl Store {
         debug!("     in position: {:?}", position);
         debug!("     with aspect: {:?}", aspect_name);
 
-        let guard = match position {
-                HookPosition::StoreUnload  => self.store_unload_aspects.clone(),
-
-                HookPosition::PreCreate    => self.pre_create_aspects.clone(),
-                HookPosition::PostCreate   => self.post_create_aspects.clone(),
-                HookPosition::PreRetrieve  => self.pre_retrieve_aspects.clone(),
-                HookPosition::PostRetrieve => self.post_retrieve_aspects.clone(),
-                HookPosition::PreUpdate    => self.pre_update_aspects.clone(),
-                HookPosition::PostUpdate   => self.post_update_aspects.clone(),
-                HookPosition::PreDelete    => self.pre_delete_aspects.clone(),
-                HookPosition::PostDelete   => self.post_delete_aspects.clone(),
-            };
+        let guard = self.aspects_for(&position);
 
         let mut guard = match guard.deref().lock().map_err(|_| SE::new(SEK::LockError, None)) {
             Err(e) => return Err(SEK::HookRegisterError.into_error_with_cause(Box::new(e))),
@@ -968,6 +1686,69 @@ impl Store {
         Err(SEK::HookRegisterError.into_error_with_cause(Box::new(annfe)))
     }
 
+    /// Get all aspects which are registered in the store, with the hooks in them
+    ///
+    /// The aspects are listed by position, in the order of `HookPosition`, and within a position
+    /// in the order they are executed. Positions whose lock is poisoned are skipped.
+    pub fn registered_aspects(&self) -> Vec<AspectInfo> {
+        use hook::position::HookPosition as HP;
+
+        let positions = [
+            HP::StoreUnload,
+            HP::PreCreate, HP::PostCreate,
+            HP::PreRetrieve, HP::PostRetrieve,
+            HP::PreUpdate, HP::PostUpdate,
+            HP::PreDelete, HP::PostDelete,
+            HP::PreRetrieveCopy, HP::PostRetrieveCopy,
+            HP::PreSaveTo, HP::PostSaveTo,
+            HP::PreSaveAs, HP::PostSaveAs,
+        ];
+
+        let mut infos = vec![];
+        for position in positions.iter() {
+            let aspects = self.aspects_for(position);
+            let guard   = match aspects.lock() {
+                Ok(guard) => guard,
+                Err(_)    => {
+                    warn!("Lock for {:?} aspects is poisoned, skipping", position);
+                    continue;
+                },
+            };
+
+            for aspect in guard.iter() {
+                infos.push(AspectInfo {
+                    position: position.clone(),
+                    name: aspect.name().clone(),
+                    parallel: aspect.is_parallel(),
+                    hooks: aspect.hook_names(),
+                });
+            }
+        }
+
+        infos
+    }
+
+    fn aspects_for(&self, position: &HookPosition) -> Arc<Mutex<Vec<Aspect>>> {
+        match *position {
+            HookPosition::StoreUnload  => self.store_unload_aspects.clone(),
+
+            HookPosition::PreCreate    => self.pre_create_aspects.clone(),
+            HookPosition::PostCreate   => self.post_create_aspects.clone(),
+            HookPosition::PreRetrieve  => self.pre_retrieve_aspects.clone(),
+            HookPosition::PostRetrieve => self.post_retrieve_aspects.clone(),
+            HookPosition::PreUpdate    => self.pre_update_aspects.clone(),
+            HookPosition::PostUpdate   => self.post_update_aspects.clone(),
+            HookPosition::PreDelete    => self.pre_delete_aspects.clone(),
+            HookPosition::PostDelete   => self.post_delete_aspects.clone(),
+            HookPosition::PreRetrieveCopy  => self.pre_retrieve_copy_aspects.clone(),
+            HookPosition::PostRetrieveCopy => self.post_retrieve_copy_aspects.clone(),
+            HookPosition::PreSaveTo    => self.pre_save_to_aspects.clone(),
+            HookPosition::PostSaveTo   => self.post_save_to_aspects.clone(),
+            HookPosition::PreSaveAs    => self.pre_save_as_aspects.clone(),
+            HookPosition::PostSaveAs   => self.post_save_as_aspects.clone(),
+        }
+    }
+
     /// Get the configuration for a hook by the name of the hook, from the configuration file.
     fn get_config_for_hook(&self, name: &str) -> Option<&Value> {
         match self.configuration {
@@ -1050,6 +1831,14 @@ impl Debug for Store {
         try!(write!(fmt, " - post_update_aspects    : {:?}\n", self.post_update_aspects   ));
         try!(write!(fmt, " - pre_delete_aspects     : {:?}\n", self.pre_delete_aspects    ));
         try!(write!(fmt, " - post_delete_aspects    : {:?}\n", self.post_delete_aspects   ));
+        try!(write!(fmt, " - pre_retrieve_copy_aspects  : {:?}\n", self.pre_retrieve_copy_aspects ));
+        try!(write!(fmt, " - post_retrieve_copy_aspects : {:?}\n", self.post_retrieve_copy_aspects));
+        try!(write!(fmt, " - pre_save_to_aspects    : {:?}\n", self.pre_save_to_aspects   ));
+        try!(write!(fmt, " - post_save_to_aspects   : {:?}\n", self.post_save_to_aspects  ));
+        try!(write!(fmt, " - pre_save_as_aspects    : {:?}\n", self.pre_save_as_aspects   ));
+        try!(write!(fmt, " - post_save_as_aspects   : {:?}\n", self.post_save_as_aspects  ));
+        try!(write!(fmt, " - dry_run                : {:?}\n", self.dry_run));
+        try!(write!(fmt, " - backend                : {:?}\n", self.backend));
         try!(write!(fmt, "\n"));
         try!(write!(fmt, "Entries:\n"));
         try!(write!(fmt, "{:?}", self.entries));
@@ -1069,6 +1858,7 @@ impl Drop for Store {
     ///
     fn drop(&mut self) {
         match StoreId::new(Some(self.location.clone()), PathBuf::from(".")) {
+            Ok(_) if self.dry_run => info!("Dry run: Not executing store unload hooks"),
             Err(e) => {
                 trace_error(&e);
                 warn!("Cannot construct StoreId for Store to execute hooks!");
@@ -1091,6 +1881,10 @@ impl Drop for Store {
 pub struct FileLockEntry<'a> {
     store: &'a Store,
     entry: Entry,
+
+    // Whether the entry was written by `FileLockEntry::close()` already, so `Drop` does not
+    // write it again
+    flushed: bool,
 }
 
 impl<'a> FileLockEntry<'a, > {
@@ -1102,8 +1896,26 @@ impl<'a> FileLockEntry<'a, > {
         FileLockEntry {
             store: store,
             entry: entry,
+            flushed: false,
         }
     }
+
+    /// Write the entry to disk and give it back to the store, returning the result of the update
+    ///
+    /// This is what happens when a `FileLockEntry` is dropped, but other than `Drop`, this does not
+    /// swallow the errors of the update and its hooks. The entry is not written again when it is
+    /// dropped afterwards, even if the update failed.
+    pub fn close(mut self) -> Result<()> {
+        let store    = self.store;
+        let res      = store._update(&mut self, true).map_err_into(SEK::UpdateCallError);
+        self.flushed = true;
+        res
+    }
+
+    /// Get the store this entry belongs to
+    pub fn store(&self) -> &'a Store {
+        self.store
+    }
 }
 
 impl<'a> Debug for FileLockEntry<'a> {
@@ -1136,6 +1948,9 @@ impl<'a> Drop for FileLockEntry<'a> {
     /// intended for production use, though).
     fn drop(&mut self) {
         use libimagerror::trace::trace_error_dbg;
+        if self.flushed {
+            return;
+        }
         match self.store._update(self, true) {
             Err(e) => {
                 trace_error_dbg(&e);
@@ -1151,6 +1966,9 @@ impl<'a> Drop for FileLockEntry<'a> {
 
     /// This will not silently ignore errors but prints the result of the _update() call for testing
     fn drop(&mut self) {
+        if self.flushed {
+            return;
+        }
         let _ = self.store._update(self, true).map_err(|e| trace_error(&e));
     }
 
@@ -1160,14 +1978,60 @@ impl<'a> Drop for FileLockEntry<'a> {
 /// `EntryContent` type
 pub type EntryContent = String;
 
+/// The content type of entries which do not have `imag.content.type` in their header
+pub const DEFAULT_CONTENT_TYPE : &'static str = "text/plain";
+
+/// Metrics about the content of an Entry, see `Entry::content_stats()`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContentStats {
+    /// Number of unicode scalar values (not bytes)
+    pub chars: usize,
+
+    /// Number of whitespace-separated words
+    pub words: usize,
+
+    /// Number of lines, where a trailing newline does not start a new line
+    pub lines: usize,
+}
+
+/// The differences between the headers of two entries, see `Entry::header_diff()`
+///
+/// All keys are dotted paths, like `imag.version`, sorted. Tables which are present in both headers
+/// are compared recursively, all other values (including arrays) are compared as a whole.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct HeaderDiff {
+    /// Keys which are only in the other header
+    pub added: Vec<String>,
+
+    /// Keys which are only in this header
+    pub removed: Vec<String>,
+
+    /// Keys which are in both headers, but with different values
+    pub changed: Vec<String>,
+}
+
+impl HeaderDiff {
+
+    /// Check whether the headers are equal
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+}
+
 /// An Entry of the store
 //
 /// Contains location, header and content part.
+///
+/// The content is either text (`Entry::get_content()`) or binary (`Entry::get_content_bytes()`).
+/// Binary content is flagged with `imag.binary = true` in the header and written to the file as-is,
+/// after the header.
 #[derive(Debug, Clone)]
 pub struct Entry {
     location: StoreId,
     header: Value,
     content: EntryContent,
+    binary_content: Option<Vec<u8>>,
 }
 
 impl Entry {
@@ -1180,7 +2044,8 @@ impl Entry {
         Entry {
             location: loc,
             header: Entry::default_header(),
-            content: EntryContent::new()
+            content: EntryContent::new(),
+            binary_content: None,
         }
     }
 
@@ -1192,15 +2057,43 @@ impl Entry {
         Value::default_header()
     }
 
-    /// See `Entry::from_str()`, as this function is used internally. This is just a wrapper for
+    /// See `Entry::from_bytes()`, as this function is used internally. This is just a wrapper for
     /// convenience.
     pub fn from_reader<S: IntoStoreId>(loc: S, file: &mut Read) -> Result<Entry> {
-        let text = {
-            let mut s = String::new();
-            try!(file.read_to_string(&mut s));
-            s
+        let bytes = {
+            let mut v = Vec::new();
+            try!(file.read_to_end(&mut v));
+            v
         };
-        Self::from_str(loc, &text[..])
+        Self::from_bytes(loc, &bytes[..])
+    }
+
+    /// Create a new Entry from the bytes passed, which may contain binary content.
+    ///
+    /// If the header has the flag `imag.binary = true`, everything after the header is the binary
+    /// content of the entry. Otherwise, this works like `Entry::from_str()`.
+    ///
+    /// # Return value
+    ///
+    /// This errors if the entry has no binary content and
+    ///
+    /// - the bytes are not valid UTF-8
+    /// - `Entry::from_str()` fails
+    ///
+    pub fn from_bytes<S: IntoStoreId>(loc: S, bytes: &[u8]) -> Result<Entry> {
+        if let Some((header, content)) = binary_parts(bytes) {
+            debug!("Building entry with binary content");
+            return Ok(Entry {
+                location: try!(loc.into_storeid()),
+                header: header,
+                content: EntryContent::new(),
+                binary_content: Some(content.to_vec()),
+            });
+        }
+
+        ::std::str::from_utf8(bytes)
+            .map_err_into(SEK::EncodingError)
+            .and_then(|s| Self::from_str(loc, s))
     }
 
     /// Create a new Entry, with contents from the string passed.
@@ -1238,11 +2131,15 @@ impl Entry {
 
         let content = matches.name("content").map(|r| r.as_str()).unwrap_or("");
 
+        // Parse errors are reported relative to the start of the entry, not of the header
+        let line_offset = s[..header.start()].matches('\n').count();
+
         debug!("Header and content found. Yay! Building Entry object now");
         Ok(Entry {
             location: try!(loc.into_storeid()),
-            header: try!(Value::parse(header.as_str())),
+            header: try!(parse_header_at(header.as_str(), line_offset)),
             content: String::from(content),
+            binary_content: None,
         })
     }
 
@@ -1250,10 +2147,31 @@ impl Entry {
     ///
     /// This means not only the content of the entry, but the complete entry (from memory, not from
     /// disk).
+    ///
+    /// Binary content which is not valid UTF-8 is converted lossy, use `Entry::to_bytes()` for
+    /// entries with binary content.
     pub fn to_str(&self) -> String {
-        format!("---\n{header}---\n{content}",
-                header  = ::toml::ser::to_string(&self.header).unwrap(),
-                content = self.content)
+        match self.binary_content {
+            Some(ref bytes) => format!("---\n{header}---\n{content}",
+                                       header  = ::toml::ser::to_string(&self.header).unwrap(),
+                                       content = String::from_utf8_lossy(bytes)),
+            None => format!("---\n{header}---\n{content}",
+                            header  = ::toml::ser::to_string(&self.header).unwrap(),
+                            content = self.content),
+        }
+    }
+
+    /// Return the byte representation of this entry, as it is written to disk
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v = format!("---\n{header}---\n",
+                            header = ::toml::ser::to_string(&self.header).unwrap())
+            .into_bytes();
+
+        match self.binary_content {
+            Some(ref bytes) => v.extend_from_slice(bytes),
+            None            => v.extend_from_slice(self.content.as_bytes()),
+        }
+        v
     }
 
     /// Get the location of the Entry
@@ -1281,6 +2199,90 @@ impl Entry {
         &mut self.content
     }
 
+    /// Get the content of the Entry as bytes
+    ///
+    /// This is the binary content if the entry has one, the bytes of the text content otherwise.
+    pub fn get_content_bytes(&self) -> &[u8] {
+        match self.binary_content {
+            Some(ref bytes) => bytes,
+            None            => self.content.as_bytes(),
+        }
+    }
+
+    /// Set binary content for the Entry
+    ///
+    /// This sets the flag `imag.binary` in the header and clears the text content. As long as the
+    /// entry has binary content, the text content is ignored, use `Entry::clear_content_bytes()`
+    /// to turn the entry into a text entry again.
+    pub fn set_content_bytes(&mut self, bytes: &[u8]) -> Result<()> {
+        try!(self.header.set("imag.binary", Value::Boolean(true)));
+        self.content        = EntryContent::new();
+        self.binary_content = Some(bytes.to_vec());
+        Ok(())
+    }
+
+    /// Remove the binary content of the Entry and the `imag.binary` flag from the header
+    pub fn clear_content_bytes(&mut self) -> Result<()> {
+        try!(self.header.delete_key("imag.binary"));
+        self.binary_content = None;
+        Ok(())
+    }
+
+    /// Check whether the Entry has binary content
+    pub fn has_binary_content(&self) -> bool {
+        self.binary_content.is_some()
+    }
+
+    /// Set the MIME type of the content, like `text/markdown`, in `imag.content.type`
+    pub fn set_content_type(&mut self, mime: &str) -> Result<()> {
+        if !self.header.has_key("imag.content") {
+            try!(self.header.set("imag.content", Value::Table(BTreeMap::new())));
+        }
+
+        self.header
+            .set("imag.content.type", Value::String(String::from(mime)))
+            .map(|_| ())
+    }
+
+    /// Get the MIME type of the content from `imag.content.type`
+    ///
+    /// If the header does not contain a content type, `DEFAULT_CONTENT_TYPE` is returned. Returns
+    /// `None` if the content type in the header is not a String.
+    pub fn get_content_type(&self) -> Option<String> {
+        match self.header.read_string("imag.content.type") {
+            Ok(Some(mime)) => Some(mime),
+            Ok(None)       => Some(String::from(DEFAULT_CONTENT_TYPE)),
+            Err(e)         => {
+                warn!("Cannot read content type of {}", self.location);
+                trace_error(&e);
+                None
+            },
+        }
+    }
+
+    /// Get the number of characters, words and lines of the (text) content of the Entry
+    pub fn content_stats(&self) -> ContentStats {
+        ContentStats {
+            chars: self.content.chars().count(),
+            words: self.content.split_whitespace().count(),
+            lines: self.content.lines().count(),
+        }
+    }
+
+    /// Compare the header of this Entry with the header of `other`
+    ///
+    /// In contrast to `PartialEq`, this tells which keys differ. The content is not compared.
+    pub fn header_diff(&self, other: &Entry) -> HeaderDiff {
+        let mut diff = HeaderDiff::default();
+        diff_values("", &self.header, &other.header, &mut diff);
+
+        // The recursion yields the keys table by table, which is not the order of the full paths
+        diff.added.sort();
+        diff.removed.sort();
+        diff.changed.sort();
+        diff
+    }
+
     /// Verify the entry.
     ///
     /// Currently, this only verifies the header. This might change in the future.
@@ -1290,12 +2292,66 @@ impl Entry {
 
 }
 
+/// Compare `old` and `new` at the dotted path `path`, recursing into tables, and record the
+/// differences in `diff`
+fn diff_values(path: &str, old: &Value, new: &Value, diff: &mut HeaderDiff) {
+    match (old, new) {
+        (&Value::Table(ref old), &Value::Table(ref new)) => {
+            let key = |k: &String| if path.is_empty() { k.clone() } else { format!("{}.{}", path, k) };
+
+            for (k, v) in old.iter() {
+                match new.get(k) {
+                    Some(nv) => diff_values(&key(k), v, nv, diff),
+                    None     => diff.removed.push(key(k)),
+                }
+            }
+
+            for k in new.keys().filter(|k| !old.contains_key(*k)) {
+                diff.added.push(key(k));
+            }
+        },
+        (old, new) => if old != new {
+            diff.changed.push(String::from(path));
+        },
+    }
+}
+
+/// Get the parsed header and the content of an entry with binary content
+///
+/// The bytes are split at the first header separator. If the entry has no (valid) header or the
+/// header has no `imag.binary = true` flag, this returns `None`.
+fn binary_parts(bytes: &[u8]) -> Option<(Value, &[u8])> {
+    const START : &'static [u8] = b"---\n";
+    const SEP   : &'static [u8] = b"\n---\n";
+
+    if !bytes.starts_with(START) {
+        return None;
+    }
+
+    let rest = &bytes[START.len()..];
+    let pos  = match rest.windows(SEP.len()).position(|w| w == SEP) {
+        Some(pos) => pos,
+        None      => return None,
+    };
+
+    let header = match ::std::str::from_utf8(&rest[..pos + 1]).ok().and_then(|h| Value::parse(h).ok()) {
+        Some(header) => header,
+        None         => return None,
+    };
+
+    match header.read("imag.binary") {
+        Ok(Some(Value::Boolean(true))) => Some((header, &rest[pos + SEP.len()..])),
+        _                              => None,
+    }
+}
+
 impl PartialEq for Entry {
 
     fn eq(&self, other: &Entry) -> bool {
         self.location == other.location && // As the location only compares from the store root
             self.header == other.header && // and the other Entry could be from another store (not
-            self.content == other.content  // implemented by now, but we think ahead here)
+            self.content == other.content && // implemented by now, but we think ahead here)
+            self.binary_content == other.binary_content
     }
 
 }
@@ -1310,17 +2366,18 @@ mod glob_store_iter {
 
     use error::StoreErrorKind as SEK;
     use error::MapErrInto;
+    use store::Result;
 
     use libimagerror::trace::trace_error;
 
-    pub struct GlobStoreIdIterator {
-        store_path: PathBuf,
-        paths: Paths,
-    }
+    /// Iterator over the StoreIds of a glob(), which traces and skips all errors
+    ///
+    /// Use the `GlobStoreIdResultIterator` if the errors are of interest.
+    pub struct GlobStoreIdIterator(GlobStoreIdResultIterator);
 
     impl Debug for GlobStoreIdIterator {
 
-        fn fmt(&self, fmt: &mut Formatter) -> Result<(), FmtError> {
+        fn fmt(&self, fmt: &mut Formatter) -> ::std::result::Result<(), FmtError> {
             write!(fmt, "GlobStoreIdIterator")
         }
 
@@ -1338,11 +2395,15 @@ mod glob_store_iter {
 
         pub fn new(paths: Paths, store_path: PathBuf) -> GlobStoreIdIterator {
             debug!("Create a GlobStoreIdIterator(store_path = {:?}, /* ... */)", store_path);
+            GlobStoreIdIterator(GlobStoreIdResultIterator::new(paths, store_path))
+        }
 
-            GlobStoreIdIterator {
-                store_path: store_path,
-                paths: paths,
-            }
+    }
+
+    impl From<GlobStoreIdResultIterator> for GlobStoreIdIterator {
+
+        fn from(iter: GlobStoreIdResultIterator) -> GlobStoreIdIterator {
+            GlobStoreIdIterator(iter)
         }
 
     }
@@ -1351,16 +2412,66 @@ mod glob_store_iter {
         type Item = StoreId;
 
         fn next(&mut self) -> Option<StoreId> {
+            while let Some(res) = self.0.next() {
+                match res {
+                    Ok(id) => return Some(id),
+                    Err(e) => {
+                        debug!("GlobStoreIdIterator error: {:?}", e);
+                        trace_error(&e);
+                    },
+                }
+            }
+            None
+        }
+
+    }
+
+    /// Iterator over the StoreIds of a glob(), which yields errors instead of skipping them
+    ///
+    /// Errors are yielded for paths which cannot be read (for example because of missing
+    /// permissions) and for paths which cannot be turned into a StoreId. The iteration continues
+    /// after an error, so the caller can decide whether an incomplete listing is acceptable.
+    pub struct GlobStoreIdResultIterator {
+        store_path: PathBuf,
+        paths: Box<Iterator<Item = Result<PathBuf>>>,
+    }
+
+    impl Debug for GlobStoreIdResultIterator {
+
+        fn fmt(&self, fmt: &mut Formatter) -> ::std::result::Result<(), FmtError> {
+            write!(fmt, "GlobStoreIdResultIterator")
+        }
+
+    }
+
+    impl GlobStoreIdResultIterator {
+
+        pub fn new(paths: Paths, store_path: PathBuf) -> GlobStoreIdResultIterator {
+            debug!("Create a GlobStoreIdResultIterator(store_path = {:?}, /* ... */)", store_path);
+            let paths = paths.map(|r| r.map_err_into(SEK::StoreIdHandlingError));
+            GlobStoreIdResultIterator::from_paths(Box::new(paths), store_path)
+        }
+
+        pub fn from_paths(paths: Box<Iterator<Item = Result<PathBuf>>>, store_path: PathBuf)
+            -> GlobStoreIdResultIterator
+        {
+            GlobStoreIdResultIterator {
+                store_path: store_path,
+                paths: paths,
+            }
+        }
+
+    }
+
+    impl Iterator for GlobStoreIdResultIterator {
+        type Item = Result<StoreId>;
+
+        fn next(&mut self) -> Option<Result<StoreId>> {
             self.paths
                 .next()
-                .and_then(|o| {
-                    debug!("GlobStoreIdIterator::next() => {:?}", o);
-                    o.map_err_into(SEK::StoreIdHandlingError)
-                        .and_then(|p| StoreId::from_full_path(&self.store_path, p))
-                        .map_err(|e| {
-                            debug!("GlobStoreIdIterator error: {:?}", e);
-                            trace_error(&e);
-                        }).ok()
+                .map(|o| {
+                    debug!("GlobStoreIdResultIterator::next() => {:?}", o);
+                    o.and_then(|p| StoreId::from_full_path(&self.store_path, p))
                 })
         }
 
@@ -1368,7 +2479,6 @@ mod glob_store_iter {
 
 }
 
-
 #[cfg(test)]
 mod test {
     extern crate env_logger;
@@ -1508,52 +2618,517 @@ Hai";
         let entry = Entry::from_str(StoreId::new_baseless(PathBuf::from("test/foo~1.3")).unwrap(),
                                     TEST_ENTRY).unwrap();
 
-        assert_eq!(entry.content, "Hai");
+        assert_eq!(entry.content, "Hai");
+    }
+
+    #[test]
+    fn test_entry_to_str() {
+        use super::Entry;
+        use std::path::PathBuf;
+        println!("{}", TEST_ENTRY);
+        let entry = Entry::from_str(StoreId::new_baseless(PathBuf::from("test/foo~1.3")).unwrap(),
+                                    TEST_ENTRY).unwrap();
+        let string = entry.to_str();
+
+        assert_eq!(TEST_ENTRY, string);
+    }
+
+    static BINARY_CONTENT : &'static [u8] = &[0, 159, 146, 150, 255, b'\n', b'-', b'-', b'-', b'\n', 1];
+
+    #[test]
+    fn test_entry_binary_content_roundtrip() {
+        use super::Entry;
+        use std::path::PathBuf;
+        use toml_ext::TomlValueExt;
+
+        let id        = StoreId::new_baseless(PathBuf::from("test/binary")).unwrap();
+        let mut entry = Entry::from_str(id.clone(), TEST_ENTRY).unwrap();
+        assert!(entry.set_content_bytes(BINARY_CONTENT).is_ok());
+        assert!(entry.has_binary_content());
+
+        let bytes = entry.to_bytes();
+        let entry = Entry::from_bytes(id, &bytes[..]).unwrap();
+
+        assert!(entry.has_binary_content());
+        assert_eq!(entry.get_content_bytes(), BINARY_CONTENT);
+        assert_eq!(entry.get_header().read("imag.binary").unwrap(), Some(Value::Boolean(true)));
+    }
+
+    #[test]
+    fn test_entry_text_content_from_bytes() {
+        use super::Entry;
+        use std::path::PathBuf;
+
+        let id    = StoreId::new_baseless(PathBuf::from("test/text")).unwrap();
+        let entry = Entry::from_bytes(id, TEST_ENTRY.as_bytes()).unwrap();
+
+        assert!(!entry.has_binary_content());
+        assert_eq!(entry.get_content(), "Hai");
+        assert_eq!(entry.to_bytes(), TEST_ENTRY.as_bytes());
+    }
+
+    #[test]
+    fn test_entry_content_stats() {
+        use super::ContentStats;
+        use super::Entry;
+        use std::path::PathBuf;
+
+        let id        = StoreId::new_baseless(PathBuf::from("test/stats")).unwrap();
+        let mut entry = Entry::from_str(id, TEST_ENTRY).unwrap();
+        *entry.get_content_mut() = String::from("Grüße aus Köln\n\n  zwei\tWörter\n日本語 テキスト\n");
+
+        assert_eq!(entry.content_stats(), ContentStats { chars: 39, words: 7, lines: 4 });
+
+        entry.get_content_mut().clear();
+        assert_eq!(entry.content_stats(), ContentStats { chars: 0, words: 0, lines: 0 });
+    }
+
+    #[test]
+    fn test_entry_content_type_roundtrip() {
+        use super::Entry;
+        use std::path::PathBuf;
+
+        let id        = StoreId::new_baseless(PathBuf::from("test/content-type")).unwrap();
+        let mut entry = Entry::from_str(id.clone(), TEST_ENTRY).unwrap();
+        assert!(entry.set_content_type("text/markdown").is_ok());
+        assert_eq!(entry.get_content_type(), Some(String::from("text/markdown")));
+
+        let entry = Entry::from_str(id, &entry.to_str()).unwrap();
+        assert_eq!(entry.get_content_type(), Some(String::from("text/markdown")));
+        assert_eq!(entry.get_content(), "Hai");
+    }
+
+    #[test]
+    fn test_entry_content_type_default() {
+        use super::Entry;
+        use super::DEFAULT_CONTENT_TYPE;
+        use std::path::PathBuf;
+        use toml_ext::TomlValueExt;
+
+        let id        = StoreId::new_baseless(PathBuf::from("test/content-type")).unwrap();
+        let mut entry = Entry::from_str(id, TEST_ENTRY).unwrap();
+        assert_eq!(entry.get_content_type(), Some(String::from(DEFAULT_CONTENT_TYPE)));
+
+        assert!(entry.get_header_mut().set("imag.content", Value::Table(BTreeMap::new())).is_ok());
+        assert!(entry.get_header_mut().set("imag.content.type", Value::Integer(1)).is_ok());
+        assert_eq!(entry.get_content_type(), None);
+    }
+
+    #[test]
+    fn test_entry_from_str_reports_header_error_line() {
+        use super::Entry;
+        use std::error::Error;
+        use std::path::PathBuf;
+        use error::StoreErrorKind as SEK;
+
+        let id  = StoreId::new_baseless(PathBuf::from("test/broken")).unwrap();
+        let res = Entry::from_str(id, "---\n[imag]\nversion = \"0.3.0\"\nthis is broken\n---\ncontent\n");
+        assert!(res.is_err());
+
+        let e = res.unwrap_err();
+        assert_eq!(e.err_type(), SEK::MalformedEntry);
+
+        let syntax_error = e.cause().and_then(|c| c.cause()).map(|c| format!("{}", c)).unwrap();
+        assert!(syntax_error.starts_with("line 4,"), "{}", syntax_error);
+    }
+
+    #[test]
+    fn test_entry_clear_content_bytes() {
+        use super::Entry;
+        use std::path::PathBuf;
+
+        let id        = StoreId::new_baseless(PathBuf::from("test/binary")).unwrap();
+        let mut entry = Entry::from_str(id, TEST_ENTRY).unwrap();
+        assert!(entry.set_content_bytes(BINARY_CONTENT).is_ok());
+        assert!(entry.clear_content_bytes().is_ok());
+
+        assert!(!entry.has_binary_content());
+        assert!(entry.get_content_bytes().is_empty());
+        assert_eq!(entry.to_str(), "---\n[imag]\nversion = \"0.0.3\"\n---\n");
+    }
+
+    fn diff_entry(header: &str) -> super::Entry {
+        use super::Entry;
+        use std::path::PathBuf;
+
+        let id = StoreId::new_baseless(PathBuf::from("test/diff")).unwrap();
+        Entry::from_str(id, &format!("---\n[imag]\nversion = \"0.0.3\"\n{}---\n", header)).unwrap()
+    }
+
+    #[test]
+    fn test_header_diff_identical() {
+        let a = diff_entry("tags = [\"a\"]\n[foo]\nbar = 1\n");
+        let b = diff_entry("tags = [\"a\"]\n[foo]\nbar = 1\n");
+
+        assert!(a.header_diff(&b).is_empty());
+    }
+
+    #[test]
+    fn test_header_diff_nested_key() {
+        let a = diff_entry("[foo]\nbar = 1\nold = true\n[foo.baz]\nqux = \"a\"\n");
+        let b = diff_entry("[foo]\nbar = 1\nnew = true\n[foo.baz]\nqux = \"b\"\n");
+
+        let diff = a.header_diff(&b);
+        assert_eq!(diff.added, vec!["foo.new"]);
+        assert_eq!(diff.removed, vec!["foo.old"]);
+        assert_eq!(diff.changed, vec!["foo.baz.qux"]);
+
+        let reverse = b.header_diff(&a);
+        assert_eq!(reverse.added, vec!["foo.old"]);
+        assert_eq!(reverse.removed, vec!["foo.new"]);
+    }
+
+    #[test]
+    fn test_header_diff_tags() {
+        let a = diff_entry("tags = [\"a\", \"b\"]\n");
+        let b = diff_entry("tags = [\"a\", \"c\"]\n");
+        let c = diff_entry("");
+
+        let diff = a.header_diff(&b);
+        assert!(diff.added.is_empty());
+        assert!(diff.removed.is_empty());
+        assert_eq!(diff.changed, vec!["imag.tags"]);
+
+        assert_eq!(a.header_diff(&c).removed, vec!["imag.tags"]);
+    }
+
+    #[test]
+    fn test_header_diff_sorted() {
+        // "foo.a-b" sorts before "foo.a.z", although the table "a" comes before the key "a-b"
+        let a = diff_entry("[foo]\na-b = 1\n[foo.a]\nz = 1\n");
+        let b = diff_entry("[foo]\na-b = 2\n[foo.a]\nz = 2\n");
+
+        assert_eq!(a.header_diff(&b).changed, vec!["foo.a-b", "foo.a.z"]);
+    }
+
+}
+
+#[cfg(test)]
+mod store_tests {
+    use std::path::PathBuf;
+    use std::sync::Arc;
+    use std::sync::mpsc::{channel, Receiver, Sender};
+    use std::thread::{sleep, spawn, JoinHandle};
+    use std::time::Duration;
+
+    use super::Store;
+
+    pub fn get_store() -> Store {
+        Store::new_in_memory(PathBuf::from("/"), None).unwrap()
+    }
+
+    #[test]
+    fn test_store_instantiation() {
+        let store = get_store();
+
+        assert_eq!(store.location, PathBuf::from("/"));
+        assert!(store.entries.read().unwrap().is_empty());
+
+        assert!(store.store_unload_aspects.lock().unwrap().is_empty());
+
+        assert!(store.pre_create_aspects.lock().unwrap().is_empty());
+        assert!(store.post_create_aspects.lock().unwrap().is_empty());
+        assert!(store.pre_retrieve_aspects.lock().unwrap().is_empty());
+        assert!(store.post_retrieve_aspects.lock().unwrap().is_empty());
+        assert!(store.pre_update_aspects.lock().unwrap().is_empty());
+        assert!(store.post_update_aspects.lock().unwrap().is_empty());
+        assert!(store.pre_delete_aspects.lock().unwrap().is_empty());
+        assert!(store.post_delete_aspects.lock().unwrap().is_empty());
+        assert!(store.pre_move_aspects.lock().unwrap().is_empty());
+        assert!(store.post_move_aspects.lock().unwrap().is_empty());
+        assert!(store.pre_retrieve_copy_aspects.lock().unwrap().is_empty());
+        assert!(store.post_retrieve_copy_aspects.lock().unwrap().is_empty());
+        assert!(store.pre_save_to_aspects.lock().unwrap().is_empty());
+        assert!(store.post_save_to_aspects.lock().unwrap().is_empty());
+        assert!(store.pre_save_as_aspects.lock().unwrap().is_empty());
+        assert!(store.post_save_as_aspects.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_store_update_non_borrowed_entry_errors() {
+        use super::Entry;
+        use super::FileLockEntry;
+        use error::StoreErrorKind as SEK;
+
+        let store = get_store();
+        let id    = {
+            let entry = store.create(PathBuf::from("test-update-non-borrowed")).unwrap();
+            entry.get_location().clone()
+        };
+
+        // the entry is not borrowed anymore, so updating it must fail, not panic. This holds with
+        // the early-panic feature as well, which `make libimagstore-early-panic-test` checks.
+        let mut fle = FileLockEntry::new(&store, Entry::new(id));
+        let res     = store._update(&mut fle, false);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().err_type(), SEK::EntryNotBorrowed);
+        assert!(store.update(&mut fle).is_err());
+    }
+
+    #[test]
+    fn test_glob_result_iterator_reports_errors() {
+        use error::StoreErrorKind as SEK;
+        use libimagerror::into::IntoError;
+        use super::GlobStoreIdResultIterator;
+
+        let paths = vec![
+            Ok(PathBuf::from("/store/test/a")),
+            Err(SEK::FileError.into_error()),
+            Ok(PathBuf::from("/elsewhere/b")),
+            Ok(PathBuf::from("/store/test/c")),
+        ];
+
+        let iter = GlobStoreIdResultIterator::from_paths(Box::new(paths.into_iter()),
+                                                         PathBuf::from("/store"));
+        let res  = iter.collect::<Vec<_>>();
+
+        assert_eq!(res.len(), 4);
+        assert!(res[0].is_ok());
+        assert_eq!(res[1].as_ref().unwrap_err().err_type(), SEK::FileError);
+        assert_eq!(res[2].as_ref().unwrap_err().err_type(), SEK::StoreIdBuildFromFullPathError);
+        assert!(res[3].is_ok());
+    }
+
+    #[test]
+    fn test_store_retrieve_twice_fails() {
+        use error::StoreErrorCategory;
+
+        let store = get_store();
+        {
+            let _first = store.retrieve(PathBuf::from("test-retrieve-twice")).unwrap();
+
+            let second = store.retrieve(PathBuf::from("test-retrieve-twice"));
+            assert!(second.is_err());
+            assert_eq!(second.unwrap_err().category(), StoreErrorCategory::Locked);
+
+            let via_get = store.get(PathBuf::from("test-retrieve-twice"));
+            assert!(via_get.is_err());
+            assert_eq!(via_get.unwrap_err().category(), StoreErrorCategory::Locked);
+        }
+
+        // After the first FileLockEntry is dropped, the entry can be retrieved again
+        assert!(store.retrieve(PathBuf::from("test-retrieve-twice")).is_ok());
+    }
+
+    #[test]
+    fn test_store_failed_retrieve_does_not_borrow() {
+        use storeid::StoreId;
+
+        let store = get_store();
+        let id    = {
+            let entry = store.create(PathBuf::from("test-failed-retrieve")).unwrap();
+            entry.get_location().clone()
+        };
+
+        {
+            let mut hsmap = store.entries.write().unwrap();
+            let se        = hsmap.get_mut(&id).unwrap();
+            assert!(se.file.write_file_content(b"not an entry").is_ok());
+        }
+        assert!(store.retrieve(id.clone()).is_err());
+
+        {
+            let mut hsmap = store.entries.write().unwrap();
+            let se        = hsmap.get_mut(&id).unwrap();
+            assert!(!se.is_borrowed());
+            let entry = super::Entry::new(StoreId::new_baseless(PathBuf::from("test-failed-retrieve")).unwrap());
+            assert!(se.file.write_file_content(&entry.to_bytes()).is_ok());
+        }
+        assert!(store.retrieve(id).is_ok());
+    }
+
+    fn borrow_in_thread(store: Arc<Store>, id: &str)
+        -> (JoinHandle<()>, Receiver<()>, Sender<()>)
+    {
+        let (borrowed_tx, borrowed_rx) = channel();
+        let (release_tx, release_rx)   = channel();
+        let id = PathBuf::from(id);
+
+        let handle = spawn(move || {
+            let entry = store.retrieve(id).unwrap();
+            borrowed_tx.send(()).unwrap();
+            release_rx.recv().unwrap();
+            drop(entry);
+        });
+
+        (handle, borrowed_rx, release_tx)
+    }
+
+    #[test]
+    fn test_retrieve_timeout_succeeds_after_release() {
+        let store = Arc::new(get_store());
+        let (handle, borrowed, release) = borrow_in_thread(store.clone(), "test-timeout-release");
+        borrowed.recv().unwrap();
+
+        let releaser = spawn(move || {
+            sleep(Duration::from_millis(50));
+            release.send(()).unwrap();
+        });
+
+        let entry = store.retrieve_timeout(PathBuf::from("test-timeout-release"),
+                                           Duration::from_secs(10));
+        assert!(entry.is_ok());
+
+        releaser.join().unwrap();
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_retrieve_timeout_times_out() {
+        use error::StoreErrorKind as SEK;
+
+        let store = Arc::new(get_store());
+        let (handle, borrowed, release) = borrow_in_thread(store.clone(), "test-timeout");
+        borrowed.recv().unwrap();
+
+        let res = store.retrieve_timeout(PathBuf::from("test-timeout"), Duration::from_millis(50));
+        assert_eq!(res.unwrap_err().err_type(), SEK::LockTimeout);
+
+        release.send(()).unwrap();
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_store_update_all() {
+        use std::io::Read;
+
+        let store       = get_store();
+        let mut entries = (0..3)
+            .map(|n| store.retrieve(PathBuf::from(format!("test-update-all-{}", n))).unwrap())
+            .collect::<Vec<_>>();
+
+        for (n, entry) in entries.iter_mut().enumerate() {
+            *entry.get_content_mut() = format!("content {}", n);
+        }
+
+        assert!(store.update_all(entries.iter_mut()).is_ok());
+
+        // Check the file contents while the entries are still borrowed, as dropping writes them
+        for (n, entry) in entries.iter().enumerate() {
+            let mut hsmap = store.entries.write().unwrap();
+            let se        = hsmap.get_mut(entry.get_location()).unwrap();
+            let mut s     = String::new();
+            assert!(se.file.get_file_content().unwrap().read_to_string(&mut s).is_ok());
+            assert!(s.ends_with(&format!("---\ncontent {}", n)));
+        }
+    }
+
+    #[test]
+    fn test_store_filter_by_content_hash() {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+
+        fn hash(s: &str) -> String {
+            let mut hasher = DefaultHasher::new();
+            s.hash(&mut hasher);
+            format!("{:x}", hasher.finish())
+        }
+
+        let store = get_store();
+        for (name, content) in vec![("dup-1", "same"), ("dup-2", "same"), ("other", "different")] {
+            let mut entry = store.create(PathBuf::from(name)).unwrap();
+            *entry.get_content_mut() = String::from(content);
+        }
+
+        let found = store.find_by_content_hash(hash, &hash("same")).unwrap();
+        let found = found.iter().map(|id| format!("{}", id)).collect::<Vec<_>>();
+        assert_eq!(found, vec!["dup-1", "dup-2"]);
+
+        // A borrowed entry is skipped instead of failing the whole search
+        let borrowed = store.retrieve(PathBuf::from("dup-1")).unwrap();
+        let found    = store.find_by_content_hash(hash, &hash("same")).unwrap();
+        let found    = found.iter().map(|id| format!("{}", id)).collect::<Vec<_>>();
+        assert_eq!(found, vec!["dup-2"]);
+        drop(borrowed);
+    }
+
+    #[test]
+    fn test_store_binary_content() {
+        let store   = get_store();
+        let content = [0u8, 159, 146, 150, 255, 0, 1];
+        let id = {
+            let mut entry = store.create(PathBuf::from("test-binary")).unwrap();
+            assert!(entry.set_content_bytes(&content).is_ok());
+            entry.get_location().clone()
+        };
+
+        let entry = store.retrieve(id).unwrap();
+        assert!(entry.has_binary_content());
+        assert_eq!(entry.get_content_bytes(), &content[..]);
     }
 
     #[test]
-    fn test_entry_to_str() {
-        use super::Entry;
-        use std::path::PathBuf;
-        println!("{}", TEST_ENTRY);
-        let entry = Entry::from_str(StoreId::new_baseless(PathBuf::from("test/foo~1.3")).unwrap(),
-                                    TEST_ENTRY).unwrap();
-        let string = entry.to_str();
+    fn test_store_close_entry() {
+        let store = get_store();
+        let mut entry = store.create(PathBuf::from("test-close")).unwrap();
+        *entry.get_content_mut() = String::from("content");
+        let id = entry.get_location().clone();
 
-        assert_eq!(TEST_ENTRY, string);
+        assert!(entry.close().is_ok());
+
+        // the entry is not borrowed anymore
+        let entry = store.get(id).unwrap();
+        assert!(entry.is_some());
+        assert_eq!(entry.unwrap().get_content(), "content");
     }
 
-}
+    #[test]
+    fn test_store_dry_run_delete() {
+        let mut store = get_store();
+        let id        = PathBuf::from("test-dry-run-delete");
 
-#[cfg(test)]
-mod store_tests {
-    use std::path::PathBuf;
+        assert!(store.create(id.clone()).is_ok());
 
-    use super::Store;
+        store.set_dry_run(true);
+        assert!(store.is_dry_run());
+        assert!(store.delete(id.clone()).is_ok());
 
-    pub fn get_store() -> Store {
-        Store::new(PathBuf::from("/"), None).unwrap()
+        store.set_dry_run(false);
+        assert!(store.get(id).unwrap().is_some());
     }
 
     #[test]
-    fn test_store_instantiation() {
-        let store = get_store();
+    fn test_store_dry_run_delete_non_existing_errors() {
+        let mut store = get_store();
+        store.set_dry_run(true);
+        assert!(store.delete(PathBuf::from("test-dry-run-delete-non-existing")).is_err());
+    }
 
-        assert_eq!(store.location, PathBuf::from("/"));
-        assert!(store.entries.read().unwrap().is_empty());
+    #[test]
+    fn test_store_dry_run_delete_borrowed_errors() {
+        let mut store = get_store();
+        let id        = PathBuf::from("test-dry-run-delete-borrowed");
+        store.set_dry_run(true);
 
-        assert!(store.store_unload_aspects.lock().unwrap().is_empty());
+        let _entry = store.create(id.clone()).unwrap();
+        assert!(store.delete(id).is_err());
+    }
 
-        assert!(store.pre_create_aspects.lock().unwrap().is_empty());
-        assert!(store.post_create_aspects.lock().unwrap().is_empty());
-        assert!(store.pre_retrieve_aspects.lock().unwrap().is_empty());
-        assert!(store.post_retrieve_aspects.lock().unwrap().is_empty());
-        assert!(store.pre_update_aspects.lock().unwrap().is_empty());
-        assert!(store.post_update_aspects.lock().unwrap().is_empty());
-        assert!(store.pre_delete_aspects.lock().unwrap().is_empty());
-        assert!(store.post_delete_aspects.lock().unwrap().is_empty());
-        assert!(store.pre_move_aspects.lock().unwrap().is_empty());
-        assert!(store.post_move_aspects.lock().unwrap().is_empty());
+    #[test]
+    fn test_store_dry_run_move_by_id() {
+        use storeid::StoreId;
+
+        let mut store = get_store();
+        let old_id    = StoreId::new_baseless(PathBuf::from("test-dry-run-move-old")).unwrap();
+        let new_id    = StoreId::new_baseless(PathBuf::from("test-dry-run-move-new")).unwrap();
+        let other_id  = StoreId::new_baseless(PathBuf::from("test-dry-run-move-other")).unwrap();
+        let none_id   = StoreId::new_baseless(PathBuf::from("test-dry-run-move-none")).unwrap();
+
+        assert!(store.create(old_id.clone()).is_ok());
+        assert!(store.create(other_id.clone()).is_ok());
+
+        store.set_dry_run(true);
+        assert!(store.move_by_id(old_id.clone(), new_id.clone()).is_ok());
+        assert!(store.move_by_id(old_id.clone(), other_id.clone()).is_err());
+        assert!(store.move_by_id(none_id, new_id.clone()).is_err());
+        {
+            let _entry = store.retrieve(old_id.clone()).unwrap();
+            assert!(store.move_by_id(old_id.clone(), new_id.clone()).is_err());
+        }
+
+        store.set_dry_run(false);
+        assert!(store.get(old_id).unwrap().is_some());
+        assert!(store.get(new_id).unwrap().is_none());
     }
 
     #[test]
@@ -1684,54 +3259,198 @@ mod store_tests {
         }
     }
 
-    // Disabled because we cannot test this by now, as we rely on glob() in
-    // Store::retieve_for_module(), which accesses the filesystem and tests run in-memory, so there
-    // are no files on the filesystem in this test after Store::create().
-    //
-    // #[test]
-    // fn test_retrieve_for_module() {
-    //     let pathes = vec![
-    //         "foo/1", "foo/2", "foo/3", "foo/4", "foo/5",
-    //         "bar/1", "bar/2", "bar/3", "bar/4", "bar/5",
-    //         "bla/1", "bla/2", "bla/3", "bla/4", "bla/5",
-    //         "boo/1", "boo/2", "boo/3", "boo/4", "boo/5",
-    //         "glu/1", "glu/2", "glu/3", "glu/4", "glu/5",
-    //     ];
-
-    //     fn test(store: &Store, modulename: &str) {
-    //         use std::path::Component;
-    //         use storeid::StoreId;
-
-    //         let retrieved = store.retrieve_for_module(modulename);
-    //         assert!(retrieved.is_ok());
-    //         let v : Vec<StoreId> = retrieved.unwrap().collect();
-    //         println!("v = {:?}", v);
-    //         assert!(v.len() == 5);
-
-    //         let retrieved = store.retrieve_for_module(modulename);
-    //         assert!(retrieved.is_ok());
-
-    //         assert!(retrieved.unwrap().all(|e| {
-    //             let first = e.components().next();
-    //             assert!(first.is_some());
-    //             match first.unwrap() {
-    //                 Component::Normal(s) => s == modulename,
-    //                 _                    => false,
-    //             }
-    //         }))
-    //     }
-
-    //     let store = get_store();
-    //     for path in pathes {
-    //         assert!(store.create(PathBuf::from(path)).is_ok());
-    //     }
-
-    //     test(&store, "foo");
-    //     test(&store, "bar");
-    //     test(&store, "bla");
-    //     test(&store, "boo");
-    //     test(&store, "glu");
-    // }
+    #[test]
+    fn test_retrieve_for_module() {
+        let pathes = vec![
+            "foo/1", "foo/2", "foo/3", "foo/4", "foo/5",
+            "bar/1", "bar/2", "bar/3", "bar/4", "bar/5",
+            "bla/1", "bla/2", "bla/3", "bla/4", "bla/5",
+            "boo/1", "boo/2", "boo/3", "boo/4", "boo/5",
+            "glu/1", "glu/2", "glu/3", "glu/4", "glu/5",
+        ];
+
+        fn test(store: &Store, modulename: &str) {
+            use std::path::Component;
+            use storeid::StoreId;
+
+            let retrieved = store.retrieve_for_module(modulename);
+            assert!(retrieved.is_ok());
+            let v : Vec<StoreId> = retrieved.unwrap().collect();
+            println!("v = {:?}", v);
+            assert!(v.len() == 5);
+
+            let retrieved = store.retrieve_for_module(modulename);
+            assert!(retrieved.is_ok());
+
+            assert!(retrieved.unwrap().all(|e| {
+                let first = e.components().next();
+                assert!(first.is_some());
+                match first.unwrap() {
+                    Component::Normal(s) => s == modulename,
+                    _                    => false,
+                }
+            }))
+        }
+
+        let store = get_store();
+        for path in pathes {
+            assert!(store.create(PathBuf::from(path)).is_ok());
+        }
+
+        test(&store, "foo");
+        test(&store, "bar");
+        test(&store, "bla");
+        test(&store, "boo");
+        test(&store, "glu");
+
+        assert_eq!(store.entries().unwrap().count(), 25);
+    }
+
+    #[test]
+    fn test_walk_in_memory() {
+        use store::StoreObject;
+
+        let store = get_store();
+        for path in vec!["walk/a", "walk/sub/b", "walk/sub/deeper/c", "other/d"] {
+            assert!(store.create(PathBuf::from(path)).is_ok());
+        }
+
+        let objects = store.walk("walk")
+            .map(|o| match o {
+                StoreObject::Id(id)           => format!("id {}", id),
+                StoreObject::Collection(path) => format!("collection {}", path.display()),
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(objects, vec![
+            "collection /walk",
+            "id walk/a",
+            "collection /walk/sub",
+            "id walk/sub/b",
+            "collection /walk/sub/deeper",
+            "id walk/sub/deeper/c",
+        ]);
+    }
+
+    fn walked_collections(walk: ::store::Walk) -> Vec<PathBuf> {
+        use store::StoreObject;
+
+        walk.filter_map(|o| match o {
+                StoreObject::Id(_)            => None,
+                StoreObject::Collection(path) => Some(path),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_find_id_collisions() {
+        let store = get_store();
+
+        for name in &["notes/2016/note", "diary/2016/note", "todo/2016/note", "notes/other",
+                      "diary/2016/other", "root"] {
+            assert!(store.create(PathBuf::from(*name)).is_ok());
+        }
+
+        let collisions = store.find_id_collisions().unwrap();
+        assert_eq!(collisions.len(), 1);
+
+        let (ref id, ref paths) = collisions[0];
+        assert_eq!(id.local(), &PathBuf::from("2016/note"));
+        assert_eq!(paths, &vec![PathBuf::from("diary/2016/note"),
+                                PathBuf::from("notes/2016/note"),
+                                PathBuf::from("todo/2016/note")]);
+    }
+
+    #[test]
+    fn test_find_id_collisions_without_collision() {
+        let store = get_store();
+
+        for name in &["notes/2016/note", "diary/2017/note", "notes/2017/other"] {
+            assert!(store.create(PathBuf::from(*name)).is_ok());
+        }
+
+        assert!(store.find_id_collisions().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_unchanged_entry_is_not_rewritten() {
+        use std::fs::{File, metadata};
+        use std::io::Read;
+        use std::thread::sleep;
+        use std::time::Duration;
+        use tempdir::TempDir;
+
+        let tmp   = TempDir::new("imag-store-test").unwrap();
+        let store = Store::new(PathBuf::from(tmp.path()), None).unwrap();
+        let path  = tmp.path().join("test/unchanged");
+
+        {
+            let mut entry = store.create(PathBuf::from("test/unchanged")).unwrap();
+            *entry.get_content_mut() = String::from("content");
+        }
+
+        let read = || {
+            let mut s = String::new();
+            File::open(&path).unwrap().read_to_string(&mut s).unwrap();
+            (s, metadata(&path).unwrap().modified().unwrap())
+        };
+
+        let (content, mtime) = read();
+        sleep(Duration::from_millis(50));
+
+        {
+            let mut entry = store.retrieve(PathBuf::from("test/unchanged")).unwrap();
+            assert!(store.update(&mut entry).is_ok());
+        }
+        assert!(store.retrieve(PathBuf::from("test/unchanged")).is_ok());
+        assert_eq!(read(), (content.clone(), mtime));
+
+        {
+            let mut entry = store.retrieve(PathBuf::from("test/unchanged")).unwrap();
+            *entry.get_content_mut() = String::from("changed");
+        }
+        let (changed, _) = read();
+        assert!(changed != content);
+        assert!(changed.ends_with("changed"));
+    }
+
+    #[test]
+    fn test_walk_relative() {
+        use tempdir::TempDir;
+
+        let tmp   = TempDir::new("imag-store-test").unwrap();
+        let store = Store::new(PathBuf::from(tmp.path()), None).unwrap();
+        for path in vec!["module/a", "module/sub/b"] {
+            assert!(store.create(PathBuf::from(path)).is_ok());
+        }
+
+        let mut relative = walked_collections(store.walk("module").relative());
+        relative.sort();
+        assert_eq!(relative, vec![PathBuf::from("module"), PathBuf::from("module/sub")]);
+
+        let mut absolute = walked_collections(store.walk("module"));
+        absolute.sort();
+        assert_eq!(absolute, vec![tmp.path().join("module"), tmp.path().join("module/sub")]);
+    }
+
+    #[test]
+    fn test_walk_relative_in_memory() {
+        let store = get_store();
+        assert!(store.create(PathBuf::from("module/sub/a")).is_ok());
+
+        assert_eq!(walked_collections(store.walk("module").relative()),
+                   vec![PathBuf::from("module"), PathBuf::from("module/sub")]);
+    }
+
+    #[test]
+    fn test_in_memory_stores_are_separate() {
+        let a = get_store();
+        let b = get_store();
+
+        assert!(a.create(PathBuf::from("only-in-a")).is_ok());
+        assert_eq!(a.entries().unwrap().count(), 1);
+        assert_eq!(b.entries().unwrap().count(), 0);
+    }
 
     #[test]
     fn test_store_move_moves_in_hm() {
@@ -1773,6 +3492,24 @@ mod store_tests {
         }
     }
 
+    #[test]
+    fn test_store_move_keeps_content() {
+        use storeid::StoreId;
+
+        let store = get_store();
+
+        {
+            let mut entry = store.create(PathBuf::from("old")).unwrap();
+            *entry.get_content_mut() = String::from("content");
+        }
+
+        assert!(store.move_by_id(StoreId::new_baseless(PathBuf::from("old")).unwrap(),
+                                 StoreId::new_baseless(PathBuf::from("new")).unwrap()).is_ok());
+
+        let entry = store.get(PathBuf::from("new")).unwrap().unwrap();
+        assert_eq!(entry.get_content(), "content");
+    }
+
 }
 
 #[cfg(test)]
@@ -1818,7 +3555,13 @@ mod store_hook_tests {
                     HP::PreCreate    |
                     HP::PreRetrieve  |
                     HP::PreDelete    |
-                    HP::PostDelete   => HDA::StoreIdAccess(&self.accessor),
+                    HP::PostDelete   |
+                    HP::PreRetrieveCopy  |
+                    HP::PostRetrieveCopy |
+                    HP::PreSaveTo    |
+                    HP::PostSaveTo   |
+                    HP::PreSaveAs    |
+                    HP::PostSaveAs   => HDA::StoreIdAccess(&self.accessor),
                     HP::PostCreate   |
                     HP::PostRetrieve |
                     HP::PreUpdate    |
@@ -1907,10 +3650,18 @@ mod store_hook_tests {
     }
 
     use std::path::PathBuf;
+    use std::sync::{Arc, Mutex};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use toml::Value;
 
+    use hook::Hook;
+    use hook::accessor::{HookDataAccessor, HookDataAccessorProvider, StoreIdAccessor,
+                         MutableHookDataAccessor};
     use hook::position::HookPosition as HP;
+    use hook::result::HookResult;
     use storeid::StoreId;
-    use store::Store;
+    use store::{Store, FileLockEntry};
 
     use self::test_hook::TestHook;
 
@@ -1919,7 +3670,105 @@ mod store_hook_tests {
 
         let cfg : ::toml::Value = from_str(mini_config()).unwrap();
         println!("Config parsed: {:?}", cfg);
-        Store::new(PathBuf::from("/"), Some(cfg.get("store").cloned().unwrap())).unwrap()
+        Store::new_in_memory(PathBuf::from("/"), Some(cfg.get("store").cloned().unwrap())).unwrap()
+    }
+
+    fn store_with_module_config() -> Store {
+        use toml::de::from_str;
+
+        let cfg = format!("{}{}", mini_config(), r#"
+[store.modules.mail]
+maildir = "~/mail"
+
+[store.modules.mail.accounts]
+default = "work"
+"#);
+        let cfg : ::toml::Value = from_str(&cfg).unwrap();
+        Store::new_in_memory(PathBuf::from("/"), Some(cfg.get("store").cloned().unwrap())).unwrap()
+    }
+
+    #[test]
+    fn test_config_value() {
+        use toml::Value;
+
+        let store = store_with_module_config();
+
+        assert_eq!(store.config_value("aspects.test.parallel"), Some(&Value::Boolean(false)));
+        assert_eq!(store.config_value("modules.mail.maildir"), Some(&Value::String(String::from("~/mail"))));
+        assert_eq!(store.config_value("modules.mail.accounts.default"),
+                   Some(&Value::String(String::from("work"))));
+        assert!(store.config_value("modules.mail").map(|v| v.as_table().is_some()).unwrap_or(false));
+    }
+
+    #[test]
+    fn test_config_value_absent() {
+        let store = store_with_module_config();
+
+        assert_eq!(store.config_value("modules.bookmark"), None);
+        assert_eq!(store.config_value("modules.mail.maildir.sub"), None);
+        assert_eq!(store.config_value("modules..mail"), None);
+        assert_eq!(store.config_value("store.modules.mail.maildir"), None);
+        assert_eq!(store.config_value(""), None);
+
+        assert_eq!(::store::store_tests::get_store().config_value("modules.mail.maildir"), None);
+    }
+
+    fn store_with_extension_config(path: PathBuf) -> Store {
+        use toml::de::from_str;
+
+        let cfg = format!("{}{}", mini_config(), r#"
+[store.modules.notes]
+extension = "md"
+"#);
+        let cfg : ::toml::Value = from_str(&cfg).unwrap();
+        Store::new(path, Some(cfg.get("store").cloned().unwrap())).unwrap()
+    }
+
+    #[test]
+    fn test_module_extension_is_appended() {
+        use tempdir::TempDir;
+
+        let tmp   = TempDir::new("imag-store-test").unwrap();
+        let store = store_with_extension_config(PathBuf::from(tmp.path()));
+
+        {
+            let mut entry = store.create(PathBuf::from("notes/foo")).unwrap();
+            assert_eq!(entry.get_location().local(), &PathBuf::from("notes/foo.md"));
+            *entry.get_content_mut() = String::from("content");
+        }
+
+        assert!(tmp.path().join("notes/foo.md").is_file());
+        assert!(!tmp.path().join("notes/foo").exists());
+
+        {
+            let entry = store.get(PathBuf::from("notes/foo")).unwrap().unwrap();
+            assert_eq!(entry.get_content(), "content");
+        }
+
+        {
+            let entry = store.get(PathBuf::from("notes/foo.md")).unwrap().unwrap();
+            assert_eq!(entry.get_content(), "content");
+        }
+
+        assert!(store.create(PathBuf::from("notes/foo.md")).is_err());
+        assert!(store.delete(PathBuf::from("notes/foo")).is_ok());
+        assert!(!tmp.path().join("notes/foo.md").exists());
+    }
+
+    #[test]
+    fn test_module_extension_only_for_configured_modules() {
+        use tempdir::TempDir;
+
+        let tmp   = TempDir::new("imag-store-test").unwrap();
+        let store = store_with_extension_config(PathBuf::from(tmp.path()));
+
+        {
+            let entry = store.create(PathBuf::from("todo/foo")).unwrap();
+            assert_eq!(entry.get_location().local(), &PathBuf::from("todo/foo"));
+        }
+
+        assert!(tmp.path().join("todo/foo").is_file());
+        assert!(store.get(PathBuf::from("todo/foo.md")).unwrap().is_none());
     }
 
     fn mini_config() -> &'static str {
@@ -1936,6 +3785,12 @@ pre-update-hook-aspects    = [ "test" ]
 post-update-hook-aspects   = [ "test" ]
 pre-delete-hook-aspects    = [ "test" ]
 post-delete-hook-aspects   = [ "test" ]
+pre-retrieve-copy-hook-aspects  = [ "test" ]
+post-retrieve-copy-hook-aspects = [ "test" ]
+pre-save-to-hook-aspects   = [ "test" ]
+post-save-to-hook-aspects  = [ "test" ]
+pre-save-as-hook-aspects   = [ "test" ]
+post-save-as-hook-aspects  = [ "test" ]
 
 [store.aspects.test]
 parallel = false
@@ -2077,6 +3932,191 @@ aspect = "test"
     }
 
 
+    /// Hook which counts how often it was executed
+    #[derive(Debug)]
+    struct CountingHook(Arc<AtomicUsize>);
+
+    impl Hook for CountingHook {
+        fn name(&self) -> &'static str { "testhook_counting" }
+        fn set_config(&mut self, _: &Value) { }
+    }
+
+    impl HookDataAccessorProvider for CountingHook {
+        fn accessor(&self) -> HookDataAccessor {
+            HookDataAccessor::StoreIdAccess(self)
+        }
+    }
+
+    impl StoreIdAccessor for CountingHook {
+        fn access(&self, _: &StoreId) -> HookResult<()> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    /// Hook which records the content of the entries it sees
+    #[derive(Debug)]
+    struct ContentRecordingHook(Arc<Mutex<Vec<String>>>);
+
+    impl Hook for ContentRecordingHook {
+        fn name(&self) -> &'static str { "testhook_content_recording" }
+        fn set_config(&mut self, _: &Value) { }
+    }
+
+    impl HookDataAccessorProvider for ContentRecordingHook {
+        fn accessor(&self) -> HookDataAccessor {
+            HookDataAccessor::MutableAccess(self)
+        }
+    }
+
+    impl MutableHookDataAccessor for ContentRecordingHook {
+        fn access_mut(&self, fle: &mut FileLockEntry) -> HookResult<()> {
+            self.0.lock().unwrap().push(fle.get_content().clone());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_registered_aspects() {
+        let mut store = get_store_with_config();
+        assert!(store.registered_aspects().iter().all(|info| info.hooks.is_empty()));
+
+        for pos in vec![HP::PreCreate, HP::PostCreate, HP::PostCreate, HP::PreDelete] {
+            let hook = TestHook::new(pos.clone(), true, false);
+            assert!(store.register_hook(pos, "test", Box::new(hook)).is_ok());
+        }
+
+        let infos = store.registered_aspects();
+
+        // one "test" aspect for each position in the configuration
+        assert_eq!(infos.len(), 15);
+        assert!(infos.iter().all(|info| info.name == "test" && !info.parallel));
+
+        let hooks_at = |pos: HP| {
+            infos.iter()
+                .find(|info| info.position == pos)
+                .map(|info| info.hooks.clone())
+                .unwrap()
+        };
+
+        assert_eq!(hooks_at(HP::PreCreate), vec!["testhook_succeeding"]);
+        assert_eq!(hooks_at(HP::PostCreate), vec!["testhook_succeeding", "testhook_succeeding"]);
+        assert_eq!(hooks_at(HP::PreDelete), vec!["testhook_succeeding"]);
+        assert!(hooks_at(HP::PostDelete).is_empty());
+    }
+
+    #[test]
+    fn test_create_with_content() {
+        let mut store = get_store_with_config();
+        let seen      = Arc::new(Mutex::new(vec![]));
+        let hook      = ContentRecordingHook(seen.clone());
+        assert!(store.register_hook(HP::PostCreate, "test", Box::new(hook)).is_ok());
+
+        let id    = StoreId::new_baseless(PathBuf::from("test_create_with_content")).unwrap();
+        let entry = store.create_with_content(id, String::from("initial content")).unwrap();
+
+        assert_eq!(entry.get_content(), "initial content");
+        assert_eq!(*seen.lock().unwrap(), vec![String::from("initial content")]);
+    }
+
+    fn count_update_hooks_for_unchanged_entry(mut store: Store) -> usize {
+        let counter = Arc::new(AtomicUsize::new(0));
+        for pos in vec![HP::PreUpdate, HP::PostUpdate] {
+            let hook = CountingHook(counter.clone());
+            assert!(store.register_hook(pos, "test", Box::new(hook)).is_ok());
+        }
+
+        let id = StoreId::new_baseless(PathBuf::from("test_unchanged_update_hooks")).unwrap();
+        assert!(store.create(id.clone()).is_ok());
+        counter.store(0, Ordering::SeqCst);
+
+        let mut entry = store.retrieve(id).unwrap();
+        assert!(store.update(&mut entry).is_ok());
+        counter.load(Ordering::SeqCst)
+    }
+
+    #[test]
+    fn test_update_hooks_run_for_unchanged_entries() {
+        assert_eq!(count_update_hooks_for_unchanged_entry(get_store_with_config()), 2);
+    }
+
+    #[test]
+    fn test_update_hooks_skipped_for_unchanged_entries() {
+        use toml::de::from_str;
+
+        let cfg = mini_config().replace("[store]\n", "[store]\nskip-hooks-when-unchanged = true\n");
+        let cfg : ::toml::Value = from_str(&cfg).unwrap();
+        let store = Store::new_in_memory(PathBuf::from("/"), Some(cfg.get("store").cloned().unwrap()))
+            .unwrap();
+
+        assert_eq!(count_update_hooks_for_unchanged_entry(store), 0);
+    }
+
+    #[test]
+    fn test_changed_entries_are_written_when_skipping_hooks_for_unchanged() {
+        use toml::de::from_str;
+
+        let cfg = mini_config().replace("[store]\n", "[store]\nskip-hooks-when-unchanged = true\n");
+        let cfg : ::toml::Value = from_str(&cfg).unwrap();
+        let mut store = Store::new_in_memory(PathBuf::from("/"), Some(cfg.get("store").cloned().unwrap()))
+            .unwrap();
+
+        let counter = Arc::new(AtomicUsize::new(0));
+        for pos in vec![HP::PreUpdate, HP::PostUpdate] {
+            let hook = CountingHook(counter.clone());
+            assert!(store.register_hook(pos, "test", Box::new(hook)).is_ok());
+        }
+
+        let id = StoreId::new_baseless(PathBuf::from("test_changed_update_hooks")).unwrap();
+        assert!(store.create(id.clone()).is_ok());
+        counter.store(0, Ordering::SeqCst);
+
+        {
+            let mut entry = store.retrieve(id.clone()).unwrap();
+            *entry.get_content_mut() = String::from("changed");
+            assert!(store.update(&mut entry).is_ok());
+        }
+        assert_eq!(counter.load(Ordering::SeqCst), 2);
+        assert_eq!(store.retrieve_copy(id).unwrap().get_content(), "changed");
+    }
+
+    #[test]
+    fn test_retrieve_copy_hooks() {
+        let mut store = get_store_with_config();
+        let counter   = Arc::new(AtomicUsize::new(0));
+        for pos in vec![HP::PreRetrieveCopy, HP::PostRetrieveCopy] {
+            let hook = CountingHook(counter.clone());
+            assert!(store.register_hook(pos, "test", Box::new(hook)).is_ok());
+        }
+
+        let id = StoreId::new_baseless(PathBuf::from("test_retrieve_copy_hooks")).unwrap();
+        assert!(store.create(id.clone()).is_ok());
+        assert_eq!(counter.load(Ordering::SeqCst), 0);
+
+        assert!(store.retrieve_copy(id).is_ok());
+        assert_eq!(counter.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_save_as_hooks_do_not_fire_on_save_to() {
+        let mut store = get_store_with_config();
+        let counter   = Arc::new(AtomicUsize::new(0));
+        let hook      = CountingHook(counter.clone());
+        assert!(store.register_hook(HP::PostSaveAs, "test", Box::new(hook)).is_ok());
+
+        let id = StoreId::new_baseless(PathBuf::from("test_save_as_hooks")).unwrap();
+        assert!(store.create(id.clone()).is_ok());
+
+        let entry = store.retrieve(id).unwrap();
+        let to    = StoreId::new_baseless(PathBuf::from("test_save_as_hooks-to")).unwrap();
+        assert!(store.save_to(&entry, to).is_ok());
+        assert_eq!(counter.load(Ordering::SeqCst), 0);
+
+        let as_ = StoreId::new_baseless(PathBuf::from("test_save_as_hooks-as")).unwrap();
+        assert!(store.save_as(entry, as_).is_ok());
+        assert_eq!(counter.load(Ordering::SeqCst), 1);
+    }
+
     fn get_store_with_aborting_hook_at_pos(pos: HP) -> Store {
         let mut store = get_store_with_config();
         let hook      = TestHook::new(pos.clone(), false, true);
@@ -2162,6 +4202,16 @@ aspect = "test"
         assert!(store.update(&mut fle).is_err());
     }
 
+    #[test]
+    fn test_post_update_error_on_close() {
+        let store = get_store_with_aborting_hook_at_pos(HP::PostUpdate);
+        let pb    = StoreId::new_baseless(PathBuf::from("test_post_update_error_on_close")).unwrap();
+        let fle   = store.create(pb.clone()).unwrap();
+
+        // Dropping the entry would only trace the error
+        assert!(fle.close().is_err());
+    }
+
     fn get_store_with_allowed_error_hook_at_pos(pos: HP) -> Store {
         let mut store = get_store_with_config();
         let hook      = TestHook::new(pos.clone(), false, false);
@@ -67,14 +67,46 @@ impl StoreId {
     }
 
     pub fn new_baseless(id: PathBuf) -> Result<StoreId> {
-        if id.is_absolute() {
-            Err(SEK::StoreIdLocalPartAbsoluteError.into_error())
-        } else {
-            Ok(StoreId {
-                base: None,
-                id: id
-            })
+        StoreId::is_valid_relative(&id).map(|_| StoreId { base: None, id: id })
+    }
+
+    /// Check whether `path` is a valid _local_ part of a StoreId, that is a relative path which
+    /// stays inside the store.
+    ///
+    /// Fails with
+    ///
+    /// * `StoreErrorKind::StoreIdEmptyError` if the path is empty
+    /// * `StoreErrorKind::StoreIdLocalPartAbsoluteError` if the path is absolute
+    /// * `StoreErrorKind::StoreIdParentDirError` if the path contains `..`, which could be used to
+    ///   escape the store
+    /// * `StoreErrorKind::StoreIdEmptyComponentError` if the path contains an empty component,
+    ///   as in `a//b`
+    pub fn is_valid_relative(path: &Path) -> Result<()> {
+        use std::path::Component;
+
+        if path.as_os_str().is_empty() {
+            return Err(SEK::StoreIdEmptyError.into_error());
+        }
+
+        if path.has_root() || path.is_absolute() {
+            return Err(SEK::StoreIdLocalPartAbsoluteError.into_error());
         }
+
+        for component in path.components() {
+            match component {
+                Component::ParentDir => return Err(SEK::StoreIdParentDirError.into_error()),
+                Component::RootDir |
+                Component::Prefix(_) => return Err(SEK::StoreIdLocalPartAbsoluteError.into_error()),
+                _ => {},
+            }
+        }
+
+        // `Path::components()` normalizes empty components away
+        if path.to_string_lossy().contains("//") {
+            return Err(SEK::StoreIdEmptyComponentError.into_error());
+        }
+
+        Ok(())
     }
 
     pub fn without_base(mut self) -> StoreId {
@@ -87,6 +119,11 @@ impl StoreId {
         self
     }
 
+    /// Get the base of the StoreId, which is the path of the store, if it is set
+    pub fn base(&self) -> Option<&PathBuf> {
+        self.base.as_ref()
+    }
+
     /// Transform the StoreId object into a PathBuf, error if the base of the StoreId is not
     /// specified.
     pub fn into_pathbuf(self) -> Result<PathBuf> {
@@ -221,6 +258,15 @@ impl StoreIdIterator {
         }
     }
 
+    /// Get an iterator which yields the ids sorted lexicographically by their _local_ part
+    ///
+    /// All ids are collected before the first one is yielded.
+    pub fn sorted(self) -> StoreIdIterator {
+        let mut ids = self.iter.collect::<Vec<StoreId>>();
+        ids.sort_by(|a, b| a.local().cmp(b.local()));
+        StoreIdIterator::new(Box::new(ids.into_iter()))
+    }
+
 }
 
 impl Iterator for StoreIdIterator {
@@ -304,6 +350,55 @@ mod test {
         });
     }
 
+    #[test]
+    fn test_storeid_iterator_sorted() {
+        use storeid::StoreIdIterator;
+
+        let ids = vec!["b", "a", "c"]
+            .into_iter()
+            .map(|s| StoreId::new_baseless(PathBuf::from(s)).unwrap())
+            .collect::<Vec<_>>();
+
+        let sorted = StoreIdIterator::new(Box::new(ids.into_iter()))
+            .sorted()
+            .map(|id| format!("{}", id))
+            .collect::<Vec<_>>();
+
+        assert_eq!(sorted, vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn test_parent_dir_is_rejected() {
+        for p in vec!["..", "../test", "test/../../test", "test/.."] {
+            let id = StoreId::new_baseless(PathBuf::from(p));
+            assert_eq!(id.unwrap_err().err_type(), SEK::StoreIdParentDirError, "{}", p);
+        }
+    }
+
+    #[test]
+    fn test_absolute_path_is_rejected() {
+        let id = StoreId::new_baseless(PathBuf::from("/tmp/test"));
+        assert_eq!(id.unwrap_err().err_type(), SEK::StoreIdLocalPartAbsoluteError);
+    }
+
+    #[test]
+    fn test_empty_components_are_rejected() {
+        let id = StoreId::new_baseless(PathBuf::from("test//test"));
+        assert_eq!(id.unwrap_err().err_type(), SEK::StoreIdEmptyComponentError);
+
+        let id = StoreId::new_baseless(PathBuf::from(""));
+        assert_eq!(id.unwrap_err().err_type(), SEK::StoreIdEmptyError);
+    }
+
+    #[test]
+    fn test_is_valid_relative() {
+        use std::path::Path;
+
+        assert!(StoreId::is_valid_relative(Path::new("test")).is_ok());
+        assert!(StoreId::is_valid_relative(Path::new("test/a.b/c")).is_ok());
+        assert!(StoreId::is_valid_relative(Path::new("test/..a")).is_ok());
+    }
+
     #[test]
     fn test_baseless_into_pathbuf_is_err() {
         let id = StoreId::new_baseless(PathBuf::from("test"));
@@ -26,6 +26,7 @@ use store::Result;
 use error::StoreError as SE;
 use error::StoreErrorKind as SEK;
 use error::{ParserErrorKind, ParserError};
+use error::HeaderSyntaxError;
 use libimagerror::into::IntoError;
 
 type Table = BTreeMap<String, Value>;
@@ -36,6 +37,9 @@ pub trait TomlValueExt {
     fn read_with_sep(&self, spec: &str, splitchr: char) -> Result<Option<Value>>;
     fn delete_with_sep(&mut self, spec: &str, splitchr: char) -> Result<Option<Value>>;
 
+    /// Get the keys of the top-level table, an empty `Vec` if the value is not a table
+    fn keys(&self) -> Vec<String>;
+
     #[inline]
     fn insert(&mut self, spec: &str, v: Value) -> Result<bool> {
         self.insert_with_sep(spec, '.', v)
@@ -55,6 +59,76 @@ pub trait TomlValueExt {
     fn delete(&mut self, spec: &str) -> Result<Option<Value>> {
         self.delete_with_sep(spec, '.')
     }
+
+    /// Check whether there is a value for `spec`
+    ///
+    /// An invalid `spec` is treated like a path which does not exist.
+    fn has_key(&self, spec: &str) -> bool {
+        match self.read(spec) {
+            Ok(Some(_)) => true,
+            _           => false,
+        }
+    }
+
+    /// Delete the value for `spec`, if there is one
+    ///
+    /// Other than `TomlValueExt::delete()`, this returns `Ok(None)` if the path to `spec` does not
+    /// exist.
+    fn delete_key(&mut self, spec: &str) -> Result<Option<Value>> {
+        if self.has_key(spec) {
+            self.delete(spec)
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Read a String from the header
+    ///
+    /// Returns `Ok(None)` if there is no value for `spec` and an error of kind
+    /// `StoreErrorKind::HeaderTypeFailure` if the value is not a String.
+    fn read_string(&self, spec: &str) -> Result<Option<String>> {
+        match try!(self.read(spec)) {
+            Some(Value::String(s)) => Ok(Some(s)),
+            Some(_)                => Err(SEK::HeaderTypeFailure.into_error()),
+            None                   => Ok(None),
+        }
+    }
+
+    /// Read a Boolean from the header, see `TomlValueExt::read_string()`
+    fn read_bool(&self, spec: &str) -> Result<Option<bool>> {
+        match try!(self.read(spec)) {
+            Some(Value::Boolean(b)) => Ok(Some(b)),
+            Some(_)                 => Err(SEK::HeaderTypeFailure.into_error()),
+            None                    => Ok(None),
+        }
+    }
+
+    /// Read an Integer from the header, see `TomlValueExt::read_string()`
+    fn read_int(&self, spec: &str) -> Result<Option<i64>> {
+        match try!(self.read(spec)) {
+            Some(Value::Integer(i)) => Ok(Some(i)),
+            Some(_)                 => Err(SEK::HeaderTypeFailure.into_error()),
+            None                    => Ok(None),
+        }
+    }
+
+    /// Read an Array of Strings from the header, see `TomlValueExt::read_string()`
+    ///
+    /// If one of the elements of the Array is not a String, this fails as well.
+    fn read_string_array(&self, spec: &str) -> Result<Option<Vec<String>>> {
+        match try!(self.read(spec)) {
+            Some(Value::Array(a)) => a
+                .into_iter()
+                .map(|v| match v {
+                    Value::String(s) => Ok(s),
+                    _                => Err(SEK::HeaderTypeFailure.into_error()),
+                })
+                .collect::<Result<Vec<String>>>()
+                .map(Some),
+            Some(_) => Err(SEK::HeaderTypeFailure.into_error()),
+            None    => Ok(None),
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -289,6 +363,13 @@ impl TomlValueExt for Value {
         }
     }
 
+    fn keys(&self) -> Vec<String> {
+        match *self {
+            Value::Table(ref t) => t.keys().cloned().collect(),
+            _                   => vec![],
+        }
+    }
+
 }
 
 fn setup<'a>(v: &'a mut Value, spec: &str, sep: char)
@@ -380,12 +461,7 @@ impl Header for Value {
     }
 
     fn parse(s: &str) -> EntryResult<Value> {
-        use toml::de::from_str;
-
-        from_str(s)
-            .map_err(|_| ParserErrorKind::TOMLParserErrors.into())
-            .and_then(verify_header_consistency)
-            .map(Value::Table)
+        parse_header_at(s, 0)
     }
 
     fn default_header() -> Value {
@@ -405,6 +481,23 @@ impl Header for Value {
 
 }
 
+/// Parse the header `s`, which starts after `line_offset` lines of the entry
+///
+/// Syntax errors are reported with a `HeaderSyntaxError` as cause, which holds the position of the
+/// error in the entry (rather than in `s`).
+pub fn parse_header_at(s: &str, line_offset: usize) -> EntryResult<Value> {
+    use toml::de::from_str;
+
+    from_str(s)
+        .map_err(|e| {
+            let position = e.line_col().map(|(line, col)| (line + line_offset + 1, col + 1));
+            let cause    = HeaderSyntaxError::new(position, format!("{}", e));
+            ParserErrorKind::TOMLParserErrors.into_error_with_cause(Box::new(cause))
+        })
+        .and_then(verify_header_consistency)
+        .map(Value::Table)
+}
+
 pub fn verify_header_consistency(t: Table) -> EntryResult<Table> {
     verify_header(&t)
         .map_err(Box::new)
@@ -890,5 +983,83 @@ mod test {
 
     }
 
+    fn create_typed_header() -> Value {
+        use toml::de::from_str;
+
+        from_str(r#"
+            string = "s"
+            bool   = true
+            int    = 1
+            array  = [ "a", "b" ]
+            mixed  = [ 1, 2 ]
+        "#).unwrap()
+    }
+
+    fn is_type_failure<T>(r: ::store::Result<T>) -> bool {
+        r.map_err(|e| e.err_type() == ::error::StoreErrorKind::HeaderTypeFailure)
+            .err()
+            .unwrap_or(false)
+    }
+
+    #[test]
+    fn test_header_read_string() {
+        let h = create_typed_header();
+        assert_eq!(h.read_string("string").unwrap(), Some(String::from("s")));
+        assert!(is_type_failure(h.read_string("int")));
+        assert_eq!(h.read_string("nothing").unwrap(), None);
+    }
+
+    #[test]
+    fn test_header_read_bool() {
+        let h = create_typed_header();
+        assert_eq!(h.read_bool("bool").unwrap(), Some(true));
+        assert!(is_type_failure(h.read_bool("string")));
+        assert_eq!(h.read_bool("nothing").unwrap(), None);
+    }
+
+    #[test]
+    fn test_header_read_int() {
+        let h = create_typed_header();
+        assert_eq!(h.read_int("int").unwrap(), Some(1));
+        assert!(is_type_failure(h.read_int("bool")));
+        assert_eq!(h.read_int("nothing").unwrap(), None);
+    }
+
+    #[test]
+    fn test_header_read_string_array() {
+        let h = create_typed_header();
+        assert_eq!(h.read_string_array("array").unwrap(),
+                   Some(vec![String::from("a"), String::from("b")]));
+        assert!(is_type_failure(h.read_string_array("string")));
+        assert!(is_type_failure(h.read_string_array("mixed")));
+        assert_eq!(h.read_string_array("nothing").unwrap(), None);
+    }
+
+    #[test]
+    fn test_header_has_key() {
+        let h = create_header();
+        assert!(h.has_key("a"));
+        assert!(h.has_key("a.array.1"));
+        assert!(h.has_key("d.something"));
+        assert!(!h.has_key("a.nothing"));
+        assert!(!h.has_key("nothing.at.all"));
+    }
+
+    #[test]
+    fn test_header_delete_key() {
+        let mut h = create_header();
+        assert_eq!(h.delete_key("d.something").unwrap(), Some(Value::String(String::from("else"))));
+        assert!(!h.has_key("d.something"));
+        assert!(h.has_key("d"));
+        assert_eq!(h.delete_key("nothing.at.all").unwrap(), None);
+    }
+
+    #[test]
+    fn test_header_keys() {
+        let h = create_header();
+        assert_eq!(h.keys(), vec!["a", "b", "c", "d"]);
+        assert!(Value::Integer(1).keys().is_empty());
+    }
+
 }
 
@@ -22,7 +22,7 @@
 macro_rules! if_cfg_panic {
     ()                       => { panic!() };
     ($msg:expr)              => { panic!($msg) };
-    ($fmt:expr, $($arg:tt)+) => { panic!($fmt, $($($arg),*)) };
+    ($fmt:expr, $($arg:tt)+) => { panic!($fmt, $($arg)+) };
 }
 
 #[cfg(not(feature = "early-panic"))]
@@ -0,0 +1,276 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015, 2016 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+
+//! Hook for transparent compression of the entry content
+//!
+//! Register the hook in the `PreUpdate` position to compress the content before it is written to
+//! disk and in the `PostRetrieve` (and `PostUpdate`) position to decompress it again. The content
+//! is gzip-compressed and stored base64-encoded, the header gets the flag `imag.compressed = true`.
+//! Entries without this flag are not touched when decompressing, so a store can contain compressed
+//! and uncompressed entries.
+//!
+//! The hook is configured in the `[store.hooks.stdhook_compression]` section, where `level` can be
+//! one of `"fast"`, `"default"` or `"best"`. The aspect the hook is registered in must allow
+//! mutable hooks.
+
+use std::io::Read;
+use std::io::Write;
+
+use toml::Value;
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+
+use libimagstore::hook::Hook;
+use libimagstore::hook::error::HookErrorKind as HEK;
+use libimagstore::hook::accessor::HookDataAccessor as HDA;
+use libimagstore::hook::accessor::HookDataAccessorProvider;
+use libimagstore::hook::accessor::MutableHookDataAccessor;
+use libimagstore::hook::accessor::StoreIdAccessor;
+use libimagstore::hook::position::HookPosition as HP;
+use libimagstore::hook::result::HookResult;
+use libimagstore::store::FileLockEntry;
+use libimagstore::storeid::StoreId;
+use libimagstore::toml_ext::TomlValueExt;
+use libimagerror::into::IntoError;
+use libimagerror::trace::trace_error;
+
+mod error {
+    generate_error_imports!();
+    generate_error_types!(CompressionHookError, CompressionHookErrorKind,
+        HeaderReadError  => "Error while reading the header of the entry",
+        HeaderWriteError => "Error while writing the header of the entry",
+        CompressionError   => "Error while compressing the content",
+        DecompressionError => "Error while decompressing the content"
+    );
+}
+use self::error::CompressionHookErrorKind as CHEK;
+use self::error::MapErrInto;
+
+#[derive(Debug)]
+pub struct CompressionHook {
+    position: HP,
+    level: Compression,
+}
+
+impl CompressionHook {
+
+    pub fn new(position: HP) -> CompressionHook {
+        CompressionHook {
+            position: position,
+            level: Compression::Default,
+        }
+    }
+
+    fn compress(&self, fle: &mut FileLockEntry) -> HookResult<()> {
+        if try!(is_compressed(fle)) {
+            debug!("[COMPRESSION HOOK] Already compressed: {:?}", fle.get_location());
+            return Ok(());
+        }
+
+        debug!("[COMPRESSION HOOK] Compressing {:?}", fle.get_location());
+        let compressed = {
+            let mut encoder = GzEncoder::new(Vec::new(), self.level);
+            let written     = encoder.write_all(fle.get_content().as_bytes());
+            try!(written
+                 .and_then(|_| encoder.finish())
+                 .map_err_into(CHEK::CompressionError)
+                 .map_err(Box::new)
+                 .map_err(|e| HEK::HookExecutionError.into_error_with_cause(e)))
+        };
+
+        *fle.get_content_mut() = ::base64::encode(&compressed);
+        fle.get_header_mut()
+            .set("imag.compressed", Value::Boolean(true))
+            .map(|_| ())
+            .map_err_into(CHEK::HeaderWriteError)
+            .map_err(Box::new)
+            .map_err(|e| HEK::HookExecutionError.into_error_with_cause(e))
+    }
+
+    fn decompress(&self, fle: &mut FileLockEntry) -> HookResult<()> {
+        if !try!(is_compressed(fle)) {
+            return Ok(());
+        }
+
+        debug!("[COMPRESSION HOOK] Decompressing {:?}", fle.get_location());
+        let content = {
+            let bytes = try!(::base64::decode(fle.get_content().trim())
+                 .map_err_into(CHEK::DecompressionError)
+                 .map_err(Box::new)
+                 .map_err(|e| HEK::HookExecutionError.into_error_with_cause(e)));
+
+            let mut content = String::new();
+            try!(GzDecoder::new(&bytes[..])
+                 .and_then(|mut decoder| decoder.read_to_string(&mut content))
+                 .map_err_into(CHEK::DecompressionError)
+                 .map_err(Box::new)
+                 .map_err(|e| HEK::HookExecutionError.into_error_with_cause(e)));
+            content
+        };
+
+        *fle.get_content_mut() = content;
+        fle.get_header_mut()
+            .delete("imag.compressed")
+            .map(|_| ())
+            .map_err_into(CHEK::HeaderWriteError)
+            .map_err(Box::new)
+            .map_err(|e| HEK::HookExecutionError.into_error_with_cause(e))
+    }
+
+}
+
+fn is_compressed(fle: &FileLockEntry) -> HookResult<bool> {
+    match fle.get_header().read("imag.compressed") {
+        Ok(Some(Value::Boolean(b))) => Ok(b),
+        Ok(_)  => Ok(false),
+        Err(e) => Err(e)
+            .map_err_into(CHEK::HeaderReadError)
+            .map_err(Box::new)
+            .map_err(|e| HEK::HookExecutionError.into_error_with_cause(e)),
+    }
+}
+
+impl Hook for CompressionHook {
+
+    fn name(&self) -> &'static str {
+        "stdhook_compression"
+    }
+
+    fn set_config(&mut self, v: &Value) {
+        self.level = match v.read("level") {
+            Ok(Some(Value::String(ref s))) if s == "fast"    => Compression::Fast,
+            Ok(Some(Value::String(ref s))) if s == "default" => Compression::Default,
+            Ok(Some(Value::String(ref s))) if s == "best"    => Compression::Best,
+            Ok(Some(_)) => {
+                warn!("Configuration error, 'level' must be one of \"fast\", \"default\", \"best\".");
+                warn!("Assuming \"default\" now.");
+                Compression::Default
+            },
+            Ok(None) => Compression::Default,
+            Err(e) => {
+                error!("Error parsing TOML:");
+                trace_error(&e);
+                Compression::Default
+            },
+        };
+    }
+
+}
+
+impl HookDataAccessorProvider for CompressionHook {
+
+    fn accessor(&self) -> HDA {
+        match self.position {
+            HP::PreUpdate    |
+            HP::PostUpdate   |
+            HP::PostCreate   |
+            HP::PostRetrieve => HDA::MutableAccess(self),
+            _                => HDA::StoreIdAccess(self),
+        }
+    }
+
+}
+
+impl StoreIdAccessor for CompressionHook {
+
+    fn access(&self, _: &StoreId) -> HookResult<()> {
+        debug!("[COMPRESSION HOOK] Nothing to do in position {:?}", self.position);
+        Ok(())
+    }
+
+}
+
+impl MutableHookDataAccessor for CompressionHook {
+
+    fn access_mut(&self, fle: &mut FileLockEntry) -> HookResult<()> {
+        match self.position {
+            HP::PreUpdate => self.compress(fle),
+            _             => self.decompress(fle),
+        }
+    }
+
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs::File;
+    use std::io::Read;
+    use std::path::PathBuf;
+
+    use tempdir::TempDir;
+
+    use libimagstore::hook::position::HookPosition as HP;
+    use libimagstore::store::Store;
+    use libimagstore::storeid::StoreId;
+    use libimagstore::toml_ext::TomlValueExt;
+
+    use test_util;
+    use super::CompressionHook;
+
+    fn get_store(tmp: &TempDir) -> Store {
+        let positions = vec![HP::PreUpdate, HP::PostUpdate, HP::PostRetrieve];
+        test_util::get_store_at(tmp, positions, "level = \"best\"", |pos| Box::new(CompressionHook::new(pos)))
+    }
+
+    #[test]
+    fn test_compression_roundtrip() {
+        let tmp     = TempDir::new("imag-stdhook-test").unwrap();
+        let store   = get_store(&tmp);
+        let id      = StoreId::new_baseless(PathBuf::from("test/compressed")).unwrap();
+        let content = ::std::iter::repeat("A large body of text. ").take(1000).collect::<String>();
+
+        {
+            let mut entry = store.create(id.clone()).unwrap();
+            *entry.get_content_mut() = content.clone();
+        }
+
+        let mut on_disk = String::new();
+        let _ = File::open(tmp.path().join("test/compressed"))
+            .unwrap()
+            .read_to_string(&mut on_disk)
+            .unwrap();
+        assert!(on_disk.contains("compressed = true"));
+        assert!(!on_disk.contains("A large body of text."));
+        assert!(on_disk.len() < content.len());
+
+        let entry = store.retrieve(id).unwrap();
+        assert_eq!(*entry.get_content(), content);
+        assert!(entry.get_header().read("imag.compressed").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_uncompressed_entries_are_not_touched() {
+        let tmp   = TempDir::new("imag-stdhook-test").unwrap();
+        let store = Store::new(PathBuf::from(tmp.path()), None).unwrap();
+        let id    = StoreId::new_baseless(PathBuf::from("test/plain")).unwrap();
+
+        {
+            let mut entry = store.create(id.clone()).unwrap();
+            *entry.get_content_mut() = String::from("plain content");
+        }
+        drop(store);
+
+        let store = get_store(&tmp);
+        let entry = store.retrieve(id).unwrap();
+        assert_eq!(*entry.get_content(), "plain content");
+    }
+
+}
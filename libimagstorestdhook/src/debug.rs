@@ -67,7 +67,13 @@ impl HookDataAccessorProvider for DebugHook {
             HP::PreCreate    |
             HP::PreRetrieve  |
             HP::PreDelete    |
-            HP::PostDelete   => HDA::StoreIdAccess(&self.accessor),
+            HP::PostDelete   |
+            HP::PreRetrieveCopy  |
+            HP::PostRetrieveCopy |
+            HP::PreSaveTo    |
+            HP::PostSaveTo   |
+            HP::PreSaveAs    |
+            HP::PostSaveAs   => HDA::StoreIdAccess(&self.accessor),
             HP::PostCreate   |
             HP::PostRetrieve |
             HP::PreUpdate    |
@@ -0,0 +1,385 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015, 2016 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Hook for encryption of the entry content with gpg
+//!
+//! Register the hook in the `PreUpdate` position to encrypt the content before it is written to
+//! disk and in the `PostRetrieve` (and `PostUpdate`) position to decrypt it again. Only the
+//! content is encrypted, the header stays in cleartext (so listing and tagging still work) and
+//! gets the flag `imag.encrypted = true`. Entries without this flag are not touched when
+//! decrypting.
+//!
+//! The hook shells out to `gpg` and is configured in the `[store.hooks.stdhook_encryption]`
+//! section:
+//!
+//! * `recipients`: Array of key ids to encrypt the content to (required for encryption)
+//! * `gpg`: The gpg binary to use (optional, defaults to `"gpg"`)
+//! * `homedir`: The gpg home directory to use (optional)
+//!
+//! The aspect the hook is registered in must allow mutable hooks.
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::thread;
+
+use toml::Value;
+
+use libimagstore::hook::Hook;
+use libimagstore::hook::error::HookErrorKind as HEK;
+use libimagstore::hook::accessor::HookDataAccessor as HDA;
+use libimagstore::hook::accessor::HookDataAccessorProvider;
+use libimagstore::hook::accessor::MutableHookDataAccessor;
+use libimagstore::hook::accessor::StoreIdAccessor;
+use libimagstore::hook::position::HookPosition as HP;
+use libimagstore::hook::result::HookResult;
+use libimagstore::store::FileLockEntry;
+use libimagstore::storeid::StoreId;
+use libimagstore::toml_ext::TomlValueExt;
+use libimagerror::into::IntoError;
+use libimagerror::trace::trace_error;
+
+mod error {
+    generate_error_imports!();
+    generate_error_types!(EncryptionHookError, EncryptionHookErrorKind,
+        HeaderReadError  => "Error while reading the header of the entry",
+        HeaderWriteError => "Error while writing the header of the entry",
+        NoRecipients     => "No recipients configured to encrypt to",
+        GpgExecError     => "Error while executing gpg",
+        EncryptionError  => "Error while encrypting the content",
+        DecryptionError  => "Error while decrypting the content"
+    );
+}
+use self::error::EncryptionHookError;
+use self::error::EncryptionHookErrorKind as EHEK;
+use self::error::MapErrInto;
+
+#[derive(Debug)]
+pub struct EncryptionHook {
+    position: HP,
+    gpg: String,
+    homedir: Option<String>,
+    recipients: Vec<String>,
+}
+
+impl EncryptionHook {
+
+    pub fn new(position: HP) -> EncryptionHook {
+        EncryptionHook {
+            position: position,
+            gpg: String::from("gpg"),
+            homedir: None,
+            recipients: vec![],
+        }
+    }
+
+    fn encrypt(&self, fle: &mut FileLockEntry) -> HookResult<()> {
+        if try!(is_encrypted(fle)) {
+            debug!("[ENCRYPTION HOOK] Already encrypted: {:?}", fle.get_location());
+            return Ok(());
+        }
+
+        if self.recipients.is_empty() {
+            return Err(EHEK::NoRecipients.into_error())
+                .map_err(Box::new)
+                .map_err(|e| HEK::HookExecutionError.into_error_with_cause(e));
+        }
+
+        debug!("[ENCRYPTION HOOK] Encrypting {:?}", fle.get_location());
+        let mut args = vec![String::from("--armor"), String::from("--trust-model"), String::from("always")];
+        for r in self.recipients.iter() {
+            args.push(String::from("--recipient"));
+            args.push(r.clone());
+        }
+        args.push(String::from("--encrypt"));
+
+        let encrypted = try!(self.run_gpg(&args, fle.get_content().as_bytes())
+            .map_err_into(EHEK::EncryptionError)
+            .and_then(|out| String::from_utf8(out).map_err_into(EHEK::EncryptionError))
+            .map_err(Box::new)
+            .map_err(|e| HEK::HookExecutionError.into_error_with_cause(e)));
+
+        *fle.get_content_mut() = encrypted;
+        fle.get_header_mut()
+            .set("imag.encrypted", Value::Boolean(true))
+            .map(|_| ())
+            .map_err_into(EHEK::HeaderWriteError)
+            .map_err(Box::new)
+            .map_err(|e| HEK::HookExecutionError.into_error_with_cause(e))
+    }
+
+    fn decrypt(&self, fle: &mut FileLockEntry) -> HookResult<()> {
+        if !try!(is_encrypted(fle)) {
+            return Ok(());
+        }
+
+        debug!("[ENCRYPTION HOOK] Decrypting {:?}", fle.get_location());
+        let args      = vec![String::from("--decrypt")];
+        let decrypted = try!(self.run_gpg(&args, fle.get_content().as_bytes())
+            .map_err_into(EHEK::DecryptionError)
+            .and_then(|out| String::from_utf8(out).map_err_into(EHEK::DecryptionError))
+            .map_err(Box::new)
+            .map_err(|e| HEK::HookExecutionError.into_error_with_cause(e)));
+
+        *fle.get_content_mut() = decrypted;
+        fle.get_header_mut()
+            .delete("imag.encrypted")
+            .map(|_| ())
+            .map_err_into(EHEK::HeaderWriteError)
+            .map_err(Box::new)
+            .map_err(|e| HEK::HookExecutionError.into_error_with_cause(e))
+    }
+
+    /// Run gpg with `args`, feeding it `input` on stdin, and return what it printed on stdout
+    fn run_gpg(&self, args: &[String], input: &[u8]) -> Result<Vec<u8>, EncryptionHookError> {
+        let mut cmd = Command::new(&self.gpg);
+        cmd.arg("--batch").arg("--yes").arg("--quiet");
+        if let Some(ref homedir) = self.homedir {
+            cmd.arg("--homedir").arg(homedir);
+        }
+
+        let mut child = try!(cmd.args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err_into(EHEK::GpgExecError));
+
+        // Write on another thread, gpg might block on a full stdout pipe otherwise
+        let writer = {
+            let mut stdin = child.stdin.take().unwrap(); // stdin is piped
+            let input     = input.to_vec();
+            thread::spawn(move || stdin.write_all(&input))
+        };
+
+        let output = try!(child.wait_with_output().map_err_into(EHEK::GpgExecError));
+        let _      = writer.join();
+
+        if output.status.success() {
+            Ok(output.stdout)
+        } else {
+            debug!("[ENCRYPTION HOOK] gpg failed: {}", String::from_utf8_lossy(&output.stderr));
+            Err(EHEK::GpgExecError.into_error())
+        }
+    }
+
+}
+
+fn is_encrypted(fle: &FileLockEntry) -> HookResult<bool> {
+    match fle.get_header().read("imag.encrypted") {
+        Ok(Some(Value::Boolean(b))) => Ok(b),
+        Ok(_)  => Ok(false),
+        Err(e) => Err(e)
+            .map_err_into(EHEK::HeaderReadError)
+            .map_err(Box::new)
+            .map_err(|e| HEK::HookExecutionError.into_error_with_cause(e)),
+    }
+}
+
+fn read_string_cfg(v: &Value, name: &str) -> Option<String> {
+    match v.read(name) {
+        Ok(Some(Value::String(s))) => Some(s),
+        Ok(Some(_)) => {
+            warn!("Configuration error, '{}' must be a String.", name);
+            None
+        },
+        Ok(None) => None,
+        Err(e) => {
+            error!("Error parsing TOML:");
+            trace_error(&e);
+            None
+        },
+    }
+}
+
+impl Hook for EncryptionHook {
+
+    fn name(&self) -> &'static str {
+        "stdhook_encryption"
+    }
+
+    fn set_config(&mut self, v: &Value) {
+        if let Some(gpg) = read_string_cfg(v, "gpg") {
+            self.gpg = gpg;
+        }
+        self.homedir = read_string_cfg(v, "homedir");
+
+        self.recipients = match v.read("recipients") {
+            Ok(Some(Value::Array(a))) => a
+                .into_iter()
+                .filter_map(|r| match r {
+                    Value::String(s) => Some(s),
+                    _ => {
+                        warn!("Configuration error, 'recipients' must be an Array of Strings.");
+                        None
+                    },
+                })
+                .collect(),
+            Ok(Some(_)) => {
+                warn!("Configuration error, 'recipients' must be an Array of Strings.");
+                vec![]
+            },
+            Ok(None) => vec![],
+            Err(e) => {
+                error!("Error parsing TOML:");
+                trace_error(&e);
+                vec![]
+            },
+        };
+
+        if self.recipients.is_empty() {
+            warn!("No recipients configured for the encryption hook, entries cannot be encrypted");
+        }
+    }
+
+}
+
+impl HookDataAccessorProvider for EncryptionHook {
+
+    fn accessor(&self) -> HDA {
+        match self.position {
+            HP::PreUpdate    |
+            HP::PostUpdate   |
+            HP::PostCreate   |
+            HP::PostRetrieve => HDA::MutableAccess(self),
+            _                => HDA::StoreIdAccess(self),
+        }
+    }
+
+}
+
+impl StoreIdAccessor for EncryptionHook {
+
+    fn access(&self, _: &StoreId) -> HookResult<()> {
+        debug!("[ENCRYPTION HOOK] Nothing to do in position {:?}", self.position);
+        Ok(())
+    }
+
+}
+
+impl MutableHookDataAccessor for EncryptionHook {
+
+    fn access_mut(&self, fle: &mut FileLockEntry) -> HookResult<()> {
+        match self.position {
+            HP::PreUpdate => self.encrypt(fle),
+            _             => self.decrypt(fle),
+        }
+    }
+
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs::File;
+    use std::io::Read;
+    use std::path::PathBuf;
+    use std::process::{Command, Stdio};
+
+    use tempdir::TempDir;
+
+    use libimagstore::hook::position::HookPosition as HP;
+    use libimagstore::store::Store;
+    use libimagstore::storeid::StoreId;
+    use libimagstore::toml_ext::TomlValueExt;
+
+    use test_util;
+    use super::EncryptionHook;
+
+    const KEY_UID : &'static str = "imag test <imag-test@example.com>";
+
+    /// Generate a key without passphrase in a fresh gpg home directory.
+    ///
+    /// Returns `None` if gpg is not available (or too old to generate keys in batch mode), so the
+    /// tests can be skipped.
+    fn gpg_home() -> Option<TempDir> {
+        let home = TempDir::new("imag-stdhook-gpg").unwrap();
+        let ok   = Command::new("gpg")
+            .arg("--batch")
+            .arg("--homedir").arg(home.path())
+            .arg("--passphrase").arg("")
+            .arg("--quick-gen-key").arg(KEY_UID)
+            .arg("default").arg("default").arg("never")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .map(|s| s.success())
+            .unwrap_or(false);
+
+        if ok { Some(home) } else { None }
+    }
+
+    fn get_store(tmp: &TempDir, home: &TempDir) -> Store {
+        let positions = vec![HP::PreUpdate, HP::PostUpdate, HP::PostRetrieve];
+        let config    = format!("recipients = [ \"imag-test@example.com\" ]\nhomedir = \"{}\"",
+                                home.path().display());
+        test_util::get_store_at(tmp, positions, &config, |pos| Box::new(EncryptionHook::new(pos)))
+    }
+
+    #[test]
+    fn test_encryption_roundtrip() {
+        let home = match gpg_home() {
+            Some(home) => home,
+            None => return, // no usable gpg, skipping
+        };
+
+        let tmp   = TempDir::new("imag-stdhook-test").unwrap();
+        let store = get_store(&tmp, &home);
+        let id    = StoreId::new_baseless(PathBuf::from("test/encrypted")).unwrap();
+
+        {
+            let mut entry = store.create(id.clone()).unwrap();
+            *entry.get_content_mut() = String::from("My secret diary");
+        }
+
+        let mut on_disk = String::new();
+        let _ = File::open(tmp.path().join("test/encrypted"))
+            .unwrap()
+            .read_to_string(&mut on_disk)
+            .unwrap();
+        assert!(on_disk.contains("encrypted = true"));
+        assert!(on_disk.contains("BEGIN PGP MESSAGE"));
+        assert!(!on_disk.contains("My secret diary"));
+
+        let entry = store.retrieve(id).unwrap();
+        assert_eq!(*entry.get_content(), "My secret diary");
+        assert!(entry.get_header().read("imag.encrypted").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_unencrypted_entries_are_not_touched() {
+        let home = match gpg_home() {
+            Some(home) => home,
+            None => return, // no usable gpg, skipping
+        };
+
+        let tmp   = TempDir::new("imag-stdhook-test").unwrap();
+        let store = Store::new(PathBuf::from(tmp.path()), None).unwrap();
+        let id    = StoreId::new_baseless(PathBuf::from("test/plain")).unwrap();
+
+        {
+            let mut entry = store.create(id.clone()).unwrap();
+            *entry.get_content_mut() = String::from("plain content");
+        }
+        drop(store);
+
+        let store = get_store(&tmp, &home);
+        let entry = store.retrieve(id).unwrap();
+        assert_eq!(*entry.get_content(), "plain content");
+    }
+
+}
@@ -36,7 +36,11 @@
 #[macro_use] extern crate log;
 extern crate toml;
 extern crate fs2;
-extern crate git2;
+#[cfg(feature = "git")] extern crate git2;
+extern crate flate2;
+extern crate base64;
+extern crate chrono;
+#[cfg(test)] extern crate tempdir;
 
 extern crate libimagstore;
 extern crate libimagentrylink;
@@ -44,9 +48,14 @@ extern crate libimaginteraction;
 #[macro_use] extern crate libimagerror;
 extern crate libimagutil;
 
+pub mod compression;
 pub mod debug;
 pub mod denylinkeddelete;
+pub mod encryption;
 pub mod flock;
 pub mod linkverify;
+pub mod timestamp;
 pub mod vcs;
 
+#[cfg(test)] mod test_util;
+
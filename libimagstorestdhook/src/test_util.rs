@@ -0,0 +1,117 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015, 2016 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Store setup shared by the tests of the hooks
+
+use std::path::PathBuf;
+
+use tempdir::TempDir;
+use toml::Value;
+use toml::de::from_str;
+
+use libimagstore::hook::Hook;
+use libimagstore::hook::position::HookPosition as HP;
+use libimagstore::error::StoreError;
+use libimagstore::store::Store;
+
+/// The aspect the hooks of the tests are registered in
+const ASPECT : &'static str = "test";
+
+/// The configuration keys for the aspects of all positions, with the position (if a hook can be
+/// registered for it)
+fn aspect_keys() -> Vec<(&'static str, Option<HP>)> {
+    vec![
+        ("store-unload-hook-aspects",       Some(HP::StoreUnload)),
+        ("pre-create-hook-aspects",         Some(HP::PreCreate)),
+        ("post-create-hook-aspects",        Some(HP::PostCreate)),
+        ("pre-move-hook-aspects",           None),
+        ("post-move-hook-aspects",          None),
+        ("pre-retrieve-hook-aspects",       Some(HP::PreRetrieve)),
+        ("post-retrieve-hook-aspects",      Some(HP::PostRetrieve)),
+        ("pre-update-hook-aspects",         Some(HP::PreUpdate)),
+        ("post-update-hook-aspects",        Some(HP::PostUpdate)),
+        ("pre-delete-hook-aspects",         Some(HP::PreDelete)),
+        ("post-delete-hook-aspects",        Some(HP::PostDelete)),
+        ("pre-retrieve-copy-hook-aspects",  Some(HP::PreRetrieveCopy)),
+        ("post-retrieve-copy-hook-aspects", Some(HP::PostRetrieveCopy)),
+        ("pre-save-to-hook-aspects",        Some(HP::PreSaveTo)),
+        ("post-save-to-hook-aspects",       Some(HP::PostSaveTo)),
+        ("pre-save-as-hook-aspects",        Some(HP::PreSaveAs)),
+        ("post-save-as-hook-aspects",       Some(HP::PostSaveAs)),
+    ]
+}
+
+/// Build a store configuration which executes the hook `hook_name` at the `positions`
+///
+/// `hook_config` are the TOML lines of the `[hooks.<hook_name>]` table, which the hook gets via
+/// `Hook::set_config()`.
+pub fn config(positions: &[HP], hook_name: &str, hook_config: &str) -> Value {
+    let mut cfg = String::new();
+
+    for (key, pos) in aspect_keys() {
+        let enabled = pos.map(|p| positions.contains(&p)).unwrap_or(false);
+        let aspects = if enabled { format!("[ \"{}\" ]", ASPECT) } else { String::from("[ ]") };
+        cfg.push_str(&format!("{} = {}\n", key, aspects));
+    }
+
+    cfg.push_str(&format!("\n[aspects.{}]\nparallel = false\nmutable_hooks = true\n", ASPECT));
+    cfg.push_str(&format!("\n[hooks.{}]\naspect = \"{}\"\n{}\n", hook_name, ASPECT, hook_config));
+
+    from_str(&cfg).unwrap()
+}
+
+/// Create an in-memory store, with a hook built by `mkhook` registered at each of the `positions`
+///
+/// See `config()` for `hook_config`.
+pub fn get_store<F>(positions: Vec<HP>, hook_config: &str, mkhook: F) -> Store
+    where F: Fn(HP) -> Box<Hook>
+{
+    with_hooks(positions, hook_config, mkhook, |config| {
+        Store::new_in_memory(PathBuf::from("/"), Some(config))
+    })
+}
+
+/// Like `get_store()`, but the store lives in `tmp`, for tests which look at the files on disk
+pub fn get_store_at<F>(tmp: &TempDir, positions: Vec<HP>, hook_config: &str, mkhook: F) -> Store
+    where F: Fn(HP) -> Box<Hook>
+{
+    with_hooks(positions, hook_config, mkhook, |config| {
+        Store::new(PathBuf::from(tmp.path()), Some(config))
+    })
+}
+
+fn with_hooks<F, S>(positions: Vec<HP>, hook_config: &str, mkhook: F, mkstore: S) -> Store
+    where F: Fn(HP) -> Box<Hook>,
+          S: FnOnce(Value) -> Result<Store, StoreError>
+{
+    let hooks : Vec<(HP, Box<Hook>)> = positions
+        .into_iter()
+        .map(|pos| (pos.clone(), mkhook(pos)))
+        .collect();
+
+    let name      = hooks.first().map(|&(_, ref h)| h.name()).unwrap_or("");
+    let positions = hooks.iter().map(|&(ref pos, _)| pos.clone()).collect::<Vec<_>>();
+    let config    = config(&positions, name, hook_config);
+
+    let mut store = mkstore(config).unwrap();
+    for (pos, hook) in hooks {
+        assert!(store.register_hook(pos, ASPECT, hook).is_ok());
+    }
+    store
+}
@@ -0,0 +1,199 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015, 2016 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Hook for maintaining the `imag.created` and `imag.updated` timestamps of entries
+//!
+//! Register the hook in the `PostCreate` and `PreUpdate` positions. When an entry is created,
+//! `imag.created` and `imag.updated` are set, whenever the entry is written to disk, `imag.updated`
+//! is set. An already existing `imag.created` is never overwritten, but it is set on update if it is
+//! missing (for entries which existed before the hook was enabled).
+//!
+//! `PreCreate` cannot be used, as hooks in that position only get the id of the entry and cannot
+//! alter the header.
+//!
+//! The timestamps are RFC 3339 formatted strings in local time. The aspect the hook is registered
+//! in must allow mutable hooks.
+
+use chrono::Local;
+use toml::Value;
+
+use libimagstore::hook::Hook;
+use libimagstore::hook::error::HookErrorKind as HEK;
+use libimagstore::hook::accessor::HookDataAccessor as HDA;
+use libimagstore::hook::accessor::HookDataAccessorProvider;
+use libimagstore::hook::accessor::MutableHookDataAccessor;
+use libimagstore::hook::accessor::StoreIdAccessor;
+use libimagstore::hook::position::HookPosition as HP;
+use libimagstore::hook::result::HookResult;
+use libimagstore::store::FileLockEntry;
+use libimagstore::storeid::StoreId;
+use libimagstore::toml_ext::TomlValueExt;
+use libimagerror::into::IntoError;
+
+mod error {
+    generate_error_imports!();
+    generate_error_types!(TimestampHookError, TimestampHookErrorKind,
+        HeaderReadError  => "Error while reading the header of the entry",
+        HeaderWriteError => "Error while writing the header of the entry"
+    );
+}
+use self::error::TimestampHookErrorKind as THEK;
+use self::error::MapErrInto;
+
+#[derive(Debug)]
+pub struct TimestampHook {
+    position: HP,
+}
+
+impl TimestampHook {
+
+    pub fn new(position: HP) -> TimestampHook {
+        TimestampHook {
+            position: position,
+        }
+    }
+
+}
+
+fn set_timestamps(fle: &mut FileLockEntry) -> HookResult<()> {
+    let now = Value::String(Local::now().to_rfc3339());
+
+    let has_created = try!(fle.get_header()
+        .read("imag.created")
+        .map(|v| v.is_some())
+        .map_err_into(THEK::HeaderReadError)
+        .map_err(Box::new)
+        .map_err(|e| HEK::HookExecutionError.into_error_with_cause(e)));
+
+    if !has_created {
+        debug!("[TIMESTAMP HOOK] Setting 'imag.created' for {:?}", fle.get_location());
+        try!(fle.get_header_mut()
+            .set("imag.created", now.clone())
+            .map_err_into(THEK::HeaderWriteError)
+            .map_err(Box::new)
+            .map_err(|e| HEK::HookExecutionError.into_error_with_cause(e)));
+    }
+
+    debug!("[TIMESTAMP HOOK] Setting 'imag.updated' for {:?}", fle.get_location());
+    fle.get_header_mut()
+        .set("imag.updated", now)
+        .map(|_| ())
+        .map_err_into(THEK::HeaderWriteError)
+        .map_err(Box::new)
+        .map_err(|e| HEK::HookExecutionError.into_error_with_cause(e))
+}
+
+impl Hook for TimestampHook {
+
+    fn name(&self) -> &'static str {
+        "stdhook_timestamp"
+    }
+
+    fn set_config(&mut self, _: &Value) {
+        () // We are not configurable here.
+    }
+
+}
+
+impl HookDataAccessorProvider for TimestampHook {
+
+    fn accessor(&self) -> HDA {
+        match self.position {
+            HP::PostCreate |
+            HP::PreUpdate  => HDA::MutableAccess(self),
+            _              => HDA::StoreIdAccess(self),
+        }
+    }
+
+}
+
+impl StoreIdAccessor for TimestampHook {
+
+    fn access(&self, _: &StoreId) -> HookResult<()> {
+        debug!("[TIMESTAMP HOOK] Nothing to do in position {:?}", self.position);
+        Ok(())
+    }
+
+}
+
+impl MutableHookDataAccessor for TimestampHook {
+
+    fn access_mut(&self, fle: &mut FileLockEntry) -> HookResult<()> {
+        set_timestamps(fle)
+    }
+
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+    use std::thread::sleep;
+    use std::time::Duration;
+
+    use toml::Value;
+
+    use libimagstore::hook::position::HookPosition as HP;
+    use libimagstore::store::Store;
+    use libimagstore::storeid::StoreId;
+    use libimagstore::toml_ext::TomlValueExt;
+
+    use test_util;
+    use super::TimestampHook;
+
+    fn get_store() -> Store {
+        let positions = vec![HP::PostCreate, HP::PreUpdate];
+        test_util::get_store(positions, "", |pos| Box::new(TimestampHook::new(pos)))
+    }
+
+    fn timestamps(store: &Store, id: &StoreId) -> (Value, Value) {
+        let entry   = store.retrieve(id.clone()).unwrap();
+        let created = entry.get_header().read("imag.created").unwrap().unwrap();
+        let updated = entry.get_header().read("imag.updated").unwrap().unwrap();
+        (created, updated)
+    }
+
+    #[test]
+    fn test_created_is_stable_updated_changes() {
+        let store = get_store();
+        let id    = StoreId::new_baseless(PathBuf::from("test/timestamp")).unwrap();
+
+        let _ = store.create(id.clone()).unwrap();
+        let (created_1, updated_1) = timestamps(&store, &id);
+        assert!(is_string(&created_1));
+        assert!(is_string(&updated_1));
+
+        sleep(Duration::from_millis(10));
+        {
+            let mut entry = store.retrieve(id.clone()).unwrap();
+            *entry.get_content_mut() = String::from("changed");
+        }
+
+        let (created_2, updated_2) = timestamps(&store, &id);
+        assert_eq!(created_1, created_2);
+        assert!(updated_1 != updated_2);
+    }
+
+    fn is_string(v: &Value) -> bool {
+        match *v {
+            Value::String(_) => true,
+            _                => false,
+        }
+    }
+
+}
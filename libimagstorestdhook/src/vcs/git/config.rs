@@ -81,27 +81,33 @@ fn commit_with_editor(config: &Value, action: &StoreAction) -> bool {
 }
 
 /// Get the commit default message
-fn commit_default_msg<'a>(config: &'a Value, action: &'a StoreAction) -> Result<String> {
+///
+/// If there is no message configured, `imag: <action> <id>` is used, for example
+/// `imag: update contact/foo`.
+fn commit_default_msg<'a>(config: &'a Value, action: &'a StoreAction, id: &StoreId) -> Result<String> {
     config.read("commit.message")
         .map(|m| match m {
             Some(Value::String(b)) => String::from(b),
             Some(_) => {
                 warn!("Configuration error, 'store.hooks.stdhook_git_{}.commit.message' must be a String.",
                       action);
-                warn!("Defaulting to commit.message = '{}'", action.as_commit_message());
-                String::from(action.as_commit_message())
+                warn!("Defaulting to commit.message = '{}'", generated_commit_msg(action, id));
+                generated_commit_msg(action, id)
             },
             None => {
-                warn!("Unavailable configuration for");
-                warn!("\t'store.hooks.stdhook_git_{}.commit.message'", action);
-                warn!("Defaulting to commit.message = '{}'", action.as_commit_message());
-                String::from(action.as_commit_message())
+                debug!("No 'store.hooks.stdhook_git_{}.commit.message' configured", action);
+                generated_commit_msg(action, id)
             },
         })
         .map_err_into(GHEK::ConfigError)
 
 }
 
+/// Generate the commit message which is used if there is none configured
+fn generated_commit_msg(action: &StoreAction, id: &StoreId) -> String {
+    format!("imag: {} {}", action.as_commit_message().to_lowercase(), id)
+}
+
 /// Get the commit template
 ///
 /// TODO: Implement good template string
@@ -144,7 +150,7 @@ pub fn commit_message(repo: &Repository, config: &Value, action: StoreAction, id
             Ok(ask_string("Commit Message", None, false, false, None, "> "))
         }
     } else {
-        commit_default_msg(config, &action)
+        commit_default_msg(config, &action, id)
     }
 }
 
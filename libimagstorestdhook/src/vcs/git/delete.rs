@@ -240,3 +240,44 @@ impl StoreIdAccessor for DeleteHook {
 
 }
 
+
+#[cfg(test)]
+mod test {
+    use std::fs::{File, remove_file};
+    use std::io::Write;
+    use std::path::PathBuf;
+
+    use tempdir::TempDir;
+
+    use libimagstore::hook::Hook;
+    use libimagstore::hook::accessor::StoreIdAccessor;
+    use libimagstore::hook::position::HookPosition;
+    use libimagstore::storeid::StoreId;
+
+    use vcs::git::update::UpdateHook;
+    use vcs::git::util::test::{init_repo, committing_config, head_commit_message};
+
+    use super::DeleteHook;
+
+    #[test]
+    fn test_deleted_file_is_committed() {
+        let tmp  = TempDir::new("imag-git-delete-hook").unwrap();
+        let repo = init_repo(tmp.path());
+        let path = tmp.path().join("test");
+        let id   = StoreId::new_baseless(PathBuf::from("test")).unwrap();
+
+        let _ = File::create(&path).and_then(|mut f| f.write_all(b"content")).unwrap();
+
+        let mut update = UpdateHook::new(PathBuf::from(tmp.path()), HookPosition::PostUpdate);
+        update.set_config(&committing_config());
+        assert!(update.access(&id).is_ok());
+
+        remove_file(&path).unwrap();
+
+        let mut hook = DeleteHook::new(PathBuf::from(tmp.path()), HookPosition::PostDelete);
+        hook.set_config(&committing_config());
+        assert!(hook.access(&id).is_ok());
+        assert_eq!(head_commit_message(&repo), "imag: delete test");
+    }
+
+}
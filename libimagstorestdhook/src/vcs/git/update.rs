@@ -40,11 +40,17 @@ use vcs::git::runtime::Runtime as GRuntime;
 /// The `UpdateHook` type
 ///
 /// Represents a hook which is executed whenever a entry in the store is updated (written to disk).
+/// It can also be registered for `HookPosition::PostCreate`, in which case it commits with the
+/// "create" action.
 ///
 /// # Time of execution
 ///
 /// This hook is executed _after_ the store operation succeeded, so _after_ the file is written to
 /// disk.
+///
+/// The store writes a created entry only when it is updated (or dropped), so a `PostCreate`
+/// hook usually finds no file and does nothing. The new file is then committed by the
+/// `PostUpdate` hook.
 pub struct UpdateHook {
     storepath: PathBuf,
 
@@ -124,7 +130,10 @@ impl StoreIdAccessor for UpdateHook {
 
         debug!("[GIT UPDATE HOOK]: {:?}", id);
 
-        let action = StoreAction::Update;
+        let action = match self.position {
+            HookPosition::PostCreate => StoreAction::Create,
+            _                        => StoreAction::Update,
+        };
         let cfg    = try!(self.runtime.config_value_or_err(&action));
 
         if !is_enabled(cfg) {
@@ -147,6 +156,11 @@ impl StoreIdAccessor for UpdateHook {
             }
         }
 
+        if !self.storepath.join(id.local()).is_file() {
+            debug!("[GIT UPDATE HOOK]: {:?} is not written to disk yet, nothing to commit", id);
+            return Ok(());
+        }
+
         let _         = try!(self.runtime.ensure_cfg_branch_is_checked_out(&action));
         let repo      = try!(self.runtime.repository(&action));
 
@@ -241,7 +255,7 @@ impl StoreIdAccessor for UpdateHook {
                 .map_into_hook_error()
         );
 
-        let message = try!(commit_message(&repo, cfg, StoreAction::Update, &id)
+        let message = try!(commit_message(&repo, cfg, action, &id)
                 .map_dbg_err_str("Failed to get commit message")
                 .map_dbg_str("[GIT UPDATE HOOK]: Fetched commit message"));
 
@@ -261,3 +275,84 @@ impl StoreIdAccessor for UpdateHook {
 
 }
 
+
+#[cfg(test)]
+mod test {
+    use std::fs::File;
+    use std::io::Write;
+    use std::path::PathBuf;
+
+    use tempdir::TempDir;
+    use toml::Value;
+
+    use libimagstore::hook::Hook;
+    use libimagstore::hook::accessor::StoreIdAccessor;
+    use libimagstore::hook::position::HookPosition;
+    use libimagstore::storeid::StoreId;
+
+    use vcs::git::util::test::{init_repo, committing_config, head_commit_message};
+
+    use super::UpdateHook;
+
+    #[test]
+    fn test_new_file_is_committed() {
+        let tmp  = TempDir::new("imag-git-update-hook").unwrap();
+        let repo = init_repo(tmp.path());
+
+        let _ = File::create(tmp.path().join("test"))
+            .and_then(|mut f| f.write_all(b"content"))
+            .unwrap();
+
+        let mut hook = UpdateHook::new(PathBuf::from(tmp.path()), HookPosition::PostUpdate);
+        hook.set_config(&committing_config());
+
+        let id = StoreId::new_baseless(PathBuf::from("test")).unwrap();
+        assert!(hook.access(&id).is_ok());
+        assert_eq!(head_commit_message(&repo), "imag: update test");
+    }
+
+    #[test]
+    fn test_created_file_is_committed() {
+        let tmp  = TempDir::new("imag-git-update-hook").unwrap();
+        let repo = init_repo(tmp.path());
+
+        let _ = File::create(tmp.path().join("test"))
+            .and_then(|mut f| f.write_all(b"content"))
+            .unwrap();
+
+        let mut hook = UpdateHook::new(PathBuf::from(tmp.path()), HookPosition::PostCreate);
+        hook.set_config(&committing_config());
+
+        let id = StoreId::new_baseless(PathBuf::from("test")).unwrap();
+        assert!(hook.access(&id).is_ok());
+        assert_eq!(head_commit_message(&repo), "imag: create test");
+    }
+
+    #[test]
+    fn test_created_entry_not_on_disk_is_skipped() {
+        let tmp  = TempDir::new("imag-git-update-hook").unwrap();
+        let repo = init_repo(tmp.path());
+
+        let mut hook = UpdateHook::new(PathBuf::from(tmp.path()), HookPosition::PostCreate);
+        hook.set_config(&committing_config());
+
+        let id = StoreId::new_baseless(PathBuf::from("test")).unwrap();
+        assert!(hook.access(&id).is_ok());
+        assert_eq!(head_commit_message(&repo), "init");
+    }
+
+    #[test]
+    fn test_no_repository_is_skipped_if_configured() {
+        let tmp = TempDir::new("imag-git-update-hook").unwrap();
+
+        let mut cfg = committing_config();
+        cfg.as_table_mut().unwrap().insert(String::from("abort_on_repo_init_failure"), Value::Boolean(false));
+
+        let mut hook = UpdateHook::new(PathBuf::from(tmp.path()), HookPosition::PostUpdate);
+        hook.set_config(&cfg);
+
+        let id = StoreId::new_baseless(PathBuf::from("test")).unwrap();
+        assert!(hook.access(&id).is_ok());
+    }
+
+}
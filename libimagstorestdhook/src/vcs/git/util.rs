@@ -40,3 +40,45 @@ pub fn fetch_index(repo: &Repository, action: &StoreAction) -> Result<Index, Hoo
         .map_into_hook_error()
 }
 
+
+#[cfg(test)]
+pub mod test {
+    use std::path::Path;
+
+    use git2::{Repository, Signature};
+    use toml::Value;
+
+    /// Initialize a repository in `path` which has a commit on "master", so the hooks have a
+    /// HEAD to commit onto
+    pub fn init_repo(path: &Path) -> Repository {
+        let repo = Repository::init(path).unwrap();
+        {
+            let sig  = Signature::now("imag", "imag@example.com").unwrap();
+            let tree = repo.index().unwrap().write_tree().unwrap();
+            let tree = repo.find_tree(tree).unwrap();
+            let _    = repo.commit(Some("HEAD"), &sig, &sig, "init", &tree, &[]).unwrap();
+
+            let mut cfg = repo.config().unwrap();
+            cfg.set_str("user.name", "imag").unwrap();
+            cfg.set_str("user.email", "imag@example.com").unwrap();
+        }
+        repo
+    }
+
+    /// Hook configuration which commits non-interactively with the default message
+    pub fn committing_config() -> Value {
+        r#"
+            [commit]
+            enabled = true
+            interactive = false
+        "#.parse().unwrap()
+    }
+
+    /// Get the message of the commit HEAD points to
+    pub fn head_commit_message(repo: &Repository) -> String {
+        let target = repo.head().unwrap().target().unwrap();
+        let commit = repo.find_commit(target).unwrap();
+        String::from(commit.message().unwrap())
+    }
+
+}
@@ -29,11 +29,48 @@ pub fn is_url(s: String) -> Result<(), String> {
 }
 
 pub fn is_tag(s: String) -> Result<(), String> {
-    use regex::Regex;
-    lazy_static! { static ref TAG_RE : Regex = Regex::new("[:alpha:][:word:]*").unwrap(); }
+    is_valid_tag(&s).as_result((), format!("Not a valid Tag: '{}' - Valid are letters, digits, '-' and '_', starting with a letter, levels separated by '/'", s))
+}
+
+/// Check whether `s` is a valid tag
+///
+/// A tag consists of one or more levels separated by `/`, like `project/imag/docs`. Each level is
+/// a non-empty sequence of alphanumeric characters, `-` and `_`, where alphanumeric is meant in the
+/// Unicode sense, so `café` and `日本語` are valid tags. The first character has to be alphabetic.
+/// Whitespace, control characters and `,`, which separates tags on the commandline, are rejected.
+///
+/// This is the single rule for tags, used by the `is_tag()` validator for the commandline as well
+/// as by `libimagentrytag`.
+pub fn is_valid_tag(s: &str) -> bool {
+    let is_tag_char = |c: char| c.is_alphanumeric() || c == '-' || c == '_';
 
-    TAG_RE
-        .is_match(&s)
-        .as_result((), format!("Not a valid Tag: '{}' - Valid is [a-zA-Z][0-9a-zA-Z]*", s))
+    s.chars().next().map(|c| c.is_alphabetic()).unwrap_or(false) &&
+        s.split('/').all(|level| !level.is_empty() && level.chars().all(&is_tag_char))
 }
 
+#[cfg(test)]
+mod test {
+    use super::is_tag;
+    use super::is_valid_tag;
+
+    #[test]
+    fn test_is_valid_tag_unicode() {
+        assert!(is_valid_tag("imag"));
+        assert!(is_valid_tag("café"));
+        assert!(is_valid_tag("日本語"));
+        assert!(is_valid_tag("Straße/öffnungszeiten_2017"));
+
+        assert!(!is_valid_tag("1st"));
+        assert!(!is_valid_tag("tag,other"));
+        assert!(!is_valid_tag("tag\u{7}"));
+        assert!(!is_valid_tag("日本 語"));
+    }
+
+    #[test]
+    fn test_is_tag_validator_agrees() {
+        for tag in &["café", "日本語", "project/imag", "a b", "a,b", ""] {
+            assert_eq!(is_tag(String::from(*tag)).is_ok(), is_valid_tag(tag));
+        }
+    }
+
+}